@@ -0,0 +1,31 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 002: Close data + Open Positions (สำหรับ Rehydrate ตอน Restart)
+
+-- ── Trade Records: ข้อมูลตอนปิด Position ──────────────────────────────────────
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS close_price  NUMERIC(20, 5);
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS profit_pips  NUMERIC(20, 5);
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS close_reason VARCHAR(50);    -- 'TP' | 'SL' | 'MANUAL' | ...
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS closed_at    TIMESTAMPTZ;
+
+-- ── Open Positions ────────────────────────────────────────────────────────────
+-- Position ที่ยังเปิดอยู่ใน MT5 — ลบแถวออกเมื่อ EA แจ้ง position-close
+CREATE TABLE IF NOT EXISTS positions (
+    mt5_ticket      BIGINT          PRIMARY KEY,
+    position_id     UUID            NOT NULL,
+    strategy_id     UUID            NOT NULL,
+    symbol          VARCHAR(20)     NOT NULL,
+    direction       VARCHAR(10)     NOT NULL,
+    entry_price     NUMERIC(20, 5)  NOT NULL,
+    lot_size        NUMERIC(10, 4)  NOT NULL,
+    take_profit     NUMERIC(20, 5)  NOT NULL,
+    stop_loss       NUMERIC(20, 5)  NOT NULL,
+    sl_moved_to_be  BOOLEAN         NOT NULL DEFAULT FALSE,
+    opposing_low    NUMERIC(20, 5),
+    opposing_high   NUMERIC(20, 5),
+    opened_at       TIMESTAMPTZ     NOT NULL,
+    updated_at      TIMESTAMPTZ     NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_positions_symbol ON positions(symbol);
+
+CREATE INDEX IF NOT EXISTS idx_risk_events_occurred_at ON risk_events(occurred_at DESC);
@@ -0,0 +1,6 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 003: Realized P&L (สกุลเงินบัญชี) สำหรับ Daily/Weekly Loss และ Drawdown
+
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS profit NUMERIC(20, 2);
+
+CREATE INDEX IF NOT EXISTS idx_trade_records_closed_at ON trade_records(closed_at DESC);
@@ -0,0 +1,4 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 004: Position Sizing decision ต่อ Trade (Equity, ความเสี่ยง, Volume ก่อน/หลัง Clamp)
+
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS sizing JSONB;
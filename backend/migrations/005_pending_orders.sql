@@ -0,0 +1,6 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 005: Pending Order (Limit / Stop) ที่ขอบ Entry Zone — ประเภท Order, Order ticket, Volume ที่ Fill แล้ว
+
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS order_kind    VARCHAR(10) NOT NULL DEFAULT 'MARKET';
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS order_ticket  BIGINT;
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS filled_volume NUMERIC(10, 4);
@@ -0,0 +1,7 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 006: Exit Policy ต่อ Position — ความเสี่ยง / Volume ตอนเปิด, Policy ของ Strategy, Scale-out ที่ทำแล้ว
+
+ALTER TABLE positions ADD COLUMN IF NOT EXISTS initial_stop_loss NUMERIC(20, 5) NOT NULL DEFAULT 0;
+ALTER TABLE positions ADD COLUMN IF NOT EXISTS initial_lot_size  NUMERIC(10, 4) NOT NULL DEFAULT 0;
+ALTER TABLE positions ADD COLUMN IF NOT EXISTS exit_policy       JSONB;
+ALTER TABLE positions ADD COLUMN IF NOT EXISTS scale_outs_done   INTEGER        NOT NULL DEFAULT 0;
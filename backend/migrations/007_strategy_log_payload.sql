@@ -0,0 +1,7 @@
+-- Antigravity — PostgreSQL Schema
+-- Migration 007: เก็บ Strategy ทั้งก้อน (JSON) — Backtest Replay Timeline ได้ครบทุก Field
+-- (opposing_zone / entry_mode / exit_policy / confirmation ไม่มีคอลัมน์ของตัวเอง)
+
+ALTER TABLE strategy_log ADD COLUMN IF NOT EXISTS payload JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_strategy_log_symbol_created ON strategy_log(symbol, created_at);
@@ -0,0 +1,427 @@
+//! # book — Strategy Book, Position Book, Pending Order Book & EA Command Queue
+//!
+//! เก็บแผนการเทรดและ Position แยกตาม Symbol เพื่อให้ Backend ตัวเดียว
+//! เทรดได้หลาย Symbol พร้อมกัน (Basket)
+//!
+//! ```text
+//! StrategyBook:  symbol ──▶ strategy_id ──▶ ActiveStrategy
+//! PositionBook:  mt5_ticket ──▶ OpenPosition
+//! OrderBook:     order_ticket ──▶ PendingOrder (Limit / Stop ที่ยังไม่ Fill ครบ)
+//! CommandBook:   [EaCommand] ตามลำดับเข้าคิว (รอ EA รับ / รอ Ack)
+//! ```
+//!
+//! - Strategy ของ XAUUSD จะไม่ไปทับ Strategy ของ BTCUSD อีกต่อไป
+//! - Position ของ BTCUSD จะ Block เฉพาะการเข้าซ้ำใน BTCUSD เท่านั้น
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::models::{
+    command::{CommandKind, CommandStatus, EaCommand},
+    ActiveStrategy, OpenPosition, PendingOrder,
+};
+
+// ─── Strategy Book ────────────────────────────────────────────────────────────
+
+/// แผนการเทรดทั้งหมดที่ Armed อยู่ — Key = symbol → strategy_id
+#[derive(Debug, Default)]
+pub struct StrategyBook {
+    by_symbol: HashMap<String, HashMap<Uuid, ActiveStrategy>>,
+}
+
+impl StrategyBook {
+    /// เพิ่ม Strategy เข้า Book โดยไม่แตะ Strategy อื่นของ Symbol เดียวกัน
+    pub fn insert(&mut self, strategy: ActiveStrategy) {
+        self.by_symbol
+            .entry(strategy.symbol.clone())
+            .or_default()
+            .insert(strategy.strategy_id, strategy);
+    }
+
+    /// แทนที่ทุก Strategy ของ Symbol นั้นด้วย Strategy ใหม่
+    /// คืนรายการ Strategy เดิมที่ถูกแทนที่
+    pub fn replace_symbol(&mut self, strategy: ActiveStrategy) -> Vec<ActiveStrategy> {
+        let replaced = self.clear_symbol(&strategy.symbol);
+        self.insert(strategy);
+        replaced
+    }
+
+    /// ลบ Strategy ตาม ID
+    pub fn remove(&mut self, strategy_id: Uuid) -> Option<ActiveStrategy> {
+        let symbol = self
+            .by_symbol
+            .iter()
+            .find(|(_, book)| book.contains_key(&strategy_id))
+            .map(|(symbol, _)| symbol.clone())?;
+
+        let book    = self.by_symbol.get_mut(&symbol)?;
+        let removed = book.remove(&strategy_id);
+        if book.is_empty() {
+            self.by_symbol.remove(&symbol);
+        }
+        removed
+    }
+
+    /// ลบทุก Strategy ของ Symbol
+    pub fn clear_symbol(&mut self, symbol: &str) -> Vec<ActiveStrategy> {
+        self.by_symbol
+            .remove(symbol)
+            .map(|book| book.into_values().collect())
+            .unwrap_or_default()
+    }
+
+    /// ลบทุก Strategy ในทุก Symbol
+    pub fn clear_all(&mut self) -> Vec<ActiveStrategy> {
+        self.by_symbol
+            .drain()
+            .flat_map(|(_, book)| book.into_values())
+            .collect()
+    }
+
+    /// Strategy ทั้งหมดของ Symbol เรียงตาม created_at (เก่า → ใหม่)
+    pub fn for_symbol(&self, symbol: &str) -> Vec<ActiveStrategy> {
+        let mut list: Vec<ActiveStrategy> = self
+            .by_symbol
+            .get(symbol)
+            .map(|book| book.values().cloned().collect())
+            .unwrap_or_default();
+        list.sort_by_key(|s| s.created_at);
+        list
+    }
+
+    /// Strategy ทั้งหมดทุก Symbol เรียงตาม symbol แล้ว created_at
+    pub fn all(&self) -> Vec<ActiveStrategy> {
+        let mut list: Vec<ActiveStrategy> = self
+            .by_symbol
+            .values()
+            .flat_map(|book| book.values().cloned())
+            .collect();
+        list.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.created_at.cmp(&b.created_at)));
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_symbol.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_symbol.is_empty()
+    }
+}
+
+// ─── Position Book ────────────────────────────────────────────────────────────
+
+/// Position ที่เปิดอยู่ใน MT5 ทั้งหมด — Key = MT5 ticket
+#[derive(Debug, Default)]
+pub struct PositionBook {
+    by_ticket: HashMap<u64, OpenPosition>,
+}
+
+impl PositionBook {
+    /// บันทึก Position ใหม่ (หรือแทนที่ถ้า Ticket ซ้ำ)
+    pub fn open(&mut self, ticket: u64, mut position: OpenPosition) {
+        position.mt5_ticket = Some(ticket);
+        self.by_ticket.insert(ticket, position);
+    }
+
+    pub fn close(&mut self, ticket: u64) -> Option<OpenPosition> {
+        self.by_ticket.remove(&ticket)
+    }
+
+    pub fn get(&self, ticket: u64) -> Option<&OpenPosition> {
+        self.by_ticket.get(&ticket)
+    }
+
+    pub fn get_mut(&mut self, ticket: u64) -> Option<&mut OpenPosition> {
+        self.by_ticket.get_mut(&ticket)
+    }
+
+    /// Position ทั้งหมดของ Symbol เรียงตาม opened_at
+    pub fn for_symbol(&self, symbol: &str) -> Vec<OpenPosition> {
+        let mut list: Vec<OpenPosition> = self
+            .by_ticket
+            .values()
+            .filter(|p| p.symbol == symbol)
+            .cloned()
+            .collect();
+        list.sort_by_key(|p| p.opened_at);
+        list
+    }
+
+    pub fn has_symbol(&self, symbol: &str) -> bool {
+        self.by_ticket.values().any(|p| p.symbol == symbol)
+    }
+
+    /// Position ทั้งหมดทุก Symbol เรียงตาม symbol แล้ว opened_at
+    pub fn all(&self) -> Vec<OpenPosition> {
+        let mut list: Vec<OpenPosition> = self.by_ticket.values().cloned().collect();
+        list.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.opened_at.cmp(&b.opened_at)));
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_ticket.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_ticket.is_empty()
+    }
+}
+
+// ─── Pending Order Book ───────────────────────────────────────────────────────
+
+/// Pending Order ที่วางอยู่ที่โบรกเกอร์ — Key = Order ticket
+///
+/// Strategy หนึ่งตัวมี Pending Order ได้ครั้งละหนึ่ง Order — `reserve` จองไว้ก่อน I/O
+/// เพื่อไม่ให้ Tick ที่เข้ามาระหว่างรอโบรกเกอร์ตอบวาง Order ซ้ำ
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    by_ticket: HashMap<u64, PendingOrder>,
+    /// Strategy ที่กำลังวาง Order (ยังไม่ได้ Ticket)
+    placing:   HashSet<Uuid>,
+}
+
+impl OrderBook {
+    /// จองสิทธิ์วาง Order ให้ Strategy — false = มี Order อยู่แล้วหรือกำลังวาง
+    pub fn reserve(&mut self, strategy_id: Uuid) -> bool {
+        !self.has_strategy(strategy_id) && self.placing.insert(strategy_id)
+    }
+
+    /// วางไม่สำเร็จ → ปล่อยการจอง
+    pub fn release(&mut self, strategy_id: Uuid) {
+        self.placing.remove(&strategy_id);
+    }
+
+    /// โบรกเกอร์รับ Order แล้ว (ปล่อยการจองของ Strategy ไปพร้อมกัน)
+    pub fn insert(&mut self, order: PendingOrder) {
+        self.placing.remove(&order.strategy.strategy_id);
+        self.by_ticket.insert(order.order_ticket, order);
+    }
+
+    pub fn remove(&mut self, order_ticket: u64) -> Option<PendingOrder> {
+        self.by_ticket.remove(&order_ticket)
+    }
+
+    pub fn get(&self, order_ticket: u64) -> Option<&PendingOrder> {
+        self.by_ticket.get(&order_ticket)
+    }
+
+    pub fn get_mut(&mut self, order_ticket: u64) -> Option<&mut PendingOrder> {
+        self.by_ticket.get_mut(&order_ticket)
+    }
+
+    /// Strategy นี้มี Order วางอยู่หรือกำลังวาง
+    pub fn has_strategy(&self, strategy_id: Uuid) -> bool {
+        self.placing.contains(&strategy_id)
+            || self.by_ticket.values().any(|o| o.strategy.strategy_id == strategy_id)
+    }
+
+    /// Ticket ของ Order ที่ Strategy เหล่านี้วางไว้
+    pub fn tickets_for_strategies(&self, strategy_ids: &[Uuid]) -> Vec<u64> {
+        self.tickets_where(|o| strategy_ids.contains(&o.strategy.strategy_id))
+    }
+
+    /// Ticket ของ Order ทั้งหมดของ Symbol
+    pub fn tickets_for_symbol(&self, symbol: &str) -> Vec<u64> {
+        self.tickets_where(|o| o.strategy.symbol == symbol)
+    }
+
+    /// Ticket ของ Order ของ Symbol ที่ Strategy หมดอายุแล้ว
+    pub fn expired(&self, symbol: &str, now: DateTime<Utc>) -> Vec<u64> {
+        self.tickets_where(|o| o.strategy.symbol == symbol && o.is_expired(now))
+    }
+
+    pub fn tickets(&self) -> Vec<u64> {
+        self.tickets_where(|_| true)
+    }
+
+    /// Order ทั้งหมดเรียงตาม symbol แล้ว placed_at
+    pub fn all(&self) -> Vec<PendingOrder> {
+        let mut list: Vec<PendingOrder> = self.by_ticket.values().cloned().collect();
+        list.sort_by(|a, b| a.strategy.symbol.cmp(&b.strategy.symbol).then(a.placed_at.cmp(&b.placed_at)));
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_ticket.len()
+    }
+
+    fn tickets_where(&self, keep: impl Fn(&PendingOrder) -> bool) -> Vec<u64> {
+        let mut tickets: Vec<u64> = self
+            .by_ticket
+            .values()
+            .filter(|o| keep(o))
+            .map(|o| o.order_ticket)
+            .collect();
+        tickets.sort_unstable();
+        tickets
+    }
+}
+
+// ─── Command Book ─────────────────────────────────────────────────────────────
+
+/// คำสั่งที่รอ EA ทำ ตามลำดับที่เข้าคิว — ออกจากคิวเมื่อ Ack หรือ Timeout ครบทุกครั้ง
+#[derive(Debug, Default)]
+pub struct CommandBook {
+    commands: VecDeque<EaCommand>,
+}
+
+impl CommandBook {
+    pub fn enqueue(&mut self, command: EaCommand) {
+        self.commands.push_back(command);
+    }
+
+    /// Position นี้มีคำสั่งที่ยังไม่ Ack — Engine ต้องรอผลก่อนสั่งอะไรเพิ่ม
+    pub fn in_flight_for(&self, ticket: u64) -> bool {
+        self.commands.iter().any(|c| c.kind.ticket() == ticket)
+    }
+
+    /// EA `ea_id` ของ `symbol` มารับคำสั่ง — QUEUED → DELIVERED สูงสุด `max` คำสั่ง
+    pub fn lease(&mut self, ea_id: &str, symbol: &str, max: usize, now: DateTime<Utc>) -> Vec<EaCommand> {
+        self.commands
+            .iter_mut()
+            .filter(|c| c.symbol == symbol && c.status == CommandStatus::Queued)
+            .take(max)
+            .map(|c| {
+                c.status       = CommandStatus::Delivered;
+                c.attempts    += 1;
+                c.delivered_to = Some(ea_id.to_string());
+                c.delivered_at = Some(now);
+                c.clone()
+            })
+            .collect()
+    }
+
+    /// EA รายงานผลแล้ว → ออกจากคิว (None = ไม่รู้จัก / Timeout ไปแล้ว)
+    pub fn ack(&mut self, command_id: Uuid) -> Option<EaCommand> {
+        let index = self.commands.iter().position(|c| c.command_id == command_id)?;
+        self.commands.remove(index)
+    }
+
+    /// ผลการปิดบางส่วนของ Position นี้มาถึงแล้ว → คำสั่ง Scale-out ที่ค้างอยู่ถือว่าทำแล้ว (ออกจากคิว)
+    /// Ack ที่มาทีหลังจึงถูกทิ้ง / Ack ที่หายไม่ทำให้ Timeout แล้วสั่งขั้นเดิมซ้ำ
+    pub fn settle_partial_close(&mut self, ticket: u64) -> Option<EaCommand> {
+        let index = self.commands.iter().position(|c| {
+            matches!(c.kind, CommandKind::ClosePosition { ticket: t, volume: Some(_) } if t == ticket)
+        })?;
+        self.commands.remove(index)
+    }
+
+    /// DELIVERED ที่ไม่ Ack ภายใน `timeout` → QUEUED ให้ EA รับซ้ำ (command_id เดิม)
+    /// ส่งครบ `max_attempts` แล้ว หรือไม่มี EA มารับเลยภายใน `timeout × max_attempts` → TIMED_OUT
+    ///
+    /// คืนคำสั่งที่ TIMED_OUT (ออกจากคิวแล้ว)
+    pub fn expire(&mut self, now: DateTime<Utc>, timeout: Duration, max_attempts: u32) -> Vec<EaCommand> {
+        let deadline = timeout * max_attempts as i32;
+        for c in self.commands.iter_mut() {
+            let lapsed = c.delivered_at.is_some_and(|at| now - at >= timeout);
+            match c.status {
+                CommandStatus::Delivered if lapsed && c.attempts >= max_attempts => c.status = CommandStatus::TimedOut,
+                CommandStatus::Delivered if lapsed                              => c.status = CommandStatus::Queued,
+                CommandStatus::Queued if now - c.created_at >= deadline         => c.status = CommandStatus::TimedOut,
+                _ => {}
+            }
+        }
+
+        let (timed_out, pending) = std::mem::take(&mut self.commands)
+            .into_iter()
+            .partition(|c| c.status == CommandStatus::TimedOut);
+        self.commands = pending;
+        timed_out.into()
+    }
+
+    pub fn all(&self) -> Vec<EaCommand> {
+        self.commands.iter().cloned().collect()
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::models::strategy::test_strategy;
+
+    #[test]
+    fn test_replace_only_touches_same_symbol() {
+        let mut book = StrategyBook::default();
+        let btc = test_strategy("BTCUSD");
+        book.insert(btc.clone());
+
+        let xau = test_strategy("XAUUSD");
+        let replaced = book.replace_symbol(xau.clone());
+
+        assert!(replaced.is_empty());
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.for_symbol("BTCUSD"), vec![btc]);
+
+        let xau2 = test_strategy("XAUUSD");
+        let replaced = book.replace_symbol(xau2.clone());
+        assert_eq!(replaced, vec![xau]);
+        assert_eq!(book.for_symbol("XAUUSD"), vec![xau2]);
+    }
+
+    #[test]
+    fn test_remove_by_id_drops_empty_symbol() {
+        let mut book = StrategyBook::default();
+        let s = test_strategy("BTCUSD");
+        book.insert(s.clone());
+
+        assert_eq!(book.remove(s.strategy_id), Some(s));
+        assert!(book.is_empty());
+        assert!(book.remove(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn test_position_book_is_per_symbol() {
+        let mut positions = PositionBook::default();
+        let pos = OpenPosition::from_strategy(&test_strategy("BTCUSD"), 2000.0);
+        positions.open(1001, pos);
+
+        assert!(positions.has_symbol("BTCUSD"));
+        assert!(!positions.has_symbol("XAUUSD"));
+        assert_eq!(positions.for_symbol("BTCUSD")[0].mt5_ticket, Some(1001));
+        assert!(positions.close(1001).is_some());
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn test_command_redelivered_until_acked_or_timed_out() {
+        use crate::models::command::CommandKind;
+
+        let mut book    = CommandBook::default();
+        let timeout     = Duration::seconds(5);
+        let modify      = EaCommand::new("XAUUSD", CommandKind::ModifyPosition { ticket: 1, sl: 2000.0, tp: 2010.0 }, "BREAK_EVEN");
+        let close       = EaCommand::new("BTCUSD", CommandKind::ClosePosition { ticket: 2, volume: None }, "MAX_HOLD_TIME");
+        let now         = Utc::now();
+        book.enqueue(modify.clone());
+        book.enqueue(close.clone());
+        assert!(book.in_flight_for(1));
+
+        // EA ของ XAUUSD ได้เฉพาะคำสั่งของ Symbol ตัวเอง
+        let leased = book.lease("ea-xau", "XAUUSD", 10, now);
+        assert_eq!(leased.len(), 1);
+        assert_eq!((leased[0].command_id, leased[0].attempts), (modify.command_id, 1));
+        assert!(book.lease("ea-xau", "XAUUSD", 10, now).is_empty(), "delivered — not leased twice");
+
+        // ไม่ Ack → ส่งซ้ำด้วย command_id เดิม
+        assert!(book.expire(now + timeout, timeout, 2).is_empty());
+        let retry = book.lease("ea-xau", "XAUUSD", 10, now + timeout);
+        assert_eq!((retry[0].command_id, retry[0].attempts), (modify.command_id, 2));
+
+        // ครบ max_attempts → TIMED_OUT / คำสั่งที่ไม่มี EA มารับก็หมดเวลาเหมือนกัน
+        let timed_out = book.expire(now + timeout * 2, timeout, 2);
+        assert_eq!(timed_out.len(), 2);
+        assert_eq!(timed_out[0].status, CommandStatus::TimedOut);
+        assert!(book.all().is_empty());
+        assert!(book.ack(modify.command_id).is_none(), "late ack after timeout is ignored");
+
+        book.enqueue(close.clone());
+        book.lease("ea-btc", "BTCUSD", 10, now);
+        assert_eq!(book.ack(close.command_id).map(|c| c.command_id), Some(close.command_id));
+        assert!(!book.in_flight_for(2));
+    }
+}
@@ -0,0 +1,188 @@
+//! # broker::costs — ต้นทุนการเทรดของ Venue จำลอง
+//!
+//! Paper / Backtest ที่ Fill ตรงราคา Tick เป๊ะๆ ดูดีเกินจริง — Cost Model ต่อ Symbol ใส่ต้นทุนที่ Broker จริงเก็บ
+//!
+//! ```text
+//! commission_per_lot  → ทุกขา (เปิด / ปิดแต่ละส่วน) × Volume
+//! slippage            → Market Fill (send_order / close_position / Stop order) — สุ่มตาม Distribution (Points)
+//! sl_slippage_points  → SL ถูกแตะ = Stop → Market: slippage + ค่านี้ (TP / Limit ไม่มี Slippage)
+//! swap_long / short   → ทุก Rollover (00:00 ของเวลา Tick) ที่ Position ข้าม × Volume
+//!                       คืนเสาร์ / อาทิตย์ไม่คิด — triple_swap_day คิด 3 เท่าแทน (แบบ MT5)
+//! ```
+//!
+//! `PAPER_COSTS` = JSON Object `{ "XAUUSD": {...}, "*": {...} }` — `"*"` = Symbol ที่ไม่ได้ระบุ
+//!
+//! ทุกค่าใน [`TradeCosts`] เป็นผลต่อกำไร (สกุลเงินบัญชี, ติดลบ = ต้นทุน) แบบเดียวกับ `Commission()` / `Swap()` ของ MT5
+
+use std::collections::HashMap;
+
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::models::Direction;
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+/// Slippage ของ Market Fill (Points) — บวก = เสียเปรียบ / ลบ = ได้ราคาดีกว่า
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Slippage {
+    /// ทุก Fill เท่ากัน
+    Fixed   { points: f64 },
+    /// สุ่มสม่ำเสมอในช่วง `[min, max]`
+    Uniform { min: f64, max: f64 },
+    /// สุ่มแบบปกติ — หางยาวเหมือนช่วงข่าว
+    Normal  { mean: f64, std_dev: f64 },
+}
+
+impl Default for Slippage {
+    fn default() -> Self {
+        Slippage::Fixed { points: 0.0 }
+    }
+}
+
+impl Slippage {
+    pub fn sample(&self, rng: &mut CostRng) -> f64 {
+        match *self {
+            Slippage::Fixed { points }         => points,
+            Slippage::Uniform { min, max }     => min + (max - min) * rng.next_f64(),
+            Slippage::Normal { mean, std_dev } => mean + std_dev * rng.next_normal(),
+        }
+    }
+}
+
+/// ต้นทุนของ Symbol หนึ่งตัว
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SymbolCosts {
+    /// Commission ต่อ 1 Lot ต่อขา (สกุลเงินบัญชี, ใส่เป็นบวก)
+    pub commission_per_lot: f64,
+    pub slippage:           Slippage,
+    /// Slippage เพิ่มเมื่อ SL ถูกแตะ (Points)
+    pub sl_slippage_points: f64,
+    /// Swap ต่อ 1 Lot ต่อคืน (สกุลเงินบัญชี, ติดลบ = จ่าย)
+    pub swap_long:          f64,
+    pub swap_short:         f64,
+    /// คืนที่คิด Swap 3 เท่า (ชดเชยเสาร์ / อาทิตย์)
+    pub triple_swap_day:    Weekday,
+}
+
+impl Default for SymbolCosts {
+    fn default() -> Self {
+        Self {
+            commission_per_lot: 0.0,
+            slippage:           Slippage::default(),
+            sl_slippage_points: 0.0,
+            swap_long:          0.0,
+            swap_short:         0.0,
+            triple_swap_day:    Weekday::Wed,
+        }
+    }
+}
+
+impl SymbolCosts {
+    /// Commission ของขาหนึ่ง (ติดลบ)
+    pub fn commission(&self, volume: f64) -> f64 {
+        -self.commission_per_lot.abs() * volume
+    }
+
+    /// Swap ต่อ 1 Lot ของคืนที่เริ่มวัน `night`
+    pub fn swap_per_lot(&self, direction: Direction, night: Weekday) -> f64 {
+        let per_night = match direction {
+            Direction::Sell => self.swap_short,
+            _               => self.swap_long,
+        };
+        match night {
+            Weekday::Sat | Weekday::Sun    => 0.0,
+            d if d == self.triple_swap_day => per_night * 3.0,
+            _                              => per_night,
+        }
+    }
+}
+
+/// Cost Model ทุก Symbol — Key = Symbol หรือ `"*"` (ค่าเริ่มต้น)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CostModel(HashMap<String, SymbolCosts>);
+
+impl CostModel {
+    /// `PAPER_COSTS` — อ่านไม่ได้ = ไม่มีต้นทุน (เตือนใน Log)
+    pub fn from_env() -> Self {
+        let Ok(raw) = std::env::var("PAPER_COSTS") else {
+            return Self::default();
+        };
+        serde_json::from_str(&raw).unwrap_or_else(|e| {
+            warn!(error = %e, "Invalid PAPER_COSTS — simulating without trading costs");
+            Self::default()
+        })
+    }
+
+    pub fn for_symbol(&self, symbol: &str) -> SymbolCosts {
+        self.0.get(symbol).or_else(|| self.0.get("*")).cloned().unwrap_or_default()
+    }
+}
+
+// ─── Trade Costs ──────────────────────────────────────────────────────────────
+
+/// ต้นทุนของ Trade หนึ่งรายการ (สกุลเงินบัญชี, ติดลบ = ต้นทุน)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TradeCosts {
+    pub commission: f64,
+    pub swap:       f64,
+    /// ราคาที่เสียไปจาก Slippage — อยู่ในราคา Fill แล้ว (ไม่นับซ้ำใน `total`)
+    pub slippage:   f64,
+}
+
+impl TradeCosts {
+    /// ต้นทุนที่หักจากกำไรของราคา (Commission + Swap)
+    pub fn total(&self) -> f64 {
+        self.commission + self.swap
+    }
+
+    pub fn add(&mut self, other: TradeCosts) {
+        self.commission += other.commission;
+        self.swap       += other.swap;
+        self.slippage   += other.slippage;
+    }
+
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            commission: self.commission * factor,
+            swap:       self.swap * factor,
+            slippage:   self.slippage * factor,
+        }
+    }
+}
+
+// ─── RNG ──────────────────────────────────────────────────────────────────────
+
+/// สุ่ม Slippage แบบกำหนด Seed ได้ (SplitMix64) — Replay ชุดเดิม + Seed เดิม = ผลเดิม
+#[derive(Debug, Clone, Default)]
+pub struct CostRng(u64);
+
+impl CostRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal (Box–Muller)
+    fn next_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
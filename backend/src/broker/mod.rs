@@ -0,0 +1,278 @@
+//! # broker — Execution Venue Abstraction
+//!
+//! [`Broker`] คือทุกอย่างที่ Engine ต้องการจากโบรกเกอร์ — Route / Reconciler
+//! เรียกผ่าน `state.broker` เท่านั้น เพิ่ม Venue ใหม่ = เพิ่ม Implementation ใหม่
+//! ไม่ต้องแตะ `routes::mt5`
+//!
+//! ```text
+//! BROKER=mt5        → Mt5HttpBroker   (HTTP ไปที่ MT5 Adapter — MT5_BASE_URL)
+//! BROKER=paper      → PaperBroker     (จำลอง Fill / SL / TP / Balance จาก Tick จริง)
+//! BROKER=recording  → RecordingBroker (บันทึกทุกคำสั่ง แล้วส่งต่อให้ PaperBroker)
+//! MT5_BASE_URL=mock → เหมือน BROKER=paper (ค่าเดิมก่อนมี BROKER)
+//! ```
+
+pub mod costs;
+pub mod mt5;
+pub mod paper;
+pub mod recording;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use tokio::sync::RwLock;
+
+use crate::error::AppError;
+use crate::models::{AccountInfo, Direction, OrderKind, TickData};
+use crate::symbols::SymbolRegistry;
+
+pub use costs::{CostModel, TradeCosts};
+pub use mt5::Mt5HttpBroker;
+pub use paper::{PaperBroker, PaperConfig};
+pub use recording::RecordingBroker;
+
+// ─── Order / Fill ─────────────────────────────────────────────────────────────
+
+/// Order ที่ Engine ต้องการส่ง (ไม่ผูกกับ Wire format ของ Venue ใด)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderRequest {
+    pub symbol:     String,
+    pub direction:  Direction,
+    pub kind:       OrderKind,
+    pub volume:     f64,
+    /// Market: ราคาที่ Engine เห็นตอนตัดสินใจ (ask สำหรับ BUY / bid สำหรับ SELL)
+    /// Limit / Stop: ราคาที่ Order รออยู่
+    pub price:      f64,
+    pub sl:         f64,
+    pub tp:         f64,
+    /// Client order ID จาก trade_id — เหมือนเดิมทุกครั้งที่ส่งซ้ำ
+    /// Venue ใช้ตัดคำสั่งซ้ำ และ `Broker::order_status` ใช้ถามผล
+    pub client_id:  String,
+    pub comment:    String,
+    /// Magic number — ใช้แยก Position ของ Antigravity ออกจาก EA ตัวอื่น
+    pub magic:      u64,
+    /// Pending Order หมดอายุเอง ณ เวลานี้ (None = จนกว่าจะยกเลิก)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// ผลการส่ง Order ที่สำเร็จ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderFill {
+    /// Ticket ของ Position — Position Book ใช้เป็น Key
+    pub ticket:  u64,
+    /// ราคาที่ได้จริง (Venue ไม่บอก → ราคาที่ขอ)
+    pub price:   f64,
+    pub comment: String,
+}
+
+/// Pending Order ถูก Fill (บางส่วนหรือทั้งหมด) — EA ส่งมาทาง `/api/mt5/order-fill`
+/// หรือ Venue จำลอง Fill เองใน `on_tick`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingFill {
+    pub order_ticket:     u64,
+    /// Position ที่ Fill นี้เปิด / เพิ่ม — Position Book ใช้เป็น Key
+    pub position_ticket:  u64,
+    pub symbol:           String,
+    /// Volume ของ Fill ครั้งนี้
+    pub volume:           f64,
+    pub price:            f64,
+    /// Volume ที่ยังรอ Fill — 0 = Order ครบแล้ว
+    #[serde(default)]
+    pub remaining_volume: f64,
+}
+
+// ─── Broker Position ──────────────────────────────────────────────────────────
+
+/// Position หนึ่งรายการตามที่โบรกเกอร์รายงาน
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrokerPosition {
+    pub ticket:     u64,
+    pub symbol:     String,
+    pub direction:  Direction,
+    pub volume:     f64,
+    pub price_open: f64,
+    #[serde(default)]
+    pub sl:         f64,
+    #[serde(default)]
+    pub tp:         f64,
+    pub magic:      u64,
+    /// Comment ของ Order ที่เปิด Position (= client_id ของ Antigravity)
+    #[serde(default)]
+    pub comment:    String,
+    /// เวลาเปิดตามโบรกเกอร์ (ถ้ามี)
+    #[serde(default)]
+    pub time:       Option<DateTime<Utc>>,
+}
+
+// ─── Position Close ───────────────────────────────────────────────────────────
+
+/// Position ถูกปิดที่โบรกเกอร์ — EA ส่งมาทาง `/api/mt5/position-close`
+/// หรือ Venue จำลองปิดเอง (SL / TP / Bailout) — ทั้งสองทางเข้า Close path เดียวกัน
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionClose {
+    pub mt5_ticket:   Option<u64>,
+    pub symbol:       String,
+    pub close_price:  f64,
+    /// ไม่ต้องส่ง — Backend คำนวณจาก entry/close ผ่าน SymbolSpec (ใช้ค่านี้เมื่อไม่รู้จัก Symbol)
+    #[serde(default)]
+    pub profit_pips:  Option<f64>,
+    /// กำไร/ขาดทุนจริงในสกุลเงินบัญชี — ใช้คุม Daily/Weekly Loss และ Drawdown
+    #[serde(default)]
+    pub profit:       Option<f64>,
+    /// "TP" | "SL" | "MANUAL" | เหตุผลจาก Engine เช่น "OPPOSING_ZONE_BAILOUT"
+    pub close_reason: String,
+    /// Lot ที่ปิด — ไม่ส่ง = ปิดทั้ง Position / น้อยกว่า Volume ที่เปิด = ปิดบางส่วน (Scale-out)
+    #[serde(default)]
+    pub volume:       Option<f64>,
+}
+
+/// Position ที่ปิดไปแล้วตามประวัติ Deal ของโบรกเกอร์ — ใช้เมื่อ Callback position-close หายไป
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosedPosition {
+    /// ราคาปิดของขาสุดท้าย
+    pub close_price: f64,
+    /// กำไร/ขาดทุนสุทธิรวมทุกขา (Scale-out / Commission / Swap) ในสกุลเงินบัญชี
+    pub profit:      f64,
+}
+
+/// สิ่งที่ Venue จำลองทำเองระหว่าง Tick — Caller ส่งต่อเข้า Path เดียวกับ Callback ของ EA
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrokerEvent {
+    Filled(PendingFill),
+    Closed(PositionClose),
+}
+
+// ─── Broker Trait ─────────────────────────────────────────────────────────────
+
+/// Execution venue — ทุก Method คืน `AppError::ExecutionError` เมื่อ Venue ปฏิเสธ
+/// หรือติดต่อไม่ได้, `AppError::ExecutionUnknown` เมื่อส่งไปแล้วแต่ไม่รู้ผล (Timeout)
+/// และ `AppError::NotFound` เมื่อไม่รู้จัก Ticket
+#[async_trait]
+pub trait Broker: Send + Sync {
+    fn kind(&self) -> BrokerKind;
+
+    /// ส่ง Market Order — สำเร็จต้องได้ Ticket เสมอ
+    async fn send_order(&self, order: &OrderRequest) -> Result<OrderFill, AppError>;
+
+    /// วาง Pending Order (Limit / Stop) — สำเร็จคืน Order ticket (ยังไม่ใช่ Position)
+    /// Fill ตามมาทาง `/api/mt5/order-fill` (MT5) หรือ `on_tick` (Paper)
+    async fn place_order(&self, order: &OrderRequest) -> Result<u64, AppError>;
+
+    /// ผลของ Order ที่ส่งด้วย `client_id` นี้ — ถามหลัง `ExecutionUnknown` ก่อนส่งซ้ำ
+    ///
+    /// `Some` = Venue ได้รับแล้ว (Market: Position ticket / Pending: Order ticket)
+    /// `None` = Venue ไม่เคยได้รับ → ส่งซ้ำได้อย่างปลอดภัย
+    async fn order_status(&self, client_id: &str) -> Result<Option<OrderFill>, AppError>;
+
+    /// ตั้ง SL / TP ใหม่ของ Position (ส่งทั้งคู่ — 0 = ไม่มี)
+    async fn modify_position(&self, ticket: u64, sl: f64, tp: f64) -> Result<(), AppError>;
+
+    /// ปิด Position ที่ราคาตลาด — `volume` None = ทั้งหมด / Some = ปิดบางส่วน (Scale-out)
+    ///
+    /// `Some` = Venue ปิดเสร็จแล้วและรู้ราคาปิด (Paper) → Caller ปิด Book ต่อเลย
+    /// `None` = ผลการปิดจะตามมาทาง `/api/mt5/position-close` (MT5)
+    async fn close_position(&self, ticket: u64, volume: Option<f64>) -> Result<Option<PositionClose>, AppError>;
+
+    /// ยกเลิก Pending Order ส่วนที่ยังไม่ Fill
+    async fn cancel_order(&self, ticket: u64) -> Result<(), AppError>;
+
+    /// Position ของ Antigravity ที่เปิดอยู่ที่โบรกเกอร์ตอนนี้
+    async fn list_positions(&self) -> Result<Vec<BrokerPosition>, AppError>;
+
+    /// Balance / Equity ปัจจุบัน
+    async fn account_info(&self) -> Result<AccountInfo, AppError>;
+
+    /// ผลของ Position ที่ปิดไปแล้ว (ค้นจาก Deal history) — Reconciler ใช้หา P&L ที่ Callback ไม่ได้ส่งมา
+    /// `None` = ไม่พบ / Venue ไม่มีประวัติให้ถาม (Venue จำลองส่งผลการปิดเองทุกครั้ง)
+    async fn closed_position(&self, _ticket: u64) -> Result<Option<ClosedPosition>, AppError> {
+        Ok(None)
+    }
+
+    /// Tick ใหม่ทุก Tick — Venue จำลองใช้ Fill Pending Order และตรวจ SL / TP
+    /// คืน Fill / Position ที่ถูกปิดด้วย Tick นี้ตามลำดับ (Venue จริงไม่ต้องทำอะไร)
+    async fn on_tick(&self, _tick: &TickData) -> Vec<BrokerEvent> {
+        Vec::new()
+    }
+
+    /// Commission / Swap / Slippage ของ Position `ticket` (รวมขาที่ยังเปิด) — Venue จริงรายงานผ่าน
+    /// `profit` ของ Position close แล้ว (None)
+    async fn trade_costs(&self, _ticket: u64) -> Option<TradeCosts> {
+        None
+    }
+}
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerKind {
+    Mt5,
+    Paper,
+    Recording,
+}
+
+impl BrokerKind {
+    /// ไม่มีเงินจริง — EA ต้องไม่ทำตามคำสั่งเทรดใน Tick response
+    pub fn is_simulated(self) -> bool {
+        matches!(self, BrokerKind::Paper | BrokerKind::Recording)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BrokerConfig {
+    pub kind:          BrokerKind,
+    /// Base URL ของ MT5 Adapter (`MT5_BASE_URL`)
+    pub mt5_base_url:  String,
+    pub paper:         PaperConfig,
+}
+
+impl BrokerConfig {
+    pub fn from_env() -> Self {
+        let mt5_base_url = std::env::var("MT5_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:8081".to_string());
+
+        let kind = match std::env::var("BROKER").as_deref() {
+            Ok("paper")     => BrokerKind::Paper,
+            Ok("recording") => BrokerKind::Recording,
+            Ok("mt5") | Err(_) => BrokerKind::Mt5,
+            Ok(other) => {
+                warn!(broker = other, "Unknown BROKER — falling back to mt5");
+                BrokerKind::Mt5
+            }
+        };
+
+        // MT5_BASE_URL=mock คือ MOCK mode เดิม — ไม่มี Adapter ให้คุยด้วย
+        let kind = if kind == BrokerKind::Mt5 && mt5_base_url == "mock" {
+            warn!("MT5_BASE_URL=mock is deprecated — use BROKER=paper");
+            BrokerKind::Paper
+        } else {
+            kind
+        };
+
+        Self {
+            kind,
+            mt5_base_url,
+            paper: PaperConfig::from_env(),
+        }
+    }
+}
+
+/// สร้าง Broker ตาม Config — Paper ใช้ SymbolSpec ชุดเดียวกับ Engine (Pip / Tick value)
+pub fn from_config(
+    config:  &BrokerConfig,
+    client:  reqwest::Client,
+    symbols: Arc<RwLock<SymbolRegistry>>,
+) -> Arc<dyn Broker> {
+    info!(broker = ?config.kind, "🏦 Broker selected");
+
+    match config.kind {
+        BrokerKind::Mt5       => Arc::new(Mt5HttpBroker::new(client, &config.mt5_base_url)),
+        BrokerKind::Paper     => Arc::new(PaperBroker::new(config.paper.clone(), symbols)),
+        BrokerKind::Recording => Arc::new(RecordingBroker::new(Arc::new(
+            PaperBroker::new(config.paper.clone(), symbols),
+        ))),
+    }
+}
@@ -0,0 +1,344 @@
+//! # broker::mt5 — MT5 HTTP Adapter
+//!
+//! ## MT5 Adapter API Contract (ฝั่ง MQL5)
+//! ```text
+//! POST /order/send      { symbol, action: "BUY"|"SELL", volume, price, sl, tp, client_id, comment, magic }
+//! POST /order/place     { symbol, action: "BUY_LIMIT"|"SELL_LIMIT"|"BUY_STOP"|"SELL_STOP",
+//!                         volume, price, sl, tp, client_id, comment, magic, expiration }
+//! POST /position/modify { ticket, sl, tp }
+//! POST /position/close  { ticket, volume }   volume null = ปิดทั้งหมด / ค่า = ปิดบางส่วน
+//! POST /order/cancel    { ticket }
+//!   → { "retcode": 10009, "order": 123456, "price": 2345.10, "comment": "Request completed" }
+//!
+//! GET  /positions?magic=420001 → { "positions": [ BrokerPosition, ... ] }
+//! GET  /account                → { "balance": 10000.0, "equity": 10012.5, "currency": "USD" }
+//! GET  /order/status?client_id=AGV-… → { "found": true, "order": 123456, "price": 2345.10 }
+//!                                    | { "found": false }
+//! GET  /position/history?ticket=123456 → { "found": true, "close_price": 2351.20, "profit": -42.5 }
+//!                                      | { "found": false }   profit = สุทธิรวมทุก Deal ของ Position
+//! ```
+//! retcode 10009 = `TRADE_RETCODE_DONE` (สำเร็จ) — `order` จำเป็นสำหรับ `/order/send` และ `/order/place`
+//! `expiration` = Unix seconds (`ORDER_TIME_SPECIFIED`), 0 = GTC
+//! Fill ของ Pending Order → EA เรียก `/api/mt5/order-fill` ของ Backend
+//! Exit Policy ของ Reflex Loop ไม่เรียก `/position/modify` / `/position/close` — ผ่านคิวคำสั่งของ EA
+//! (`engine::commands`) ที่ต้องได้ Ack ก่อน Book เปลี่ยน
+//!
+//! ## Idempotency
+//! `client_id` ต้องถูกใส่เป็น Order comment — Adapter ที่ได้ `client_id` ซ้ำ
+//! ต้องคืนผลของ Order เดิมแทนการส่งใหม่ และ `/order/status` ค้นจาก comment
+//! (Position / Order / History) — Timeout หลังส่งจึงถามผลได้ก่อนส่งซ้ำ
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use super::{Broker, BrokerKind, BrokerPosition, ClosedPosition, OrderFill, OrderRequest, PositionClose};
+use crate::engine::executor::AGV_MAGIC;
+use crate::error::AppError;
+use crate::models::{AccountInfo, Direction, OrderKind};
+
+/// `TRADE_RETCODE_DONE`
+const RETCODE_DONE: u32 = 10009;
+
+/// ห้ามรอ MT5 นานกว่านี้
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// ─── Wire Format ──────────────────────────────────────────────────────────────
+
+/// Payload ของ `/order/send` และ `/order/place`
+#[derive(Debug, Serialize)]
+struct Mt5OrderRequest<'a> {
+    symbol:     &'a str,
+    action:     &'static str,  // "BUY" | "SELL" | "BUY_LIMIT" | ... | "SELL_STOP"
+    volume:     f64,
+    price:      f64,
+    sl:         f64,
+    tp:         f64,
+    client_id:  &'a str,       // Idempotency key — ต้องเป็น Order comment ด้วย
+    comment:    &'a str,
+    magic:      u64,           // Antigravity magic number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiration: Option<i64>,   // เฉพาะ /order/place
+}
+
+impl<'a> Mt5OrderRequest<'a> {
+    fn new(order: &'a OrderRequest) -> Result<Self, AppError> {
+        let action = match (order.direction, order.kind) {
+            (Direction::Buy,  OrderKind::Market) => "BUY",
+            (Direction::Sell, OrderKind::Market) => "SELL",
+            (Direction::Buy,  OrderKind::Limit)  => "BUY_LIMIT",
+            (Direction::Sell, OrderKind::Limit)  => "SELL_LIMIT",
+            (Direction::Buy,  OrderKind::Stop)   => "BUY_STOP",
+            (Direction::Sell, OrderKind::Stop)   => "SELL_STOP",
+            (Direction::NoTrade, _) => {
+                return Err(AppError::BadRequest("Cannot send order for NoTrade direction".into()))
+            }
+        };
+
+        Ok(Self {
+            symbol:     &order.symbol,
+            action,
+            volume:     order.volume,
+            price:      order.price,
+            sl:         order.sl,
+            tp:         order.tp,
+            client_id:  &order.client_id,
+            comment:    &order.comment,
+            magic:      order.magic,
+            expiration: (order.kind != OrderKind::Market)
+                .then(|| order.expires_at.map_or(0, |t| t.timestamp())),
+        })
+    }
+}
+
+/// Response ของทุกคำสั่งเทรด
+#[derive(Debug, Deserialize)]
+struct Mt5TradeResponse {
+    /// MT5 Return Code — 10009 = SUCCESS
+    retcode: u32,
+    /// MT5 Ticket / Order ID (มีเมื่อ retcode = 10009)
+    #[serde(default)]
+    order:   Option<u64>,
+    /// ราคาที่ Fill จริง
+    #[serde(default)]
+    price:   Option<f64>,
+    /// ข้อความอธิบายจาก MT5
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+/// Response ของ `/order/status`
+#[derive(Debug, Deserialize)]
+struct Mt5OrderStatusResponse {
+    found:   bool,
+    #[serde(default)]
+    order:   Option<u64>,
+    #[serde(default)]
+    price:   Option<f64>,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+/// Response ของ `/position/history`
+#[derive(Debug, Deserialize)]
+struct Mt5PositionHistoryResponse {
+    found:       bool,
+    #[serde(default)]
+    close_price: Option<f64>,
+    #[serde(default)]
+    profit:      Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mt5PositionsResponse {
+    positions: Vec<BrokerPosition>,
+}
+
+// ─── Mt5HttpBroker ────────────────────────────────────────────────────────────
+
+pub struct Mt5HttpBroker {
+    client:   reqwest::Client,
+    base_url: String,
+}
+
+impl Mt5HttpBroker {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// แปลง Response เป็น JSON
+    ///
+    /// ต่อไม่ติด / HTTP error → `ExecutionError` (คำสั่งไม่ถูกทำ)
+    /// Timeout / Response หายหรืออ่านไม่ได้หลังส่งแล้ว / HTTP 504 → `ExecutionUnknown`
+    async fn parse<T: DeserializeOwned>(
+        response: Result<reqwest::Response, reqwest::Error>,
+    ) -> Result<T, AppError> {
+        let response = response.map_err(|e| {
+            if e.is_connect() {
+                error!(error = %e, "MT5 unreachable");
+                AppError::ExecutionError(format!("MT5 unreachable: {e}"))
+            } else {
+                error!(error = %e, "MT5 request lost after send");
+                AppError::ExecutionUnknown(format!("MT5 request lost: {e}"))
+            }
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body   = response.text().await.unwrap_or_default();
+            error!(http_status = %status, body = %body, "MT5 returned HTTP error");
+            if status == reqwest::StatusCode::GATEWAY_TIMEOUT {
+                return Err(AppError::ExecutionUnknown(format!("MT5 HTTP {status}: {body}")));
+            }
+            return Err(AppError::ExecutionError(format!("MT5 HTTP {status}: {body}")));
+        }
+
+        response.json().await.map_err(|e| {
+            error!(error = %e, "MT5 response parse failed");
+            AppError::ExecutionUnknown(format!("MT5 response parse error: {e}"))
+        })
+    }
+
+    /// POST คำสั่งเทรด — retcode ≠ 10009 → `ExecutionError`
+    async fn trade<B: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<Mt5TradeResponse, AppError> {
+        let response = self
+            .client
+            .post(format!("{}{path}", self.base_url))
+            .json(body)
+            .timeout(TIMEOUT)
+            .send()
+            .await;
+        let resp: Mt5TradeResponse = Self::parse(response).await?;
+
+        if resp.retcode != RETCODE_DONE {
+            let msg = format!(
+                "MT5 rejected {path}: retcode={} comment={}",
+                resp.retcode,
+                resp.comment.as_deref().unwrap_or("unknown")
+            );
+            warn!("{msg}");
+            return Err(AppError::ExecutionError(msg));
+        }
+
+        Ok(resp)
+    }
+
+    async fn get<T: DeserializeOwned, Q: Serialize + ?Sized>(&self, path: &str, query: &Q) -> Result<T, AppError> {
+        let response = self
+            .client
+            .get(format!("{}{path}", self.base_url))
+            .query(query)
+            .timeout(TIMEOUT)
+            .send()
+            .await;
+        Self::parse(response).await
+    }
+}
+
+#[async_trait]
+impl Broker for Mt5HttpBroker {
+    fn kind(&self) -> BrokerKind {
+        BrokerKind::Mt5
+    }
+
+    async fn send_order(&self, order: &OrderRequest) -> Result<OrderFill, AppError> {
+        let request = Mt5OrderRequest::new(order)?;
+
+        info!(
+            symbol  = %order.symbol,
+            action  = request.action,
+            volume  = order.volume,
+            price   = order.price,
+            sl      = order.sl,
+            tp      = order.tp,
+            mt5_url = %self.base_url,
+            "🚀 [MT5] Sending order"
+        );
+
+        let resp = self.trade("/order/send", &request).await?;
+
+        // Position Book ใช้ Ticket เป็น Key — Order ที่ไม่มี Ticket ติดตามต่อไม่ได้
+        let Some(ticket) = resp.order else {
+            let msg = "MT5 accepted order but returned no ticket".to_string();
+            warn!("{msg}");
+            return Err(AppError::ExecutionError(msg));
+        };
+
+        Ok(OrderFill {
+            ticket,
+            price:   resp.price.unwrap_or(order.price),
+            comment: resp.comment.unwrap_or_else(|| "Request completed".to_string()),
+        })
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<u64, AppError> {
+        if order.kind == OrderKind::Market {
+            return Err(AppError::BadRequest("place_order requires a LIMIT or STOP order".into()));
+        }
+        let request = Mt5OrderRequest::new(order)?;
+
+        info!(
+            symbol     = %order.symbol,
+            action     = request.action,
+            volume     = order.volume,
+            price      = order.price,
+            expiration = ?request.expiration,
+            mt5_url    = %self.base_url,
+            "📌 [MT5] Placing pending order"
+        );
+
+        let resp = self.trade("/order/place", &request).await?;
+        resp.order.ok_or_else(|| {
+            let msg = "MT5 accepted pending order but returned no ticket".to_string();
+            warn!("{msg}");
+            AppError::ExecutionError(msg)
+        })
+    }
+
+    async fn order_status(&self, client_id: &str) -> Result<Option<OrderFill>, AppError> {
+        let resp: Mt5OrderStatusResponse = self.get("/order/status", &[("client_id", client_id)]).await?;
+        if !resp.found {
+            return Ok(None);
+        }
+
+        let Some(ticket) = resp.order else {
+            return Err(AppError::ExecutionUnknown(format!(
+                "MT5 found order {client_id} but returned no ticket"
+            )));
+        };
+        info!(client_id, ticket, "🔎 [MT5] Order found by client_id");
+
+        Ok(Some(OrderFill {
+            ticket,
+            price:   resp.price.unwrap_or_default(),
+            comment: resp.comment.unwrap_or_else(|| "Recovered by order status".to_string()),
+        }))
+    }
+
+    async fn modify_position(&self, ticket: u64, sl: f64, tp: f64) -> Result<(), AppError> {
+        self.trade("/position/modify", &json!({ "ticket": ticket, "sl": sl, "tp": tp }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn close_position(&self, ticket: u64, volume: Option<f64>) -> Result<Option<PositionClose>, AppError> {
+        // ราคาปิด / กำไรจริงตามมากับ Callback position-close ของ EA
+        self.trade("/position/close", &json!({ "ticket": ticket, "volume": volume }))
+            .await
+            .map(|_| None)
+    }
+
+    async fn cancel_order(&self, ticket: u64) -> Result<(), AppError> {
+        self.trade("/order/cancel", &json!({ "ticket": ticket }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn list_positions(&self) -> Result<Vec<BrokerPosition>, AppError> {
+        let parsed: Mt5PositionsResponse = self.get("/positions", &[("magic", AGV_MAGIC)]).await?;
+        Ok(parsed.positions)
+    }
+
+    async fn account_info(&self) -> Result<AccountInfo, AppError> {
+        self.get("/account", &[] as &[(&str, &str)]).await
+    }
+
+    async fn closed_position(&self, ticket: u64) -> Result<Option<ClosedPosition>, AppError> {
+        let resp: Mt5PositionHistoryResponse = self.get("/position/history", &[("ticket", ticket)]).await?;
+        match (resp.found, resp.close_price, resp.profit) {
+            (false, _, _) => Ok(None),
+            (true, Some(close_price), Some(profit)) => Ok(Some(ClosedPosition { close_price, profit })),
+            _ => Err(AppError::ExecutionError(format!(
+                "MT5 found closed position {ticket} but returned no close_price / profit"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,791 @@
+//! # broker::paper — Paper Trading
+//!
+//! โบรกเกอร์จำลองที่ใช้ราคาจาก Tick จริง — Forward-test Strategy ของ OpenClaw
+//! ได้เป็นสัปดาห์โดยไม่ต้องมี MT5 Terminal และไม่เสี่ยงเงินจริง (`BROKER=paper`)
+//!
+//! ```text
+//! send_order     → รอ PAPER_LATENCY_MS → Fill ที่ ask (BUY) / bid (SELL) ของ Tick ล่าสุด
+//!                  ± PAPER_SLIPPAGE_PIPS (ไปทางที่เสียเปรียบเสมอ) + Slippage ของ Cost Model
+//! place_order    → เก็บ Limit / Stop ไว้จนราคามาถึง (Position ticket = Order ticket)
+//! client_id ซ้ำ  → คืนผลเดิม ไม่ Fill / วางซ้ำ (order_status ถามผลจาก client_id ได้)
+//! on_tick        → Fill Pending Order ที่ราคามาถึงทั้ง Volume (Limit ไม่มี Slippage / Stop มี)
+//!                  แล้ว BUY ปิดที่ bid / SELL ปิดที่ ask เมื่อแตะ SL (+ Slippage ของ SL) หรือ TP
+//!                  และคิด Swap ของทุก Position ที่ข้าม Rollover (เที่ยงคืนของเวลา Tick)
+//! modify         → Break-Even / Trailing ย้าย SL จริง (SL ใหม่มีผลกับ Tick ถัดไป)
+//! close_position → Bailout / Scale-out ปิดทั้งหมดหรือบางส่วนที่ราคาตลาด (มี Slippage)
+//! balance        = PAPER_BALANCE + กำไร/ขาดทุนที่ปิดแล้ว (หัก Commission / Swap แล้ว)
+//! equity         = balance + กำไร/ขาดทุนของ Position ที่เปิดอยู่ ณ Tick ล่าสุด − ต้นทุนที่ค้างอยู่
+//! ```
+//!
+//! ต้นทุน (Commission / Slippage / Swap) ตาม [`CostModel`] (`PAPER_COSTS`) — `PositionClose.profit` รวม
+//! Commission + Swap เหมือนที่ EA รายงาน, แยกรายการได้จาก [`Broker::trade_costs`]
+//!
+//! Fill / Position ที่ปิดคืนเป็น [`BrokerEvent`] ให้ Caller ส่งเข้า Path เดียวกับ
+//! Callback `/api/mt5/order-fill` และ `/api/mt5/position-close` ของ EA
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use tokio::sync::{Mutex, RwLock};
+use tracing::info;
+
+use super::costs::{CostModel, CostRng, SymbolCosts, TradeCosts};
+use super::{
+    Broker, BrokerEvent, BrokerKind, BrokerPosition, OrderFill, OrderRequest, PendingFill, PositionClose,
+};
+use crate::clock::Clock;
+use crate::error::AppError;
+use crate::models::{AccountInfo, Direction, OrderKind, TickData};
+use crate::symbols::{SymbolRegistry, SymbolSpec};
+
+/// Ticket แรกของ Paper account
+const FIRST_TICKET: u64 = 900_000;
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct PaperConfig {
+    /// Balance เริ่มต้น
+    pub balance:        f64,
+    /// Slippage ต่อ Fill (Pips ตาม SymbolSpec) — ไปทางที่เสียเปรียบเสมอ
+    pub slippage_pips:  f64,
+    /// เวลาที่ Order ใช้เดินทางถึง "โบรกเกอร์" — Fill ที่ราคาหลังจากรอ
+    pub latency_ms:     u64,
+    /// Commission / Slippage / Swap ต่อ Symbol (`PAPER_COSTS`)
+    pub costs:          CostModel,
+    /// Seed ของ Slippage แบบสุ่ม (`PAPER_SEED`)
+    pub seed:           u64,
+}
+
+impl PaperConfig {
+    pub fn from_env() -> Self {
+        Self {
+            balance:       env_parse("PAPER_BALANCE", 10_000.0),
+            slippage_pips: env_parse("PAPER_SLIPPAGE_PIPS", 0.0),
+            latency_ms:    env_parse("PAPER_LATENCY_MS", 0),
+            costs:         CostModel::from_env(),
+            seed:          env_parse("PAPER_SEED", 0),
+        }
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+// ─── Paper Account ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+struct Quote {
+    bid: f64,
+    ask: f64,
+}
+
+impl Quote {
+    /// ราคาที่ปิด Position ทิศทางนี้ได้ (BUY ขายที่ bid / SELL ซื้อคืนที่ ask)
+    fn exit_price(&self, direction: Direction) -> f64 {
+        match direction {
+            Direction::Sell => self.ask,
+            _               => self.bid,
+        }
+    }
+
+    /// ราคาที่เปิด Position ทิศทางนี้ได้ (BUY ซื้อที่ ask / SELL ขายที่ bid)
+    fn entry_price(&self, direction: Direction) -> f64 {
+        match direction {
+            Direction::Sell => self.bid,
+            _               => self.ask,
+        }
+    }
+}
+
+/// ต้นทุนของ Position หนึ่งตัว
+#[derive(Debug, Default)]
+struct Ledger {
+    /// ยังไม่ Realise — Commission ขาเปิด + Swap สะสม (ตัดตามสัดส่วน Volume ที่ปิดแต่ละขา)
+    accrued: TradeCosts,
+    /// Realise แล้วกับขาที่ปิด + Slippage ทุกขา
+    booked:  TradeCosts,
+}
+
+/// ราคาที่ Fill ได้จริง + ต้นทุนของขานั้น
+#[derive(Debug, Clone, Copy)]
+struct Fill {
+    price: f64,
+    costs: TradeCosts,
+}
+
+impl Fill {
+    /// ได้ราคาที่ขอพอดี (Limit / TP) — มีแต่ Commission
+    fn exact(price: f64, costs: &SymbolCosts, volume: f64) -> Self {
+        Self { price, costs: TradeCosts { commission: costs.commission(volume), ..TradeCosts::default() } }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Account {
+    positions: HashMap<u64, BrokerPosition>,
+    /// Pending Order ที่ยังไม่ Fill — Key = Order ticket
+    orders:    HashMap<u64, OrderRequest>,
+    /// ผลของทุก Order ที่ได้รับ — Key = client_id
+    received:  HashMap<String, OrderFill>,
+    quotes:    HashMap<String, Quote>,
+    balance:   f64,
+    /// ต้นทุนต่อ Ticket (เก็บไว้หลังปิดให้ `trade_costs` ถามได้)
+    ledgers:   HashMap<u64, Ledger>,
+    rng:       CostRng,
+    /// วันของ Tick ล่าสุด — ข้ามวัน = Rollover (คิด Swap)
+    day:       Option<NaiveDate>,
+}
+
+impl Account {
+    /// Market Fill ที่ราคา `base` — `buy` = ขานี้ซื้อ (เปิด BUY / ปิด SELL: Slippage ดันราคาขึ้น)
+    /// `slippage` = ระยะราคาที่เสียเปรียบนอก Cost Model (`PAPER_SLIPPAGE_PIPS` / Slippage ของ SL)
+    fn market_fill(
+        &mut self,
+        spec:     &SymbolSpec,
+        costs:    &SymbolCosts,
+        buy:      bool,
+        base:     f64,
+        slippage: f64,
+        volume:   f64,
+    ) -> Fill {
+        let slippage = slippage + costs.slippage.sample(&mut self.rng) * spec.point;
+        let price    = spec.normalize_price(if buy { base + slippage } else { base - slippage });
+        let adverse  = if buy { price - base } else { base - price };
+        Fill {
+            price,
+            costs: TradeCosts {
+                commission: costs.commission(volume),
+                swap:       0.0,
+                slippage:   -spec.profit(adverse, volume),
+            },
+        }
+    }
+
+    /// Position ใหม่ + Commission ขาเปิด (Realise ตอนปิด)
+    fn open(&mut self, fill: Fill, position: BrokerPosition) {
+        let ledger = self.ledgers.entry(position.ticket).or_default();
+        ledger.accrued.commission += fill.costs.commission;
+        ledger.booked.slippage    += fill.costs.slippage;
+        self.positions.insert(position.ticket, position);
+    }
+
+    /// Rollover ทุกเที่ยงคืนระหว่างวันของ Tick ก่อนหน้ากับ `now` → Swap ของ Position ที่เปิดก่อนเที่ยงคืนนั้น
+    fn rollover(&mut self, now: DateTime<Utc>, model: &CostModel) {
+        let today = now.date_naive();
+        let Some(mut night) = self.day.replace(today).filter(|day| *day < today) else { return };
+
+        while night < today {
+            let Some(next) = night.succ_opt() else { break };
+            let at = next.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+            for position in self.positions.values() {
+                if position.time.is_some_and(|t| t >= at) {
+                    continue;
+                }
+                let swap = model.for_symbol(&position.symbol).swap_per_lot(position.direction, night.weekday())
+                    * position.volume;
+                if swap != 0.0 {
+                    self.ledgers.entry(position.ticket).or_default().accrued.swap += swap;
+                }
+            }
+            night = next;
+        }
+    }
+
+    /// ต้นทุนที่ค้างอยู่ของ Position ที่เปิด (Equity)
+    fn accrued(&self) -> f64 {
+        self.positions.keys().filter_map(|t| self.ledgers.get(t)).map(|l| l.accrued.total()).sum()
+    }
+
+    /// ลบ Position (หรือลด Volume ถ้าปิดบางส่วน) แล้ว Realise กำไร/ขาดทุน + ต้นทุนของขานี้เข้า Balance
+    fn settle(
+        &mut self,
+        ticket: u64,
+        fill:   Fill,
+        spec:   &SymbolSpec,
+        reason: &str,
+        volume: Option<f64>,
+    ) -> Option<PositionClose> {
+        let price = fill.price;
+        // ปิดบางส่วน → ลด Volume ของ Position เดิม / ไม่งั้นปิดทั้งหมด
+        let open_volume = self.positions.get(&ticket)?.volume;
+        let partial     = volume.filter(|v| *v < open_volume - 1e-9);
+        let position    = match partial {
+            Some(v) => {
+                let open    = self.positions.get_mut(&ticket)?;
+                let left    = open_volume - v;
+                open.volume = spec.normalize_volume(left).unwrap_or(left);
+                BrokerPosition { volume: v, ..open.clone() }
+            }
+            None => self.positions.remove(&ticket)?,
+        };
+        // ต้นทุนค้างตามสัดส่วน Volume ที่ปิด + Commission / Slippage ของขานี้
+        let ledger    = self.ledgers.entry(ticket).or_default();
+        let share     = ledger.accrued.scaled((position.volume / open_volume).min(1.0));
+        let mut costs = fill.costs;
+        costs.add(TradeCosts { slippage: 0.0, ..share });
+        ledger.accrued.add(share.scaled(-1.0));
+        ledger.booked.add(costs);
+
+        let delta    = favourable_move(&position, price);
+        let profit   = spec.profit(delta, position.volume) + costs.total();
+        self.balance += profit;
+
+        info!(
+            ticket,
+            symbol     = %position.symbol,
+            price,
+            profit,
+            commission = costs.commission,
+            swap       = costs.swap,
+            balance    = self.balance,
+            reason,
+            "🎭 [PAPER] Position closed"
+        );
+
+        Some(PositionClose {
+            mt5_ticket:   Some(ticket),
+            symbol:       position.symbol,
+            close_price:  price,
+            profit_pips:  Some(spec.pips(delta)),
+            profit:       Some(profit),
+            close_reason: reason.to_string(),
+            volume:       partial,
+        })
+    }
+}
+
+fn favourable_move(position: &BrokerPosition, price: f64) -> f64 {
+    match position.direction {
+        Direction::Sell => position.price_open - price,
+        _               => price - position.price_open,
+    }
+}
+
+/// Pending Order ถูกราคาตลาดแตะแล้ว
+fn order_triggered(order: &OrderRequest, quote: Quote) -> bool {
+    let market = quote.entry_price(order.direction);
+    match (order.kind, order.direction) {
+        (OrderKind::Limit, Direction::Buy) | (OrderKind::Stop, Direction::Sell) => market <= order.price,
+        (OrderKind::Limit, Direction::Sell) | (OrderKind::Stop, Direction::Buy) => market >= order.price,
+        _ => false,
+    }
+}
+
+/// SL / TP ที่ถูกแตะที่ราคาปิด `price`
+fn hit_reason(position: &BrokerPosition, price: f64) -> Option<&'static str> {
+    let (sl_hit, tp_hit) = match position.direction {
+        Direction::Sell => (position.sl > 0.0 && price >= position.sl, position.tp > 0.0 && price <= position.tp),
+        _               => (position.sl > 0.0 && price <= position.sl, position.tp > 0.0 && price >= position.tp),
+    };
+    // แตะทั้งคู่ใน Tick เดียว (Gap) → ถือว่าโดน SL ก่อน
+    if sl_hit {
+        Some("SL")
+    } else if tp_hit {
+        Some("TP")
+    } else {
+        None
+    }
+}
+
+// ─── PaperBroker ──────────────────────────────────────────────────────────────
+
+pub struct PaperBroker {
+    config:      PaperConfig,
+    /// Pip size / Tick value ชุดเดียวกับ Engine
+    symbols:     Arc<RwLock<SymbolRegistry>>,
+    /// Ticket ถัดไป — ต้องไม่ซ้ำกัน เพราะ Position Book ใช้ Ticket เป็น Key
+    next_ticket: AtomicU64,
+    account:     Mutex<Account>,
+    /// เวลาเปิด Position / เวลาของ Account (Backtest = เวลาของ Tick)
+    clock:       Arc<Clock>,
+}
+
+impl PaperBroker {
+    pub fn new(config: PaperConfig, symbols: Arc<RwLock<SymbolRegistry>>) -> Self {
+        Self::with_clock(config, symbols, Arc::new(Clock::system()))
+    }
+
+    pub fn with_clock(config: PaperConfig, symbols: Arc<RwLock<SymbolRegistry>>, clock: Arc<Clock>) -> Self {
+        let account = Account { balance: config.balance, rng: CostRng::new(config.seed), ..Account::default() };
+        Self {
+            config,
+            symbols,
+            next_ticket: AtomicU64::new(FIRST_TICKET),
+            account:     Mutex::new(account),
+            clock,
+        }
+    }
+
+    async fn spec(&self, symbol: &str) -> Result<SymbolSpec, AppError> {
+        self.symbols
+            .read()
+            .await
+            .get(symbol)
+            .cloned()
+            .ok_or_else(|| AppError::ExecutionError(format!("Paper: no SymbolSpec for {symbol}")))
+    }
+
+    /// Slippage เป็นหน่วยราคา
+    fn slippage(&self, spec: &SymbolSpec) -> f64 {
+        self.config.slippage_pips * spec.pip_size
+    }
+
+    fn costs(&self, symbol: &str) -> SymbolCosts {
+        self.config.costs.for_symbol(symbol)
+    }
+}
+
+fn unknown_ticket(ticket: u64) -> AppError {
+    AppError::NotFound(format!("Paper position {ticket} not found"))
+}
+
+#[async_trait]
+impl Broker for PaperBroker {
+    fn kind(&self) -> BrokerKind {
+        BrokerKind::Paper
+    }
+
+    async fn send_order(&self, order: &OrderRequest) -> Result<OrderFill, AppError> {
+        if order.direction == Direction::NoTrade {
+            return Err(AppError::BadRequest("Cannot send order for NoTrade direction".into()));
+        }
+        let spec = self.spec(&order.symbol).await?;
+
+        if self.config.latency_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(self.config.latency_ms)).await;
+        }
+
+        let mut account = self.account.lock().await;
+        if let Some(fill) = account.received.get(&order.client_id) {
+            info!(client_id = %order.client_id, ticket = fill.ticket, "🎭 [PAPER] Duplicate order ignored");
+            return Ok(fill.clone());
+        }
+        let quote = *account.quotes.get(&order.symbol).ok_or_else(|| {
+            AppError::ExecutionError(format!("Paper: no market price for {}", order.symbol))
+        })?;
+
+        let costs = self.costs(&order.symbol);
+        let buy   = order.direction != Direction::Sell;
+        let base  = quote.entry_price(order.direction);
+        let fill  = account.market_fill(&spec, &costs, buy, base, self.slippage(&spec), order.volume);
+        let price = fill.price;
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        account.open(fill, BrokerPosition {
+            ticket,
+            symbol:     order.symbol.clone(),
+            direction:  order.direction,
+            volume:     order.volume,
+            price_open: price,
+            sl:         order.sl,
+            tp:         order.tp,
+            magic:      order.magic,
+            comment:    order.comment.clone(),
+            time:       Some(self.clock.now()),
+        });
+
+        info!(ticket, symbol = %order.symbol, requested = order.price, price, "🎭 [PAPER] Order filled");
+
+        let fill = OrderFill {
+            ticket,
+            price,
+            comment: "Paper fill".to_string(),
+        };
+        account.received.insert(order.client_id.clone(), fill.clone());
+        Ok(fill)
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<u64, AppError> {
+        if order.direction == Direction::NoTrade || order.kind == OrderKind::Market {
+            return Err(AppError::BadRequest("Paper: pending order must be a BUY/SELL LIMIT or STOP".into()));
+        }
+        self.spec(&order.symbol).await?;
+
+        let mut account = self.account.lock().await;
+        if let Some(fill) = account.received.get(&order.client_id) {
+            info!(client_id = %order.client_id, ticket = fill.ticket, "🎭 [PAPER] Duplicate pending order ignored");
+            return Ok(fill.ticket);
+        }
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        account.orders.insert(ticket, order.clone());
+        account.received.insert(order.client_id.clone(), OrderFill {
+            ticket,
+            price:   order.price,
+            comment: "Paper pending order".to_string(),
+        });
+
+        info!(ticket, symbol = %order.symbol, kind = ?order.kind, price = order.price, "🎭 [PAPER] Pending order placed");
+        Ok(ticket)
+    }
+
+    async fn order_status(&self, client_id: &str) -> Result<Option<OrderFill>, AppError> {
+        Ok(self.account.lock().await.received.get(client_id).cloned())
+    }
+
+    async fn modify_position(&self, ticket: u64, sl: f64, tp: f64) -> Result<(), AppError> {
+        let mut account = self.account.lock().await;
+        let position = account.positions.get_mut(&ticket).ok_or_else(|| unknown_ticket(ticket))?;
+        position.sl = sl;
+        position.tp = tp;
+        Ok(())
+    }
+
+    async fn close_position(&self, ticket: u64, volume: Option<f64>) -> Result<Option<PositionClose>, AppError> {
+        let symbol = {
+            let account = self.account.lock().await;
+            account.positions.get(&ticket).ok_or_else(|| unknown_ticket(ticket))?.symbol.clone()
+        };
+        let spec = self.spec(&symbol).await?;
+
+        let mut account = self.account.lock().await;
+        let (direction, open_volume, quote) = match (account.positions.get(&ticket), account.quotes.get(&symbol)) {
+            (Some(p), Some(q)) => (p.direction, p.volume, *q),
+            (None, _)          => return Err(unknown_ticket(ticket)),
+            (_, None) => {
+                return Err(AppError::ExecutionError(format!("Paper: no market price for {symbol}")))
+            }
+        };
+
+        // ปิด BUY = ขายที่ bid / ปิด SELL = ซื้อคืนที่ ask
+        let buy  = direction == Direction::Sell;
+        let leg  = volume.unwrap_or(open_volume).min(open_volume);
+        let base = quote.exit_price(direction);
+        let fill = account.market_fill(&spec, &self.costs(&symbol), buy, base, self.slippage(&spec), leg);
+
+        Ok(account.settle(ticket, fill, &spec, "MANUAL", volume))
+    }
+
+    async fn cancel_order(&self, ticket: u64) -> Result<(), AppError> {
+        match self.account.lock().await.orders.remove(&ticket) {
+            Some(order) => {
+                info!(ticket, symbol = %order.symbol, "🎭 [PAPER] Pending order cancelled");
+                Ok(())
+            }
+            None => Err(AppError::NotFound(format!("Paper order {ticket} not found"))),
+        }
+    }
+
+    async fn list_positions(&self) -> Result<Vec<BrokerPosition>, AppError> {
+        let mut positions: Vec<_> = self.account.lock().await.positions.values().cloned().collect();
+        positions.sort_by_key(|p| p.ticket);
+        Ok(positions)
+    }
+
+    async fn account_info(&self) -> Result<AccountInfo, AppError> {
+        let (balance, accrued, open) = {
+            let account = self.account.lock().await;
+            let open: Vec<_> = account
+                .positions
+                .values()
+                .filter_map(|p| account.quotes.get(&p.symbol).map(|q| (p.clone(), *q)))
+                .collect();
+            (account.balance, account.accrued(), open)
+        };
+
+        let mut floating = 0.0;
+        for (position, quote) in open {
+            let spec = self.spec(&position.symbol).await?;
+            floating += spec.profit(favourable_move(&position, quote.exit_price(position.direction)), position.volume);
+        }
+
+        Ok(AccountInfo {
+            balance,
+            equity:      balance + floating + accrued,
+            margin_free: None,
+            currency:    Some("USD".to_string()),
+            updated_at:  self.clock.now(),
+        })
+    }
+
+    async fn on_tick(&self, tick: &TickData) -> Vec<BrokerEvent> {
+        let spec = self.symbols.read().await.get(&tick.symbol).cloned();
+
+        let mut account = self.account.lock().await;
+        account.quotes.insert(tick.symbol.clone(), Quote { bid: tick.bid, ask: tick.ask });
+        account.rollover(self.clock.now(), &self.config.costs);
+
+        let Some(spec) = spec else { return Vec::new() };
+        let quote = Quote { bid: tick.bid, ask: tick.ask };
+        let costs = self.costs(&tick.symbol);
+
+        // ── Pending Order ที่ราคามาถึง → Position (Ticket เดียวกับ Order) ────────
+        let mut triggered: Vec<u64> = account
+            .orders
+            .iter()
+            .filter(|(_, o)| o.symbol == tick.symbol && order_triggered(o, quote))
+            .map(|(ticket, _)| *ticket)
+            .collect();
+        triggered.sort_unstable();
+
+        let mut events = Vec::new();
+        for ticket in triggered {
+            let Some(order) = account.orders.remove(&ticket) else { continue };
+            // Limit ได้ราคาตลาดซึ่งดีกว่าหรือเท่ากับราคาที่วาง / Stop กลายเป็น Market → มี Slippage
+            let base = quote.entry_price(order.direction);
+            let fill = match order.kind {
+                OrderKind::Stop => {
+                    let buy = order.direction != Direction::Sell;
+                    account.market_fill(&spec, &costs, buy, base, self.slippage(&spec), order.volume)
+                }
+                _ => Fill::exact(spec.normalize_price(base), &costs, order.volume),
+            };
+            let price = fill.price;
+
+            account.open(fill, BrokerPosition {
+                ticket,
+                symbol:     order.symbol.clone(),
+                direction:  order.direction,
+                volume:     order.volume,
+                price_open: price,
+                sl:         order.sl,
+                tp:         order.tp,
+                magic:      order.magic,
+                comment:    order.comment.clone(),
+                time:       Some(self.clock.now()),
+            });
+
+            info!(ticket, symbol = %order.symbol, kind = ?order.kind, requested = order.price, price, "🎭 [PAPER] Pending order filled");
+
+            events.push(BrokerEvent::Filled(PendingFill {
+                order_ticket:     ticket,
+                position_ticket:  ticket,
+                symbol:           order.symbol,
+                volume:           order.volume,
+                price,
+                remaining_volume: 0.0,
+            }));
+        }
+
+        let mut hits: Vec<(u64, f64, &'static str)> = account
+            .positions
+            .values()
+            .filter(|p| p.symbol == tick.symbol)
+            .filter_map(|p| {
+                let price = quote.exit_price(p.direction);
+                hit_reason(p, price).map(|reason| (p.ticket, price, reason))
+            })
+            .collect();
+        hits.sort_by_key(|(ticket, ..)| *ticket);
+
+        for (ticket, price, reason) in hits {
+            let Some((direction, volume)) = account.positions.get(&ticket).map(|p| (p.direction, p.volume)) else {
+                continue;
+            };
+            // SL = Stop → Market (Slippage ของ Cost Model + SL) / TP = Limit ได้ราคาของ Tick
+            let fill = match reason {
+                "SL" => {
+                    let extra = costs.sl_slippage_points * spec.point;
+                    account.market_fill(&spec, &costs, direction == Direction::Sell, price, extra, volume)
+                }
+                _ => Fill::exact(price, &costs, volume),
+            };
+            events.extend(account.settle(ticket, fill, &spec, reason, None).map(BrokerEvent::Closed));
+        }
+        events
+    }
+
+    async fn trade_costs(&self, ticket: u64) -> Option<TradeCosts> {
+        let account = self.account.lock().await;
+        let ledger  = account.ledgers.get(&ticket)?;
+        let mut costs = ledger.booked;
+        costs.add(ledger.accrued);
+        Some(costs)
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, Json};
+    use chrono::Utc;
+    use serde_json::json;
+
+    use axum::extract::Query;
+
+    use crate::engine::executor::{build_order, fire_trade};
+    use crate::models::strategy::{test_strategy, EntryMode, EntryZone};
+    use crate::models::{ActiveStrategy, OpenPosition, TradeRecord, TradeStatus};
+    use crate::routes::brain::{set_strategy, SetStrategyParams};
+    use crate::routes::mt5::handle_tick;
+    use crate::state::AppState;
+
+    fn config(slippage_pips: f64) -> PaperConfig {
+        PaperConfig { balance: 10_000.0, slippage_pips, ..PaperConfig::default() }
+    }
+
+    fn tick(bid: f64, ask: f64) -> TickData {
+        serde_json::from_value(json!({
+            "symbol": "XAUUSD", "bid": bid, "ask": ask, "volume": 1.0, "time": Utc::now(),
+        }))
+        .unwrap()
+    }
+
+    fn order(direction: Direction, sl: f64, tp: f64) -> OrderRequest {
+        build_order("XAUUSD", direction, 0.0, sl, tp, 0.1, uuid::Uuid::new_v4()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fills_with_slippage_and_honours_break_even() {
+        let broker = PaperBroker::new(config(2.0), Arc::new(RwLock::new(SymbolRegistry::from_env())));
+        assert!(broker.send_order(&order(Direction::Buy, 1995.0, 2010.0)).await.is_err(), "no quote yet");
+
+        broker.on_tick(&tick(2000.0, 2000.5)).await;
+        // ask 2000.5 + 2 pips (0.2) — ไม่ใช่ราคาที่ Engine ขอ
+        let fill = broker.send_order(&order(Direction::Buy, 1995.0, 2010.0)).await.unwrap();
+        assert_eq!(fill.price, 2000.7);
+
+        // Floating: (2001.7 − 2000.7) × 100 oz × 0.1 Lot = +10
+        assert!(broker.on_tick(&tick(2001.7, 2002.2)).await.is_empty());
+        let account = broker.account_info().await.unwrap();
+        assert!((account.equity - 10_010.0).abs() < 1e-6);
+
+        // Break-Even → SL ใหม่ถูกแตะก่อน SL เดิม
+        broker.modify_position(fill.ticket, 2000.7, 2010.0).await.unwrap();
+        let events = broker.on_tick(&tick(2000.6, 2001.1)).await;
+        let [BrokerEvent::Closed(close)] = events.as_slice() else {
+            panic!("expected one close, got {events:?}");
+        };
+        assert_eq!(close.close_reason, "SL");
+        assert_eq!(close.close_price, 2000.6);
+        assert!((close.profit.unwrap() + 1.0).abs() < 1e-6);
+
+        let account = broker.account_info().await.unwrap();
+        assert!((account.balance - 9_999.0).abs() < 1e-6);
+        assert_eq!(account.equity, account.balance);
+        assert!(broker.list_positions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_costs_commission_sl_slippage_and_triple_swap() {
+        let costs: CostModel = serde_json::from_value(json!({
+            "XAUUSD": { "commission_per_lot": 7.0, "sl_slippage_points": 20.0, "swap_long": -10.0 },
+        }))
+        .unwrap();
+        // อังคาร 10:00 → พุธ (คืนอังคาร ×1) → พฤหัส (คืนพุธ ×3)
+        let at    = |day: u32| chrono::NaiveDate::from_ymd_opt(2026, 1, day).unwrap().and_hms_opt(10, 0, 0).unwrap().and_utc();
+        let clock = Arc::new(Clock::simulated(at(6)));
+        let broker = PaperBroker::with_clock(
+            PaperConfig { costs, ..config(0.0) },
+            Arc::new(RwLock::new(SymbolRegistry::from_env())),
+            clock.clone(),
+        );
+        let tick_at = |day: u32, bid: f64| {
+            clock.advance_to(at(day));
+            TickData { time: at(day), ..tick(bid, bid + 0.5) }
+        };
+
+        broker.on_tick(&tick_at(6, 2000.0)).await;
+        let fill = broker.send_order(&order(Direction::Buy, 1995.0, 2010.0)).await.unwrap();
+        assert_eq!(fill.price, 2000.5);
+        broker.on_tick(&tick_at(7, 2001.0)).await;
+        // Equity หัก Commission ขาเปิด + Swap ที่ค้าง: 5 − 0.7 − 1
+        assert!((broker.account_info().await.unwrap().equity - 10_003.3).abs() < 1e-6);
+
+        // bid 1994.9 แตะ SL → ปิดที่ 1994.9 − 20 points = 1994.7
+        let events = broker.on_tick(&tick_at(8, 1994.9)).await;
+        let [BrokerEvent::Closed(close)] = events.as_slice() else {
+            panic!("expected one close, got {events:?}");
+        };
+        assert_eq!(close.close_price, 1994.7);
+        // (1994.7 − 2000.5) × 100 × 0.1 = −58 / Commission 2 ขา −1.4 / Swap 1 + 3 คืน −4
+        assert!((close.profit.unwrap() + 63.4).abs() < 1e-6);
+
+        let costs = broker.trade_costs(fill.ticket).await.unwrap();
+        assert!((costs.commission + 1.4).abs() < 1e-9);
+        assert!((costs.swap + 4.0).abs() < 1e-9);
+        assert!((costs.slippage + 2.0).abs() < 1e-6);
+        assert!((broker.account_info().await.unwrap().balance - 9_936.6).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_take_profit_closes_through_position_close_path() {
+        let mut state = AppState::new();
+        state.broker  = Arc::new(PaperBroker::new(config(0.0), state.symbols.clone()));
+        let state     = Arc::new(state);
+
+        // SELL เปิดที่ bid 2000.0
+        state.broker.on_tick(&tick(2000.0, 2000.3)).await;
+        let sell = order(Direction::Sell, 2010.0, 1990.0);
+        let fill = fire_trade(&sell, state.broker.as_ref(), &state.execution_config).await.unwrap();
+
+        let strategy = ActiveStrategy {
+            direction:   Direction::Sell,
+            entry_zone:  EntryZone { low: 1999.0, high: 2001.0 },
+            take_profit: 1990.0,
+            stop_loss:   2010.0,
+            ..test_strategy("XAUUSD")
+        };
+        let mut record = TradeRecord::from_strategy(&strategy, fill.price);
+        record.mt5_ticket = Some(fill.ticket);
+        state.push_trade_record(record).await;
+        state.open_position(fill.ticket, OpenPosition::from_strategy(&strategy, fill.price)).await;
+
+        // ask แตะ TP → Paper ปิดเอง → Book / History / Risk อัปเดตเหมือน EA callback
+        handle_tick(State(state.clone()), Json(tick(1989.5, 1989.8))).await.unwrap();
+
+        assert!(state.positions.read().await.is_empty());
+        let history = state.trade_history.read().await;
+        assert_eq!(history[0].close_reason.as_deref(), Some("TP"));
+        assert_eq!(history[0].close_price, Some(1989.8));
+        // (2000.0 − 1989.8) × 100 × 0.1 = +102
+        assert!((history[0].profit.unwrap() - 102.0).abs() < 1e-6);
+        assert!((history[0].profit_pips.unwrap() - 102.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_pending_limit_fills_at_zone_edge_and_cancels_on_replace() {
+        let mut state = AppState::new();
+        state.broker  = Arc::new(PaperBroker::new(config(0.0), state.symbols.clone()));
+        let state     = Arc::new(state);
+
+        let pending_buy = || ActiveStrategy {
+            entry_mode: EntryMode::Pending,
+            stop_loss:  1985.0,
+            expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..test_strategy("XAUUSD")
+        };
+        let install = |strategy: ActiveStrategy| {
+            set_strategy(State(state.clone()), Query(SetStrategyParams::default()), Json(strategy))
+        };
+
+        // ราคาอยู่เหนือ Zone → BUY LIMIT ที่ zone.high ครั้งเดียว
+        let first = pending_buy();
+        install(first.clone()).await.unwrap();
+        handle_tick(State(state.clone()), Json(tick(2010.0, 2010.3))).await.unwrap();
+        handle_tick(State(state.clone()), Json(tick(2009.0, 2009.3))).await.unwrap();
+        let orders = state.orders.read().await.all();
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].kind, orders[0].price), (OrderKind::Limit, 2000.0));
+        assert_eq!(orders[0].strategy.strategy_id, first.strategy_id);
+
+        // แผนใหม่แทนที่ → Order เดิมถูกยกเลิกที่ Broker ก่อน Fill
+        let second = pending_buy();
+        install(second.clone()).await.unwrap();
+        assert_eq!(state.orders.read().await.len(), 0);
+        assert_eq!(state.trade_history.read().await[0].status, TradeStatus::Cancelled);
+
+        handle_tick(State(state.clone()), Json(tick(2005.0, 2005.3))).await.unwrap();
+        let ticket = state.orders.read().await.all()[0].order_ticket;
+
+        // ask ย่อลงมาถึงขอบ Zone → Fill ที่ ask (ดีกว่าราคาที่วาง) → Position + Strategy ถูกใช้แล้ว
+        handle_tick(State(state.clone()), Json(tick(1999.5, 1999.8))).await.unwrap();
+        assert_eq!(state.orders.read().await.len(), 0);
+        assert!(state.strategies_for("XAUUSD").await.is_empty());
+
+        let position = state.positions.read().await.get(ticket).cloned().unwrap();
+        assert_eq!((position.strategy_id, position.entry_price), (second.strategy_id, 1999.8));
+
+        let history = state.trade_history.read().await;
+        assert_eq!(history[1].status, TradeStatus::Confirmed);
+        assert_eq!(history[1].mt5_ticket, Some(ticket));
+        assert_eq!(history[1].filled_volume, Some(position.lot_size));
+    }
+}
@@ -0,0 +1,242 @@
+//! # broker::recording — Recording Test Double
+//!
+//! ห่อ Broker ตัวอื่น (ปกติคือ [`PaperBroker`](super::PaperBroker)) แล้วบันทึกทุกคำสั่ง
+//! ตามลำดับ — ใช้ตรวจว่า Engine สั่งอะไรโบรกเกอร์บ้าง และจำลอง Venue ปฏิเสธ Order
+//! หรือ Timeout (ไม่รู้ผล) ระหว่างส่ง Order / Venue ที่ตอบช้า (Latency)
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::info;
+
+use super::{
+    costs::TradeCosts, Broker, BrokerEvent, BrokerKind, BrokerPosition, ClosedPosition, OrderFill, OrderRequest,
+    PositionClose,
+};
+use crate::error::AppError;
+use crate::models::{AccountInfo, TickData};
+
+/// คำสั่งหนึ่งครั้งที่ Engine ส่งถึงโบรกเกอร์
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "call", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BrokerCall {
+    SendOrder      { order: OrderRequest },
+    PlaceOrder     { order: OrderRequest },
+    OrderStatus    { client_id: String },
+    ModifyPosition { ticket: u64, sl: f64, tp: f64 },
+    ClosePosition  { ticket: u64, volume: Option<f64> },
+    CancelOrder    { ticket: u64 },
+    ListPositions,
+    AccountInfo,
+    ClosedPosition { ticket: u64 },
+}
+
+pub struct RecordingBroker {
+    inner:    Arc<dyn Broker>,
+    calls:    Mutex<Vec<BrokerCall>>,
+    /// ตั้งค่า → ทุกคำสั่งเทรดถูกปฏิเสธด้วยข้อความนี้ (ยังถูกบันทึก)
+    reject:   Mutex<Option<String>>,
+    /// Order ที่จะ Timeout อีกกี่ครั้ง + ถึง Venue แล้วหรือยัง
+    timeouts: Mutex<(u32, bool)>,
+    /// หน่วงทุกคำสั่งเท่านี้ก่อนส่งต่อ (จำลอง Venue / Network ช้า)
+    latency:  Mutex<Duration>,
+}
+
+#[allow(dead_code)] // ใช้จาก Test
+impl RecordingBroker {
+    pub fn new(inner: Arc<dyn Broker>) -> Self {
+        Self {
+            inner,
+            calls:    Mutex::new(Vec::new()),
+            reject:   Mutex::new(None),
+            timeouts: Mutex::new((0, false)),
+            latency:  Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// คำสั่งทั้งหมดที่ได้รับ เรียงตามเวลา
+    pub fn calls(&self) -> Vec<BrokerCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// จำลอง Venue ปฏิเสธคำสั่งเทรด (None = กลับมารับตามปกติ)
+    pub fn reject_with(&self, reason: Option<&str>) {
+        *self.reject.lock().unwrap() = reason.map(str::to_string);
+    }
+
+    /// จำลอง Timeout ของการส่ง Order `count` ครั้งถัดไป — คืน `ExecutionUnknown`
+    /// `delivered` = Venue ได้รับ Order แล้วแต่ Response หาย / false = หายก่อนถึง Venue
+    pub fn time_out_orders(&self, count: u32, delivered: bool) {
+        *self.timeouts.lock().unwrap() = (count, delivered);
+    }
+
+    /// จำลอง Venue ที่ตอบช้า — ทุกคำสั่งถัดไปรอ `latency` ก่อนได้ผล (ZERO = ปกติ)
+    pub fn delay_calls(&self, latency: Duration) {
+        *self.latency.lock().unwrap() = latency;
+    }
+
+    /// บันทึกคำสั่งทันทีที่ได้รับ แล้วรอตาม Latency ที่ตั้งไว้
+    async fn record(&self, call: BrokerCall) {
+        info!(?call, "📼 [RECORDING] Broker call");
+        self.calls.lock().unwrap().push(call);
+        let latency = *self.latency.lock().unwrap();
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    fn check_reject(&self) -> Result<(), AppError> {
+        match self.reject.lock().unwrap().as_ref() {
+            Some(reason) => Err(AppError::ExecutionError(reason.clone())),
+            None         => Ok(()),
+        }
+    }
+
+    /// Some(delivered) = Order นี้ต้อง Timeout
+    fn take_timeout(&self) -> Option<bool> {
+        let mut timeouts = self.timeouts.lock().unwrap();
+        if timeouts.0 == 0 {
+            return None;
+        }
+        timeouts.0 -= 1;
+        Some(timeouts.1)
+    }
+}
+
+fn timed_out() -> AppError {
+    AppError::ExecutionUnknown("Recording: simulated timeout".into())
+}
+
+#[async_trait]
+impl Broker for RecordingBroker {
+    fn kind(&self) -> BrokerKind {
+        BrokerKind::Recording
+    }
+
+    async fn send_order(&self, order: &OrderRequest) -> Result<OrderFill, AppError> {
+        self.record(BrokerCall::SendOrder { order: order.clone() }).await;
+        self.check_reject()?;
+        match self.take_timeout() {
+            Some(true)  => self.inner.send_order(order).await.and(Err(timed_out())),
+            Some(false) => Err(timed_out()),
+            None        => self.inner.send_order(order).await,
+        }
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<u64, AppError> {
+        self.record(BrokerCall::PlaceOrder { order: order.clone() }).await;
+        self.check_reject()?;
+        match self.take_timeout() {
+            Some(true)  => self.inner.place_order(order).await.and(Err(timed_out())),
+            Some(false) => Err(timed_out()),
+            None        => self.inner.place_order(order).await,
+        }
+    }
+
+    async fn order_status(&self, client_id: &str) -> Result<Option<OrderFill>, AppError> {
+        self.record(BrokerCall::OrderStatus { client_id: client_id.to_string() }).await;
+        self.inner.order_status(client_id).await
+    }
+
+    async fn modify_position(&self, ticket: u64, sl: f64, tp: f64) -> Result<(), AppError> {
+        self.record(BrokerCall::ModifyPosition { ticket, sl, tp }).await;
+        self.check_reject()?;
+        self.inner.modify_position(ticket, sl, tp).await
+    }
+
+    async fn close_position(&self, ticket: u64, volume: Option<f64>) -> Result<Option<PositionClose>, AppError> {
+        self.record(BrokerCall::ClosePosition { ticket, volume }).await;
+        self.check_reject()?;
+        self.inner.close_position(ticket, volume).await
+    }
+
+    async fn cancel_order(&self, ticket: u64) -> Result<(), AppError> {
+        self.record(BrokerCall::CancelOrder { ticket }).await;
+        self.check_reject()?;
+        self.inner.cancel_order(ticket).await
+    }
+
+    async fn list_positions(&self) -> Result<Vec<BrokerPosition>, AppError> {
+        self.record(BrokerCall::ListPositions).await;
+        self.inner.list_positions().await
+    }
+
+    async fn account_info(&self) -> Result<AccountInfo, AppError> {
+        self.record(BrokerCall::AccountInfo).await;
+        self.inner.account_info().await
+    }
+
+    async fn closed_position(&self, ticket: u64) -> Result<Option<ClosedPosition>, AppError> {
+        self.record(BrokerCall::ClosedPosition { ticket }).await;
+        self.inner.closed_position(ticket).await
+    }
+
+    /// Market data ไม่ใช่คำสั่ง — ส่งต่อโดยไม่บันทึก
+    async fn on_tick(&self, tick: &TickData) -> Vec<BrokerEvent> {
+        self.inner.on_tick(tick).await
+    }
+
+    async fn trade_costs(&self, ticket: u64) -> Option<TradeCosts> {
+        self.inner.trade_costs(ticket).await
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::{PaperBroker, PaperConfig};
+    use crate::engine::executor::{build_order, AGV_MAGIC};
+    use crate::models::Direction;
+    use crate::symbols::SymbolRegistry;
+
+    #[tokio::test]
+    async fn test_records_calls_and_forwards_to_paper() {
+        let paper = PaperBroker::new(
+            PaperConfig { balance: 10_000.0, ..PaperConfig::default() },
+            Arc::new(tokio::sync::RwLock::new(SymbolRegistry::from_env())),
+        );
+        let broker = RecordingBroker::new(Arc::new(paper));
+        let order  = build_order("XAUUSD", Direction::Buy, 2000.0, 1995.0, 2010.0, 0.2, uuid::Uuid::new_v4())
+            .unwrap();
+
+        // Market data ไม่ถูกบันทึกเป็นคำสั่ง
+        let tick = serde_json::from_value(serde_json::json!({
+            "symbol": "XAUUSD", "bid": 1999.7, "ask": 2000.0, "volume": 1.0, "time": chrono::Utc::now(),
+        }))
+        .unwrap();
+        broker.on_tick(&tick).await;
+
+        let fill = broker.send_order(&order).await.unwrap();
+        assert_eq!(fill.price, 2000.0);
+        broker.modify_position(fill.ticket, 2000.0, 2010.0).await.unwrap();
+
+        let open = broker.list_positions().await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!((open[0].sl, open[0].magic), (2000.0, AGV_MAGIC));
+
+        // ปิดบางส่วน → Position ยังอยู่ด้วย Volume ที่เหลือ
+        let partial = broker.close_position(fill.ticket, Some(0.1)).await.unwrap().unwrap();
+        assert_eq!(partial.volume, Some(0.1));
+        assert_eq!(broker.list_positions().await.unwrap()[0].volume, 0.1);
+
+        assert!(broker.close_position(fill.ticket, None).await.unwrap().is_some());
+        assert!(broker.list_positions().await.unwrap().is_empty());
+        assert!(matches!(broker.close_position(fill.ticket, None).await, Err(AppError::NotFound(_))));
+
+        // Venue ปฏิเสธ → ยังถูกบันทึก แต่ไม่ถึง Paper
+        broker.reject_with(Some("market closed"));
+        assert!(matches!(broker.send_order(&order).await, Err(AppError::ExecutionError(_))));
+        assert!(broker.list_positions().await.unwrap().is_empty());
+
+        let calls = broker.calls();
+        assert_eq!(calls.len(), 10);
+        assert_eq!(calls[0], BrokerCall::SendOrder { order: order.clone() });
+        assert_eq!(calls[1], BrokerCall::ModifyPosition { ticket: fill.ticket, sl: 2000.0, tp: 2010.0 });
+        assert_eq!(calls[3], BrokerCall::ClosePosition { ticket: fill.ticket, volume: Some(0.1) });
+        assert_eq!(calls[8], BrokerCall::SendOrder { order });
+    }
+}
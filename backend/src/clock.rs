@@ -0,0 +1,44 @@
+//! # clock — เวลาของ Engine
+//!
+//! ทุกที่ที่ Engine ตัดสินใจตามเวลา (Strategy หมดอายุ / Max Hold / Cooldown / วันของ Risk /
+//! Order หมดอายุ / เวลาเปิด-ปิด Position) อ่านจาก [`Clock`] ตัวเดียวกันใน `AppState`
+//!
+//! ```text
+//! Live     → Clock::system()          = Utc::now()
+//! Backtest → Clock::simulated(start)  = เวลาของ Tick ล่าสุด (process_tick เลื่อนให้ทุก Tick)
+//! ```
+
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Default)]
+pub struct Clock {
+    /// None = นาฬิกาจริง / Some = เวลาจำลอง (Microseconds ตั้งแต่ Epoch)
+    simulated: Option<AtomicI64>,
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self { simulated: None }
+    }
+
+    /// นาฬิกาจำลองที่เริ่มที่ `start` — เดินเมื่อเรียก [`Clock::advance_to`] เท่านั้น
+    pub fn simulated(start: DateTime<Utc>) -> Self {
+        Self { simulated: Some(AtomicI64::new(start.timestamp_micros())) }
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.simulated {
+            Some(micros) => DateTime::from_timestamp_micros(micros.load(Ordering::Relaxed)).unwrap_or_default(),
+            None         => Utc::now(),
+        }
+    }
+
+    /// เลื่อนนาฬิกาจำลองไปที่ `time` (ไม่ถอยหลัง — Tick ที่มาช้าไม่ย้อนเวลา) / นาฬิกาจริงไม่มีผล
+    pub fn advance_to(&self, time: DateTime<Utc>) {
+        if let Some(micros) = &self.simulated {
+            micros.fetch_max(time.timestamp_micros(), Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! # db::migrate — Versioned Schema Migrations
+//!
+//! Migration ทุกไฟล์ถูก embed เข้า Binary ตามลำดับ Version และบันทึกลงตาราง
+//! `schema_migrations` พร้อม SHA-256 checksum ของ SQL ที่รันไปแล้ว
+//!
+//! ```text
+//! antigravity migrate          → รัน Migration ที่ยังค้าง (ใน Transaction ทีละไฟล์)
+//! antigravity migrate status   → แสดงสถานะแต่ละ Version
+//! antigravity (serve)          → ตรวจ Schema — ค้าง/checksum ไม่ตรง = ไม่ยอม Start
+//!                                (ยกเว้นตั้ง DB_AUTO_MIGRATE=true)
+//! ```
+//!
+//! ## เพิ่ม Migration ใหม่
+//! 1. สร้างไฟล์ `migrations/NNN_name.sql`
+//! 2. เพิ่มเข้า [`MIGRATIONS`] ท้ายสุด (ห้ามแก้ไฟล์ที่ Apply ไปแล้ว — checksum จะไม่ตรง)
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use tracing::{info, warn};
+
+/// Key ของ `pg_advisory_lock` — กัน 2 Process รัน Migration พร้อมกัน
+const MIGRATION_LOCK_KEY: i64 = 0x4147_565f_4d49_4752; // "AGV_MIGR"
+
+// ─── Migration List ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i32,
+    pub name:    &'static str,
+    pub sql:     &'static str,
+}
+
+impl Migration {
+    /// SHA-256 ของ SQL (hex)
+    pub fn checksum(&self) -> String {
+        Sha256::digest(self.sql.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+/// Migration ทั้งหมด เรียงตาม Version (ต่อท้ายเท่านั้น)
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name:    "init",
+        sql:     include_str!("../../migrations/001_init.sql"),
+    },
+    Migration {
+        version: 2,
+        name:    "persistence",
+        sql:     include_str!("../../migrations/002_persistence.sql"),
+    },
+    Migration {
+        version: 3,
+        name:    "trade_profit",
+        sql:     include_str!("../../migrations/003_trade_profit.sql"),
+    },
+    Migration {
+        version: 4,
+        name:    "trade_sizing",
+        sql:     include_str!("../../migrations/004_trade_sizing.sql"),
+    },
+    Migration {
+        version: 5,
+        name:    "pending_orders",
+        sql:     include_str!("../../migrations/005_pending_orders.sql"),
+    },
+    Migration {
+        version: 6,
+        name:    "exit_policy",
+        sql:     include_str!("../../migrations/006_exit_policy.sql"),
+    },
+    Migration {
+        version: 7,
+        name:    "strategy_log_payload",
+        sql:     include_str!("../../migrations/007_strategy_log_payload.sql"),
+    },
+];
+
+// ─── Status ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationState {
+    Applied { at: DateTime<Utc> },
+    Pending,
+    /// SQL ในไฟล์ถูกแก้หลังจาก Apply ไปแล้ว
+    ChecksumMismatch { recorded: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i32,
+    pub name:    &'static str,
+    pub state:   MigrationState,
+}
+
+async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version     INTEGER      PRIMARY KEY,
+            name        VARCHAR(100) NOT NULL,
+            checksum    CHAR(64)     NOT NULL,
+            applied_at  TIMESTAMPTZ  NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to create schema_migrations table")?;
+
+    Ok(())
+}
+
+/// สถานะของทุก Migration เทียบกับตาราง `schema_migrations`
+pub async fn status(pool: &PgPool) -> anyhow::Result<Vec<MigrationStatus>> {
+    ensure_table(pool).await?;
+
+    let rows = sqlx::query("SELECT version, checksum, applied_at FROM schema_migrations")
+        .fetch_all(pool)
+        .await
+        .context("Failed to read schema_migrations")?;
+
+    let mut applied: HashMap<i32, (String, DateTime<Utc>)> = HashMap::new();
+    for row in rows {
+        applied.insert(
+            row.try_get("version")?,
+            (row.try_get("checksum")?, row.try_get("applied_at")?),
+        );
+    }
+
+    // Version ที่ DB รู้จักแต่ Binary ไม่รู้จัก = Binary เก่ากว่า Schema
+    if let Some(unknown) = applied
+        .keys()
+        .filter(|v| !MIGRATIONS.iter().any(|m| m.version == **v))
+        .max()
+    {
+        bail!("Database has migration version {unknown} which this binary does not know — upgrade antigravity");
+    }
+
+    Ok(MIGRATIONS
+        .iter()
+        .map(|m| {
+            let state = match applied.get(&m.version) {
+                None => MigrationState::Pending,
+                Some((recorded, at)) if recorded.trim() == m.checksum() => {
+                    MigrationState::Applied { at: *at }
+                }
+                Some((recorded, _)) => MigrationState::ChecksumMismatch {
+                    recorded: recorded.trim().to_string(),
+                },
+            };
+            MigrationStatus { version: m.version, name: m.name, state }
+        })
+        .collect())
+}
+
+/// ตรวจว่า Schema ตรงกับ Binary — ใช้ตอน Boot
+pub async fn verify(pool: &PgPool) -> anyhow::Result<()> {
+    let statuses = status(pool).await?;
+
+    if let Some(bad) = statuses
+        .iter()
+        .find(|s| matches!(s.state, MigrationState::ChecksumMismatch { .. }))
+    {
+        bail!(
+            "Migration {:03}_{} was modified after it was applied (checksum mismatch)",
+            bad.version, bad.name
+        );
+    }
+
+    let pending: Vec<String> = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .map(|s| format!("{:03}_{}", s.version, s.name))
+        .collect();
+
+    if !pending.is_empty() {
+        bail!(
+            "Database schema is out of date — pending migrations: {}. \
+             Run `antigravity migrate` (or set DB_AUTO_MIGRATE=true)",
+            pending.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+// ─── Runner ───────────────────────────────────────────────────────────────────
+
+/// รัน Migration ที่ยังค้างตามลำดับ — คืน Version ที่เพิ่ง Apply
+///
+/// แต่ละไฟล์รันใน Transaction ของตัวเอง (SQL + บันทึก Version พร้อมกัน)
+/// ถ้าเจอ checksum ไม่ตรงจะหยุดก่อนแตะ Schema
+pub async fn migrate(pool: &PgPool) -> anyhow::Result<Vec<i32>> {
+    ensure_table(pool).await?;
+
+    let mut lock = pool.acquire().await.context("Failed to acquire connection")?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *lock)
+        .await
+        .context("Failed to take migration lock")?;
+
+    let result = apply_pending(pool).await;
+
+    // ผลของ Migration มาก่อน — Unlock ไม่ผ่านแค่ Log แล้วทิ้ง Connection (Session จบ = Lock หลุดเอง)
+    let unlock = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *lock)
+        .await;
+    if let Err(e) = unlock {
+        warn!(error = %e, "Failed to release migration lock — closing its connection");
+        drop(lock.detach());
+    }
+
+    result
+}
+
+async fn apply_pending(pool: &PgPool) -> anyhow::Result<Vec<i32>> {
+    let statuses = status(pool).await?;
+
+    if let Some(bad) = statuses
+        .iter()
+        .find(|s| matches!(s.state, MigrationState::ChecksumMismatch { .. }))
+    {
+        bail!(
+            "Refusing to migrate: {:03}_{} was modified after it was applied",
+            bad.version, bad.name
+        );
+    }
+
+    let mut applied = Vec::new();
+    for (migration, st) in MIGRATIONS.iter().zip(&statuses) {
+        if st.state != MigrationState::Pending {
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::raw_sql(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| {
+                format!("Migration {:03}_{} failed", migration.version, migration.name)
+            })?;
+
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        info!(version = migration.version, name = migration.name, "📦 Migration applied");
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+// ─── Tests (ต้องมี PostgreSQL — ตั้ง TEST_DATABASE_URL แล้วรันด้วย --include-ignored) ───
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{postgres::PgPoolOptions, Connection, PgConnection};
+
+    /// สร้าง Database ใหม่ชั่วคราวสำหรับแต่ละ Test
+    /// Test เหล่านี้ `#[ignore]` ไว้ — รันแล้วไม่ได้ตั้ง TEST_DATABASE_URL = Fail (ไม่ผ่านแบบไม่ได้ทดสอบ)
+    async fn fresh_db() -> (PgPool, String, String) {
+        let admin_url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must point at a PostgreSQL admin database");
+
+        let name = format!("agv_test_{}", uuid::Uuid::new_v4().simple());
+        let mut admin = PgConnection::connect(&admin_url).await.expect("connect admin");
+        sqlx::raw_sql(&format!("CREATE DATABASE {name}"))
+            .execute(&mut admin)
+            .await
+            .expect("create test database");
+
+        let (base, _) = admin_url.rsplit_once('/').expect("url has database path");
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&format!("{base}/{name}"))
+            .await
+            .expect("connect test database");
+
+        (pool, admin_url, name)
+    }
+
+    async fn drop_db(pool: PgPool, admin_url: &str, name: &str) {
+        pool.close().await;
+        let mut admin = PgConnection::connect(admin_url).await.expect("connect admin");
+        sqlx::raw_sql(&format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)"))
+            .execute(&mut admin)
+            .await
+            .expect("drop test database");
+    }
+
+    #[test]
+    fn test_versions_are_strictly_increasing() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+        assert_eq!(MIGRATIONS[0].checksum().len(), 64);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_migrate_applies_all_then_is_noop() {
+        let (pool, admin, name) = fresh_db().await;
+
+        assert!(verify(&pool).await.is_err(), "fresh DB must be reported as out of date");
+
+        let applied = migrate(&pool).await.unwrap();
+        assert_eq!(applied, MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>());
+        verify(&pool).await.unwrap();
+
+        assert!(migrate(&pool).await.unwrap().is_empty());
+
+        let positions: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = 'positions'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(positions, 1);
+
+        drop_db(pool, &admin, &name).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_checksum_mismatch_refuses_start_and_migrate() {
+        let (pool, admin, name) = fresh_db().await;
+
+        migrate(&pool).await.unwrap();
+        sqlx::query("UPDATE schema_migrations SET checksum = $1 WHERE version = 1")
+            .bind("0".repeat(64))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = verify(&pool).await.unwrap_err().to_string();
+        assert!(err.contains("checksum mismatch"), "{err}");
+        assert!(migrate(&pool).await.is_err());
+
+        drop_db(pool, &admin, &name).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_unknown_version_in_db_is_rejected() {
+        let (pool, admin, name) = fresh_db().await;
+
+        migrate(&pool).await.unwrap();
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum) VALUES (999, 'future', $1)")
+            .bind("0".repeat(64))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = verify(&pool).await.unwrap_err().to_string();
+        assert!(err.contains("999"), "{err}");
+
+        drop_db(pool, &admin, &name).await;
+    }
+}
@@ -0,0 +1,173 @@
+//! # db — Persistence Layer
+//!
+//! [`Persistence`] คือ Handle ที่ทุก Handler ใช้บันทึกข้อมูลลง Database
+//! โดยไม่ต้องรอ I/O — ทุกคำสั่งถูกส่งเข้า Channel แล้ว Writer Task ตัวเดียว
+//! เขียนตามลำดับ (Position open → close จะไม่สลับกัน)
+//!
+//! - ไม่ได้เปิด feature `postgres` หรือไม่ได้ตั้ง `DATABASE_URL`
+//!   → Handle ว่าง ทุกคำสั่งเป็น no-op (In-memory only)
+//! - เปิด feature `postgres` + `DATABASE_URL` → เขียนลง PostgreSQL ผ่าน [`pg`]
+
+#[cfg(feature = "postgres")]
+pub mod migrate;
+#[cfg(feature = "postgres")]
+pub mod pg;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc;
+
+use crate::models::{ActiveStrategy, OpenPosition, TradeRecord};
+
+// ─── CLI: `antigravity migrate` ───────────────────────────────────────────────
+
+/// `antigravity migrate [status]` — ต้องตั้ง `DATABASE_URL`
+#[cfg(feature = "postgres")]
+pub async fn run_migrate_command(args: &[String]) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let url  = std::env::var("DATABASE_URL").context("DATABASE_URL is not set")?;
+    let pool = pg::connect(&url).await?;
+
+    match args.first().map(String::as_str) {
+        None => {
+            let applied = migrate::migrate(&pool).await?;
+            if applied.is_empty() {
+                println!("Schema is up to date — nothing to apply.");
+            } else {
+                println!("Applied migrations: {applied:?}");
+            }
+        }
+        Some("status") => {
+            for s in migrate::status(&pool).await? {
+                let state = match s.state {
+                    migrate::MigrationState::Applied { at } => format!("applied {at}"),
+                    migrate::MigrationState::Pending        => "PENDING".to_string(),
+                    migrate::MigrationState::ChecksumMismatch { recorded } => {
+                        format!("CHECKSUM MISMATCH (recorded {recorded})")
+                    }
+                };
+                println!("{:03}_{:<20} {state}", s.version, s.name);
+            }
+        }
+        Some(other) => anyhow::bail!("Unknown migrate command '{other}' (expected: status)"),
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+pub async fn run_migrate_command(_args: &[String]) -> anyhow::Result<()> {
+    anyhow::bail!("antigravity was built without PostgreSQL support — rebuild with `--features postgres`")
+}
+
+// ─── Risk Event Types ─────────────────────────────────────────────────────────
+
+/// ค่า `event_type` ในตาราง `risk_events`
+pub mod risk_event {
+    pub const KILL_SWITCH_ON:  &str = "KILL_SWITCH_ON";
+    pub const KILL_SWITCH_OFF: &str = "KILL_SWITCH_OFF";
+    pub const AUTO_KILL:       &str = "AUTO_KILL";
+    pub const COOLDOWN:        &str = "COOLDOWN";
+}
+
+// ─── DB Operation ─────────────────────────────────────────────────────────────
+
+/// คำสั่งเขียนหนึ่งรายการที่ Writer Task จะทำตามลำดับ
+#[derive(Debug)]
+#[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+pub enum DbOp {
+    /// Strategy ใหม่จาก OpenClaw → `strategy_log`
+    Strategy(Box<ActiveStrategy>),
+    /// TradeRecord ใหม่หรือเปลี่ยนสถานะ (รวมข้อมูลตอนปิด) → `trade_records`
+    Trade(Box<TradeRecord>),
+    /// Position เปิดหรือถูกแก้ไข (เช่น SL บังทุน) → `positions`
+    PositionUpsert(Box<OpenPosition>),
+    /// Position ถูกปิดแล้ว → ลบออกจาก `positions`
+    PositionClosed { mt5_ticket: u64 },
+    /// Kill Switch / Auto-Kill / Cooldown → `risk_events`
+    RiskEvent { event_type: &'static str, details: String },
+}
+
+// ─── Persistence Handle ───────────────────────────────────────────────────────
+
+/// Handle ที่ Clone ได้ สำหรับส่งคำสั่งเขียนไปยัง Writer Task
+#[derive(Debug, Clone, Default)]
+pub struct Persistence {
+    tx:   Option<mpsc::UnboundedSender<DbOp>>,
+    /// Pool สำหรับอ่าน (Backtest โหลด `strategy_log`) — Writer ใช้ Clone ของตัวเดียวกัน
+    #[cfg(feature = "postgres")]
+    pool: Option<sqlx::PgPool>,
+}
+
+impl Persistence {
+    /// เริ่ม Writer Task ที่เขียนลง PostgreSQL ตามลำดับที่ได้รับ
+    #[cfg(feature = "postgres")]
+    pub fn spawn(pool: sqlx::PgPool) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<DbOp>();
+        let reader = pool.clone();
+
+        tokio::spawn(async move {
+            while let Some(op) = rx.recv().await {
+                if let Err(e) = pg::apply(&pool, &op).await {
+                    tracing::error!(error = %e, ?op, "❌ DB write failed");
+                }
+            }
+        });
+
+        Self { tx: Some(tx), pool: Some(reader) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// Strategy จาก `strategy_log` ที่ Active ช่วง `[from, to]` — None = ไม่ได้เชื่อม Database
+    pub async fn strategy_log(
+        &self,
+        symbol: Option<&str>,
+        from:   DateTime<Utc>,
+        to:     DateTime<Utc>,
+    ) -> anyhow::Result<Option<Vec<ActiveStrategy>>> {
+        #[cfg(feature = "postgres")]
+        if let Some(pool) = &self.pool {
+            return pg::load_strategy_log(pool, symbol, from, to).await.map(Some);
+        }
+        let _ = (symbol, from, to);
+        Ok(None)
+    }
+
+    fn send(&self, op: DbOp) {
+        if let Some(tx) = &self.tx {
+            // Err = Writer Task ตายไปแล้ว — ไม่ควรทำให้ Hot path ล่ม
+            let _ = tx.send(op);
+        }
+    }
+
+    pub fn strategy(&self, strategy: &ActiveStrategy) {
+        if self.is_enabled() {
+            self.send(DbOp::Strategy(Box::new(strategy.clone())));
+        }
+    }
+
+    pub fn trade(&self, record: &TradeRecord) {
+        if self.is_enabled() {
+            self.send(DbOp::Trade(Box::new(record.clone())));
+        }
+    }
+
+    pub fn position(&self, position: &OpenPosition) {
+        if self.is_enabled() {
+            self.send(DbOp::PositionUpsert(Box::new(position.clone())));
+        }
+    }
+
+    pub fn position_closed(&self, mt5_ticket: u64) {
+        self.send(DbOp::PositionClosed { mt5_ticket });
+    }
+
+    pub fn risk_event(&self, event_type: &'static str, details: impl Into<String>) {
+        if self.is_enabled() {
+            self.send(DbOp::RiskEvent { event_type, details: details.into() });
+        }
+    }
+}
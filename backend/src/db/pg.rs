@@ -0,0 +1,551 @@
+//! # db::pg — PostgreSQL Database Layer
+//!
+//! ใช้ `sqlx` สำหรับ async PostgreSQL — runtime queries (ไม่ต้องมี
+//! `DATABASE_URL` ตอน compile)
+//!
+//! ## Setup
+//! 1. ติดตั้ง PostgreSQL และสร้าง database
+//! 2. ตั้ง `DATABASE_URL` ใน `.env`
+//! 3. `cargo run --features postgres -- migrate` — สร้าง/อัปเดต Schema
+//! 4. `cargo run --features postgres` — Boot จะตรวจ Schema ก่อน (ดู [`super::migrate`])
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use tracing::info;
+
+use super::{migrate, risk_event, DbOp};
+use crate::models::{
+    position::{OpenPosition, TradeRecord},
+    strategy::EntryZone,
+    ActiveStrategy,
+};
+use crate::state::AppState;
+
+/// จำนวน TradeRecord ล่าสุดที่โหลดกลับเข้า Memory ตอน startup
+const HISTORY_REHYDRATE_LIMIT: i64 = 500;
+
+// ─── Pool Init ────────────────────────────────────────────────────────────────
+
+/// เชื่อมต่อ PostgreSQL (ยังไม่แตะ Schema)
+pub async fn connect(database_url: &str) -> anyhow::Result<PgPool> {
+    info!("Connecting to PostgreSQL...");
+
+    PgPoolOptions::new()
+        .max_connections(10)
+        .acquire_timeout(std::time::Duration::from_secs(5))
+        .connect(database_url)
+        .await
+        .context("Failed to connect to PostgreSQL")
+}
+
+/// สร้าง PgPool สำหรับ Server — Schema ต้องเป็นปัจจุบัน
+///
+/// `DB_AUTO_MIGRATE=true` → รัน Migration ที่ค้างให้เลย
+/// ไม่ตั้ง → ไม่ยอม Start ถ้ามี Migration ค้าง (ให้รัน `antigravity migrate` ก่อน)
+pub async fn init_pool(database_url: &str) -> anyhow::Result<PgPool> {
+    let pool = connect(database_url).await?;
+
+    let auto_migrate = std::env::var("DB_AUTO_MIGRATE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    if auto_migrate {
+        let applied = migrate::migrate(&pool).await?;
+        info!(?applied, "✅ PostgreSQL connected (DB_AUTO_MIGRATE)");
+    } else {
+        migrate::verify(&pool).await?;
+        info!("✅ PostgreSQL connected — schema up to date");
+    }
+
+    Ok(pool)
+}
+
+// ─── Enum <-> Text ────────────────────────────────────────────────────────────
+// เก็บ Enum เป็นชื่อเดียวกับ JSON API ("BUY", "CONFIRMED", ...)
+
+fn enum_to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        other => format!("{other:?}"),
+    }
+}
+
+fn enum_from_text<T: DeserializeOwned>(text: &str) -> anyhow::Result<T> {
+    serde_json::from_value(serde_json::Value::String(text.to_string()))
+        .with_context(|| format!("unknown enum value '{text}'"))
+}
+
+// ─── Writer (เรียกโดย Persistence Writer Task) ───────────────────────────────
+
+/// เขียน DbOp หนึ่งรายการลง Database
+pub async fn apply(pool: &PgPool, op: &DbOp) -> anyhow::Result<()> {
+    match op {
+        DbOp::Strategy(strategy)          => log_strategy(pool, strategy).await,
+        DbOp::Trade(record)               => upsert_trade_record(pool, record).await,
+        DbOp::PositionUpsert(position)    => upsert_position(pool, position).await,
+        DbOp::PositionClosed { mt5_ticket } => delete_position(pool, *mt5_ticket).await,
+        DbOp::RiskEvent { event_type, details } => {
+            insert_risk_event(pool, event_type, details).await
+        }
+    }
+}
+
+// ─── Trade Records ────────────────────────────────────────────────────────────
+
+/// บันทึก TradeRecord ลง PostgreSQL (Insert หรือ Update สถานะ / ข้อมูลตอนปิด)
+pub async fn upsert_trade_record(
+    pool:   &PgPool,
+    record: &TradeRecord,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO trade_records
+          (trade_id, strategy_id, symbol, direction, entry_price,
+           lot_size, take_profit, stop_loss, mt5_ticket, status, status_message, fired_at,
+           close_price, profit_pips, close_reason, closed_at, profit, sizing,
+           order_kind, order_ticket, filled_volume)
+        VALUES ($1, $2, $3, $4, $5::float8::numeric, $6::float8::numeric,
+                $7::float8::numeric, $8::float8::numeric, $9, $10, $11, $12,
+                $13::float8::numeric, $14::float8::numeric, $15, $16, $17::float8::numeric,
+                $18::jsonb, $19, $20, $21::float8::numeric)
+        ON CONFLICT (trade_id) DO UPDATE SET
+          entry_price    = EXCLUDED.entry_price,
+          status         = EXCLUDED.status,
+          status_message = EXCLUDED.status_message,
+          mt5_ticket     = EXCLUDED.mt5_ticket,
+          close_price    = EXCLUDED.close_price,
+          profit_pips    = EXCLUDED.profit_pips,
+          close_reason   = EXCLUDED.close_reason,
+          closed_at      = EXCLUDED.closed_at,
+          profit         = EXCLUDED.profit,
+          order_ticket   = EXCLUDED.order_ticket,
+          filled_volume  = EXCLUDED.filled_volume
+        "#,
+    )
+    .bind(record.trade_id)
+    .bind(record.strategy_id)
+    .bind(&record.symbol)
+    .bind(enum_to_text(&record.direction))
+    .bind(record.entry_price)
+    .bind(record.lot_size)
+    .bind(record.take_profit)
+    .bind(record.stop_loss)
+    .bind(record.mt5_ticket.map(|t| t as i64))
+    .bind(enum_to_text(&record.status))
+    .bind(&record.status_message)
+    .bind(record.fired_at)
+    .bind(record.close_price)
+    .bind(record.profit_pips)
+    .bind(&record.close_reason)
+    .bind(record.closed_at)
+    .bind(record.profit)
+    .bind(record.sizing.as_ref().map(serde_json::to_string).transpose()?)
+    .bind(enum_to_text(&record.order_kind))
+    .bind(record.order_ticket.map(|t| t as i64))
+    .bind(record.filled_volume)
+    .execute(pool)
+    .await
+    .context("upsert_trade_record failed")?;
+
+    Ok(())
+}
+
+/// โหลด Trade History ล่าสุดเพื่อ seed in-memory state ตอน startup
+/// คืนค่าเรียงตาม fired_at (เก่า → ใหม่) เหมือน `AppState::trade_history`
+pub async fn load_trade_history(pool: &PgPool) -> anyhow::Result<Vec<TradeRecord>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT trade_id, strategy_id, symbol, direction,
+               entry_price::float8 AS entry_price, lot_size::float8 AS lot_size,
+               take_profit::float8 AS take_profit, stop_loss::float8 AS stop_loss,
+               mt5_ticket, status, status_message, fired_at,
+               close_price::float8 AS close_price, profit_pips::float8 AS profit_pips,
+               close_reason, closed_at, profit::float8 AS profit, sizing::text AS sizing,
+               order_kind, order_ticket, filled_volume::float8 AS filled_volume
+        FROM trade_records
+        ORDER BY fired_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(HISTORY_REHYDRATE_LIMIT)
+    .fetch_all(pool)
+    .await
+    .context("load_trade_history failed")?;
+
+    let mut history = rows
+        .into_iter()
+        .map(|row| -> anyhow::Result<TradeRecord> {
+            Ok(TradeRecord {
+                trade_id:       row.try_get("trade_id")?,
+                strategy_id:    row.try_get("strategy_id")?,
+                symbol:         row.try_get("symbol")?,
+                direction:      enum_from_text(row.try_get("direction")?)?,
+                entry_price:    row.try_get("entry_price")?,
+                lot_size:       row.try_get("lot_size")?,
+                take_profit:    row.try_get("take_profit")?,
+                stop_loss:      row.try_get("stop_loss")?,
+                mt5_ticket:     row.try_get::<Option<i64>, _>("mt5_ticket")?.map(|t| t as u64),
+                status:         enum_from_text(row.try_get("status")?)?,
+                status_message: row.try_get::<Option<String>, _>("status_message")?.unwrap_or_default(),
+                fired_at:       row.try_get("fired_at")?,
+                close_price:    row.try_get("close_price")?,
+                profit_pips:    row.try_get("profit_pips")?,
+                profit:         row.try_get("profit")?,
+                close_reason:   row.try_get("close_reason")?,
+                closed_at:      row.try_get("closed_at")?,
+                sizing:         row
+                    .try_get::<Option<String>, _>("sizing")?
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()?,
+                order_kind:     enum_from_text(row.try_get("order_kind")?)?,
+                order_ticket:   row.try_get::<Option<i64>, _>("order_ticket")?.map(|t| t as u64),
+                filled_volume:  row.try_get("filled_volume")?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    history.reverse();
+    Ok(history)
+}
+
+// ─── Strategy Log ─────────────────────────────────────────────────────────────
+
+/// บันทึกทุก Strategy ที่ OpenClaw ส่งมา (สำหรับ analysis)
+pub async fn log_strategy(
+    pool:     &PgPool,
+    strategy: &ActiveStrategy,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO strategy_log
+          (strategy_id, symbol, direction, entry_zone_low, entry_zone_high,
+           take_profit, stop_loss, lot_size, rationale, created_at, expires_at, payload)
+        VALUES ($1, $2, $3, $4::float8::numeric, $5::float8::numeric,
+                $6::float8::numeric, $7::float8::numeric, $8::float8::numeric, $9, $10, $11,
+                $12::jsonb)
+        ON CONFLICT (strategy_id) DO NOTHING
+        "#,
+    )
+    .bind(strategy.strategy_id)
+    .bind(&strategy.symbol)
+    .bind(enum_to_text(&strategy.direction))
+    .bind(strategy.entry_zone.low)
+    .bind(strategy.entry_zone.high)
+    .bind(strategy.take_profit)
+    .bind(strategy.stop_loss)
+    .bind(strategy.lot_size)
+    .bind(&strategy.rationale)
+    .bind(strategy.created_at)
+    .bind(strategy.expires_at)
+    .bind(serde_json::to_string(strategy)?)
+    .execute(pool)
+    .await
+    .context("log_strategy failed")?;
+
+    Ok(())
+}
+
+/// Strategy ที่ Active ช่วงใดช่วงหนึ่งใน `[from, to]` เรียงตาม `created_at` (Backtest Timeline)
+///
+/// แถวก่อน Migration 007 ไม่มี `payload` → สร้างจากคอลัมน์ (ไม่มี Opposing Zone / Exit Policy / Confirmation)
+pub async fn load_strategy_log(
+    pool:   &PgPool,
+    symbol: Option<&str>,
+    from:   DateTime<Utc>,
+    to:     DateTime<Utc>,
+) -> anyhow::Result<Vec<ActiveStrategy>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT strategy_id, symbol, direction,
+               entry_zone_low::float8 AS entry_zone_low, entry_zone_high::float8 AS entry_zone_high,
+               take_profit::float8 AS take_profit, stop_loss::float8 AS stop_loss,
+               lot_size::float8 AS lot_size, rationale, created_at, expires_at,
+               payload::text AS payload
+        FROM strategy_log
+        WHERE created_at <= $2
+          AND (expires_at IS NULL OR expires_at > $1)
+          AND ($3::text IS NULL OR symbol = $3)
+        ORDER BY created_at, received_at
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(symbol)
+    .fetch_all(pool)
+    .await
+    .context("load_strategy_log failed")?;
+
+    rows.into_iter()
+        .map(|row| -> anyhow::Result<ActiveStrategy> {
+            if let Some(payload) = row.try_get::<Option<String>, _>("payload")? {
+                return Ok(serde_json::from_str(&payload)?);
+            }
+            let strategy = serde_json::json!({
+                "strategy_id": row.try_get::<uuid::Uuid, _>("strategy_id")?,
+                "symbol":      row.try_get::<String, _>("symbol")?,
+                "direction":   row.try_get::<String, _>("direction")?,
+                "entry_zone":  {
+                    "low":  row.try_get::<f64, _>("entry_zone_low")?,
+                    "high": row.try_get::<f64, _>("entry_zone_high")?,
+                },
+                "take_profit": row.try_get::<f64, _>("take_profit")?,
+                "stop_loss":   row.try_get::<f64, _>("stop_loss")?,
+                "lot_size":    row.try_get::<f64, _>("lot_size")?,
+                "rationale":   row.try_get::<Option<String>, _>("rationale")?.unwrap_or_default(),
+                "created_at":  row.try_get::<DateTime<Utc>, _>("created_at")?,
+                "expires_at":  row.try_get::<Option<DateTime<Utc>>, _>("expires_at")?,
+            });
+            Ok(serde_json::from_value(strategy)?)
+        })
+        .collect()
+}
+
+// ─── Open Positions ───────────────────────────────────────────────────────────
+
+/// บันทึก Position ที่เปิดอยู่ (หรืออัปเดต SL หลังบังทุน)
+pub async fn upsert_position(
+    pool:     &PgPool,
+    position: &OpenPosition,
+) -> anyhow::Result<()> {
+    let Some(ticket) = position.mt5_ticket else {
+        // Position ที่ไม่มี Ticket ไม่มีทางจับคู่กับ MT5 ได้หลัง Restart
+        return Ok(());
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO positions
+          (mt5_ticket, position_id, strategy_id, symbol, direction, entry_price,
+           lot_size, take_profit, stop_loss, sl_moved_to_be, opposing_low, opposing_high,
+           opened_at, initial_stop_loss, initial_lot_size, exit_policy, scale_outs_done, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6::float8::numeric, $7::float8::numeric,
+                $8::float8::numeric, $9::float8::numeric, $10,
+                $11::float8::numeric, $12::float8::numeric, $13,
+                $14::float8::numeric, $15::float8::numeric, $16::jsonb, $17, NOW())
+        ON CONFLICT (mt5_ticket) DO UPDATE SET
+          strategy_id      = EXCLUDED.strategy_id,
+          entry_price      = EXCLUDED.entry_price,
+          lot_size         = EXCLUDED.lot_size,
+          take_profit      = EXCLUDED.take_profit,
+          stop_loss        = EXCLUDED.stop_loss,
+          sl_moved_to_be   = EXCLUDED.sl_moved_to_be,
+          initial_lot_size = EXCLUDED.initial_lot_size,
+          scale_outs_done  = EXCLUDED.scale_outs_done,
+          updated_at       = NOW()
+        "#,
+    )
+    .bind(ticket as i64)
+    .bind(position.position_id)
+    .bind(position.strategy_id)
+    .bind(&position.symbol)
+    .bind(enum_to_text(&position.direction))
+    .bind(position.entry_price)
+    .bind(position.lot_size)
+    .bind(position.take_profit)
+    .bind(position.stop_loss)
+    .bind(position.sl_moved_to_be)
+    .bind(position.opposing_zone.map(|z| z.low))
+    .bind(position.opposing_zone.map(|z| z.high))
+    .bind(position.opened_at)
+    .bind(position.initial_stop_loss)
+    .bind(position.initial_lot_size)
+    .bind(position.exit_policy.as_ref().map(serde_json::to_string).transpose()?)
+    .bind(position.scale_outs_done as i32)
+    .execute(pool)
+    .await
+    .context("upsert_position failed")?;
+
+    Ok(())
+}
+
+/// ลบ Position ที่ปิดแล้วออก
+pub async fn delete_position(pool: &PgPool, mt5_ticket: u64) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM positions WHERE mt5_ticket = $1")
+        .bind(mt5_ticket as i64)
+        .execute(pool)
+        .await
+        .context("delete_position failed")?;
+
+    Ok(())
+}
+
+/// โหลด Position ที่ยังเปิดอยู่ทั้งหมด
+pub async fn load_open_positions(pool: &PgPool) -> anyhow::Result<Vec<OpenPosition>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT mt5_ticket, position_id, strategy_id, symbol, direction,
+               entry_price::float8 AS entry_price, lot_size::float8 AS lot_size,
+               take_profit::float8 AS take_profit, stop_loss::float8 AS stop_loss,
+               sl_moved_to_be,
+               opposing_low::float8 AS opposing_low, opposing_high::float8 AS opposing_high,
+               opened_at, initial_stop_loss::float8 AS initial_stop_loss,
+               initial_lot_size::float8 AS initial_lot_size, exit_policy::text AS exit_policy,
+               scale_outs_done
+        FROM positions
+        ORDER BY opened_at
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("load_open_positions failed")?;
+
+    rows.into_iter()
+        .map(|row| -> anyhow::Result<OpenPosition> {
+            let opposing_low:  Option<f64> = row.try_get("opposing_low")?;
+            let opposing_high: Option<f64> = row.try_get("opposing_high")?;
+
+            Ok(OpenPosition {
+                position_id:       row.try_get("position_id")?,
+                strategy_id:       row.try_get("strategy_id")?,
+                symbol:            row.try_get("symbol")?,
+                direction:         enum_from_text(row.try_get("direction")?)?,
+                entry_price:       row.try_get("entry_price")?,
+                lot_size:          row.try_get("lot_size")?,
+                take_profit:       row.try_get("take_profit")?,
+                stop_loss:         row.try_get("stop_loss")?,
+                mt5_ticket:        Some(row.try_get::<i64, _>("mt5_ticket")? as u64),
+                opened_at:         row.try_get("opened_at")?,
+                sl_moved_to_be:    row.try_get("sl_moved_to_be")?,
+                opposing_zone:     opposing_low
+                    .zip(opposing_high)
+                    .map(|(low, high)| EntryZone { low, high }),
+                initial_stop_loss: row.try_get("initial_stop_loss")?,
+                initial_lot_size:  row.try_get("initial_lot_size")?,
+                exit_policy:       row
+                    .try_get::<Option<String>, _>("exit_policy")?
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()?,
+                scale_outs_done:   row.try_get::<i32, _>("scale_outs_done")? as usize,
+                closing:           false,
+            })
+        })
+        .collect()
+}
+
+// ─── Risk Events ──────────────────────────────────────────────────────────────
+
+pub async fn insert_risk_event(
+    pool:       &PgPool,
+    event_type: &str,
+    details:    &str,
+) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO risk_events (event_type, details) VALUES ($1, $2)")
+        .bind(event_type)
+        .bind(details)
+        .execute(pool)
+        .await
+        .context("insert_risk_event failed")?;
+
+    Ok(())
+}
+
+/// สถานะ Kill Switch ล่าสุด — `Some(reason)` ถ้ายัง Killed อยู่
+pub async fn load_kill_state(pool: &PgPool) -> anyhow::Result<Option<String>> {
+    let row = sqlx::query(
+        r#"
+        SELECT event_type, details
+        FROM risk_events
+        WHERE event_type IN ($1, $2, $3)
+        ORDER BY occurred_at DESC, id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(risk_event::KILL_SWITCH_ON)
+    .bind(risk_event::AUTO_KILL)
+    .bind(risk_event::KILL_SWITCH_OFF)
+    .fetch_optional(pool)
+    .await
+    .context("load_kill_state failed")?;
+
+    let Some(row) = row else { return Ok(None) };
+
+    let event_type: String         = row.try_get("event_type")?;
+    let details:    Option<String> = row.try_get("details")?;
+
+    if event_type == risk_event::KILL_SWITCH_OFF {
+        Ok(None)
+    } else {
+        Ok(Some(details.unwrap_or_else(|| event_type.clone())))
+    }
+}
+
+/// Realized P&L ของทุก Trade ที่ปิดแล้ว เรียงตาม closed_at (เก่า → ใหม่)
+/// ไม่จำกัดจำนวนแบบ History — Equity / Peak ของ Drawdown ต้องไม่ขึ้นกับ Limit ของ Row
+pub async fn load_realized_closes(pool: &PgPool) -> anyhow::Result<Vec<(f64, DateTime<Utc>)>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT profit::float8 AS profit, closed_at
+        FROM trade_records
+        WHERE profit IS NOT NULL AND closed_at IS NOT NULL
+        ORDER BY closed_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("load_realized_closes failed")?;
+
+    rows.into_iter()
+        .map(|row| Ok((row.try_get("profit")?, row.try_get("closed_at")?)))
+        .collect()
+}
+
+/// เวลา Re-arm ล่าสุด (`KILL_SWITCH_OFF`) — Peak ของ Drawdown เริ่มใหม่ ณ จุดนั้น
+pub async fn load_last_rearm(pool: &PgPool) -> anyhow::Result<Option<DateTime<Utc>>> {
+    sqlx::query_scalar("SELECT MAX(occurred_at) FROM risk_events WHERE event_type = $1")
+        .bind(risk_event::KILL_SWITCH_OFF)
+        .fetch_one(pool)
+        .await
+        .context("load_last_rearm failed")
+}
+
+// ─── Rehydrate ────────────────────────────────────────────────────────────────
+
+/// โหลดสถานะที่ยัง Live อยู่กลับเข้า AppState ตอน startup
+/// (trade_history, open positions, kill switch)
+pub async fn rehydrate(pool: &PgPool, state: &AppState) -> anyhow::Result<()> {
+    let history   = load_trade_history(pool).await?;
+    let positions = load_open_positions(pool).await?;
+    let killed    = load_kill_state(pool).await?;
+    let closes    = load_realized_closes(pool).await?;
+    let rearmed   = load_last_rearm(pool).await?;
+
+    let (n_history, n_positions) = (history.len(), positions.len());
+
+    // Realized P&L ย้อนหลัง → Daily/Weekly Loss + Drawdown ต่อจากก่อน Restart
+    // Re-arm ก่อน Restart ยอมรับ Drawdown ตอนนั้นเป็นฐานใหม่ — เล่นซ้ำที่จุดเดิม
+    let split = rearmed.map_or(0, |at| closes.partition_point(|&(_, closed_at)| closed_at <= at));
+    for &(profit, closed_at) in &closes[..split] {
+        state.risk.restore_close(profit, closed_at).await;
+    }
+    if rearmed.is_some() {
+        state.risk.restore_rearm().await;
+    }
+    for &(profit, closed_at) in &closes[split..] {
+        state.risk.restore_close(profit, closed_at).await;
+    }
+
+    *state.trade_history.write().await = history;
+
+    {
+        let mut book = state.positions.write().await;
+        for position in positions {
+            if let Some(ticket) = position.mt5_ticket {
+                book.open(ticket, position);
+            }
+        }
+    }
+
+    if let Some(reason) = &killed {
+        state.risk.kill(reason).await;
+    }
+
+    info!(
+        trades    = n_history,
+        positions = n_positions,
+        killed    = killed.is_some(),
+        "♻️  State rehydrated from PostgreSQL"
+    );
+    Ok(())
+}
@@ -2,20 +2,42 @@
 //! 
 //! สร้างแท่งเทียน (Candle) จาก Tick Data เพื่อนำไปใช้วิเคราะห์ Price Action
 //! เช่น การหาไส้เทียน (Wick Rejection) สไตล์ SMC ใน Timeframe เล็ก (M1, M5)
+//!
+//! ## CandleAggregator
+//!
+//! ```text
+//! Tick (ขอบแท่งตาม tick.time — ไม่ใช่นาฬิกาเครื่อง) ──▶ M1 · M5 · M15 · H1 · H4 · D1 ต่อ Symbol
+//!     Tick อยู่ในแท่งที่กำลังสร้าง  → รวมเข้าแท่ง (มาไม่ตามลำดับ → Open / Close ตามเวลาของ Tick)
+//!     Tick ขึ้นแท่งใหม่            → ปิดแท่งเดิม → Ring buffer (CANDLE_HISTORY_SIZE) + CANDLE_CLOSED
+//!         ข้ามไป ≤ CANDLE_MAX_GAP_FILL แท่ง → เติมแท่งแบน (tick_count = 0) / มากกว่านั้น = ตลาดปิด ไม่เติม
+//!     Tick ของแท่งที่ปิดไปแล้ว      → ทิ้ง (นับ late_ticks) — แท่งที่ปิดแล้วไม่เปลี่ยนอีก
+//! ```
+//!
+//! Live อยู่ใน `AppState.candles` (ป้อนใน `evaluate_tick`) — Backtest สร้างของตัวเอง
+
+use std::collections::{HashMap, VecDeque};
 
 use chrono::{DateTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::models::{timeframe::Timeframe, TickData};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candle {
     pub symbol: String,
-    /// เวลาเริ่มต้นของแท่งเทียนนี้ (ปัดเศษนาที)
+    #[serde(default)]
+    pub timeframe: Timeframe,
+    /// เวลาเริ่มต้นของแท่งเทียนนี้ (ปัดเศษตาม Timeframe)
     pub start_time: DateTime<Utc>,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
     pub tick_count: u32,
+    /// ผลรวม `volume` ของ Tick ในแท่ง (FX มักเป็น 0 — ใช้ `tick_count` แทน)
+    #[serde(default)]
+    pub volume: f64,
 }
 
 impl Candle {
@@ -29,12 +51,36 @@ impl Candle {
 
         Self {
             symbol: symbol.to_string(),
+            timeframe: Timeframe::M1,
             start_time,
             open: price,
             high: price,
             low: price,
             close: price,
             tick_count: 1,
+            volume: 0.0,
+        }
+    }
+
+    /// แท่งของ `timeframe` ที่ `time` ตกอยู่ (ขอบแท่งตามเวลาของ Tick)
+    pub fn for_timeframe(symbol: &str, timeframe: Timeframe, time: DateTime<Utc>, price: f64) -> Self {
+        Self {
+            timeframe,
+            start_time: timeframe.bucket_start(time),
+            ..Self::new(symbol, time, price)
+        }
+    }
+
+    /// แท่งแบนต่อจาก `prev` สำหรับช่วงที่ไม่มี Tick (O = H = L = C = Close เดิม, tick_count = 0)
+    fn flat_after(prev: &Candle, start_time: DateTime<Utc>) -> Self {
+        Self {
+            start_time,
+            open:       prev.close,
+            high:       prev.close,
+            low:        prev.close,
+            tick_count: 0,
+            volume:     0.0,
+            ..prev.clone()
         }
     }
 
@@ -49,31 +95,293 @@ impl Candle {
         self.tick_count += 1;
     }
 
+    /// สัดส่วนไส้ฝั่ง Rejection ต่อทั้งแท่ง — BUY = ไส้ล่าง / SELL = ไส้บน (แท่งแบน = 0)
+    pub fn wick_ratio(&self, is_buy_signal: bool) -> f64 {
+        let total_range = self.high - self.low;
+        if total_range == 0.0 {
+            return 0.0;
+        }
+        let wick = if is_buy_signal {
+            self.open.min(self.close) - self.low
+        } else {
+            self.high - self.open.max(self.close)
+        };
+        wick / total_range
+    }
+
     /// ตรวจว่าแท่งนี้จบด้วย "ไส้เทียนถูกตบกลับ" (Rejection Wick) หรือไม่?
     /// - `is_buy_signal`: สนใจไส้ล่าง (Lower Wick)? ถ้าใช่ = แรงซื้อตบกลับ (Rejection Buy)
     /// - `min_wick_ratio`: ไส้ต้องยาวกี่ % ของทั้งแท่ง (เช่น 0.6 = 60%)
     pub fn has_rejection_wick(&self, is_buy_signal: bool, min_wick_ratio: f64) -> bool {
-        let total_range = self.high - self.low;
-        if total_range == 0.0 {
+        if self.high == self.low {
             return false;
         }
+        // ไส้ยาวเกินกำหนดแปลว่ากวาดสภาพคล่องแล้วเด้งกลับ = Rejection!
+        // BUY ยิ่งปิดเขียวยิ่งดี / SELL ยิ่งปิดแดงยิ่งดี
+        let closed_with_signal = if is_buy_signal { self.close >= self.open } else { self.close <= self.open };
+        self.wick_ratio(is_buy_signal) >= min_wick_ratio && closed_with_signal
+    }
+}
 
-        let body_top = self.open.max(self.close);
-        let body_bottom = self.open.min(self.close);
+// ─── Candle Aggregator ────────────────────────────────────────────────────────
 
-        if is_buy_signal {
-            // Rejection สลับแรงเทขาย → หางล่าง (Lower Wick) ต้องยาวมาก
-            let lower_wick = body_bottom - self.low;
-            let wick_ratio = lower_wick / total_range;
+#[derive(Debug, Clone)]
+pub struct CandleConfig {
+    /// จำนวนแท่งที่ปิดแล้วที่เก็บต่อ Symbol × Timeframe
+    pub history_size: usize,
+    /// เติมแท่งแบนเมื่อไม่มี Tick ไม่เกินกี่แท่งติดกัน (0 = ไม่เติมเลย เหมือน Chart ของ MT5)
+    pub max_gap_fill: usize,
+}
 
-            // ไส้ล่างยาวเกินกำหนดแปลว่ากวาดสภาพคล่องแล้วเด้งกลับ = Rejection!
-            wick_ratio >= min_wick_ratio && self.close >= self.open // ยิ่งปิดเขียวยิ่งดี
-        } else {
-            // Rejection สลับแรงซื้อ → หางบน (Upper Wick) ต้องยาวมาก
-            let upper_wick = self.high - body_top;
-            let wick_ratio = upper_wick / total_range;
+impl CandleConfig {
+    pub fn from_env() -> Self {
+        let env = |key: &str, default: usize| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            history_size: env("CANDLE_HISTORY_SIZE", 500).max(1),
+            max_gap_fill: env("CANDLE_MAX_GAP_FILL", 0),
+        }
+    }
+}
+
+/// ตัวนับของ Aggregator (`GET /api/monitor/stats`)
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CandleStats {
+    /// แท่งที่ปิดแล้วทุก Timeframe (รวมแท่งที่เติม)
+    pub closed:     u64,
+    /// Tick ที่ทิ้งเพราะแท่งของมันปิดไปแล้ว
+    pub late_ticks: u64,
+    /// ช่วงที่ไม่มี Tick ข้ามไปอย่างน้อยหนึ่งแท่ง
+    pub gaps:       u64,
+    /// แท่งแบนที่เติมให้ (`CANDLE_MAX_GAP_FILL`)
+    pub gap_fills:  u64,
+}
+
+/// แท่งของ Symbol × Timeframe หนึ่งชุด
+#[derive(Debug, Clone)]
+struct CandleSeries {
+    timeframe:  Timeframe,
+    forming:    Option<Candle>,
+    /// เวลาของ Tick แรก / ล่าสุดในแท่งที่กำลังสร้าง — ตัดสิน Open / Close ของ Tick ที่มาไม่ตามลำดับ
+    first_time: DateTime<Utc>,
+    last_time:  DateTime<Utc>,
+    /// แท่งที่ปิดแล้ว (ล่าสุดอยู่ท้าย)
+    closed:     VecDeque<Candle>,
+}
+
+impl CandleSeries {
+    fn new(timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            forming:    None,
+            first_time: DateTime::<Utc>::MIN_UTC,
+            last_time:  DateTime::<Utc>::MIN_UTC,
+            closed:     VecDeque::new(),
+        }
+    }
+
+    /// คืน false ถ้า Tick อยู่ในแท่งที่ปิดไปแล้ว (ทิ้ง)
+    fn on_tick(
+        &mut self,
+        tick:   &TickData,
+        price:  f64,
+        config: &CandleConfig,
+        stats:  &mut CandleStats,
+        out:    &mut Vec<Candle>,
+    ) -> bool {
+        let start = self.timeframe.bucket_start(tick.time);
+        let Some(mut forming) = self.forming.take() else {
+            self.start(tick, price);
+            return true;
+        };
+
+        if start < forming.start_time {
+            self.forming = Some(forming);
+            return false;
+        }
+
+        if start == forming.start_time {
+            if tick.time >= self.last_time {
+                forming.update(price);
+                self.last_time = tick.time;
+            } else {
+                // มาไม่ตามลำดับ — นับ High / Low แต่ Close ยังเป็นของ Tick ล่าสุด
+                forming.high        = forming.high.max(price);
+                forming.low         = forming.low.min(price);
+                forming.tick_count += 1;
+                if tick.time < self.first_time {
+                    forming.open    = price;
+                    self.first_time = tick.time;
+                }
+            }
+            forming.volume += tick.volume;
+            self.forming    = Some(forming);
+            return true;
+        }
 
-            wick_ratio >= min_wick_ratio && self.close <= self.open // ยิ่งปิดแดงยิ่งดี
+        // ขึ้นแท่งใหม่ — ปิดแท่งเดิม แล้วจัดการช่วงที่ไม่มี Tick
+        let step    = self.timeframe.seconds();
+        let missing = (start - forming.start_time).num_seconds() / step - 1;
+        let last    = forming.clone();
+        self.close(forming, config, stats, out);
+        if missing > 0 {
+            stats.gaps += 1;
+            if missing as usize <= config.max_gap_fill {
+                for k in 1..=missing {
+                    let fill = Candle::flat_after(&last, last.start_time + chrono::Duration::seconds(k * step));
+                    stats.gap_fills += 1;
+                    self.close(fill, config, stats, out);
+                }
+            } else {
+                debug!(symbol = %tick.symbol, timeframe = %self.timeframe, missing, "Candle gap — not filled");
+            }
         }
+        self.start(tick, price);
+        true
+    }
+
+    fn start(&mut self, tick: &TickData, price: f64) {
+        let mut candle  = Candle::for_timeframe(&tick.symbol, self.timeframe, tick.time, price);
+        candle.volume   = tick.volume;
+        self.forming    = Some(candle);
+        self.first_time = tick.time;
+        self.last_time  = tick.time;
+    }
+
+    fn close(&mut self, candle: Candle, config: &CandleConfig, stats: &mut CandleStats, out: &mut Vec<Candle>) {
+        while self.closed.len() >= config.history_size {
+            self.closed.pop_front();
+        }
+        self.closed.push_back(candle.clone());
+        stats.closed += 1;
+        out.push(candle);
+    }
+}
+
+/// แท่งทุก Timeframe ([`Timeframe::ALL`]) ต่อ Symbol จากเวลาของ Tick
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    config: CandleConfig,
+    series: HashMap<String, Vec<CandleSeries>>,
+    stats:  CandleStats,
+}
+
+impl CandleAggregator {
+    pub fn new(config: CandleConfig) -> Self {
+        Self { config, series: HashMap::new(), stats: CandleStats::default() }
+    }
+
+    pub fn config(&self) -> &CandleConfig {
+        &self.config
+    }
+
+    /// ใส่ Tick — คืนแท่งที่เพิ่งปิด (เรียงจาก Timeframe เล็กไปใหญ่ / เก่าไปใหม่)
+    pub fn on_tick(&mut self, tick: &TickData) -> Vec<Candle> {
+        let price  = tick.effective_mid();
+        let series = self
+            .series
+            .entry(tick.symbol.clone())
+            .or_insert_with(|| Timeframe::ALL.iter().map(|tf| CandleSeries::new(*tf)).collect());
+
+        let mut closed = Vec::new();
+        let mut late   = false;
+        for s in series.iter_mut() {
+            late |= !s.on_tick(tick, price, &self.config, &mut self.stats, &mut closed);
+        }
+        if late {
+            self.stats.late_ticks += 1;
+            debug!(symbol = %tick.symbol, time = %tick.time, "Late tick for a closed candle — dropped");
+        }
+        closed
+    }
+
+    fn series(&self, symbol: &str, timeframe: Timeframe) -> Option<&CandleSeries> {
+        self.series.get(symbol)?.iter().find(|s| s.timeframe == timeframe)
+    }
+
+    /// แท่งที่กำลังสร้างของ Symbol × Timeframe
+    pub fn forming(&self, symbol: &str, timeframe: Timeframe) -> Option<&Candle> {
+        self.series(symbol, timeframe)?.forming.as_ref()
+    }
+
+    /// แท่งที่ปิดแล้ว `limit` แท่งล่าสุด (เก่า → ใหม่)
+    pub fn closed(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Vec<Candle> {
+        let Some(series) = self.series(symbol, timeframe) else {
+            return Vec::new();
+        };
+        let skip = series.closed.len().saturating_sub(limit);
+        series.closed.iter().skip(skip).cloned().collect()
+    }
+
+    /// แท่งที่ปิดแล้วที่เริ่มตั้งแต่ `since` + แท่งที่กำลังสร้าง (เก่า → ใหม่)
+    pub fn since(&self, symbol: &str, timeframe: Timeframe, since: DateTime<Utc>) -> Vec<Candle> {
+        let Some(series) = self.series(symbol, timeframe) else {
+            return Vec::new();
+        };
+        series
+            .closed
+            .iter()
+            .chain(series.forming.as_ref())
+            .filter(|c| c.start_time >= since)
+            .cloned()
+            .collect()
+    }
+
+    pub fn stats(&self) -> CandleStats {
+        self.stats
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(secs: i64, price: f64) -> TickData {
+        TickData {
+            symbol: "XAUUSD".to_string(),
+            bid:    price,
+            ask:    price,
+            mid:    None,
+            volume: 1.0,
+            spread: None,
+            time:   DateTime::from_timestamp(1_740_700_800 + secs, 0).unwrap(),
+            rsi_14: None,
+            ma_20:  None,
+            ma_50:  None,
+        }
+    }
+
+    #[test]
+    fn test_aggregator_uses_tick_time_with_gaps_and_late_ticks() {
+        let mut agg = CandleAggregator::new(CandleConfig { history_size: 3, max_gap_fill: 2 });
+
+        assert!(agg.on_tick(&tick(10, 100.0)).is_empty());
+        assert!(agg.on_tick(&tick(50, 104.0)).is_empty());
+        assert!(agg.on_tick(&tick(5, 99.0)).is_empty(), "out-of-order tick inside the forming candle");
+        let m1 = agg.forming("XAUUSD", Timeframe::M1).unwrap();
+        assert_eq!((m1.open, m1.high, m1.low, m1.close, m1.tick_count), (99.0, 104.0, 99.0, 104.0, 3));
+
+        // นาที 0 → นาที 3: ปิดแท่งนาที 0 + เติมนาที 1, 2
+        let closed = agg.on_tick(&tick(185, 106.0));
+        let starts: Vec<_> = closed.iter().map(|c| (c.start_time.timestamp() - 1_740_700_800, c.tick_count)).collect();
+        assert_eq!(starts, vec![(0, 3), (60, 0), (120, 0)]);
+        assert!(closed.iter().all(|c| c.timeframe == Timeframe::M1), "M5 is still forming");
+        assert_eq!(closed[2].close, 104.0);
+
+        assert!(agg.on_tick(&tick(70, 101.0)).is_empty(), "minute 1 is already closed");
+        assert_eq!(agg.forming("XAUUSD", Timeframe::M5).unwrap().tick_count, 5, "still inside the M5 candle");
+
+        // ข้ามไป 10 นาที (> max_gap_fill) → M1 ไม่เติม, M5 ปิด 00:00 + เติม 00:05
+        let closed = agg.on_tick(&tick(13 * 60, 107.0));
+        let m5: Vec<_> = closed.iter().filter(|c| c.timeframe == Timeframe::M5).collect();
+        assert_eq!(m5.len(), 2, "one M5 gap candle (00:05) filled, 00:10 is the new one");
+        assert_eq!(closed.iter().filter(|c| c.timeframe == Timeframe::M1).count(), 1);
+
+        let stats = agg.stats();
+        assert_eq!((stats.late_ticks, stats.gaps, stats.gap_fills), (1, 3, 3));
+        assert_eq!(agg.series("XAUUSD", Timeframe::M1).unwrap().closed.len(), 3, "ring buffer is capped");
     }
 }
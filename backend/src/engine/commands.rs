@@ -0,0 +1,267 @@
+//! # engine::commands
+//!
+//! **EA Command Channel** — คำสั่งจัดการ Position (ย้าย SL / ปิด / ปิดบางส่วน) ที่ต้องได้ Ack จาก MT5
+//! ก่อน Position Book จะเปลี่ยน (เดิมแนบไปกับ Response ของ Tick ถัดไปแล้วถือว่าทำแล้ว)
+//!
+//! ```text
+//! Exit Policy → dispatch()
+//!     Broker จำลอง (paper / recording) → สั่ง Broker ตรง → สำเร็จ = Apply ทันที
+//!     BROKER=mt5                        → เข้าคิว (CommandBook) → Book ยังไม่เปลี่ยน
+//! EA  GET  /api/mt5/commands?ea_id=&symbol=   → รับคำสั่งของ Symbol ตัวเอง (DELIVERED)
+//! EA  POST /api/mt5/commands/ack              → ok = Apply / ไม่ ok = Book เดิม (Tick ถัดไปประเมินใหม่)
+//! ทุก Tick / Poll → expire_commands: ไม่ Ack ภายใน EA_COMMAND_ACK_TIMEOUT_MS → ส่งซ้ำ (command_id เดิม)
+//!                                     ครบ EA_COMMAND_MAX_ATTEMPTS → TIMED_OUT (Reconciler ตาม SL จริงให้)
+//! EA  POST /api/mt5/position-close (ปิดบางส่วน) ก่อน Ack → Scale-out ขั้นนั้นนับแล้ว + ออกจากคิว
+//!                                     (Ack ที่หาย / มาทีหลังไม่ทำให้สั่งขั้นเดิมซ้ำ)
+//! ```
+//!
+//! ระหว่างที่ Position มีคำสั่งค้าง หรือ Ack ปิดทั้งหมดแล้วแต่ผลการปิดยังไม่มา (`closing`)
+//! Reflex Loop จะไม่สั่งอะไรเพิ่มกับ Position นั้น
+
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::broker::{BrokerKind, PositionClose};
+use crate::error::AppError;
+use crate::events::WsEvent;
+use crate::models::command::{CommandAck, CommandKind, EaCommand};
+use crate::state::SharedState;
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct CommandConfig {
+    /// รอ Ack นานเท่าไหร่ก่อนส่งคำสั่งเดิมซ้ำ
+    pub ack_timeout_ms: u64,
+    /// ส่งให้ EA ได้สูงสุดกี่ครั้งต่อคำสั่ง (รวมครั้งแรก)
+    pub max_attempts:   u32,
+}
+
+impl CommandConfig {
+    pub fn from_env() -> Self {
+        let env = |key: &str, default: u64| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            ack_timeout_ms: env("EA_COMMAND_ACK_TIMEOUT_MS", 5_000),
+            max_attempts:   env("EA_COMMAND_MAX_ATTEMPTS", 3).max(1) as u32,
+        }
+    }
+
+    pub fn ack_timeout(&self) -> chrono::Duration {
+        chrono::Duration::milliseconds(self.ack_timeout_ms as i64)
+    }
+}
+
+// ─── Dispatch ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub enum Dispatch {
+    /// Broker ทำเสร็จและ Book อัปเดตแล้ว — `Some` = Venue ปิดเสร็จพร้อมราคาปิด (Caller ปิด Book ต่อ)
+    Applied(Option<PositionClose>),
+    /// อยู่ในคิวของ EA — Book เปลี่ยนเมื่อ EA Ack
+    Queued(Uuid),
+}
+
+/// ส่งคำสั่งจัดการ Position ของ `symbol` ไปยัง Venue
+pub async fn dispatch(
+    state:  &SharedState,
+    symbol: &str,
+    kind:   CommandKind,
+    reason: &str,
+) -> Result<Dispatch, AppError> {
+    if state.broker.kind() == BrokerKind::Mt5 {
+        let command    = EaCommand::new(symbol, kind, reason);
+        let command_id = command.command_id;
+        info!(%command_id, symbol, ?kind, reason, "📨 EA command queued");
+        state.commands.write().await.enqueue(command.clone());
+        state.broadcast(&WsEvent::CommandQueued { command: Box::new(command) });
+        return Ok(Dispatch::Queued(command_id));
+    }
+
+    // Broker จำลองตอบผลทันที — ผลตอบกลับคือ Ack
+    let close = match kind {
+        CommandKind::ModifyPosition { ticket, sl, tp } => {
+            state.broker.modify_position(ticket, sl, tp).await?;
+            None
+        }
+        CommandKind::ClosePosition { ticket, volume } => {
+            state.broker.close_position(ticket, volume).await?.map(|mut close| {
+                close.close_reason = reason.to_string();
+                close
+            })
+        }
+    };
+    apply(state, &kind).await;
+    Ok(Dispatch::Applied(close))
+}
+
+// ─── Ack / Timeout ────────────────────────────────────────────────────────────
+
+/// EA รายงานผล — คืนคำสั่งที่ถูก Ack (None = ไม่รู้จัก หรือ Timeout ไปแล้ว)
+pub async fn apply_ack(state: &SharedState, ack: &CommandAck) -> Option<EaCommand> {
+    let Some(command) = state.commands.write().await.ack(ack.command_id) else {
+        warn!(command_id = %ack.command_id, ok = ack.ok, "📨 Ack for unknown or timed-out command — ignored");
+        return None;
+    };
+
+    if ack.ok {
+        apply(state, &command.kind).await;
+        info!(command_id = %command.command_id, kind = ?command.kind, attempts = command.attempts, "📨 EA command acknowledged");
+    } else {
+        warn!(
+            command_id = %command.command_id,
+            kind       = ?command.kind,
+            retcode    = ?ack.retcode,
+            message    = %ack.message,
+            "📨 EA command failed — position book unchanged"
+        );
+    }
+
+    state.broadcast(&WsEvent::CommandAcked {
+        command: Box::new(command.clone()),
+        ok:      ack.ok,
+        message: ack.message.clone(),
+    });
+    Some(command)
+}
+
+/// คำสั่งที่ส่งครบทุกครั้งแล้วยังไม่มี Ack → ทิ้ง (Book ไม่เปลี่ยน) — เรียกทุก Tick และทุก Poll
+pub async fn expire_commands(state: &SharedState) -> usize {
+    let config    = &state.command_config;
+    let timed_out = state
+        .commands
+        .write()
+        .await
+        .expire(state.now(), config.ack_timeout(), config.max_attempts);
+
+    for command in &timed_out {
+        warn!(
+            command_id = %command.command_id,
+            kind       = ?command.kind,
+            attempts   = command.attempts,
+            "📨 EA command timed out — no acknowledgement"
+        );
+        state.broadcast(&WsEvent::CommandTimedOut { command: Box::new(command.clone()) });
+    }
+    timed_out.len()
+}
+
+/// MT5 ทำคำสั่งแล้ว → อัปเดต Position Book
+///
+/// ปิดทั้งหมด → แค่ `closing` — Book ปิดเมื่อผลการปิดมาถึง (`/api/mt5/position-close` พร้อมราคาปิดจริง)
+/// ระหว่างนั้นคำสั่งออกจากคิวแล้ว Reflex Loop จึงดูจาก `closing` แทน (ไม่สั่งปิดซ้ำ)
+async fn apply(state: &SharedState, kind: &CommandKind) {
+    let mut positions = state.positions.write().await;
+    let Some(pos) = positions.get_mut(kind.ticket()) else {
+        return;
+    };
+    match *kind {
+        CommandKind::ModifyPosition { sl, .. } => {
+            // SL ถึงทุนแล้ว (Break-Even หรือ Trail ผ่านราคาเข้า) → ไม่ต้องย้ายไปทุนอีก
+            pos.sl_moved_to_be = pos.sl_moved_to_be || pos.favourable_move(sl) >= 0.0;
+            pos.stop_loss      = sl;
+        }
+        // Scale-out ขั้นนี้ทำแล้ว — Volume ลดลงเมื่อผลการปิดบางส่วนมาถึง
+        // (ผลมาก่อน Ack → `settle_partial_close` นับไปแล้ว และ Ack นี้ไม่รู้จักคำสั่งนั้น)
+        CommandKind::ClosePosition { volume: Some(_), .. } => pos.scale_outs_done += 1,
+        CommandKind::ClosePosition { volume: None, .. }    => {
+            pos.closing = true;
+            return;
+        }
+    }
+    state.db.position(pos);
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::engine::reflex::{evaluate_tick, Hold, TradeSignal};
+    use crate::models::exit::{ExitLevel, ScaleOut};
+    use crate::models::{strategy::test_strategy, tick::test_tick, ActiveStrategy, ExitPolicy, OpenPosition};
+    use crate::routes::mt5::apply_position_close;
+    use crate::state::AppState;
+    use crate::symbols::test_spec;
+
+    #[tokio::test]
+    async fn test_full_close_ack_pauses_exit_policy_until_close_result() {
+        let state: SharedState = Arc::new(AppState::new());
+        state.symbols.write().await.upsert(test_spec("XAUUSD"));
+
+        let strategy = ActiveStrategy {
+            exit_policy: Some(ExitPolicy { max_hold_minutes: Some(1), ..ExitPolicy::default() }),
+            ..test_strategy("XAUUSD")
+        };
+        let mut position = OpenPosition::from_strategy(&strategy, 2000.0);
+        position.opened_at = Utc::now() - chrono::Duration::hours(1);
+        state.open_position(7, position).await;
+
+        let kind    = CommandKind::ClosePosition { ticket: 7, volume: None };
+        let command = EaCommand::new("XAUUSD", kind, "MAX_HOLD_TIME");
+        state.commands.write().await.enqueue(command.clone());
+        let ack = CommandAck { command_id: command.command_id, ok: true, retcode: Some(10009), message: String::new() };
+        assert!(apply_ack(&state, &ack).await.is_some());
+
+        // คำสั่งออกจากคิวแล้ว แต่ /position-close ยังไม่มา → ห้ามสั่ง MAX_HOLD_TIME ซ้ำ
+        let tick   = test_tick("XAUUSD", Utc::now(), 2001.0);
+        let signal = evaluate_tick(&tick, &state).await.unwrap();
+        assert_eq!(signal, TradeSignal::NoAction(Hold::PositionOpen));
+    }
+
+    #[tokio::test]
+    async fn test_partial_close_result_settles_scale_out_when_ack_is_lost() {
+        let state: SharedState = Arc::new(AppState::new());
+        state.symbols.write().await.upsert(test_spec("XAUUSD"));
+
+        // ขั้นเดียว: ครึ่งหนึ่งที่ 1R — สั่งซ้ำ = ส่วนที่เหลือถูกปิดทั้งหมด
+        let strategy = ActiveStrategy {
+            exit_policy: Some(ExitPolicy {
+                scale_out: vec![ScaleOut { at: ExitLevel::R(1.0), fraction: 0.5 }],
+                ..ExitPolicy::default()
+            }),
+            ..test_strategy("XAUUSD")
+        };
+        state.open_position(7, OpenPosition::from_strategy(&strategy, 2000.0)).await;
+
+        let tick = test_tick("XAUUSD", Utc::now(), 2010.0);
+        let signal = evaluate_tick(&tick, &state).await.unwrap();
+        let TradeSignal::ClosePartial { volume, .. } = signal else {
+            panic!("expected scale-out, got {signal:?}");
+        };
+        let kind    = CommandKind::ClosePosition { ticket: 7, volume: Some(volume) };
+        let command = EaCommand::new("XAUUSD", kind, "SCALE_OUT");
+        state.commands.write().await.enqueue(command.clone());
+        state.commands.write().await.lease("ea-1", "XAUUSD", 10, Utc::now());
+
+        // EA ปิดแล้ว → ผลการปิดบางส่วนมาถึง แต่ Ack หาย
+        let close = PositionClose {
+            mt5_ticket:   Some(7),
+            symbol:       "XAUUSD".into(),
+            close_price:  2010.0,
+            profit_pips:  None,
+            profit:       Some(50.0),
+            close_reason: "SCALE_OUT".into(),
+            volume:       Some(volume),
+        };
+        assert!(apply_position_close(&state, &close).await.is_some());
+        let position = state.positions.read().await.get(7).cloned().unwrap();
+        assert_eq!(position.scale_outs_done, 1);
+
+        // เลย Ack timeout ทุกครั้ง → ไม่มีอะไร TIMED_OUT และไม่สั่งปิดซ้ำ
+        let later = Utc::now() + chrono::Duration::hours(1);
+        assert!(state.commands.write().await.expire(later, chrono::Duration::seconds(5), 3).is_empty());
+        let signal = evaluate_tick(&tick, &state).await.unwrap();
+        assert_eq!(signal, TradeSignal::NoAction(Hold::PositionOpen));
+
+        // Ack ที่มาช้าไม่นับขั้นซ้ำ
+        let ack = CommandAck { command_id: command.command_id, ok: true, retcode: Some(10009), message: String::new() };
+        assert!(apply_ack(&state, &ack).await.is_none());
+        assert_eq!(state.positions.read().await.get(7).unwrap().scale_outs_done, 1);
+    }
+}
@@ -1,6 +1,6 @@
 //! # engine::confirmation
 //!
-//! **Confirmation Engine** — ตรวจสอบ 3 ชั้นก่อนยิง Order
+//! **Confirmation Engine** — Pipeline ของชั้นตรวจสอบก่อนยิง Order
 //!
 //! ## ทำไมถึงต้องมี Confirmation?
 //!
@@ -9,25 +9,54 @@
 //! - อาจเป็นช่วงข่าว Spread กว้าง (High Risk)  
 //! - อาจเป็นแค่ Wick ผ่านไปชั่วขณะ (Fake Touch)
 //!
-//! ## 3 ชั้นการตรวจสอบ
+//! ## Pipeline
+//!
+//! แต่ละชั้น implement [`ConfirmationLayer`] — [`ConfirmationPipeline`] ตรวจเรียงตามลำดับ หยุดที่ชั้นแรกที่ไม่ผ่าน
+//! ลำดับมาจาก `ActiveStrategy.confirmation` หรือ `CONFIRM_PIPELINE` (ดู `models::confirmation`)
+//!
+//! Spread และ Zone width วัดเป็น **Pips** ผ่าน [`SymbolSpec`] — Config ชุดเดียวใช้ได้ทั้ง FX / Gold / Crypto
 //!
 //! ```text
-//! ราคาเข้า Zone
+//! ราคาเข้า Zone   (ค่าเริ่มต้น — ชั้นที่ปิดด้วย CONFIRM_REQUIRE_* ถูกตัดออก)
 //!     │
-//!     ├─ [1] Spread Check   → ป้องกันช่วง High Volatility / News
+//!     ├─ zone_width      → Zone กว้างเกิน = แผนไม่ชัด (ไม่ใช่ Supply/Demand จริง)
 //!     │
-//!     ├─ [2] Zone Probe     → ราคาเคย "สัมผัส" นอก Zone ก่อนไหม?
+//!     ├─ spread          → ป้องกันช่วง High Volatility / News
+//!     │
+//!     ├─ zone_probe      → ราคาเคย "สัมผัส" นอก Zone ก่อนไหม?
 //!     │      BUY:  เคยต่ำกว่า zone_low  → แสดงว่า Support ถูก Test แล้ว
 //!     │      SELL: เคยสูงกว่า zone_high → แสดงว่า Resistance ถูก Reject แล้ว
 //!     │
-//!     └─ [3] Zone Dwell     → อยู่ใน Zone ต่อเนื่อง ≥ N ticks
-//!            ป้องกัน Wick ผ่านชั่วขณะ
+//!     ├─ zone_dwell      → อยู่ใน Zone ต่อเนื่อง ≥ N ticks — ป้องกัน Wick ผ่านชั่วขณะ
+//!     │
+//!     ├─ rsi             → BUY ห้าม Overbought / SELL ห้าม Oversold
+//!     │
+//!     └─ wick_rejection  → แท่งที่กำลังสร้างมีไส้ตบกลับ (SMC)
 //! ```
+//!
+//! ชั้นเสริม (ต้องใส่ใน Pipeline เอง): `trend` — BUY เฉพาะขาขึ้น / SELL เฉพาะขาลงบน Timeframe ที่สูงกว่า
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Instant;
 
-use std::collections::VecDeque;
-use tracing::debug;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
 
-use crate::models::{Direction, strategy::EntryZone};
+use crate::engine::{candle_builder::CandleAggregator, indicators::IndicatorEngine};
+use crate::models::{
+    confirmation::{ConfirmationLayerSpec, TrendSource, TrendStrictness},
+    strategy::EntryZone,
+    ActiveStrategy, Direction, TickData, Timeframe,
+};
+use crate::symbols::SymbolSpec;
+
+/// แท่งต้องมี Tick อย่างน้อยเท่านี้ถึงจะอ่านรูปไส้เทียนได้
+const MIN_CANDLE_TICKS: u32 = 5;
+
+/// Swing High / Low = สูง / ต่ำกว่าแท่งข้างละเท่านี้แท่ง (Fractal)
+const SWING_STRENGTH: usize = 2;
 
 // ─── Config ───────────────────────────────────────────────────────────────────
 
@@ -35,9 +64,12 @@ use crate::models::{Direction, strategy::EntryZone};
 /// อ่านจาก Environment Variables ผ่าน `ConfirmationConfig::from_env()`
 #[derive(Debug, Clone)]
 pub struct ConfirmationConfig {
-    /// Spread สูงสุดที่ยอมรับได้ (หน่วยเดียวกับราคา)
-    /// เช่น BTCUSD: 50.0 = $50 | EURUSD: 0.0003 = 3 pips
-    pub max_spread: f64,
+    /// Spread สูงสุดที่ยอมรับได้ (Pips ตาม `SymbolSpec.pip_size`)
+    /// เช่น EURUSD: 3.0 = 0.0003 | XAUUSD: 3.0 = $0.30 | BTCUSD: 50.0 = $50
+    pub max_spread_pips: f64,
+
+    /// ความกว้าง Entry Zone สูงสุด (Pips, 0 = ไม่จำกัด)
+    pub max_zone_pips: f64,
 
     /// ต้องมี Zone Probe ก่อนถึงจะเข้าไหม?
     /// true  = ต้องเห็นราคาทดสอบ นอก Zone ก่อน (แนะนำ)
@@ -64,18 +96,50 @@ pub struct ConfirmationConfig {
 
     // ── [5] RSI Filter ─────────────────────────────────────────────────────
     /// RSI ที่เรียกว่า Overbought (สำหรับ BUY: ทางเปิดเมื่อ RSI < overbought)
-    /// ถ้า TickData ไม่เห็น rsi_14 → ข้าม RSI check
+    /// RSI มาจาก Indicator Engine (`rsi_timeframe`) — ยังไม่พอ Period → `rsi_14` ของ EA → ไม่มีเลย = ข้าม
     pub rsi_overbought: f64,
 
     /// RSI ที่เรียกว่า Oversold (สำหรับ SELL: ทางเปิดเมื่อ RSI > oversold)
     pub rsi_oversold: f64,
+
+    /// Timeframe ของ RSI (ต้องอยู่ใน `INDICATOR_TIMEFRAMES`)
+    pub rsi_timeframe: Timeframe,
+
+    /// ลำดับชั้นจาก `CONFIRM_PIPELINE` (เช่น `spread,zone_dwell,wick_rejection`)
+    /// None = 5 ชั้นเดิมตาม `require_zone_probe` / `require_wick_rejection` (ดู `default_pipeline`)
+    pub pipeline: Option<Vec<ConfirmationLayerSpec>>,
+
+    // ── Trend Filter (ชั้น `trend`) ───────────────────────────────────────
+    /// Timeframe ที่ดูเทรนด์ — MA ต้องอยู่ใน `INDICATOR_TIMEFRAMES`
+    pub trend_timeframe: Timeframe,
+
+    /// `ma` / `structure` / `both`
+    pub trend_source: TrendSource,
+
+    /// `strict` (ทุกแหล่งตรง) / `normal` (อย่างน้อยหนึ่ง) / `lenient` (แค่ไม่สวน)
+    pub trend_strictness: TrendStrictness,
+
+    /// MA เร็วต้องห่าง MA ช้าอย่างน้อยกี่ Pips (0 = แค่อยู่เหนือ / ใต้)
+    pub trend_min_gap_pips: f64,
+
+    /// แท่งที่ปิดแล้วย้อนหลังกี่แท่งสำหรับหา Swing
+    pub trend_swing_lookback: usize,
+
+    // ── Trace (`CONFIRMATION_TRACE`) ──────────────────────────────────────
+    /// Trace ที่เก็บต่อ Strategy (`GET /api/brain/strategy/:id/trace`)
+    pub trace_history: usize,
+
+    /// ผลเดิมซ้ำ → เก็บ / Broadcast ถี่สุดทุกกี่ ms (ตามเวลาของ Tick)
+    pub trace_interval_ms: u64,
 }
 
 impl ConfirmationConfig {
     pub fn from_env() -> Self {
         Self {
-            max_spread:        std::env::var("CONFIRM_MAX_SPREAD")
+            max_spread_pips:   std::env::var("CONFIRM_MAX_SPREAD_PIPS")
                 .ok().and_then(|v| v.parse().ok()).unwrap_or(50.0),
+            max_zone_pips:     std::env::var("CONFIRM_MAX_ZONE_PIPS")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(0.0),
             require_zone_probe: std::env::var("CONFIRM_REQUIRE_PROBE")
                 .map(|v| v != "false" && v != "0").unwrap_or(true),
             min_zone_ticks:    std::env::var("CONFIRM_MIN_ZONE_TICKS")
@@ -90,8 +154,55 @@ impl ConfirmationConfig {
                 .ok().and_then(|v| v.parse().ok()).unwrap_or(70.0),
             rsi_oversold:      std::env::var("CONFIRM_RSI_OVERSOLD")
                 .ok().and_then(|v| v.parse().ok()).unwrap_or(30.0),
+            rsi_timeframe:     std::env::var("CONFIRM_RSI_TIMEFRAME")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(Timeframe::M1),
+            pipeline:          std::env::var("CONFIRM_PIPELINE")
+                .ok().filter(|v| !v.trim().is_empty()).map(|v| parse_pipeline(&v)),
+            trend_timeframe:   std::env::var("CONFIRM_TREND_TIMEFRAME")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(Timeframe::H1),
+            trend_source:      std::env::var("CONFIRM_TREND_SOURCE")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(TrendSource::Both),
+            trend_strictness:  std::env::var("CONFIRM_TREND_STRICTNESS")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(TrendStrictness::Normal),
+            trend_min_gap_pips: std::env::var("CONFIRM_TREND_MIN_GAP_PIPS")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(0.0),
+            trend_swing_lookback: std::env::var("CONFIRM_TREND_SWING_LOOKBACK")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(50),
+            trace_history:     std::env::var("CONFIRM_TRACE_HISTORY")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(200),
+            trace_interval_ms: std::env::var("CONFIRM_TRACE_INTERVAL_MS")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(1_000),
         }
     }
+
+    /// Pipeline ของระบบ — `CONFIRM_PIPELINE` หรือลำดับเดิม (ตัดชั้นที่ปิดด้วย `CONFIRM_REQUIRE_*`)
+    pub fn default_pipeline(&self) -> Vec<ConfirmationLayerSpec> {
+        if let Some(pipeline) = &self.pipeline {
+            return pipeline.clone();
+        }
+        let mut names = vec!["zone_width", "spread"];
+        if self.require_zone_probe {
+            names.push("zone_probe");
+        }
+        names.extend(["zone_dwell", "rsi"]);
+        if self.require_wick_rejection {
+            names.push("wick_rejection");
+        }
+        names.into_iter().filter_map(|n| n.parse().ok()).collect()
+    }
+}
+
+/// รายชื่อชั้นคั่นด้วย `,` — ตัวที่อ่านไม่ได้ถูกข้าม (Log เตือน)
+fn parse_pipeline(raw: &str) -> Vec<ConfirmationLayerSpec> {
+    raw.split(',')
+        .filter_map(|name| match name.parse() {
+            Ok(spec) => Some(spec),
+            Err(e) => {
+                warn!("CONFIRM_PIPELINE: {e} — skipped");
+                None
+            }
+        })
+        .collect()
 }
 
 impl Default for ConfirmationConfig {
@@ -107,6 +218,7 @@ impl Default for ConfirmationConfig {
 #[derive(Debug, Clone, Copy)]
 pub struct RecentTick {
     pub mid:    f64,
+    #[allow(dead_code)]
     pub spread: f64,
 }
 
@@ -126,150 +238,524 @@ impl RecentTick {
 pub enum ConfirmationResult {
     /// ผ่านทุกชั้น → ยิง Trade ได้
     Confirmed,
-    /// ไม่ผ่าน — พร้อมบอกสาเหตุ (สำหรับ Log)
-    Rejected { reason: &'static str },
+    /// ไม่ผ่าน — ชั้นที่ Reject (`ConfirmationLayer::name`) + สาเหตุ (สำหรับ Log / Backtest)
+    Rejected { layer: &'static str, reason: String },
 }
 
-// ─── Main Check ───────────────────────────────────────────────────────────────
+// ─── Trace ────────────────────────────────────────────────────────────────────
 
-/// ตรวจสอบ 4 ชั้น: Spread → Zone Probe → Zone Dwell → RSI
-///
-/// # Arguments
-/// * `current_bid` / `current_ask` — ราคาปัจจุบัน
-/// * `zone`     — Entry Zone จาก ActiveStrategy
-/// * `dir`      — BUY หรือ SELL
-/// * `buffer`   — Tick Buffer ย้อนหลัง (ล่าสุดอยู่ท้าย VecDeque)
-/// * `candle`   — แท่งเทียน M1 ปัจจุบัน
-/// * `rsi`      — RSI ปัจจุบัน (ส่ง None ถ้า MT5 ไม่คำนวณหรือไม่ส่งมา → ข้ามได้)
-/// * `config`   — Confirmation parameters
-pub fn check_confirmation(
-    current_bid: f64,
-    current_ask: f64,
-    zone:        &EntryZone,
-    dir:         Direction,
-    buffer:      &VecDeque<RecentTick>,
-    candle:      Option<&crate::engine::candle_builder::Candle>,
-    rsi:         Option<f64>,
-    config:      &ConfirmationConfig,
-) -> ConfirmationResult {
-    let spread = current_ask - current_bid;
-    let mid    = (current_bid + current_ask) / 2.0;
-
-    // ── [1] Spread Check ──────────────────────────────────────────────────────
-    if spread > config.max_spread {
-        debug!(
-            spread       = spread,
-            max_spread   = config.max_spread,
-            "❌ Confirmation REJECTED: spread too wide"
-        );
-        return ConfirmationResult::Rejected { reason: "spread too wide" };
+/// ผลของชั้นเดียว — ค่าที่วัดได้ (`inputs`) เทียบกับเกณฑ์ (`threshold`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LayerCheck {
+    pub passed:    bool,
+    /// สาเหตุที่ไม่ผ่าน (หรือเหตุที่ข้าม เช่นไม่มี RSI)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason:    Option<String>,
+    pub inputs:    BTreeMap<&'static str, f64>,
+    pub threshold: Option<f64>,
+}
+
+impl LayerCheck {
+    pub fn pass() -> Self {
+        Self { passed: true, ..Self::default() }
+    }
+
+    pub fn fail(reason: impl Into<String>) -> Self {
+        Self { passed: false, reason: Some(reason.into()), ..Self::default() }
+    }
+
+    /// ผ่านถ้า `passed` ไม่งั้น Reject ด้วย `reason`
+    pub fn check(passed: bool, reason: &str) -> Self {
+        if passed { Self::pass() } else { Self::fail(reason) }
+    }
+
+    pub fn input(mut self, name: &'static str, value: f64) -> Self {
+        self.inputs.insert(name, value);
+        self
+    }
+
+    pub fn threshold(mut self, value: f64) -> Self {
+        self.threshold = Some(value);
+        self
+    }
+
+    fn note(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+}
+
+/// หนึ่งชั้นใน [`ConfirmationTrace`]
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerTrace {
+    pub layer:      &'static str,
+    #[serde(flatten)]
+    pub check:      LayerCheck,
+    pub elapsed_us: f64,
+}
+
+/// การประเมินหนึ่งครั้งตอนราคาอยู่ใน Zone — ทุกชั้นถูกตรวจ (ไม่หยุดที่ชั้นแรกที่ไม่ผ่าน)
+/// เพื่อให้เห็นว่าเหลือกี่ชั้นที่ยังขาด (Near-miss)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmationTrace {
+    /// เวลาของ Tick
+    pub time:        DateTime<Utc>,
+    pub symbol:      String,
+    pub direction:   Direction,
+    pub bid:         f64,
+    pub ask:         f64,
+    pub confirmed:   bool,
+    /// ชั้นแรกที่ไม่ผ่าน (None = Confirmed)
+    pub rejected_by: Option<&'static str>,
+    /// จำนวนชั้นที่ไม่ผ่าน — 1 = Near-miss
+    pub failed:      usize,
+    pub layers:      Vec<LayerTrace>,
+    pub elapsed_us:  f64,
+}
+
+impl ConfirmationTrace {
+    pub fn result(&self) -> ConfirmationResult {
+        match self.layers.iter().find(|l| !l.check.passed) {
+            Some(l) => ConfirmationResult::Rejected {
+                layer:  l.layer,
+                reason: l.check.reason.clone().unwrap_or_default(),
+            },
+            None => ConfirmationResult::Confirmed,
+        }
+    }
+}
+
+// ─── Layer Trait ──────────────────────────────────────────────────────────────
+
+/// ข้อมูลตลาด ณ Tick ที่ราคาเข้า Zone — ทุกชั้นอ่านจากที่เดียวกัน
+pub struct ConfirmationContext<'a> {
+    pub tick:       &'a TickData,
+    /// SymbolSpec สำหรับแปลงระยะราคาเป็น Pips
+    pub spec:       &'a SymbolSpec,
+    pub zone:       &'a EntryZone,
+    pub direction:  Direction,
+    /// Tick Buffer ย้อนหลัง (ล่าสุดอยู่ท้าย — ยังไม่รวม Tick ปัจจุบัน)
+    pub buffer:     &'a VecDeque<RecentTick>,
+    /// แท่งที่กำลังสร้างทุก Timeframe (Wick Rejection)
+    pub candles:    &'a CandleAggregator,
+    pub indicators: &'a IndicatorEngine,
+}
+
+impl ConfirmationContext<'_> {
+    fn mid(&self) -> f64 {
+        (self.tick.bid + self.tick.ask) / 2.0
+    }
+}
+
+/// หนึ่งชั้นของ Confirmation — คืนค่าที่วัดได้ + ผ่าน / ไม่ผ่าน
+pub trait ConfirmationLayer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck;
+}
+
+// ─── Pipeline ─────────────────────────────────────────────────────────────────
+
+/// ชั้นตรวจเรียงตามลำดับ — สร้างใหม่ต่อการประเมิน (ค่าจาก Spec + Config ณ ตอนนั้น)
+pub struct ConfirmationPipeline {
+    layers: Vec<Box<dyn ConfirmationLayer>>,
+}
+
+impl ConfirmationPipeline {
+    pub fn from_specs(specs: &[ConfirmationLayerSpec], config: &ConfirmationConfig) -> Self {
+        Self { layers: specs.iter().map(|spec| build_layer(spec, config)).collect() }
+    }
+
+    /// `strategy.confirmation` ถ้าส่งมา ไม่งั้น Pipeline ของระบบ
+    pub fn for_strategy(strategy: &ActiveStrategy, config: &ConfirmationConfig) -> Self {
+        match &strategy.confirmation {
+            Some(specs) => Self::from_specs(specs, config),
+            None        => Self::from_specs(&config.default_pipeline(), config),
+        }
     }
 
-    // ── [2] Zone Probe Check ──────────────────────────────────────────────────
-    // ตรวจว่าราคาเคย "สัมผัส" นอก Zone ก่อนที่จะกลับเข้ามาไหม
-    if config.require_zone_probe {
-        let lookback = buffer.len().min(config.probe_lookback);
-        let recent   = buffer.iter().rev().take(lookback);
+    /// ตรวจทุกชั้นพร้อมค่าที่วัดได้และเวลาที่ใช้
+    pub fn evaluate(&self, ctx: &ConfirmationContext) -> ConfirmationTrace {
+        let started = Instant::now();
+        let layers: Vec<LayerTrace> = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let t0    = Instant::now();
+                let check = layer.check(ctx);
+                LayerTrace { layer: layer.name(), check, elapsed_us: t0.elapsed().as_secs_f64() * 1e6 }
+            })
+            .collect();
+        let failed      = layers.iter().filter(|l| !l.check.passed).count();
+        let rejected_by = layers.iter().find(|l| !l.check.passed).map(|l| l.layer);
+
+        ConfirmationTrace {
+            time:        ctx.tick.time,
+            symbol:      ctx.tick.symbol.clone(),
+            direction:   ctx.direction,
+            bid:         ctx.tick.bid,
+            ask:         ctx.tick.ask,
+            confirmed:   failed == 0,
+            rejected_by,
+            failed,
+            layers,
+            elapsed_us:  started.elapsed().as_secs_f64() * 1e6,
+        }
+    }
+}
+
+fn build_layer(spec: &ConfirmationLayerSpec, config: &ConfirmationConfig) -> Box<dyn ConfirmationLayer> {
+    match spec {
+        ConfirmationLayerSpec::ZoneWidth { max_pips } => Box::new(ZoneWidth {
+            max_pips: max_pips.unwrap_or(config.max_zone_pips),
+        }),
+        ConfirmationLayerSpec::Spread { max_pips } => Box::new(SpreadCheck {
+            max_pips: max_pips.unwrap_or(config.max_spread_pips),
+        }),
+        ConfirmationLayerSpec::ZoneProbe { lookback } => Box::new(ZoneProbe {
+            lookback: lookback.unwrap_or(config.probe_lookback),
+        }),
+        ConfirmationLayerSpec::ZoneDwell { min_ticks } => Box::new(ZoneDwell {
+            min_ticks: min_ticks.unwrap_or(config.min_zone_ticks),
+        }),
+        ConfirmationLayerSpec::Rsi { overbought, oversold, timeframe } => Box::new(RsiFilter {
+            overbought: overbought.unwrap_or(config.rsi_overbought),
+            oversold:   oversold.unwrap_or(config.rsi_oversold),
+            timeframe:  timeframe.unwrap_or(config.rsi_timeframe),
+        }),
+        ConfirmationLayerSpec::WickRejection { min_ratio, timeframe } => Box::new(WickRejection {
+            min_ratio: min_ratio.unwrap_or(config.min_wick_ratio),
+            timeframe: timeframe.unwrap_or(Timeframe::M1),
+        }),
+        ConfirmationLayerSpec::Trend { timeframe, source, strictness, min_gap_pips, swing_lookback } => {
+            Box::new(TrendFilter {
+                timeframe:      timeframe.unwrap_or(config.trend_timeframe),
+                source:         source.unwrap_or(config.trend_source),
+                strictness:     strictness.unwrap_or(config.trend_strictness),
+                min_gap_pips:   min_gap_pips.unwrap_or(config.trend_min_gap_pips),
+                swing_lookback: swing_lookback.unwrap_or(config.trend_swing_lookback),
+            })
+        }
+    }
+}
+
+// ─── Layers ───────────────────────────────────────────────────────────────────
+
+/// Zone กว้างเกิน = แผนไม่ชัด (0 = ไม่จำกัด)
+struct ZoneWidth {
+    max_pips: f64,
+}
+
+impl ConfirmationLayer for ZoneWidth {
+    fn name(&self) -> &'static str { "zone_width" }
+
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let zone_pips = ctx.spec.pips(ctx.zone.high - ctx.zone.low);
+        LayerCheck::check(self.max_pips <= 0.0 || zone_pips <= self.max_pips, "zone too wide")
+            .input("zone_pips", zone_pips)
+            .threshold(self.max_pips)
+    }
+}
+
+/// ป้องกันช่วง High Volatility / News
+struct SpreadCheck {
+    max_pips: f64,
+}
+
+impl ConfirmationLayer for SpreadCheck {
+    fn name(&self) -> &'static str { "spread" }
+
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let spread = ctx.spec.pips(ctx.tick.ask - ctx.tick.bid);
+        LayerCheck::check(spread <= self.max_pips, "spread too wide")
+            .input("spread_pips", spread)
+            .threshold(self.max_pips)
+    }
+}
+
+/// ราคาเคย "สัมผัส" นอก Zone ก่อนกลับเข้ามาไหม
+struct ZoneProbe {
+    lookback: usize,
+}
+
+impl ConfirmationLayer for ZoneProbe {
+    fn name(&self) -> &'static str { "zone_probe" }
+
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let recent = ctx.buffer.iter().rev().take(self.lookback).map(|t| t.mid);
 
         // BUY:  ราคาเคยต่ำกว่า zone_low → "Support ถูก Test แล้วกลับมา" ✅
         // SELL: ราคาเคยสูงกว่า zone_high → "Resistance ถูก Reject แล้วกลับมา" ✅
-        let probe_found = match dir {
-            Direction::Buy  => recent.clone().any(|t| t.mid < zone.low),
-            Direction::Sell => recent.clone().any(|t| t.mid > zone.high),
-            Direction::NoTrade => false,
+        let (extreme, edge, probe_found) = match ctx.direction {
+            Direction::Buy => {
+                let low = recent.reduce(f64::min);
+                (low, ctx.zone.low, low.is_some_and(|l| l < ctx.zone.low))
+            }
+            Direction::Sell => {
+                let high = recent.reduce(f64::max);
+                (high, ctx.zone.high, high.is_some_and(|h| h > ctx.zone.high))
+            }
+            Direction::NoTrade => (None, 0.0, false),
         };
-
-        if !probe_found {
-            debug!(
-                direction   = ?dir,
-                zone_low    = zone.low,
-                zone_high   = zone.high,
-                lookback,
-                "❌ Confirmation REJECTED: no zone probe in recent ticks"
-            );
-            return ConfirmationResult::Rejected { reason: "no zone probe detected" };
+        let check = LayerCheck::check(probe_found, "no zone probe detected")
+            .input("lookback", self.lookback as f64)
+            .threshold(edge);
+        match extreme {
+            Some(price) => check.input("extreme", price),
+            None        => check,
         }
-
-        debug!("✓ Zone probe confirmed");
     }
+}
 
-    // ── [3] Zone Dwell Check ──────────────────────────────────────────────────
-    // นับ Ticks ที่อยู่ใน Zone ต่อเนื่องกัน (จากล่าสุดย้อนขึ้นไป)
-    // ถ้าน้อยเกินไป = ราคาแค่ผ่าน Zone (Wick/Spike) ไม่ใช่ Price Action จริง
-    let in_zone_consecutive = buffer
-        .iter()
-        .rev()                                              // นับจากล่าสุด
-        .take_while(|t| zone.contains(t.mid))              // หยุดเมื่อออกนอก Zone
-        .count();
+/// อยู่ใน Zone ต่อเนื่อง — น้อยเกินไป = ราคาแค่ผ่าน Zone (Wick/Spike) ไม่ใช่ Price Action จริง
+struct ZoneDwell {
+    min_ticks: usize,
+}
 
-    // บวก 1 สำหรับ Tick ปัจจุบัน (ซึ่งยังไม่ได้ push ลง buffer)
-    let total_dwell = in_zone_consecutive + if zone.contains(mid) { 1 } else { 0 };
+impl ConfirmationLayer for ZoneDwell {
+    fn name(&self) -> &'static str { "zone_dwell" }
 
-    if total_dwell < config.min_zone_ticks {
-        debug!(
-            dwell_ticks  = total_dwell,
-            min_required = config.min_zone_ticks,
-            "❌ Confirmation REJECTED: insufficient zone dwell"
-        );
-        return ConfirmationResult::Rejected { reason: "insufficient zone dwell" };
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let in_zone_consecutive = ctx
+            .buffer
+            .iter()
+            .rev()                                              // นับจากล่าสุด
+            .take_while(|t| ctx.zone.contains(t.mid))          // หยุดเมื่อออกนอก Zone
+            .count();
+
+        // บวก 1 สำหรับ Tick ปัจจุบัน (ซึ่งยังไม่ได้ push ลง buffer)
+        let total_dwell = in_zone_consecutive + usize::from(ctx.zone.contains(ctx.mid()));
+        LayerCheck::check(total_dwell >= self.min_ticks, "insufficient zone dwell")
+            .input("dwell_ticks", total_dwell as f64)
+            .threshold(self.min_ticks as f64)
     }
+}
+
+/// RSI ของ Indicator Engine (`timeframe`) — ยังไม่พอ Period → `rsi_14` ของ EA → ไม่มีเลย = ข้าม
+struct RsiFilter {
+    overbought: f64,
+    oversold:   f64,
+    timeframe:  Timeframe,
+}
 
-    debug!(
-        dwell_ticks = total_dwell,
-        spread,
-        "✅ Zone checks passed — checking RSI..."
-    );
+impl ConfirmationLayer for RsiFilter {
+    fn name(&self) -> &'static str { "rsi" }
 
-    // ── [4] RSI Filter (สามารถ Skip ได้ถ้าไม่ส่ง RSI) ───────────────────────────
-    if let Some(rsi_val) = rsi {
-        let blocked = match dir {
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let Some(rsi) = ctx.indicators.rsi(&ctx.tick.symbol, self.timeframe).or(ctx.tick.rsi_14) else {
+            return LayerCheck::pass().note("rsi not available — skipped");
+        };
+        let (blocked, threshold) = match ctx.direction {
             // BUY: ห้ามเข้าเมื่อ Overbought (RSI สูง)
-            Direction::Buy  => rsi_val >= config.rsi_overbought,
+            Direction::Buy     => (rsi >= self.overbought, self.overbought),
             // SELL: ห้ามเข้าเมื่อ Oversold (RSI ต่ำ)
-            Direction::Sell => rsi_val <= config.rsi_oversold,
-            Direction::NoTrade => false,
+            Direction::Sell    => (rsi <= self.oversold, self.oversold),
+            Direction::NoTrade => (false, 0.0),
         };
+        LayerCheck::check(!blocked, "rsi out of range")
+            .input("rsi", rsi)
+            .threshold(threshold)
+    }
+}
+
+/// SMC Style — แท่งที่กำลังสร้างของ `timeframe` ต้องมีไส้ตบกลับ
+struct WickRejection {
+    min_ratio: f64,
+    timeframe: Timeframe,
+}
+
+impl ConfirmationLayer for WickRejection {
+    fn name(&self) -> &'static str { "wick_rejection" }
+
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let Some(candle) = ctx.candles.forming(&ctx.tick.symbol, self.timeframe) else {
+            return LayerCheck::fail("no candle data available").threshold(self.min_ratio);
+        };
+        let is_buy = ctx.direction == Direction::Buy;
+        let check = if candle.tick_count < MIN_CANDLE_TICKS {
+            // ต้องรอให้แท่งเทียนมีอย่างน้อย X ticks ถึงจะพออ่านหน้าตาได้
+            LayerCheck::fail("waiting for candle formation")
+        } else {
+            LayerCheck::check(candle.has_rejection_wick(is_buy, self.min_ratio), "no wick rejection detected")
+        };
+        check
+            .input("tick_count", candle.tick_count as f64)
+            .input("wick_ratio", candle.wick_ratio(is_buy))
+            .threshold(self.min_ratio)
+    }
+}
 
-        if blocked {
-            debug!(
-                rsi          = rsi_val,
-                overbought   = config.rsi_overbought,
-                oversold     = config.rsi_oversold,
-                direction    = ?dir,
-                "❌ Confirmation REJECTED: RSI out of range"
-            );
-            return ConfirmationResult::Rejected { reason: "rsi out of range" };
+/// เทรนด์บน Timeframe ที่สูงกว่า — BUY เฉพาะขาขึ้น / SELL เฉพาะขาลง
+struct TrendFilter {
+    timeframe:      Timeframe,
+    source:         TrendSource,
+    strictness:     TrendStrictness,
+    min_gap_pips:   f64,
+    swing_lookback: usize,
+}
+
+impl TrendFilter {
+    /// MA เร็ว − ช้า (Pips) → +1 ขาขึ้น / −1 ขาลง / 0 ห่างไม่ถึง `min_gap_pips` — None = ยังไม่มี MA
+    fn ma_trend(&self, ctx: &ConfirmationContext, check: &mut LayerCheck) -> Option<i8> {
+        let snapshot = ctx.indicators.series(&ctx.tick.symbol, self.timeframe).map(|s| s.snapshot());
+        let fast = snapshot.and_then(|s| s.ema_fast).or(ctx.tick.ma_20)?;
+        let slow = snapshot.and_then(|s| s.ema_slow).or(ctx.tick.ma_50)?;
+        let gap  = ctx.spec.pips(fast - slow);
+        check.inputs.extend([("ma_fast", fast), ("ma_slow", slow), ("ma_gap_pips", gap)]);
+
+        Some(if gap > self.min_gap_pips.max(0.0) {
+            1
+        } else if gap < -self.min_gap_pips.max(0.0) {
+            -1
+        } else {
+            0
+        })
+    }
+
+    /// Swing สองจุดล่าสุด → +1 HH + HL / −1 LH + LL / 0 ปนกัน — None = Swing ไม่พอ
+    fn structure_trend(&self, ctx: &ConfirmationContext, check: &mut LayerCheck) -> Option<i8> {
+        let candles = ctx.candles.closed(&ctx.tick.symbol, self.timeframe, self.swing_lookback);
+        let (highs, lows) = swing_points(&candles);
+        let [.., prev_high, last_high] = highs[..] else { return None };
+        let [.., prev_low, last_low]   = lows[..]  else { return None };
+        check.inputs.extend([
+            ("swing_high_prev", prev_high),
+            ("swing_high_last", last_high),
+            ("swing_low_prev",  prev_low),
+            ("swing_low_last",  last_low),
+        ]);
+
+        Some(if last_high > prev_high && last_low > prev_low {
+            1
+        } else if last_high < prev_high && last_low < prev_low {
+            -1
+        } else {
+            0
+        })
+    }
+}
+
+impl ConfirmationLayer for TrendFilter {
+    fn name(&self) -> &'static str { "trend" }
+
+    fn check(&self, ctx: &ConfirmationContext) -> LayerCheck {
+        let want: i8 = match ctx.direction {
+            Direction::Buy     => 1,
+            Direction::Sell    => -1,
+            Direction::NoTrade => return LayerCheck::fail("no direction"),
+        };
+
+        let mut check = LayerCheck::pass().threshold(self.min_gap_pips);
+        let mut signals = Vec::with_capacity(2);
+        if matches!(self.source, TrendSource::Ma | TrendSource::Both) {
+            let signal = self.ma_trend(ctx, &mut check);
+            check = check.input("ma_trend", signal.map_or(0.0, f64::from));
+            signals.push(signal);
+        }
+        if matches!(self.source, TrendSource::Structure | TrendSource::Both) {
+            let signal = self.structure_trend(ctx, &mut check);
+            check = check.input("structure_trend", signal.map_or(0.0, f64::from));
+            signals.push(signal);
         }
-        debug!(rsi = rsi_val, "✓ RSI check passed");
-    } else {
-        debug!("— RSI not available, skipping RSI check");
+
+        // ข้อมูลยังไม่พอ (None) นับเป็น "ไม่มีเทรนด์" — Lenient ผ่าน, Strict / Normal ไม่ผ่าน
+        let aligned = |s: &Option<i8>| *s == Some(want);
+        let against = |s: &Option<i8>| *s == Some(-want);
+        let passed  = match self.strictness {
+            TrendStrictness::Strict  => signals.iter().all(aligned),
+            TrendStrictness::Normal  => signals.iter().any(aligned),
+            TrendStrictness::Lenient => !signals.iter().any(against),
+        };
+        if !passed {
+            check.passed = false;
+            check.reason = Some(if signals.iter().any(against) {
+                "trend against direction".into()
+            } else {
+                "trend not confirmed".into()
+            });
+        }
+        check
     }
+}
 
-    // ── [5] Wick Rejection (SMC Style) ──────────────────────────────────────
-    if config.require_wick_rejection {
-        if let Some(c) = candle {
-            // ต้องรอให้แท่งเทียนมีอย่างน้อย X ticks ถึงจะพออ่านหน้าตาได้
-            if c.tick_count < 5 {
-                return ConfirmationResult::Rejected { reason: "waiting for candle formation" };
-            }
+/// Swing High / Low แบบ Fractal (สูง / ต่ำกว่า `SWING_STRENGTH` แท่งทั้งสองข้าง) เรียงเก่า → ใหม่
+fn swing_points(candles: &[crate::engine::candle_builder::Candle]) -> (Vec<f64>, Vec<f64>) {
+    let mut highs = Vec::new();
+    let mut lows  = Vec::new();
+    if candles.len() < 2 * SWING_STRENGTH + 1 {
+        return (highs, lows);
+    }
+    for i in SWING_STRENGTH..candles.len() - SWING_STRENGTH {
+        let window = &candles[i - SWING_STRENGTH..=i + SWING_STRENGTH];
+        let candle = &candles[i];
+        let others = || window.iter().enumerate().filter(|&(j, _)| j != SWING_STRENGTH).map(|(_, c)| c);
+        if others().all(|c| candle.high > c.high) {
+            highs.push(candle.high);
+        }
+        if others().all(|c| candle.low < c.low) {
+            lows.push(candle.low);
+        }
+    }
+    (highs, lows)
+}
 
-            let is_rejection = c.has_rejection_wick(
-                dir == Direction::Buy,
-                config.min_wick_ratio
-            );
+// ─── Trace Book ───────────────────────────────────────────────────────────────
 
-            if !is_rejection {
-                debug!("❌ Confirmation REJECTED: waiting for wick rejection");
-                return ConfirmationResult::Rejected { reason: "no wick rejection detected" };
+/// Strategy ที่เก็บ Trace ได้พร้อมกัน — เกินนี้ทิ้งของ Strategy ที่เก่าที่สุด
+const MAX_TRACED_STRATEGIES: usize = 64;
+
+/// Ring Buffer ของ Trace ต่อ Strategy + Throttle ของ `CONFIRMATION_TRACE`
+///
+/// เก็บ (และ Broadcast) เมื่อผลเปลี่ยน (ชั้นที่ Reject / Confirmed) หรือห่างจากครั้งก่อน
+/// ≥ `CONFIRM_TRACE_INTERVAL_MS` ตามเวลาของ Tick — ราคาค้างใน Zone นานๆ ไม่ท่วม Buffer
+#[derive(Debug)]
+pub struct TraceBook {
+    history:  usize,
+    interval: chrono::Duration,
+    traces:   HashMap<Uuid, VecDeque<ConfirmationTrace>>,
+    /// ลำดับ Strategy ที่เริ่มเก็บ (เก่า → ใหม่)
+    order:    VecDeque<Uuid>,
+}
+
+impl TraceBook {
+    pub fn new(config: &ConfirmationConfig) -> Self {
+        Self {
+            history:  config.trace_history.max(1),
+            interval: chrono::Duration::milliseconds(config.trace_interval_ms as i64),
+            traces:   HashMap::new(),
+            order:    VecDeque::new(),
+        }
+    }
+
+    /// เก็บ Trace ถ้าผ่าน Throttle — คืน Trace ที่เก็บ (ให้ Broadcast ต่อ) หรือ None
+    pub fn record(&mut self, strategy_id: Uuid, trace: ConfirmationTrace) -> Option<&ConfirmationTrace> {
+        if !self.traces.contains_key(&strategy_id) {
+            if self.order.len() >= MAX_TRACED_STRATEGIES {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.traces.remove(&oldest);
+                }
             }
-            debug!("✓ Candle Rejection confirmed (Wick Ratio >= {})", config.min_wick_ratio);
-        } else {
-            return ConfirmationResult::Rejected { reason: "no candle data available" };
+            self.order.push_back(strategy_id);
+        }
+        let ring = self.traces.entry(strategy_id).or_default();
+
+        let throttled = ring.back().is_some_and(|last| {
+            last.rejected_by == trace.rejected_by && trace.time - last.time < self.interval
+        });
+        if throttled {
+            return None;
+        }
+        if ring.len() >= self.history {
+            ring.pop_front();
         }
+        ring.push_back(trace);
+        ring.back()
     }
 
-    debug!(spread, "✅ All confirmations passed — FIRE!");
-    ConfirmationResult::Confirmed
+    /// `limit` Trace ล่าสุดของ Strategy (เก่า → ใหม่)
+    pub fn for_strategy(&self, strategy_id: Uuid, limit: usize) -> Option<Vec<ConfirmationTrace>> {
+        let ring = self.traces.get(&strategy_id)?;
+        Some(ring.iter().skip(ring.len().saturating_sub(limit)).cloned().collect())
+    }
 }
 
 // ─── Tests ────────────────────────────────────────────────────────────────────
@@ -277,6 +763,8 @@ pub fn check_confirmation(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::{candle_builder::CandleConfig, indicators::IndicatorConfig};
+    use crate::symbols::test_spec;
 
     fn make_zone() -> EntryZone {
         EntryZone { low: 67000.0, high: 67050.0 }
@@ -284,7 +772,8 @@ mod tests {
 
     fn make_config() -> ConfirmationConfig {
         ConfirmationConfig {
-            max_spread:         50.0,
+            max_spread_pips:    50.0,
+            max_zone_pips:      0.0,
             require_zone_probe: true,
             min_zone_ticks:     2,
             probe_lookback:     10,
@@ -292,6 +781,15 @@ mod tests {
             min_wick_ratio:     0.60,
             rsi_overbought:     70.0,
             rsi_oversold:       30.0,
+            rsi_timeframe:      Timeframe::M1,
+            pipeline:           None,
+            trend_timeframe:    Timeframe::M1,
+            trend_source:       TrendSource::Both,
+            trend_strictness:   TrendStrictness::Normal,
+            trend_min_gap_pips: 0.0,
+            trend_swing_lookback: 50,
+            trace_history:      10,
+            trace_interval_ms:  1_000,
         }
     }
 
@@ -299,14 +797,100 @@ mod tests {
         mids.iter().map(|&m| RecentTick { mid: m, spread: 2.0 }).collect()
     }
 
+    /// Pipeline ของระบบจาก `config` กับ Tick ปัจจุบัน (ไม่มีแท่ง / Indicator — RSI มาจาก EA)
+    #[allow(clippy::too_many_arguments)]
+    fn evaluate(
+        bid:    f64,
+        ask:    f64,
+        spec:   &SymbolSpec,
+        zone:   &EntryZone,
+        dir:    Direction,
+        buffer: &VecDeque<RecentTick>,
+        rsi:    Option<f64>,
+        config: &ConfirmationConfig,
+    ) -> ConfirmationTrace {
+        let tick = TickData {
+            symbol: spec.symbol.clone(),
+            bid,
+            ask,
+            mid:    None,
+            volume: 0.0,
+            spread: None,
+            time:   chrono::Utc::now(),
+            rsi_14: rsi,
+            ma_20:  None,
+            ma_50:  None,
+        };
+        let candles    = CandleAggregator::new(CandleConfig { history_size: 10, max_gap_fill: 0 });
+        let indicators = IndicatorEngine::new(IndicatorConfig {
+            timeframes: vec![Timeframe::M1],
+            rsi_period: 14,
+            ema_fast:   20,
+            ema_slow:   50,
+            atr_period: 14,
+            bb_period:  20,
+            bb_stddev:  2.0,
+        });
+        let ctx = ConfirmationContext {
+            tick:       &tick,
+            spec,
+            zone,
+            direction:  dir,
+            buffer,
+            candles:    &candles,
+            indicators: &indicators,
+        };
+        ConfirmationPipeline::from_specs(&config.default_pipeline(), config).evaluate(&ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_confirmation(
+        bid:    f64,
+        ask:    f64,
+        spec:   &SymbolSpec,
+        zone:   &EntryZone,
+        dir:    Direction,
+        buffer: &VecDeque<RecentTick>,
+        rsi:    Option<f64>,
+        config: &ConfirmationConfig,
+    ) -> ConfirmationResult {
+        evaluate(bid, ask, spec, zone, dir, buffer, rsi, config).result()
+    }
+
+    fn rejected(layer: &'static str, reason: &str) -> ConfirmationResult {
+        ConfirmationResult::Rejected { layer, reason: reason.to_string() }
+    }
+
     #[test]
     fn test_spread_too_wide() {
         let buffer = make_buffer(&[66990.0, 67020.0, 67025.0]);
         let result = check_confirmation(
             67020.0, 67080.0,  // spread = 60 > 50
-            &make_zone(), Direction::Buy, &buffer, None, None, &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, None, &make_config()
+        );
+        assert_eq!(result, rejected("spread", "spread too wide"));
+    }
+
+    #[test]
+    fn test_spread_measured_in_pips() {
+        // EURUSD Spread 0.0004 = 4 pips — ต้องเทียบกับ max 3 pips ไม่ใช่ 3.0 หน่วยราคา
+        let zone   = EntryZone { low: 1.0800, high: 1.0820 };
+        let buffer = make_buffer(&[1.0795, 1.0805, 1.0810]);
+        let mut config = make_config();
+        config.max_spread_pips = 3.0;
+
+        let result = check_confirmation(
+            1.0810, 1.0814,
+            &test_spec("EURUSD"), &zone, Direction::Buy, &buffer, None, &config
         );
-        assert_eq!(result, ConfirmationResult::Rejected { reason: "spread too wide" });
+        assert_eq!(result, rejected("spread", "spread too wide"));
+
+        config.max_zone_pips = 10.0;  // Zone 20 pips
+        let result = check_confirmation(
+            1.0810, 1.0811,
+            &test_spec("EURUSD"), &zone, Direction::Buy, &buffer, None, &config
+        );
+        assert_eq!(result, rejected("zone_width", "zone too wide"));
     }
 
     #[test]
@@ -314,9 +898,9 @@ mod tests {
         let buffer = make_buffer(&[67010.0, 67015.0, 67020.0]);
         let result = check_confirmation(
             67020.0, 67022.0,
-            &make_zone(), Direction::Buy, &buffer, None, None, &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, None, &make_config()
         );
-        assert_eq!(result, ConfirmationResult::Rejected { reason: "no zone probe detected" });
+        assert_eq!(result, rejected("zone_probe", "no zone probe detected"));
     }
 
     #[test]
@@ -324,7 +908,7 @@ mod tests {
         let buffer = make_buffer(&[66980.0, 66995.0, 67010.0, 67020.0]);
         let result = check_confirmation(
             67025.0, 67027.0,
-            &make_zone(), Direction::Buy, &buffer, None, None, &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, None, &make_config()
         );
         assert_eq!(result, ConfirmationResult::Confirmed);
     }
@@ -334,7 +918,7 @@ mod tests {
         let buffer = make_buffer(&[67070.0, 67060.0, 67040.0, 67030.0]);
         let result = check_confirmation(
             67028.0, 67030.0,
-            &make_zone(), Direction::Sell, &buffer, None, None, &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Sell, &buffer, None, &make_config()
         );
         assert_eq!(result, ConfirmationResult::Confirmed);
     }
@@ -344,9 +928,9 @@ mod tests {
         let buffer = make_buffer(&[66985.0, 66990.0, 66999.0]);
         let result = check_confirmation(
             67005.0, 67007.0,
-            &make_zone(), Direction::Buy, &buffer, None, None, &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, None, &make_config()
         );
-        assert_eq!(result, ConfirmationResult::Rejected { reason: "insufficient zone dwell" });
+        assert_eq!(result, rejected("zone_dwell", "insufficient zone dwell"));
     }
 
     #[test]
@@ -355,9 +939,9 @@ mod tests {
         let buffer = make_buffer(&[66980.0, 66995.0, 67010.0, 67020.0]);
         let result = check_confirmation(
             67025.0, 67027.0,
-            &make_zone(), Direction::Buy, &buffer, None, Some(75.0), &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, Some(75.0), &make_config()
         );
-        assert_eq!(result, ConfirmationResult::Rejected { reason: "rsi out of range" });
+        assert_eq!(result, rejected("rsi", "rsi out of range"));
     }
 
     #[test]
@@ -366,8 +950,134 @@ mod tests {
         let buffer = make_buffer(&[66980.0, 66995.0, 67010.0, 67020.0]);
         let result = check_confirmation(
             67025.0, 67027.0,
-            &make_zone(), Direction::Buy, &buffer, None, Some(55.0), &make_config()
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, Some(55.0), &make_config()
         );
         assert_eq!(result, ConfirmationResult::Confirmed);
     }
+
+    #[test]
+    fn test_custom_pipeline_order_and_params() {
+        // Pipeline แค่ Spread + RSI (overbought 80) — ไม่มี Probe / Dwell
+        let mut config = make_config();
+        config.pipeline = Some(parse_pipeline("spread, zone_probe, bogus"));
+        assert_eq!(config.default_pipeline().len(), 2);
+
+        let specs: Vec<ConfirmationLayerSpec> =
+            serde_json::from_str(r#"[{ "type": "spread" }, { "type": "rsi", "overbought": 80 }]"#).unwrap();
+        config.pipeline = Some(specs);
+
+        let buffer = make_buffer(&[67010.0]);
+        let result = check_confirmation(
+            67025.0, 67027.0,
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, Some(75.0), &config
+        );
+        assert_eq!(result, ConfirmationResult::Confirmed);
+
+        let result = check_confirmation(
+            67025.0, 67027.0,
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, Some(85.0), &config
+        );
+        assert_eq!(result, rejected("rsi", "rsi out of range"));
+    }
+
+    #[test]
+    fn test_trace_records_every_layer_and_throttles() {
+        // ไม่มี Probe แต่ Dwell ผ่าน → Near-miss: ไม่ผ่านชั้นเดียว ค่าที่วัดได้ครบทุกชั้น
+        let buffer = make_buffer(&[67010.0, 67015.0, 67020.0]);
+        let trace  = evaluate(
+            67020.0, 67022.0,
+            &test_spec("BTCUSD"), &make_zone(), Direction::Buy, &buffer, Some(55.0), &make_config()
+        );
+        assert_eq!((trace.confirmed, trace.rejected_by, trace.failed), (false, Some("zone_probe"), 1));
+        assert_eq!(trace.layers.len(), 5);
+        let probe = &trace.layers[2];
+        assert_eq!((probe.check.inputs["extreme"], probe.check.threshold), (67010.0, Some(67000.0)));
+        assert_eq!(trace.layers[3].check.inputs["dwell_ticks"], 4.0);
+        assert_eq!(trace.layers[4].check.inputs["rsi"], 55.0);
+
+        // ผลเดิมภายใน 1 วินาที → ไม่เก็บซ้ำ / ผลเปลี่ยน → เก็บทันที
+        let id       = Uuid::new_v4();
+        let mut book = TraceBook::new(&make_config());
+        let mut next = |trace: &ConfirmationTrace, ms: i64, rejected_by| {
+            let mut t = trace.clone();
+            t.time += chrono::Duration::milliseconds(ms);
+            t.rejected_by = rejected_by;
+            book.record(id, t).is_some()
+        };
+        assert!(next(&trace, 0, Some("zone_probe")));
+        assert!(!next(&trace, 500, Some("zone_probe")));
+        assert!(next(&trace, 600, None));
+        assert!(next(&trace, 1_700, None));
+        assert_eq!(book.for_strategy(id, 10).unwrap().len(), 3);
+        assert_eq!(book.for_strategy(id, 1).unwrap()[0].rejected_by, None);
+    }
+
+    #[test]
+    fn test_trend_layer_uses_ma_and_swing_structure() {
+        // ขาขึ้น M1: Swing High 13 → 16 (HH), Swing Low 11 → 14 (HL) และ EMA 2 > EMA 3
+        let prices = [10.0, 11.0, 13.0, 12.0, 11.0, 12.0, 14.0, 16.0, 15.0, 14.0, 15.0, 17.0, 19.0];
+        let tick_at = |minute: usize, price: f64| TickData {
+            symbol: "XAUUSD".to_string(),
+            bid:    price,
+            ask:    price,
+            mid:    None,
+            volume: 0.0,
+            spread: None,
+            time:   DateTime::from_timestamp(1_740_700_800 + minute as i64 * 60, 0).unwrap(),
+            rsi_14: None,
+            ma_20:  None,
+            ma_50:  None,
+        };
+        let mut candles    = CandleAggregator::new(CandleConfig { history_size: 50, max_gap_fill: 0 });
+        let mut indicators = IndicatorEngine::new(IndicatorConfig {
+            timeframes: vec![Timeframe::M1],
+            rsi_period: 14,
+            ema_fast:   2,
+            ema_slow:   3,
+            atr_period: 14,
+            bb_period:  20,
+            bb_stddev:  2.0,
+        });
+        for (minute, &price) in prices.iter().enumerate() {
+            for candle in candles.on_tick(&tick_at(minute, price)) {
+                indicators.on_candle(&candle);
+            }
+        }
+
+        let tick   = tick_at(prices.len() - 1, 19.0);
+        let spec   = test_spec("XAUUSD");
+        let zone   = EntryZone { low: 18.0, high: 20.0 };
+        let buffer = VecDeque::new();
+        let ctx    = |direction| ConfirmationContext {
+            tick:       &tick,
+            spec:       &spec,
+            zone:       &zone,
+            direction,
+            buffer:     &buffer,
+            candles:    &candles,
+            indicators: &indicators,
+        };
+        let layer = |source, strictness| TrendFilter {
+            timeframe:      Timeframe::M1,
+            source,
+            strictness,
+            min_gap_pips:   0.0,
+            swing_lookback: 50,
+        };
+
+        let buy = layer(TrendSource::Both, TrendStrictness::Strict).check(&ctx(Direction::Buy));
+        assert!(buy.passed, "{buy:?}");
+        assert_eq!((buy.inputs["swing_high_last"], buy.inputs["swing_low_last"]), (16.0, 14.0));
+        assert_eq!((buy.inputs["ma_trend"], buy.inputs["structure_trend"]), (1.0, 1.0));
+
+        let sell = layer(TrendSource::Both, TrendStrictness::Lenient).check(&ctx(Direction::Sell));
+        assert_eq!((sell.passed, sell.reason.as_deref()), (false, Some("trend against direction")));
+
+        // H1 ยังไม่มีแท่ง / MA → Normal ไม่ผ่าน, Lenient ผ่าน
+        let mut unknown = layer(TrendSource::Structure, TrendStrictness::Normal);
+        unknown.timeframe = Timeframe::H1;
+        assert_eq!(unknown.check(&ctx(Direction::Buy)).reason.as_deref(), Some("trend not confirmed"));
+        unknown.strictness = TrendStrictness::Lenient;
+        assert!(unknown.check(&ctx(Direction::Buy)).passed);
+    }
 }
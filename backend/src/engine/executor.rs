@@ -1,48 +1,72 @@
 //! # engine::executor
 //!
-//! **Trade Executor** — ยิง Order จริงไปที่ MT5 ผ่าน HTTP
+//! **Trade Executor** — สร้าง Order จาก Strategy แล้วยิงผ่าน [`Broker`]
 //!
-//! ## MT5 EA API Contract (ฝั่ง MQL5)
-//! EA ต้องรับ POST `/order/send` และคืน JSON:
-//! ```json
-//! { "retcode": 10009, "order": 123456, "comment": "Request completed" }
+//! Executor ไม่รู้ว่าปลายทางคือ MT5, Paper หรือ Venue อื่น — Wire format
+//! และ API Contract ของแต่ละ Venue อยู่ใน `broker::*`
+//!
+//! ## Retry
+//! ```text
+//! ส่ง Order (client_id จาก trade_id)
+//!   สำเร็จ / Venue ปฏิเสธ     → จบ (ปฏิเสธชัดเจน = ไม่ส่งซ้ำ)
+//!   Timeout (ExecutionUnknown) → รอ Backoff → ถามผลด้วย client_id
+//!       พบ Order      → สำเร็จ (ไม่ส่งซ้ำ)
+//!       ไม่พบ         → ส่งซ้ำได้อย่างปลอดภัย (client_id เดิม)
+//!       ถามไม่ได้      → ถามใหม่รอบถัดไป — ห้ามส่งซ้ำจนกว่าจะรู้ผล
+//! ครบ ORDER_MAX_ATTEMPTS แล้วยังไม่รู้ผล → ExecutionUnknown (TradeStatus::Unknown)
 //! ```
-//! retcode 10009 = `TRADE_RETCODE_DONE` (สำเร็จ)
+
+use std::future::Future;
+use std::time::Duration;
 
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
+use crate::broker::{Broker, OrderFill, OrderRequest};
 use crate::error::AppError;
-use crate::models::Direction;
-
-// ─── MT5 Request / Response ───────────────────────────────────────────────────
-
-/// Payload ที่ส่งไปยัง MT5 EA endpoint
-#[derive(Debug, serde::Serialize)]
-pub struct Mt5OrderRequest {
-    pub symbol:  String,
-    pub action:  &'static str,  // "BUY" | "SELL"
-    pub volume:  f64,
-    pub price:   f64,
-    pub sl:      f64,
-    pub tp:      f64,
-    pub comment: String,
-    pub magic:   u64,           // Antigravity magic number
+use crate::models::{Direction, OrderKind};
+
+/// Magic number ของทุก Order ที่ Antigravity ส่ง — ใช้แยก Position ของเรา
+/// ออกจาก EA ตัวอื่นในบัญชีเดียวกัน (ต้องตรงกับ `AGV_MAGIC` ใน AntGravityBridge.mq5)
+pub const AGV_MAGIC: u64 = 420001;
+
+/// Client order ID ของ Trade — เหมือนเดิมทุกครั้งที่ส่งซ้ำ
+/// (20 ตัวอักษร — พอดี Order comment ของ MT5 ซึ่งยาวได้ 31)
+pub fn client_order_id(trade_id: Uuid) -> String {
+    format!("AGV-{}", &trade_id.simple().to_string()[..16])
+}
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ExecutionConfig {
+    /// ส่ง Order ได้สูงสุดกี่ครั้งต่อ Trade (รวมครั้งแรก)
+    pub max_attempts: u32,
+    /// รอก่อนถามผลครั้งแรกหลัง Timeout — เพิ่มเท่าตัวทุกรอบ
+    pub backoff_ms:   u64,
 }
 
-/// Response จาก MT5 EA
-#[derive(Debug, serde::Deserialize)]
-pub struct Mt5OrderResponse {
-    /// MT5 Return Code — 10009 = SUCCESS
-    pub retcode: u32,
-    /// MT5 Ticket / Order ID (มีเมื่อ retcode = 10009)
-    pub order:   Option<u64>,
-    /// ข้อความอธิบายจาก MT5
-    pub comment: Option<String>,
+impl ExecutionConfig {
+    pub fn from_env() -> Self {
+        let env = |key: &str, default: u64| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            max_attempts: env("ORDER_MAX_ATTEMPTS", 3).max(1) as u32,
+            backoff_ms:   env("ORDER_RETRY_BACKOFF_MS", 500),
+        }
+    }
+
+    /// Backoff ก่อนถามผลรอบที่ `round` (เริ่มที่ 1)
+    fn backoff(&self, round: u32) -> Duration {
+        Duration::from_millis(self.backoff_ms.saturating_mul(1 << (round - 1).min(10)))
+    }
 }
 
 // ─── Build Order ──────────────────────────────────────────────────────────────
 
-/// สร้าง `Mt5OrderRequest` จาก Strategy + entry price
+/// สร้าง Market `OrderRequest` จาก Strategy + entry price ของ TradeRecord `trade_id`
+/// (Pending Order = `OrderRequest { kind, expires_at, ..build_order(..)? }`)
 pub fn build_order(
     symbol: &str,
     direction: Direction,
@@ -50,109 +74,197 @@ pub fn build_order(
     sl: f64,
     tp: f64,
     lot_size: f64,
-    strategy_id: uuid::Uuid,
-) -> Result<Mt5OrderRequest, AppError> {
-    let action = match direction {
-        Direction::Buy  => "BUY",
-        Direction::Sell => "SELL",
-        Direction::NoTrade => {
-            return Err(AppError::BadRequest(
-                "Cannot build order for NoTrade direction".into(),
-            ))
-        }
-    };
+    trade_id: Uuid,
+) -> Result<OrderRequest, AppError> {
+    if direction == Direction::NoTrade {
+        return Err(AppError::BadRequest(
+            "Cannot build order for NoTrade direction".into(),
+        ));
+    }
 
-    Ok(Mt5OrderRequest {
-        symbol:  symbol.to_string(),
-        action,
-        volume:  lot_size,
-        price:   entry_price,
+    Ok(OrderRequest {
+        symbol:     symbol.to_string(),
+        direction,
+        kind:       OrderKind::Market,
+        volume:     lot_size,
+        price:      entry_price,
         sl,
         tp,
-        comment: format!("AGV-{}", &strategy_id.to_string()[..8]),
-        magic:   420001,
+        client_id:  client_order_id(trade_id),
+        // Adapter ค้นหา Order จาก comment — ต้องเท่ากับ client_id
+        comment:    client_order_id(trade_id),
+        magic:      AGV_MAGIC,
+        expires_at: None,
     })
 }
 
 // ─── Fire Trade ───────────────────────────────────────────────────────────────
 
-/// ส่ง Order ไปที่ MT5 EA และรอ Response
+/// ส่ง Market Order ไปที่ Broker และรอผล (Retry ตาม [`ExecutionConfig`])
 ///
-/// คืน `Mt5OrderResponse` ถ้าสำเร็จ, `AppError::ExecutionError` ถ้าล้มเหลว
+/// คืน `OrderFill` (มี Ticket เสมอ) ถ้าสำเร็จ, `AppError::ExecutionError` ถ้าถูกปฏิเสธ
+/// และ `AppError::ExecutionUnknown` ถ้าครบทุกรอบแล้วยังไม่รู้ว่า Venue ได้รับหรือไม่
 pub async fn fire_trade(
-    order: &Mt5OrderRequest,
-    client: &reqwest::Client,
-    mt5_base_url: &str,
-) -> Result<Mt5OrderResponse, AppError> {
-    if mt5_base_url == "mock" {
-        info!("🎭 [EXECUTOR] Running in MOCK mode — simulating MT5 success");
-        return Ok(Mt5OrderResponse {
-            retcode: 10009,
-            order:   Some(999999),
-            comment: Some("Mock Order".to_string()),
-        });
+    order:  &OrderRequest,
+    broker: &dyn Broker,
+    config: &ExecutionConfig,
+) -> Result<OrderFill, AppError> {
+    let result = submit(order, broker, config, || broker.send_order(order), |mut fill| {
+        // Venue ไม่บอกราคาของ Order ที่หาเจอ → ราคาที่ขอ
+        if fill.price <= 0.0 {
+            fill.price = order.price;
+        }
+        fill
+    })
+    .await;
+
+    match &result {
+        Ok(fill) => info!(
+            broker    = ?broker.kind(),
+            client_id = %order.client_id,
+            ticket    = fill.ticket,
+            price     = fill.price,
+            "✅ [EXECUTOR] Order accepted"
+        ),
+        Err(e) => error!(broker = ?broker.kind(), client_id = %order.client_id, error = %e, "[EXECUTOR] Order failed"),
     }
+    result
+}
 
-    let url = format!("{mt5_base_url}/order/send");
-
-    info!(
-        symbol    = %order.symbol,
-        action    = %order.action,
-        volume    = order.volume,
-        price     = order.price,
-        sl        = order.sl,
-        tp        = order.tp,
-        mt5_url   = %url,
-        "🚀 [EXECUTOR] Sending order to MT5"
-    );
-
-    // ── HTTP POST ─────────────────────────────────────────────────────────────
-    let response = client
-        .post(&url)
-        .json(order)
-        .timeout(std::time::Duration::from_secs(5))   // ห้ามรอนานกว่า 5 วิ
-        .send()
-        .await
-        .map_err(|e| {
-            error!(error = %e, "MT5 unreachable");
-            AppError::ExecutionError(format!("MT5 unreachable: {e}"))
-        })?;
-
-    // ── HTTP Status ───────────────────────────────────────────────────────────
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        error!(http_status = %status, body = %body, "MT5 returned HTTP error");
-        return Err(AppError::ExecutionError(
-            format!("MT5 HTTP {status}: {body}")
-        ));
+/// วาง Pending Order (Limit / Stop) ที่โบรกเกอร์ — คืน Order ticket ถ้าสำเร็จ (Retry เหมือน `fire_trade`)
+pub async fn place_pending(
+    order:  &OrderRequest,
+    broker: &dyn Broker,
+    config: &ExecutionConfig,
+) -> Result<u64, AppError> {
+    let result = submit(order, broker, config, || broker.place_order(order), |fill| fill.ticket).await;
+
+    match &result {
+        Ok(ticket) => info!(
+            broker    = ?broker.kind(),
+            client_id = %order.client_id,
+            ticket,
+            kind      = ?order.kind,
+            price     = order.price,
+            "📌 [EXECUTOR] Pending order placed"
+        ),
+        Err(e) => error!(broker = ?broker.kind(), kind = ?order.kind, error = %e, "[EXECUTOR] Pending order failed"),
     }
+    result
+}
 
-    // ── Parse Response ────────────────────────────────────────────────────────
-    let mt5_resp: Mt5OrderResponse = response
-        .json()
-        .await
-        .map_err(|e| {
-            error!(error = %e, "MT5 response parse failed");
-            AppError::ExecutionError(format!("MT5 response parse error: {e}"))
-        })?;
-
-    // ── Check retcode ─────────────────────────────────────────────────────────
-    // 10009 = TRADE_RETCODE_DONE (เท่านั้นที่ถือว่า success)
-    if mt5_resp.retcode != 10009 {
-        let msg = format!(
-            "MT5 rejected: retcode={} comment={}",
-            mt5_resp.retcode,
-            mt5_resp.comment.as_deref().unwrap_or("unknown")
-        );
-        warn!("{msg}");
-        return Err(AppError::ExecutionError(msg));
+/// ส่งคำสั่งจนรู้ผล — Timeout ต้องถามผลด้วย client_id ก่อนส่งซ้ำทุกครั้ง
+/// (`recovered` แปลง Order ที่หาเจอเป็นผลแบบเดียวกับ `send`)
+async fn submit<T, F, Fut>(
+    order:     &OrderRequest,
+    broker:    &dyn Broker,
+    config:    &ExecutionConfig,
+    send:      F,
+    recovered: impl Fn(OrderFill) -> T,
+) -> Result<T, AppError>
+where
+    F:   Fn() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut sent  = 0;
+    let mut round = 0;
+
+    loop {
+        match send().await {
+            Ok(result) => return Ok(result),
+            Err(AppError::ExecutionUnknown(reason)) => {
+                sent += 1;
+                warn!(client_id = %order.client_id, attempt = sent, %reason, "⏳ [EXECUTOR] Order outcome unknown");
+            }
+            // ปฏิเสธชัดเจน — ส่งซ้ำก็ได้ผลเดิม
+            Err(e) => return Err(e),
+        }
+
+        // ── ถามผลก่อนส่งซ้ำ — ถามไม่ได้ = ยังไม่รู้ → ถามใหม่ ไม่ส่งซ้ำ ─────────
+        loop {
+            round += 1;
+            if round > config.max_attempts {
+                return Err(AppError::ExecutionUnknown(format!(
+                    "order {} outcome unknown after {sent} attempt(s)",
+                    order.client_id
+                )));
+            }
+            tokio::time::sleep(config.backoff(round)).await;
+
+            match broker.order_status(&order.client_id).await {
+                Ok(Some(fill)) => {
+                    info!(client_id = %order.client_id, ticket = fill.ticket, "🔎 [EXECUTOR] Order found after timeout");
+                    return Ok(recovered(fill));
+                }
+                Ok(None) => break,
+                Err(e) => warn!(client_id = %order.client_id, error = %e, "[EXECUTOR] Order status query failed"),
+            }
+        }
+
+        // Venue ไม่เคยได้รับ — ส่งซ้ำได้ถ้ายังไม่ครบจำนวนครั้ง
+        if sent >= config.max_attempts {
+            return Err(AppError::ExecutionError(format!(
+                "order {} not received by venue after {sent} attempt(s)",
+                order.client_id
+            )));
+        }
     }
+}
 
-    info!(
-        ticket = ?mt5_resp.order,
-        "✅ [EXECUTOR] MT5 accepted order"
-    );
+// ─── Tests ────────────────────────────────────────────────────────────────────
 
-    Ok(mt5_resp)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::broker::{recording::BrokerCall, PaperBroker, PaperConfig, RecordingBroker};
+    use crate::symbols::SymbolRegistry;
+
+    #[tokio::test]
+    async fn test_timeout_queries_status_before_resending() {
+        let paper = PaperBroker::new(
+            PaperConfig { balance: 10_000.0, ..PaperConfig::default() },
+            Arc::new(tokio::sync::RwLock::new(SymbolRegistry::from_env())),
+        );
+        let broker = RecordingBroker::new(Arc::new(paper));
+        let tick = serde_json::from_value(serde_json::json!({
+            "symbol": "XAUUSD", "bid": 1999.7, "ask": 2000.0, "volume": 1.0, "time": chrono::Utc::now(),
+        }))
+        .unwrap();
+        broker.on_tick(&tick).await;
+        let config = ExecutionConfig { max_attempts: 3, backoff_ms: 0 };
+
+        // Venue Fill แล้วแต่ Response หาย → ถามผลเจอ → ไม่ส่งซ้ำ
+        let order = build_order("XAUUSD", Direction::Buy, 2000.0, 1995.0, 2010.0, 0.2, Uuid::new_v4()).unwrap();
+        broker.time_out_orders(1, true);
+        let fill = fire_trade(&order, &broker, &config).await.unwrap();
+        assert_eq!(broker.list_positions().await.unwrap().len(), 1);
+        assert_eq!(broker.calls()[..2], [
+            BrokerCall::SendOrder { order: order.clone() },
+            BrokerCall::OrderStatus { client_id: order.client_id.clone() },
+        ]);
+
+        // ส่งซ้ำด้วย client_id เดิม → Venue คืนผลเดิม ไม่เปิด Position ใหม่
+        assert_eq!(broker.send_order(&order).await.unwrap().ticket, fill.ticket);
+        assert_eq!(broker.list_positions().await.unwrap().len(), 1);
+
+        // หายก่อนถึง Venue → ถามผลไม่พบ → ส่งซ้ำได้
+        let order = build_order("XAUUSD", Direction::Buy, 2000.0, 1995.0, 2010.0, 0.2, Uuid::new_v4()).unwrap();
+        broker.time_out_orders(1, false);
+        assert!(fire_trade(&order, &broker, &config).await.is_ok());
+        assert_eq!(broker.list_positions().await.unwrap().len(), 2);
+        let sends = broker.calls().iter().filter(|c| *c == &BrokerCall::SendOrder { order: order.clone() }).count();
+        assert_eq!(sends, 2);
+
+        // หายทุกครั้งและถามผลยืนยันว่าไม่เคยได้รับ → ส่งครบ max_attempts แล้วถือว่าล้มเหลว
+        let order = build_order("XAUUSD", Direction::Buy, 2000.0, 1995.0, 2010.0, 0.2, Uuid::new_v4()).unwrap();
+        broker.time_out_orders(u32::MAX, false);
+        assert!(matches!(
+            fire_trade(&order, &broker, &config).await,
+            Err(AppError::ExecutionError(_))
+        ));
+        let sends = broker.calls().iter().filter(|c| *c == &BrokerCall::SendOrder { order: order.clone() }).count();
+        assert_eq!(sends, 3);
+    }
 }
@@ -0,0 +1,230 @@
+//! # engine::exits
+//!
+//! **Exit Management** — ประเมิน [`ExitPolicy`] กับ Position ที่เปิดอยู่ทีละ Tick
+//! ใช้ตัวเดียวกันทั้ง Reflex Loop (Live / Paper) และ Backtest — ผลต้องตรงกัน
+//!
+//! ## ลำดับความสำคัญ (คืนคำสั่งเดียวต่อ Tick)
+//! ```text
+//! 1. Opposing Zone Bailout  → ปิดทั้งหมด
+//! 2. Session Flatten        → ปิดทั้งหมด N นาทีก่อน Session ของ Symbol ปิด
+//! 3. Max Hold Time          → ปิดทั้งหมดเมื่อถือครบเวลา
+//! 4. Scale-out ขั้นถัดไป     → ปิดบางส่วน (เหลือต่ำกว่า volume_min → ปิดทั้งหมด)
+//! 5. Break-Even             → SL = entry ± offset (ครั้งเดียว)
+//! 6. Trailing Stop          → SL ตามราคา (ขยับเมื่อดีขึ้น ≥ step เท่านั้น)
+//! ```
+//!
+//! ฟังก์ชันในนี้ไม่แตะ State — ผู้เรียกเป็นคนส่งคำสั่งถึง Broker และอัปเดต Position
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::info;
+
+use crate::models::{exit::TrailDistance, Direction, ExitPolicy, OpenPosition};
+use crate::symbols::SymbolSpec;
+
+/// เหตุผลของคำสั่ง Exit (`reason` ของ Signal / TradeRecord)
+pub mod exit_reason {
+    pub const OPPOSING_ZONE_BAILOUT: &str = "OPPOSING_ZONE_BAILOUT";
+    pub const SESSION_FLATTEN:       &str = "SESSION_FLATTEN";
+    pub const MAX_HOLD_TIME:         &str = "MAX_HOLD_TIME";
+    pub const SCALE_OUT:             &str = "SCALE_OUT";
+    pub const BREAK_EVEN:            &str = "BREAK_EVEN";
+    pub const TRAILING_STOP:         &str = "TRAILING_STOP";
+}
+
+/// ข้อมูลตลาด ณ Tick ที่ประเมิน
+pub struct ExitContext<'a> {
+    pub bid:  f64,
+    pub ask:  f64,
+    /// เวลาของ Tick (Server time ของ Broker) — ใช้กับ Trading Session
+    pub time: DateTime<Utc>,
+    /// นาฬิกาเดียวกับ `OpenPosition.opened_at` — ใช้กับ Max Hold Time
+    pub now:  DateTime<Utc>,
+    pub spec: &'a SymbolSpec,
+    /// ATR ตาม `ExitPolicy::atr_params` จาก Indicator Engine (None = ยังมีแท่งไม่พอ)
+    pub atr:  Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitAction {
+    ModifySl     { new_sl: f64, reason: &'static str },
+    ClosePartial { volume: f64, reason: &'static str },
+    Close        { reason: &'static str },
+}
+
+// ─── Evaluation ───────────────────────────────────────────────────────────────
+
+/// คำสั่ง Exit ของ Position ณ Tick นี้ — None = ถือต่อ
+pub fn evaluate_exit(pos: &OpenPosition, policy: &ExitPolicy, ctx: &ExitContext) -> Option<ExitAction> {
+    // ราคาที่ปิดได้จริง: BUY ขายที่ Bid / SELL ซื้อคืนที่ Ask
+    let price = match pos.direction {
+        Direction::Buy     => ctx.bid,
+        Direction::Sell    => ctx.ask,
+        Direction::NoTrade => return None,
+    };
+    let spec       = ctx.spec;
+    let favourable = pos.favourable_move(price);
+    let risk       = pos.initial_risk();
+    let tp_dist    = (pos.take_profit - pos.entry_price).abs();
+    let reached    = |level: &crate::models::exit::ExitLevel| {
+        level.distance(risk, tp_dist, spec.pip_size).is_some_and(|d| favourable >= d)
+    };
+
+    // ── 1. Opposing Zone Bailout ──────────────────────────────────────────────
+    if pos.opposing_zone.is_some_and(|zone| zone.contains(price)) {
+        info!(symbol = %pos.symbol, price, "⚔️ OPPOSING ZONE ENTERED — Bailing out of position!");
+        return Some(ExitAction::Close { reason: exit_reason::OPPOSING_ZONE_BAILOUT });
+    }
+
+    // ── 2. Session Flatten ────────────────────────────────────────────────────
+    if let (Some(minutes), Some(end)) = (policy.flatten_before_close_minutes, spec.session_end(ctx.time)) {
+        if end - ctx.time <= Duration::minutes(minutes) {
+            info!(symbol = %pos.symbol, session_end = %end, "🌙 Session closing — flattening position");
+            return Some(ExitAction::Close { reason: exit_reason::SESSION_FLATTEN });
+        }
+    }
+
+    // ── 3. Max Hold Time ──────────────────────────────────────────────────────
+    if policy.max_hold_minutes.is_some_and(|m| ctx.now - pos.opened_at >= Duration::minutes(m)) {
+        info!(symbol = %pos.symbol, opened_at = %pos.opened_at, "⌛ Max hold time reached — closing position");
+        return Some(ExitAction::Close { reason: exit_reason::MAX_HOLD_TIME });
+    }
+
+    // ── 4. Scale-out ──────────────────────────────────────────────────────────
+    if let Some(step) = policy.scale_out.get(pos.scale_outs_done).filter(|s| reached(&s.at)) {
+        let volume = spec
+            .normalize_volume(pos.initial_volume() * step.fraction)
+            .unwrap_or(spec.volume_min)
+            .min(pos.lot_size);
+        let remainder = pos.lot_size - volume;
+        info!(symbol = %pos.symbol, volume, remainder, step = pos.scale_outs_done, "✂️ SCALE-OUT TRIGGERED");
+        return Some(if remainder < spec.volume_min - 1e-9 {
+            ExitAction::Close { reason: exit_reason::SCALE_OUT }
+        } else {
+            ExitAction::ClosePartial { volume, reason: exit_reason::SCALE_OUT }
+        });
+    }
+
+    // ── 5. Break-Even ─────────────────────────────────────────────────────────
+    if let Some(be) = policy.break_even.filter(|be| !pos.sl_moved_to_be && reached(&be.trigger)) {
+        let offset = be.offset_pips * spec.pip_size;
+        let new_sl = spec.normalize_price(match pos.direction {
+            Direction::Buy => pos.entry_price + offset,
+            _              => pos.entry_price - offset,
+        });
+        if improves(pos, new_sl, 0.0) && on_safe_side(pos, new_sl, price) {
+            info!(symbol = %pos.symbol, new_sl, favourable, "🛡️ BREAK-EVEN TRIGGERED — Moving SL to entry price");
+            return Some(ExitAction::ModifySl { new_sl, reason: exit_reason::BREAK_EVEN });
+        }
+    }
+
+    // ── 6. Trailing Stop ──────────────────────────────────────────────────────
+    if let Some(trailing) = policy.trailing.filter(|t| t.activate_at.as_ref().is_none_or(reached)) {
+        let distance = match trailing.distance {
+            TrailDistance::Pips(pips)             => Some(pips * spec.pip_size),
+            TrailDistance::Atr { multiplier, .. } => ctx.atr.map(|atr| atr * multiplier),
+        }
+        .filter(|d| *d > 0.0)?;
+
+        let new_sl = spec.normalize_price(match pos.direction {
+            Direction::Buy => price - distance,
+            _              => price + distance,
+        });
+        if improves(pos, new_sl, trailing.step_pips * spec.pip_size) && on_safe_side(pos, new_sl, price) {
+            info!(symbol = %pos.symbol, new_sl, old_sl = pos.stop_loss, "🪜 TRAILING STOP — Moving SL");
+            return Some(ExitAction::ModifySl { new_sl, reason: exit_reason::TRAILING_STOP });
+        }
+    }
+
+    None
+}
+
+/// SL ใหม่ดีกว่าเดิมอย่างน้อย `min_step` (SL เดิม 0 = ไม่มี SL → ดีกว่าเสมอ)
+fn improves(pos: &OpenPosition, new_sl: f64, min_step: f64) -> bool {
+    if pos.stop_loss <= 0.0 {
+        return true;
+    }
+    let gain = match pos.direction {
+        Direction::Buy => new_sl - pos.stop_loss,
+        _              => pos.stop_loss - new_sl,
+    };
+    gain > 0.0 && gain >= min_step
+}
+
+/// SL ต้องอยู่ฝั่งขาดทุนของราคาปัจจุบัน — ไม่งั้น Broker ปฏิเสธ (Invalid stops)
+fn on_safe_side(pos: &OpenPosition, new_sl: f64, price: f64) -> bool {
+    match pos.direction {
+        Direction::Buy => new_sl < price,
+        _              => new_sl > price,
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::exit::{BreakEven, ExitLevel, ScaleOut, TrailingStop};
+    use crate::models::strategy::test_strategy;
+    use crate::symbols::test_spec;
+
+    fn make_position() -> OpenPosition {
+        OpenPosition::from_strategy(&test_strategy("XAUUSD"), 2000.0)
+    }
+
+    #[test]
+    fn test_scale_out_then_break_even_then_trail() {
+        let spec   = test_spec("XAUUSD");
+        let policy = ExitPolicy {
+            break_even: Some(BreakEven { trigger: ExitLevel::R(1.0), offset_pips: 2.0 }),
+            trailing:   Some(TrailingStop {
+                distance:    TrailDistance::Pips(50.0),
+                activate_at: Some(ExitLevel::R(1.5)),
+                step_pips:   10.0,
+            }),
+            scale_out:  vec![ScaleOut { at: ExitLevel::R(1.0), fraction: 0.5 }],
+            max_hold_minutes: Some(60),
+            ..ExitPolicy::default()
+        };
+        let mut pos = make_position();
+        let opened  = pos.opened_at;
+        let ctx     = |bid: f64| ExitContext {
+            bid,
+            ask:  bid + 0.3,
+            time: Utc::now(),
+            now:  opened + Duration::minutes(5),
+            spec: &spec,
+            atr:  None,
+        };
+
+        // ยังไม่ถึง 1R (10$) → ถือต่อ
+        assert_eq!(evaluate_exit(&pos, &policy, &ctx(2009.0)), None);
+
+        // 1R → ปิดครึ่งก่อน Break-Even
+        assert_eq!(
+            evaluate_exit(&pos, &policy, &ctx(2010.0)),
+            Some(ExitAction::ClosePartial { volume: 0.05, reason: exit_reason::SCALE_OUT })
+        );
+        pos.lot_size        = 0.05;
+        pos.scale_outs_done = 1;
+
+        // → SL = entry + 2 pips
+        assert_eq!(
+            evaluate_exit(&pos, &policy, &ctx(2010.0)),
+            Some(ExitAction::ModifySl { new_sl: 2000.2, reason: exit_reason::BREAK_EVEN })
+        );
+        pos.stop_loss      = 2000.2;
+        pos.sl_moved_to_be = true;
+
+        // 1.5R → Trail 5$ หลังราคา / ขยับน้อยกว่า step (1$) → ไม่ส่งคำสั่ง
+        assert_eq!(
+            evaluate_exit(&pos, &policy, &ctx(2016.0)),
+            Some(ExitAction::ModifySl { new_sl: 2011.0, reason: exit_reason::TRAILING_STOP })
+        );
+        pos.stop_loss = 2011.0;
+        assert_eq!(evaluate_exit(&pos, &policy, &ctx(2016.5)), None);
+
+        // ถือครบเวลา → ปิดทั้งหมด
+        let late = ExitContext { now: opened + Duration::minutes(60), ..ctx(2016.5) };
+        assert_eq!(evaluate_exit(&pos, &policy, &late), Some(ExitAction::Close { reason: exit_reason::MAX_HOLD_TIME }));
+    }
+}
@@ -0,0 +1,436 @@
+//! # engine::indicators
+//!
+//! **Indicator Engine** — คำนวณ Indicator เองจาก Tick stream แทนค่าที่ EA ส่งมา
+//! (`rsi_14` / `ma_20` / `ma_50` ของ Chart ที่ EA ติดอยู่ — Timeframe ไม่แน่นอน)
+//!
+//! ```text
+//! Tick ──▶ CandleAggregator (engine::candle_builder — ขอบแท่งตาม tick.time)
+//!              │ แท่งปิด (เฉพาะ Timeframe ใน INDICATOR_TIMEFRAMES)
+//!              ▼
+//!          RSI (Wilder) · EMA / SMA fast+slow · ATR · VWAP (รีเซ็ตทุกวัน UTC) · Bollinger
+//!              │ อัปเดตทีละแท่ง (Incremental) → IndicatorSnapshot
+//!              ▼
+//!   Confirmation (RSI) · Exit Policy (ATR Trailing) · Backtest (Engine ตัวเดียวกัน)
+//! ```
+//!
+//! ทุกค่าคำนวณจากแท่งที่ **ปิดแล้ว** เท่านั้น — Live และ Backtest ที่ได้ Tick ชุดเดียวกันเห็นค่าเดียวกัน
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::engine::candle_builder::Candle;
+use crate::models::Timeframe;
+
+/// จำนวน True Range ที่เก็บต่อ Symbol × Timeframe (ATR ตาม Period ของ Exit Policy)
+const TRUE_RANGE_HISTORY: usize = 200;
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct IndicatorConfig {
+    /// Timeframe ที่คำนวณ (M1 มีเสมอ — Exit Policy ใช้เป็นค่าเริ่มต้น)
+    pub timeframes: Vec<Timeframe>,
+    pub rsi_period: usize,
+    pub ema_fast:   usize,
+    pub ema_slow:   usize,
+    pub atr_period: usize,
+    pub bb_period:  usize,
+    /// Bollinger: กี่เท่าของส่วนเบี่ยงเบนมาตรฐาน
+    pub bb_stddev:  f64,
+}
+
+impl IndicatorConfig {
+    pub fn from_env() -> Self {
+        let env = |key: &str, default: usize| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let mut timeframes = Timeframe::parse_list(
+            &std::env::var("INDICATOR_TIMEFRAMES").unwrap_or_else(|_| "M1,M5,M15,H1".to_string()),
+        );
+        if !timeframes.contains(&Timeframe::M1) {
+            timeframes.insert(0, Timeframe::M1);
+        }
+
+        Self {
+            timeframes,
+            rsi_period: env("INDICATOR_RSI_PERIOD", 14),
+            ema_fast:   env("INDICATOR_EMA_FAST", 20),
+            ema_slow:   env("INDICATOR_EMA_SLOW", 50),
+            atr_period: env("INDICATOR_ATR_PERIOD", 14),
+            bb_period:  env("INDICATOR_BB_PERIOD", 20),
+            bb_stddev:  std::env::var("INDICATOR_BB_STDDEV")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(2.0),
+        }
+    }
+}
+
+// ─── Snapshot ─────────────────────────────────────────────────────────────────
+
+/// ค่า Indicator ณ แท่งที่ปิดล่าสุด — None = แท่งยังไม่พอ Period
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IndicatorSnapshot {
+    /// เวลาเริ่มของแท่งที่ปิดล่าสุด
+    pub time:      Option<DateTime<Utc>>,
+    pub close:     Option<f64>,
+    /// จำนวนแท่งที่ปิดแล้วทั้งหมด
+    pub candles:   usize,
+    pub rsi:       Option<f64>,
+    pub ema_fast:  Option<f64>,
+    pub ema_slow:  Option<f64>,
+    pub sma_fast:  Option<f64>,
+    pub sma_slow:  Option<f64>,
+    pub atr:       Option<f64>,
+    pub vwap:      Option<f64>,
+    pub bb_upper:  Option<f64>,
+    pub bb_middle: Option<f64>,
+    pub bb_lower:  Option<f64>,
+}
+
+// ─── Incremental Primitives ───────────────────────────────────────────────────
+
+/// หน้าต่างเลื่อน `period` ค่าล่าสุด — SMA / Bollinger
+#[derive(Debug, Clone)]
+struct Window {
+    period: usize,
+    values: VecDeque<f64>,
+}
+
+impl Window {
+    fn new(period: usize) -> Self {
+        Self { period, values: VecDeque::with_capacity(period + 1) }
+    }
+
+    fn push(&mut self, value: f64) {
+        self.values.push_back(value);
+        if self.values.len() > self.period {
+            self.values.pop_front();
+        }
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (self.period > 0 && self.values.len() == self.period)
+            .then(|| self.values.iter().sum::<f64>() / self.period as f64)
+    }
+
+    /// ส่วนเบี่ยงเบนมาตรฐานแบบ Population (แบบเดียวกับ Bollinger ของ MT5)
+    fn stddev(&self) -> Option<f64> {
+        let mean     = self.mean()?;
+        let variance = self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / self.period as f64;
+        Some(variance.sqrt())
+    }
+}
+
+/// EMA — ค่าแรกเป็น SMA ของ `period` ค่าแรก
+#[derive(Debug, Clone)]
+struct Ema {
+    alpha: f64,
+    seed:  Window,
+    value: Option<f64>,
+}
+
+impl Ema {
+    fn new(period: usize) -> Self {
+        Self { alpha: 2.0 / (period as f64 + 1.0), seed: Window::new(period), value: None }
+    }
+
+    fn push(&mut self, value: f64) {
+        self.value = match self.value {
+            Some(prev) => Some(prev + self.alpha * (value - prev)),
+            None => {
+                self.seed.push(value);
+                self.seed.mean()
+            }
+        };
+    }
+}
+
+/// RSI แบบ Wilder — ค่าเฉลี่ยแรกเป็นค่าเฉลี่ยธรรมดาของ `period` การเปลี่ยนแปลงแรก
+#[derive(Debug, Clone)]
+struct Rsi {
+    period:   usize,
+    prev:     Option<f64>,
+    changes:  usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    fn new(period: usize) -> Self {
+        Self { period, prev: None, changes: 0, avg_gain: 0.0, avg_loss: 0.0 }
+    }
+
+    fn push(&mut self, close: f64) {
+        let Some(prev) = self.prev.replace(close) else { return };
+        if self.period == 0 {
+            return;
+        }
+        let change = close - prev;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let period = self.period as f64;
+
+        self.changes += 1;
+        if self.changes <= self.period {
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        if self.period == 0 || self.changes < self.period {
+            return None;
+        }
+        if self.avg_loss == 0.0 {
+            return Some(if self.avg_gain == 0.0 { 50.0 } else { 100.0 });
+        }
+        Some(100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss))
+    }
+}
+
+/// VWAP ของวัน (UTC) — น้ำหนัก = Volume ของแท่ง หรือจำนวน Tick ถ้าไม่มี Volume (FX)
+#[derive(Debug, Clone, Default)]
+struct Vwap {
+    day:    Option<DateTime<Utc>>,
+    pv:     f64,
+    weight: f64,
+}
+
+impl Vwap {
+    fn push(&mut self, candle: &Candle) {
+        let day = Timeframe::D1.bucket_start(candle.start_time);
+        if self.day != Some(day) {
+            *self = Self { day: Some(day), ..Self::default() };
+        }
+        let weight   = if candle.volume > 0.0 { candle.volume } else { candle.tick_count as f64 };
+        let typical  = (candle.high + candle.low + candle.close) / 3.0;
+        self.pv     += typical * weight;
+        self.weight += weight;
+    }
+
+    fn value(&self) -> Option<f64> {
+        (self.weight > 0.0).then(|| self.pv / self.weight)
+    }
+}
+
+/// True Range ของ `candle` เทียบกับ Close ของแท่งก่อนหน้า
+fn true_range(prev_close: f64, candle: &Candle) -> f64 {
+    (candle.high - candle.low)
+        .max((candle.high - prev_close).abs())
+        .max((candle.low - prev_close).abs())
+}
+
+// ─── Series (Symbol × Timeframe) ──────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct IndicatorSeries {
+    timeframe:   Timeframe,
+    prev_close:  Option<f64>,
+    /// True Range ของแท่งที่ปิดแล้ว (ล่าสุดอยู่ท้าย)
+    true_ranges: VecDeque<f64>,
+    atr_period:  usize,
+    rsi:         Rsi,
+    ema_fast:    Ema,
+    ema_slow:    Ema,
+    sma_fast:    Window,
+    sma_slow:    Window,
+    bb:          Window,
+    bb_stddev:   f64,
+    vwap:        Vwap,
+    snapshot:    IndicatorSnapshot,
+}
+
+impl IndicatorSeries {
+    pub fn new(timeframe: Timeframe, config: &IndicatorConfig) -> Self {
+        Self {
+            timeframe,
+            prev_close:  None,
+            true_ranges: VecDeque::with_capacity(TRUE_RANGE_HISTORY + 1),
+            atr_period:  config.atr_period,
+            rsi:         Rsi::new(config.rsi_period),
+            ema_fast:    Ema::new(config.ema_fast),
+            ema_slow:    Ema::new(config.ema_slow),
+            sma_fast:    Window::new(config.ema_fast),
+            sma_slow:    Window::new(config.ema_slow),
+            bb:          Window::new(config.bb_period),
+            bb_stddev:   config.bb_stddev,
+            vwap:        Vwap::default(),
+            snapshot:    IndicatorSnapshot::default(),
+        }
+    }
+
+    /// อัปเดตทุกค่าด้วยแท่งที่เพิ่งปิด
+    fn on_candle(&mut self, candle: &Candle) {
+        if let Some(prev_close) = self.prev_close.replace(candle.close) {
+            if self.true_ranges.len() >= TRUE_RANGE_HISTORY {
+                self.true_ranges.pop_front();
+            }
+            self.true_ranges.push_back(true_range(prev_close, candle));
+        }
+        self.rsi.push(candle.close);
+        self.ema_fast.push(candle.close);
+        self.ema_slow.push(candle.close);
+        self.sma_fast.push(candle.close);
+        self.sma_slow.push(candle.close);
+        self.bb.push(candle.close);
+        self.vwap.push(candle);
+
+        let bb_middle = self.bb.mean();
+        let bb_width  = self.bb.stddev().map(|sd| sd * self.bb_stddev);
+        self.snapshot = IndicatorSnapshot {
+            time:      Some(candle.start_time),
+            close:     Some(candle.close),
+            candles:   self.snapshot.candles + 1,
+            rsi:       self.rsi.value(),
+            ema_fast:  self.ema_fast.value,
+            ema_slow:  self.ema_slow.value,
+            sma_fast:  self.sma_fast.mean(),
+            sma_slow:  self.sma_slow.mean(),
+            atr:       self.atr(self.atr_period),
+            vwap:      self.vwap.value(),
+            bb_upper:  bb_middle.zip(bb_width).map(|(m, w)| m + w),
+            bb_middle,
+            bb_lower:  bb_middle.zip(bb_width).map(|(m, w)| m - w),
+        };
+    }
+
+    pub fn snapshot(&self) -> &IndicatorSnapshot {
+        &self.snapshot
+    }
+
+    /// ATR ของ `period` แท่งที่ปิดล่าสุด (ค่าเฉลี่ยของ True Range — ต้องมีอย่างน้อย `period + 1` แท่ง)
+    pub fn atr(&self, period: usize) -> Option<f64> {
+        if period == 0 || self.true_ranges.len() < period {
+            return None;
+        }
+        let sum: f64 = self.true_ranges.iter().rev().take(period).sum();
+        Some(sum / period as f64)
+    }
+}
+
+// ─── Engine ───────────────────────────────────────────────────────────────────
+
+/// Indicator ทุก Symbol × Timeframe — Live อยู่ใน `AppState.indicators`, Backtest สร้างของตัวเอง
+#[derive(Debug, Clone)]
+pub struct IndicatorEngine {
+    config: IndicatorConfig,
+    series: HashMap<String, Vec<IndicatorSeries>>,
+}
+
+impl IndicatorEngine {
+    pub fn new(config: IndicatorConfig) -> Self {
+        Self { config, series: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &IndicatorConfig {
+        &self.config
+    }
+
+    /// แท่งที่ปิดจาก `CandleAggregator` — Timeframe ที่ไม่ได้ตั้งใน `INDICATOR_TIMEFRAMES` ถูกข้าม
+    pub fn on_candle(&mut self, candle: &Candle) {
+        if !self.config.timeframes.contains(&candle.timeframe) {
+            return;
+        }
+        let config = &self.config;
+        let series = self.series.entry(candle.symbol.clone()).or_insert_with(|| {
+            config.timeframes.iter().map(|tf| IndicatorSeries::new(*tf, config)).collect()
+        });
+        if let Some(s) = series.iter_mut().find(|s| s.timeframe == candle.timeframe) {
+            s.on_candle(candle);
+        }
+    }
+
+    pub fn series(&self, symbol: &str, timeframe: Timeframe) -> Option<&IndicatorSeries> {
+        self.series.get(symbol)?.iter().find(|s| s.timeframe == timeframe)
+    }
+
+    /// ค่าล่าสุดทุก Timeframe ต่อ Symbol (`symbol` = None → ทุก Symbol) — `GET /api/monitor/indicators`
+    pub fn snapshots(&self, symbol: Option<&str>) -> BTreeMap<String, BTreeMap<Timeframe, IndicatorSnapshot>> {
+        self.series
+            .iter()
+            .filter(|(s, _)| symbol.is_none_or(|wanted| wanted == s.as_str()))
+            .map(|(s, series)| {
+                let by_tf = series.iter().map(|x| (x.timeframe, x.snapshot().clone())).collect();
+                (s.clone(), by_tf)
+            })
+            .collect()
+    }
+
+    pub fn rsi(&self, symbol: &str, timeframe: Timeframe) -> Option<f64> {
+        self.series(symbol, timeframe)?.snapshot.rsi
+    }
+
+    pub fn atr(&self, symbol: &str, timeframe: Timeframe, period: usize) -> Option<f64> {
+        self.series(symbol, timeframe)?.atr(period)
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::candle_builder::{CandleAggregator, CandleConfig};
+    use crate::models::TickData;
+
+    fn tick(minute: i64, price: f64) -> TickData {
+        TickData {
+            symbol: "XAUUSD".to_string(),
+            bid:    price,
+            ask:    price,
+            mid:    None,
+            volume: 0.0,
+            spread: None,
+            time:   DateTime::from_timestamp(1_740_700_800 + minute * 60, 0).unwrap(),
+            rsi_14: None,
+            ma_20:  None,
+            ma_50:  None,
+        }
+    }
+
+    #[test]
+    fn test_indicators_update_on_closed_candles() {
+        let config = IndicatorConfig {
+            timeframes: vec![Timeframe::M1, Timeframe::M5],
+            rsi_period: 3,
+            ema_fast:   2,
+            ema_slow:   3,
+            atr_period: 2,
+            bb_period:  3,
+            bb_stddev:  2.0,
+        };
+        let mut engine  = IndicatorEngine::new(config);
+        let mut candles = CandleAggregator::new(CandleConfig { history_size: 10, max_gap_fill: 0 });
+        let mut feed    = |engine: &mut IndicatorEngine, minute, price| {
+            for candle in candles.on_tick(&tick(minute, price)) {
+                engine.on_candle(&candle);
+            }
+        };
+        for (minute, price) in [(0, 10.0), (1, 11.0), (2, 12.0), (3, 11.0), (4, 13.0)] {
+            feed(&mut engine, minute, price);
+        }
+        let snapshot = |tf| engine.series("XAUUSD", tf).map(|s| s.snapshot().clone());
+        assert_eq!(snapshot(Timeframe::M1).unwrap().candles, 4, "the minute-4 candle is still forming");
+
+        feed(&mut engine, 5, 13.0);
+        let snapshot = |tf| engine.series("XAUUSD", tf).map(|s| s.snapshot().clone());
+        let s = snapshot(Timeframe::M1).unwrap();
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9;
+
+        assert_eq!(s.candles, 5);
+        assert!(close(s.sma_fast, 12.0));
+        assert!(close(s.ema_fast, 223.0 / 18.0), "seeded with SMA(10, 11), then 12, 11, 13 at alpha 2/3");
+        assert!(close(s.rsi, 100.0 - 100.0 / 6.0), "Wilder RSI: avg gain 10/9 vs avg loss 2/9");
+        assert!(close(s.atr, 1.5));
+        assert!(close(engine.atr("XAUUSD", Timeframe::M1, 4), 1.25), "any period from the true-range history");
+        assert!(close(s.bb_upper, 12.0 + 2.0 * (2.0f64 / 3.0).sqrt()));
+        assert!(close(s.vwap, 11.4));
+
+        // M5 ยังไม่ปิดแท่งแรก (นาที 0–4) จนกว่า Tick นาที 5 จะมา
+        assert_eq!(snapshot(Timeframe::M5).unwrap().candles, 1);
+        assert!(snapshot(Timeframe::H1).is_none());
+    }
+}
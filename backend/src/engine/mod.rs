@@ -1,6 +1,12 @@
 //! Trading engine subsystems.
 
+pub mod commands;
 pub mod confirmation;
 pub mod executor;
+pub mod exits;
+pub mod indicators;
+pub mod orders;
+pub mod reconcile;
 pub mod reflex;
+pub mod sizing;
 pub mod candle_builder;
@@ -0,0 +1,236 @@
+//! # engine::orders
+//!
+//! **Pending Order Lifecycle** — Limit / Stop ที่ขอบ Entry Zone (`entry_mode: PENDING`)
+//!
+//! ```text
+//! Strategy Armed + ราคาอยู่นอก Zone → วาง Order (routes::mt5) หมดอายุพร้อม expires_at
+//! Fill (EA /api/mt5/order-fill หรือ Paper on_tick) → apply_order_fill
+//!     Fill แรก  → เปิด Position + ถอด Strategy + ยกเลิก Order อื่นของ Symbol (Double Entry)
+//!     Fill ถัดไป → เพิ่ม Volume ของ Position เดิม (ราคาเข้าเฉลี่ย)
+//! Strategy ถูกล้าง / แทนที่ / หมดอายุ / Kill Switch → cancel_* → ยกเลิกส่วนที่ยังไม่ Fill
+//! ```
+//!
+//! ยกเลิกไม่สำเร็จ (Venue ปฏิเสธ / ติดต่อไม่ได้) → Order ยังอยู่ใน Book —
+//! ถ้า Fill ภายหลังก็ยังเปิด Position ตามปกติ และ Order ที่หมดอายุจะถูกลองยกเลิกใหม่ทุก Tick
+
+use std::sync::atomic::Ordering;
+
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::broker::PendingFill;
+use crate::error::AppError;
+use crate::events::WsEvent;
+use crate::models::{OpenPosition, PendingOrder, TradeStatus};
+use crate::state::SharedState;
+
+/// เหตุผลที่ Pending Order ถูกยกเลิก (`WsEvent::OrderCancelled.reason`)
+pub mod cancel_reason {
+    pub const STRATEGY_CLEARED:  &str = "STRATEGY_CLEARED";
+    pub const STRATEGY_REPLACED: &str = "STRATEGY_REPLACED";
+    pub const EXPIRED:           &str = "EXPIRED";
+    pub const KILL_SWITCH:       &str = "KILL_SWITCH";
+    /// Order อื่นของ Symbol เดียวกัน Fill ไปแล้ว
+    pub const DOUBLE_ENTRY:      &str = "DOUBLE_ENTRY";
+}
+
+// ─── Fill ─────────────────────────────────────────────────────────────────────
+
+/// Pending Order ถูก Fill — ใช้ทั้ง Callback ของ EA และ Fill ของ Broker จำลอง
+///
+/// คืนสถานะ Order หลัง Fill นี้ หรือ None ถ้าไม่รู้จัก Order ticket
+pub async fn apply_order_fill(state: &SharedState, fill: &PendingFill) -> Option<PendingOrder> {
+    let (order, first_fill, complete) = {
+        let mut book = state.orders.write().await;
+        let Some(order) = book.get_mut(fill.order_ticket) else {
+            warn!(
+                order_ticket = fill.order_ticket,
+                symbol       = %fill.symbol,
+                "order-fill for unknown pending order — reconciler will adopt the position"
+            );
+            return None;
+        };
+
+        let first_fill = order.position_ticket.is_none();
+        order.filled_volume += fill.volume;
+        order.position_ticket.get_or_insert(fill.position_ticket);
+
+        let complete = fill.remaining_volume <= 0.0;
+        let order    = order.clone();
+        if complete {
+            book.remove(fill.order_ticket);
+        }
+        (order, first_fill, complete)
+    };
+    let strategy = &order.strategy;
+    let ticket   = order.position_ticket.unwrap_or(fill.position_ticket);
+
+    // ── 1. Position — Fill แรกเปิดใหม่ / Fill ถัดไปเพิ่ม Volume ─────────────────
+    let position = if first_fill {
+        let mut position = OpenPosition::from_strategy(strategy, fill.price);
+        position.lot_size         = fill.volume;
+        position.initial_lot_size = fill.volume;
+        position.opened_at        = state.now();
+        state.open_position(ticket, position.clone()).await;
+        Some(position)
+    } else {
+        let mut book = state.positions.write().await;
+        book.get_mut(ticket).map(|p| {
+            let volume    = p.lot_size + fill.volume;
+            p.entry_price = (p.entry_price * p.lot_size + fill.price * fill.volume) / volume;
+            p.lot_size    = volume;
+            p.initial_lot_size += fill.volume;
+            state.db.position(p);
+            p.clone()
+        })
+    };
+    let entry_price = position.as_ref().map_or(fill.price, |p| p.entry_price);
+
+    // ── 2. TradeRecord ──────────────────────────────────────────────────────────
+    {
+        let mut history = state.trade_history.write().await;
+        if let Some(record) = history.iter_mut().rev().find(|r| r.trade_id == order.trade_id) {
+            record.mt5_ticket     = Some(ticket);
+            record.entry_price    = entry_price;
+            record.filled_volume  = Some(order.filled_volume);
+            record.status         = if complete { TradeStatus::Confirmed } else { TradeStatus::PartiallyFilled };
+            record.status_message = format!(
+                "{:?} order #{} filled {}/{} @ {}",
+                order.kind, order.order_ticket, order.filled_volume, order.volume, fill.price
+            );
+            state.db.trade(record);
+        }
+    }
+
+    // ── 3. Fill แรก — Strategy ถูกใช้แล้ว, Order อื่นของ Symbol ห้าม Fill ซ้อน ──
+    if first_fill {
+        if state.strategies.write().await.remove(strategy.strategy_id).is_some() {
+            state.broadcast(&WsEvent::StrategyCleared {
+                symbol:      Some(strategy.symbol.clone()),
+                strategy_id: Some(strategy.strategy_id),
+            });
+        }
+        state.trade_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(position) = &position {
+            state.broadcast(&WsEvent::PositionOpened {
+                position: Box::new(position.clone()),
+            });
+        }
+        cancel_for_symbol(state, &strategy.symbol, Some(order.order_ticket), cancel_reason::DOUBLE_ENTRY).await;
+    }
+
+    state.broadcast(&WsEvent::OrderFilled {
+        order: Box::new(order.clone()),
+        fill:  fill.clone(),
+    });
+
+    info!(
+        order_ticket = order.order_ticket,
+        ticket,
+        symbol       = %strategy.symbol,
+        price        = fill.price,
+        filled       = order.filled_volume,
+        volume       = order.volume,
+        complete,
+        "📌 Pending order filled"
+    );
+
+    Some(order)
+}
+
+// ─── Cancel ───────────────────────────────────────────────────────────────────
+
+/// ยกเลิก Order ของ Strategy เหล่านี้ (Strategy ถูกล้าง / แทนที่) — คืนจำนวนที่ยกเลิกได้
+pub async fn cancel_for_strategies(state: &SharedState, strategy_ids: &[Uuid], reason: &str) -> usize {
+    let tickets = state.orders.read().await.tickets_for_strategies(strategy_ids);
+    cancel_orders(state, tickets, reason).await
+}
+
+/// ยกเลิก Order ทั้งหมดของ Symbol (ยกเว้น `except`)
+pub async fn cancel_for_symbol(state: &SharedState, symbol: &str, except: Option<u64>, reason: &str) -> usize {
+    let mut tickets = state.orders.read().await.tickets_for_symbol(symbol);
+    tickets.retain(|t| Some(*t) != except);
+    cancel_orders(state, tickets, reason).await
+}
+
+/// ยกเลิกทุก Order (Kill Switch)
+pub async fn cancel_all(state: &SharedState, reason: &str) -> usize {
+    let tickets = state.orders.read().await.tickets();
+    cancel_orders(state, tickets, reason).await
+}
+
+/// ยกเลิก Order ของ Symbol ที่ Strategy หมดอายุแล้ว — เรียกทุก Tick
+///
+/// MT5 หมดอายุเองตาม `expiration` อยู่แล้ว แต่ Backend ต้องเก็บ Book ให้ตรง
+/// (และ Paper ไม่มี Expiration ของตัวเอง)
+pub async fn expire_orders(state: &SharedState, symbol: &str) -> usize {
+    let tickets = state.orders.read().await.expired(symbol, state.now());
+    cancel_orders(state, tickets, cancel_reason::EXPIRED).await
+}
+
+async fn cancel_orders(state: &SharedState, tickets: Vec<u64>, reason: &str) -> usize {
+    let mut cancelled = 0;
+    for ticket in tickets {
+        if cancel_order(state, ticket, reason).await {
+            cancelled += 1;
+        }
+    }
+    cancelled
+}
+
+/// ยกเลิกที่ Broker ก่อน — สำเร็จ (หรือ Venue ไม่มี Order นี้แล้ว) จึงถอดออกจาก Book
+async fn cancel_order(state: &SharedState, ticket: u64, reason: &str) -> bool {
+    if state.orders.read().await.get(ticket).is_none() {
+        return false;
+    }
+
+    match state.broker.cancel_order(ticket).await {
+        Ok(()) => {}
+        // หมดอายุ / ถูกลบที่ Terminal ไปแล้ว — Book ตามให้ตรง
+        Err(AppError::NotFound(_)) => {}
+        Err(e) => {
+            warn!(ticket, reason, error = %e, "📌 Cancel rejected — pending order stays in book");
+            return false;
+        }
+    }
+
+    // Fill ครบระหว่างรอ Broker → ถูกถอดไปแล้ว
+    let Some(order) = state.orders.write().await.remove(ticket) else {
+        return false;
+    };
+
+    {
+        let mut history = state.trade_history.write().await;
+        if let Some(record) = history.iter_mut().rev().find(|r| r.trade_id == order.trade_id) {
+            if order.filled_volume > 0.0 {
+                // Position จาก Fill บางส่วนยังเปิดอยู่ — Trade นี้ยืนยันแล้วด้วย Volume ที่ได้
+                record.status         = TradeStatus::Confirmed;
+                record.status_message = format!(
+                    "Filled {}/{} — remainder cancelled ({reason})",
+                    order.filled_volume, order.volume
+                );
+            } else {
+                record.status = match reason {
+                    cancel_reason::EXPIRED => TradeStatus::Expired,
+                    _                      => TradeStatus::Cancelled,
+                };
+                record.status_message = format!("{:?} order #{ticket} cancelled ({reason})", order.kind);
+            }
+            state.db.trade(record);
+        }
+    }
+
+    info!(
+        ticket,
+        symbol = %order.strategy.symbol,
+        filled = order.filled_volume,
+        reason,
+        "📌 Pending order cancelled"
+    );
+
+    state.broadcast(&WsEvent::OrderCancelled {
+        order:  Box::new(order),
+        reason: reason.to_string(),
+    });
+    true
+}
@@ -0,0 +1,537 @@
+//! # engine::reconcile
+//!
+//! **Position Reconciliation** — เทียบ Position Book กับ Position จริงใน MT5
+//!
+//! Position Book ถูกเปิดโดย Reflex Loop และปิดโดย `/api/mt5/position-close`
+//! ถ้า Callback หายไปครั้งเดียว (หรือ Backend Restart) State จะไม่ตรงกับโบรกเกอร์ตลอดไป
+//! Reconciler จึงรันตอน Boot และทุก `RECONCILE_INTERVAL_SECS`
+//!
+//! Position จริงมาจาก `Broker::list_positions` (MT5: GET `/positions?magic=420001`)
+//!
+//! ## ผลลัพธ์
+//! ```text
+//! MT5 มี  / Book ไม่มี  → ADOPT   (เพิ่มเข้า Book — Double-Entry Protection กลับมา)
+//! MT5 ไม่มี / Book มี   → CLOSE   (ลบออกจาก Book + ปิด TradeRecord เป็น "RECONCILED"
+//!                                  + P&L จาก Deal history เข้า Risk Manager)
+//! ทั้งคู่มี แต่ SL/TP ต่าง → UPDATE (เชื่อค่าจาก MT5)
+//! TradeRecord "UNKNOWN"  → RESOLVE (ถามผลด้วย client_id — ดู `engine::executor`)
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::broker::{BrokerKind, BrokerPosition};
+use crate::db::risk_event;
+use crate::engine::executor::{client_order_id, AGV_MAGIC};
+use crate::engine::orders::{cancel_all, cancel_reason};
+use crate::error::AppError;
+use crate::events::WsEvent;
+use crate::models::{Direction, OpenPosition, OrderKind, TradeStatus};
+use crate::state::SharedState;
+
+/// ความต่างของราคาที่ถือว่า SL/TP "เท่ากัน"
+const PRICE_EPSILON: f64 = 1e-6;
+
+/// close_reason ของ TradeRecord ที่ Reconciler ปิดให้ (Callback position-close หายไป)
+pub const RECONCILED: &str = "RECONCILED";
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ReconcileConfig {
+    /// รันทุกกี่วินาที (0 = รันแค่ตอน Boot)
+    pub interval_secs: u64,
+}
+
+impl ReconcileConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval_secs: std::env::var("RECONCILE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+        }
+    }
+}
+
+// ─── Adopt ────────────────────────────────────────────────────────────────────
+
+impl BrokerPosition {
+    /// สร้าง OpenPosition สำหรับ Position ที่ Book ไม่รู้จัก
+    /// (ไม่รู้ Strategy ต้นทาง → strategy_id = nil, ไม่มี Opposing Zone)
+    fn adopt(&self) -> OpenPosition {
+        // SL อยู่ที่ทุนหรือดีกว่าแล้ว → ไม่ต้องให้ Break-Even ยิงซ้ำ
+        let sl_at_or_past_entry = match self.direction {
+            Direction::Buy  => self.sl >= self.price_open,
+            Direction::Sell => self.sl > 0.0 && self.sl <= self.price_open,
+            Direction::NoTrade => false,
+        };
+
+        OpenPosition {
+            position_id:       Uuid::new_v4(),
+            strategy_id:       Uuid::nil(),
+            symbol:            self.symbol.clone(),
+            direction:         self.direction,
+            entry_price:       self.price_open,
+            lot_size:          self.volume,
+            take_profit:       self.tp,
+            stop_loss:         self.sl,
+            mt5_ticket:        Some(self.ticket),
+            opened_at:         self.time.unwrap_or_else(Utc::now),
+            sl_moved_to_be:    sl_at_or_past_entry,
+            opposing_zone:     None,
+            // ไม่รู้ SL ตอนเปิดจริง — ใช้ SL ปัจจุบันเป็นฐานของ 1R
+            initial_stop_loss: self.sl,
+            initial_lot_size:  self.volume,
+            exit_policy:       None,
+            scale_outs_done:   0,
+            closing:           false,
+        }
+    }
+}
+
+// ─── Diff ─────────────────────────────────────────────────────────────────────
+
+/// ผลการเทียบ Book กับ MT5
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconcileDiff {
+    /// Position ที่ MT5 มีแต่ Book ไม่มี → เพิ่มเข้า Book แล้ว
+    pub adopted:  Vec<OpenPosition>,
+    /// Ticket ที่ Book มีแต่ MT5 ไม่มีแล้ว → ลบออกจาก Book แล้ว
+    pub closed:   Vec<u64>,
+    /// Ticket ที่ SL/TP ใน Book ไม่ตรงกับ MT5 → อัปเดตตาม MT5 แล้ว
+    pub updated:  Vec<u64>,
+    /// Trade ที่ไม่รู้ผล (Timeout) ซึ่งถามผลจาก MT5 ได้แล้ว
+    pub resolved: Vec<Uuid>,
+}
+
+impl ReconcileDiff {
+    pub fn is_empty(&self) -> bool {
+        self.adopted.is_empty() && self.closed.is_empty() && self.updated.is_empty() && self.resolved.is_empty()
+    }
+}
+
+/// เทียบ Book กับ MT5 (Pure function — ไม่แตะ State)
+///
+/// `snapshot_at` = เวลาก่อนเริ่มถาม MT5 — Position ใน Book ที่เปิดหลังเวลานี้
+/// อาจยังไม่อยู่ใน Snapshot ของ MT5 จึงไม่ถือว่าเป็น Orphan
+pub fn diff(
+    book:        &[OpenPosition],
+    broker:      &[BrokerPosition],
+    snapshot_at: DateTime<Utc>,
+) -> ReconcileDiff {
+    let mut out = ReconcileDiff::default();
+
+    for remote in broker.iter().filter(|p| p.magic == AGV_MAGIC) {
+        match book.iter().find(|p| p.mt5_ticket == Some(remote.ticket)) {
+            None => out.adopted.push(remote.adopt()),
+            Some(local) => {
+                let sl_differs = (local.stop_loss - remote.sl).abs() > PRICE_EPSILON;
+                let tp_differs = (local.take_profit - remote.tp).abs() > PRICE_EPSILON;
+                if sl_differs || tp_differs {
+                    out.updated.push(remote.ticket);
+                }
+            }
+        }
+    }
+
+    for local in book {
+        let Some(ticket) = local.mt5_ticket else { continue };
+        let at_broker = broker.iter().any(|p| p.ticket == ticket && p.magic == AGV_MAGIC);
+        if !at_broker && local.opened_at < snapshot_at {
+            out.closed.push(ticket);
+        }
+    }
+
+    out
+}
+
+// ─── Reconcile ────────────────────────────────────────────────────────────────
+
+/// รัน Reconciliation หนึ่งรอบ: ถาม Broker → Diff → แก้ Book → Broadcast
+pub async fn reconcile_once(state: &SharedState) -> Result<ReconcileDiff, AppError> {
+    let snapshot_at = Utc::now();
+    let broker      = state.broker.list_positions().await?;
+
+    let mut changes = {
+        let mut book = state.positions.write().await;
+        let plan     = diff(&book.all(), &broker, snapshot_at);
+
+        for position in &plan.adopted {
+            if let Some(ticket) = position.mt5_ticket {
+                book.open(ticket, position.clone());
+                state.db.position(position);
+            }
+        }
+
+        for ticket in &plan.updated {
+            let remote = broker.iter().find(|p| p.ticket == *ticket);
+            if let (Some(local), Some(remote)) = (book.get_mut(*ticket), remote) {
+                local.stop_loss   = remote.sl;
+                local.take_profit = remote.tp;
+                state.db.position(local);
+            }
+        }
+
+        for ticket in &plan.closed {
+            book.close(*ticket);
+            state.db.position_closed(*ticket);
+        }
+
+        plan
+    };
+
+    if !changes.closed.is_empty() {
+        // ผลการปิดจริงจาก Deal history (Callback หายไป) — ถามก่อนจับ Lock ของ History
+        let mut deals = Vec::new();
+        for ticket in &changes.closed {
+            match state.broker.closed_position(*ticket).await {
+                Ok(deal) => deals.push((*ticket, deal)),
+                Err(e) => {
+                    warn!(ticket, error = %e, "🔄 Closed position lookup failed — P&L recorded when the callback arrives");
+                    deals.push((*ticket, None));
+                }
+            }
+        }
+
+        // ปิด TradeRecord ที่ค้างอยู่ — ไม่พบ Deal = ไม่รู้ราคาปิด (close_price ว่างไว้ให้ Callback ที่มาช้า)
+        let mut unrecorded = Vec::new();
+        {
+            let mut history = state.trade_history.write().await;
+            for (ticket, deal) in &deals {
+                let Some(record) = history
+                    .iter_mut()
+                    .rev()
+                    .find(|r| r.mt5_ticket == Some(*ticket) && r.closed_at.is_none())
+                else {
+                    continue;
+                };
+                if let Some(deal) = deal {
+                    // ขาที่ปิดไปแล้ว (Scale-out) เข้า Risk ไปแล้ว — นับเฉพาะส่วนที่เหลือ
+                    unrecorded.push(deal.profit - record.profit.unwrap_or_default());
+                    record.profit      = Some(deal.profit);
+                    record.close_price = Some(deal.close_price);
+                }
+                record.close_reason = Some(RECONCILED.to_string());
+                record.closed_at    = Some(Utc::now());
+                state.db.trade(record);
+            }
+        }
+
+        // Realized P&L → Risk Manager เหมือน Close path ปกติ (อาจชน Daily/Weekly Loss หรือ Drawdown)
+        for profit in unrecorded {
+            if let Some(reason) = state.risk.record_close(profit).await {
+                state.risk_killed(risk_event::AUTO_KILL, reason);
+                cancel_all(state, cancel_reason::KILL_SWITCH).await;
+            }
+        }
+    }
+
+    changes.resolved = resolve_unknown_trades(state, &broker).await;
+
+    if changes.is_empty() {
+        info!(broker_positions = broker.len(), "🔄 Reconciliation — book in sync with MT5");
+    } else {
+        warn!(
+            adopted = changes.adopted.len(),
+            closed  = ?changes.closed,
+            updated  = ?changes.updated,
+            resolved = ?changes.resolved,
+            "🔄 Reconciliation — book corrected from MT5"
+        );
+        state.broadcast(&WsEvent::ReconciliationDiff {
+            adopted:  changes.adopted.clone(),
+            closed:   changes.closed.clone(),
+            updated:  changes.updated.clone(),
+            resolved: changes.resolved.clone(),
+        });
+    }
+
+    Ok(changes)
+}
+
+/// ถามผลของ Trade ที่ส่งไปแล้วแต่ Timeout (`TradeStatus::Unknown`) ด้วย client_id
+///
+/// ```text
+/// Market  พบ    → CONFIRMED (Position ถูก Adopt แล้ว → ผูก strategy_id คืน / ไม่อยู่แล้ว → ปิดเป็น RECONCILED)
+/// Pending พบ    → ยกเลิก Order (Strategy ถูก Disarm ไปแล้ว) — Fill ไปแล้ว → CONFIRMED
+/// ไม่พบ         → FAILED (Venue ไม่เคยได้รับ)
+/// ถามไม่ได้      → ยัง UNKNOWN (รอบหน้าถามใหม่)
+/// ```
+async fn resolve_unknown_trades(state: &SharedState, broker: &[BrokerPosition]) -> Vec<Uuid> {
+    let unknown: Vec<_> = state
+        .trade_history
+        .read()
+        .await
+        .iter()
+        .filter(|r| r.status == TradeStatus::Unknown)
+        .map(|r| (r.trade_id, r.strategy_id, r.order_kind))
+        .collect();
+
+    let mut resolved = Vec::new();
+    for (trade_id, strategy_id, kind) in unknown {
+        let client_id = client_order_id(trade_id);
+        let found = match state.broker.order_status(&client_id).await {
+            Ok(found) => found,
+            Err(e) => {
+                warn!(%client_id, error = %e, "🔄 Order status query failed — trade stays UNKNOWN");
+                continue;
+            }
+        };
+        // Position ที่ Order นี้เปิด (Pending: Fill แล้ว) — comment = client_id
+        let position = broker.iter().find(|p| p.magic == AGV_MAGIC && p.comment == client_id);
+
+        let (status, ticket, message) = match (found, kind) {
+            (None, _) => (TradeStatus::Failed, None, "Order never reached MT5 (resolved after timeout)".to_string()),
+            (Some(fill), OrderKind::Market) => (
+                TradeStatus::Confirmed,
+                Some(fill.ticket),
+                format!("Order #{} confirmed by reconciliation after timeout", fill.ticket),
+            ),
+            (Some(fill), _) => match state.broker.cancel_order(fill.ticket).await {
+                Ok(()) | Err(AppError::NotFound(_)) => match position {
+                    Some(p) => (
+                        TradeStatus::Confirmed,
+                        Some(p.ticket),
+                        format!("Order #{} filled — confirmed by reconciliation after timeout", fill.ticket),
+                    ),
+                    None => (
+                        TradeStatus::Cancelled,
+                        None,
+                        format!("Order #{} cancelled — placed after timeout, strategy already disarmed", fill.ticket),
+                    ),
+                },
+                Err(e) => {
+                    warn!(%client_id, ticket = fill.ticket, error = %e, "🔄 Orphan pending order cancel failed");
+                    continue;
+                }
+            },
+        };
+
+        // Position ที่ถูก Adopt มาจาก Order นี้ → ผูกกลับไปที่ Strategy ต้นทาง
+        let still_open = match ticket {
+            Some(ticket) => match state.positions.write().await.get_mut(ticket) {
+                Some(position) => {
+                    position.strategy_id = strategy_id;
+                    state.db.position(position);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        };
+
+        let mut history = state.trade_history.write().await;
+        if let Some(record) = history.iter_mut().find(|r| r.trade_id == trade_id) {
+            record.status         = status;
+            record.mt5_ticket     = ticket.or(record.mt5_ticket);
+            record.status_message = message;
+            // พบ Order แต่ Position ไม่อยู่แล้ว → เปิดแล้วปิดไประหว่างที่ไม่รู้ผล
+            if ticket.is_some() && !still_open {
+                record.close_reason = Some(RECONCILED.to_string());
+                record.closed_at    = Some(Utc::now());
+            }
+            info!(%trade_id, status = ?record.status, "🔄 Unknown trade resolved");
+            state.db.trade(record);
+        }
+        resolved.push(trade_id);
+    }
+
+    resolved
+}
+
+/// Reconcile ตอน Boot แล้ววนทุก `interval_secs`
+/// (Paper broker ไม่มีโบรกเกอร์จริงให้เทียบ → ไม่รัน)
+pub fn spawn_reconciler(state: SharedState, config: ReconcileConfig) {
+    if state.broker.kind() == BrokerKind::Paper {
+        info!("🎭 Paper broker — position reconciliation disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = reconcile_once(&state).await {
+                warn!(error = %e, "🔄 Reconciliation failed — will retry");
+            }
+            if config.interval_secs == 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(config.interval_secs)).await;
+        }
+    });
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::{extract::Query, routing::get, Json, Router};
+    use serde_json::{json, Value};
+
+    use crate::broker::{Mt5HttpBroker, PositionClose};
+    use crate::models::{position::TradeRecord, strategy::test_strategy};
+    use crate::risk::{RiskConfig, RiskManager};
+    use crate::routes::mt5::apply_position_close;
+    use crate::state::AppState;
+
+    fn book_position(ticket: u64, symbol: &str) -> OpenPosition {
+        let mut pos = OpenPosition::from_strategy(&test_strategy(symbol), 2000.0);
+        pos.mt5_ticket = Some(ticket);
+        pos.opened_at  = Utc::now() - chrono::Duration::minutes(5);
+        pos
+    }
+
+    /// Mock EA: คืน Position ของ Antigravity เฉพาะเมื่อถามด้วย magic ที่ถูกต้อง
+    /// `deals` = Deal history ของ Position ที่ปิดแล้ว `{ "ticket": { close_price, profit } }`
+    async fn spawn_mock_mt5(positions: Value, deals: Value) -> String {
+        let app = Router::new()
+            .route(
+                "/positions",
+                get(move |Query(q): Query<std::collections::HashMap<String, String>>| {
+                    let positions = positions.clone();
+                    async move {
+                        assert_eq!(q.get("magic").map(String::as_str), Some("420001"));
+                        Json(json!({ "positions": positions }))
+                    }
+                }),
+            )
+            .route(
+                "/position/history",
+                get(move |Query(q): Query<std::collections::HashMap<String, String>>| {
+                    let deal = q.get("ticket").and_then(|ticket| deals.get(ticket)).cloned();
+                    async move {
+                        match deal {
+                            Some(deal) => Json(json!({ "found": true, "close_price": deal["close_price"], "profit": deal["profit"] })),
+                            None       => Json(json!({ "found": false })),
+                        }
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr     = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn test_diff_ignores_positions_opened_after_snapshot() {
+        let mut fresh = book_position(7, "XAUUSD");
+        let snapshot_at = Utc::now();
+        fresh.opened_at = snapshot_at + chrono::Duration::seconds(1);
+
+        let plan = diff(&[fresh], &[], snapshot_at);
+        assert!(plan.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_adopts_closes_and_updates() {
+        let mt5_url = spawn_mock_mt5(json!([
+            // Book รู้จัก แต่ SL ถูกแก้ใน MT5
+            { "ticket": 1001, "symbol": "XAUUSD", "direction": "BUY", "volume": 0.1,
+              "price_open": 2000.0, "sl": 1995.0, "tp": 2030.0, "magic": 420001 },
+            // Book ไม่รู้จัก (Backend restart)
+            { "ticket": 2002, "symbol": "BTCUSD", "direction": "SELL", "volume": 0.2,
+              "price_open": 60000.0, "sl": 61000.0, "tp": 58000.0, "magic": 420001 },
+            // ของ EA ตัวอื่น — ห้ามแตะ
+            { "ticket": 3003, "symbol": "EURUSD", "direction": "BUY", "volume": 1.0,
+              "price_open": 1.1, "sl": 0.0, "tp": 0.0, "magic": 777 },
+        ]), json!({}))
+        .await;
+
+        let mut state = AppState::new();
+        state.broker  = Arc::new(Mt5HttpBroker::new(reqwest::Client::new(), &mt5_url));
+        let state     = Arc::new(state);
+        state.open_position(1001, book_position(1001, "XAUUSD")).await;
+        // Callback position-close หายไป
+        state.open_position(4004, book_position(4004, "USDJPY")).await;
+        let mut record = TradeRecord::from_strategy(&test_strategy("USDJPY"), 2000.0);
+        record.mt5_ticket = Some(4004);
+        state.push_trade_record(record).await;
+
+        let mut rx = state.broadcast_tx.subscribe();
+        let diff   = reconcile_once(&state).await.unwrap();
+
+        assert_eq!(diff.adopted.len(), 1);
+        assert_eq!(diff.adopted[0].mt5_ticket, Some(2002));
+        assert_eq!(diff.closed, vec![4004]);
+        assert_eq!(diff.updated, vec![1001]);
+
+        let book = state.positions.read().await;
+        assert_eq!(book.len(), 2);
+        assert!(book.has_symbol("BTCUSD"));
+        assert!(!book.has_symbol("USDJPY"));
+        assert!(!book.has_symbol("EURUSD"));
+        assert_eq!(book.for_symbol("XAUUSD")[0].stop_loss, 1995.0);
+        drop(book);
+
+        let history = state.trade_history.read().await;
+        assert_eq!(history[0].close_reason.as_deref(), Some(RECONCILED));
+
+        let event: Value = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(event["event"], "RECONCILIATION_DIFF");
+        assert_eq!(event["closed"], json!([4004]));
+
+        // รอบที่สอง: Book ตรงกับ MT5 แล้ว
+        let again = reconcile_once(&state).await.unwrap();
+        assert!(again.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconciled_closes_feed_realized_pnl_to_risk() {
+        // 4004: Deal history รู้ผล (ขาดทุนรวม 320 — Scale-out ขาแรก −20 นับไปแล้ว)
+        // 5005: ไม่พบใน Deal history → รอ Callback ที่มาช้า
+        let mt5_url = spawn_mock_mt5(json!([]), json!({
+            "4004": { "close_price": 1990.0, "profit": -320.0 },
+        }))
+        .await;
+
+        let mut state = AppState::new();
+        state.broker  = Arc::new(Mt5HttpBroker::new(reqwest::Client::new(), &mt5_url));
+        let config    = RiskConfig { max_daily_loss: 350.0, ..RiskConfig::from_env() };
+        state.risk    = Arc::new(RiskManager::new(config, state.clock.clone()));
+        let state     = Arc::new(state);
+
+        for (ticket, symbol) in [(4004, "XAUUSD"), (5005, "EURUSD")] {
+            state.open_position(ticket, book_position(ticket, symbol)).await;
+            let mut record = TradeRecord::from_strategy(&test_strategy(symbol), 2000.0);
+            record.mt5_ticket = Some(ticket);
+            state.push_trade_record(record).await;
+        }
+        state.trade_history.write().await[0].profit = Some(-20.0);
+        assert!(state.risk.record_close(-20.0).await.is_none());
+
+        let diff = reconcile_once(&state).await.unwrap();
+        assert_eq!(diff.closed.len(), 2);
+        assert_eq!(state.risk.status().await.realized_pnl_today, -320.0);
+        {
+            let history = state.trade_history.read().await;
+            assert_eq!((history[0].profit, history[0].close_price), (Some(-320.0), Some(1990.0)));
+            assert_eq!((history[1].profit, history[1].close_price), (None, None));
+        }
+
+        // Callback ของ 5005 มาหลัง Reconciler ปิด Book ไปแล้ว → ยังนับเข้า Risk (ชน Daily Loss)
+        let late = |ticket: u64, profit: f64| PositionClose {
+            mt5_ticket:   Some(ticket),
+            symbol:       "EURUSD".into(),
+            close_price:  1.08,
+            profit_pips:  None,
+            profit:       Some(profit),
+            close_reason: "SL".into(),
+            volume:       None,
+        };
+        assert!(apply_position_close(&state, &late(5005, -80.0)).await.is_none());
+        let status = state.risk.status().await;
+        assert_eq!(status.realized_pnl_today, -400.0);
+        assert!(status.is_killed);
+        assert_eq!(state.trade_history.read().await[1].profit, Some(-80.0));
+
+        // 4004 ได้ผลจาก Deal history แล้ว → Callback ซ้ำไม่นับสองครั้ง
+        apply_position_close(&state, &late(4004, -300.0)).await;
+        assert_eq!(state.risk.status().await.realized_pnl_today, -400.0);
+    }
+}
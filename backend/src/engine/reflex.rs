@@ -4,24 +4,29 @@
 //!
 //! ## ลำดับการตรวจสอบ (ทุก Tick)
 //! ```text
-//! 1. Record tick into buffer   → ใช้โดย Confirmation Engine
-//! 2. ตรวจ Strategy / Symbol / Expiry / Direction
-//! 3. ตรวจ Double-Entry Protection
-//! 4. ตรวจ Entry Zone (ราคาอยู่ใน Zone ไหม?)
-//! 5. [NEW] Confirmation Engine:
-//!    a. Spread Check  — Spread ปกติไหม?
+//! 1. Record tick into buffer   → Confirmation Engine + Candle Aggregator → Indicator Engine (RSI / ATR)
+//! 2. ตรวจ Position ของ Symbol  → Exit Policy (engine::exits) / Double-Entry Protection
+//! 3. วนทุก Strategy ของ Symbol → Expiry / Direction
+//! 4. ตรวจ SymbolSpec + Trading Session (ไม่รู้จัก Symbol / ตลาดปิด → ไม่เข้า)
+//! 5. ตรวจ Entry Zone (ราคาอยู่ใน Zone ไหม?)
+//!    entry_mode=PENDING + ราคาอยู่นอก Zone → วาง Limit / Stop ที่ขอบ Zone (ครั้งเดียวต่อ Strategy)
+//! 6. Confirmation Engine:
+//!    a. Spread Check  — Spread ปกติไหม? (Pips)
 //!    b. Zone Probe    — ราคาเคยทดสอบนอก Zone ก่อนไหม? (Bounce pattern)
 //!    c. Zone Dwell    — ราคาอยู่ใน Zone ต่อเนื่องพอไหม?
-//! 6. → TRIGGER trade
+//! 7. → TRIGGER trade
 //! ```
 
 use std::sync::atomic::Ordering;
 use tracing::{debug, info, warn};
 
-use crate::engine::confirmation::{check_confirmation, ConfirmationResult};
+use crate::engine::confirmation::{ConfirmationContext, ConfirmationPipeline, ConfirmationResult};
+use crate::engine::exits::{evaluate_exit, ExitAction, ExitContext};
 use crate::error::AppError;
-use crate::models::{ActiveStrategy, Direction, TickData};
+use crate::events::WsEvent;
+use crate::models::{strategy::EntryMode, ActiveStrategy, Direction, OpenPosition, OrderKind, TickData};
 use crate::state::SharedState;
+use crate::symbols::SymbolSpec;
 
 // ─── Trade Signal ─────────────────────────────────────────────────────────────
 
@@ -29,12 +34,58 @@ use crate::state::SharedState;
 pub enum TradeSignal {
     /// Price เข้า Zone + ผ่าน Confirmation → ยิง Trade
     Trigger(Box<ActiveStrategy>),
+    /// Strategy แบบ Pending + ราคาอยู่นอก Zone → วาง Limit / Stop ที่ขอบ Zone
+    PlaceOrder { strategy: Box<ActiveStrategy>, kind: OrderKind, price: f64 },
     /// ส่งให้กลับไปสั่ง MT5 ทำการแก้ไข Position (เช่น เลื่อน SL บังทุน)
     ModifySL { mt5_ticket: u64, new_sl: f64, reason: String },
-    /// ปิด Position เนื่องจากเข้า Opposing Zone
+    /// ปิดบางส่วน (Scale-out) — `volume` Lot
+    ClosePartial { mt5_ticket: u64, volume: f64, reason: String },
+    /// ปิด Position ทั้งหมด (Opposing Zone / Max Hold / Session Flatten)
     ClosePosition { mt5_ticket: u64, reason: String },
-    /// ไม่มีอะไรต้องทำ Tick นี้
-    NoAction,
+    /// ไม่มีอะไรต้องทำ Tick นี้ — พร้อมเหตุผลที่ไม่เข้า (Response ของ EA / Backtest นับ Rejection)
+    NoAction(Hold),
+}
+
+/// เหตุผลที่ Tick นี้ไม่เข้า Trade — หลาย Strategy ใน Symbol = เหตุผลของ Strategy แรก
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hold {
+    /// มี Position ของ Symbol อยู่แล้ว (Double-Entry Protection) และ Exit Policy ยังไม่สั่งอะไร
+    PositionOpen,
+    NoStrategy,
+    NoSymbolSpec,
+    OutsideSession,
+    StrategyExpired,
+    NoTrade,
+    /// Pending Order ของ Strategy วางไว้แล้ว — รอ Fill
+    OrderPending,
+    OutsideZone,
+    /// อยู่ใน Zone แต่ชั้นนี้ของ Confirmation Pipeline ยังไม่ผ่าน
+    Unconfirmed(&'static str),
+}
+
+impl Hold {
+    /// ชื่อเหตุผลใน Tick response (`reason`)
+    pub fn reason(self) -> &'static str {
+        match self {
+            Hold::PositionOpen    => "POSITION_OPEN",
+            Hold::NoStrategy      => "NO_STRATEGY",
+            Hold::NoSymbolSpec    => "NO_SYMBOL_SPEC",
+            Hold::OutsideSession  => "OUTSIDE_SESSION",
+            Hold::StrategyExpired => "STRATEGY_EXPIRED",
+            Hold::NoTrade         => "NO_TRADE",
+            Hold::OrderPending    => "ORDER_PENDING",
+            Hold::OutsideZone     => "OUTSIDE_ZONE",
+            Hold::Unconfirmed(_)  => "UNCONFIRMED",
+        }
+    }
+
+    /// ชั้น Confirmation ที่ไม่ผ่าน (`Unconfirmed` เท่านั้น)
+    pub fn layer(self) -> Option<&'static str> {
+        match self {
+            Hold::Unconfirmed(layer) => Some(layer),
+            _                        => None,
+        }
+    }
 }
 
 // ─── Core Evaluation ──────────────────────────────────────────────────────────
@@ -46,100 +97,125 @@ pub async fn evaluate_tick(
 
     // ── 1. Record Tick into Buffer (ก่อนอื่นใดเลย) ────────────────────────────
     // ต้องทำก่อนทุก Guard เพราะ Buffer ต้องสะสม History แม้ในช่วงที่ไม่มี Strategy
-    state.record_tick(&tick.symbol, tick.bid, tick.ask).await;
+    state.record_tick(tick).await;
+    state.record_candles(tick).await;
 
     // ── 2. Increment tick counter ─────────────────────────────────────────────
     state.tick_count.fetch_add(1, Ordering::Relaxed);
 
-    // ── 3. Clone strategy (release lock ทันที) ────────────────────────────────
-    let maybe_strategy = {
-        let guard = state.active_strategy.read().await;
-        guard.clone()
+    // ── 3. Open Position Check (Double Entry / Exit Policy) ───────────────────
+    //    Position ของ Symbol อื่นไม่มีผลกับ Symbol นี้
+    let positions = state.positions_for(&tick.symbol).await;
+    if !positions.is_empty() {
+        for pos in &positions {
+            if let Some(signal) = manage_open_position(tick, state, pos).await {
+                return Ok(signal);
+            }
+        }
+        debug!(symbol = %tick.symbol, "Position already open — double-entry blocked");
+        return Ok(TradeSignal::NoAction(Hold::PositionOpen));
+    }
+
+    // ── 4. Strategies ของ Symbol นี้ (clone ออกมา — release lock ทันที) ────────
+    let strategies = state.strategies_for(&tick.symbol).await;
+    if strategies.is_empty() {
+        debug!(symbol = %tick.symbol, "No active strategy — tick buffered only");
+        return Ok(TradeSignal::NoAction(Hold::NoStrategy));
+    }
+
+    // ── 4b. SymbolSpec + Trading Session ──────────────────────────────────────
+    let Some(spec) = state.symbol_spec(&tick.symbol).await else {
+        warn!(symbol = %tick.symbol, "No SymbolSpec — add it to SYMBOL_SPECS or let the EA push it");
+        return Ok(TradeSignal::NoAction(Hold::NoSymbolSpec));
     };
+    if !spec.is_trading_at(tick.time) {
+        debug!(symbol = %tick.symbol, time = %tick.time, "Outside trading session — no new entries");
+        return Ok(TradeSignal::NoAction(Hold::OutsideSession));
+    }
 
-    let strategy = match maybe_strategy {
-        Some(s) => s,
-        None => {
-            debug!(symbol = %tick.symbol, "No active strategy — tick buffered only");
-            return Ok(TradeSignal::NoAction);
+    let mut first_hold = None;
+    for strategy in strategies {
+        match evaluate_strategy(tick, state, &spec, strategy).await {
+            Ok(signal) => return Ok(signal),
+            Err(hold)  => { first_hold.get_or_insert(hold); }
         }
-    };
+    }
 
-    // ── 4. Guard: Symbol match ────────────────────────────────────────────────
-    if strategy.symbol != tick.symbol {
-        return Ok(TradeSignal::NoAction);
+    Ok(TradeSignal::NoAction(first_hold.unwrap_or(Hold::NoStrategy)))
+}
+
+// ─── Exit Management ──────────────────────────────────────────────────────────
+
+/// ประเมิน Exit Policy ของ Position ที่เปิดอยู่ (Policy ของ Strategy หรือ `EXIT_POLICY`)
+async fn manage_open_position(tick: &TickData, state: &SharedState, pos: &OpenPosition) -> Option<TradeSignal> {
+    let ticket = pos.mt5_ticket?;
+    // คำสั่งก่อนหน้ายังไม่ได้ Ack — Book ยังไม่ใช่สถานะจริงของ MT5
+    if state.commands.read().await.in_flight_for(ticket) {
+        debug!(symbol = %tick.symbol, ticket, "Awaiting EA acknowledgement — exit policy paused");
+        return None;
     }
+    if pos.closing {
+        debug!(symbol = %tick.symbol, ticket, "Close acknowledged — awaiting close result");
+        return None;
+    }
+    let Some(spec) = state.symbol_spec(&tick.symbol).await else {
+        warn!(symbol = %tick.symbol, ticket, "No SymbolSpec — exit policy skipped");
+        return None;
+    };
+    let policy = pos.exit_policy.as_ref().unwrap_or(&state.exit_policy);
+    let atr = match policy.atr_params() {
+        Some((timeframe, period)) => state.indicators.read().await.atr(&tick.symbol, timeframe, period),
+        None                      => None,
+    };
+
+    let ctx = ExitContext {
+        bid:  tick.bid,
+        ask:  tick.ask,
+        time: tick.time,
+        now:  state.now(),
+        spec: &spec,
+        atr,
+    };
+    let signal = match evaluate_exit(pos, policy, &ctx)? {
+        ExitAction::ModifySl { new_sl, reason } => TradeSignal::ModifySL {
+            mt5_ticket: ticket,
+            new_sl,
+            reason:     reason.to_string(),
+        },
+        ExitAction::ClosePartial { volume, reason } => TradeSignal::ClosePartial {
+            mt5_ticket: ticket,
+            volume,
+            reason:     reason.to_string(),
+        },
+        ExitAction::Close { reason } => TradeSignal::ClosePosition {
+            mt5_ticket: ticket,
+            reason:     reason.to_string(),
+        },
+    };
+    Some(signal)
+}
+
+// ─── Entry Evaluation ─────────────────────────────────────────────────────────
 
+/// ตรวจ Strategy หนึ่งตัวกับ Tick ปัจจุบัน — คืน `Ok(Trigger / PlaceOrder)` เมื่อผ่านทุกชั้น
+async fn evaluate_strategy(
+    tick:     &TickData,
+    state:    &SharedState,
+    spec:     &SymbolSpec,
+    strategy: ActiveStrategy,
+) -> Result<TradeSignal, Hold> {
     // ── 5. Guard: Strategy expiry ─────────────────────────────────────────────
-    if !strategy.is_valid() {
+    if !strategy.is_valid_at(state.now()) {
         warn!(strategy_id = %strategy.strategy_id, "Strategy expired — skipping");
-        return Ok(TradeSignal::NoAction);
+        return Err(Hold::StrategyExpired);
     }
 
     // ── 6. Guard: Direction actionable ───────────────────────────────────────
     if strategy.direction == Direction::NoTrade {
-        return Ok(TradeSignal::NoAction);
-    }
-
-    // ── 7. Open Position Check (Double Entry / Break-Even) ────────────────────
-    if let Some(pos_guard) = state.open_position.read().await.clone() {
-        if pos_guard.symbol == tick.symbol {
-            // [NEW] Break-Even & Trailing Logic
-            if let Some(ticket) = pos_guard.mt5_ticket {
-                let pnl = match pos_guard.direction {
-                    Direction::Buy  => tick.bid - pos_guard.entry_price,
-                    Direction::Sell => pos_guard.entry_price - tick.ask,
-                    Direction::NoTrade => 0.0,
-                };
-
-                // คำนวณระยะทางถึง TP (เพื่อเอากึ่งกลาง)
-                let tp_dist = (pos_guard.take_profit - pos_guard.entry_price).abs();
-
-                // ถ้าราคาไปถึงครึ่งทางของเป้า (50% ของ TP) → เลื่อน SL มาที่ทุน
-                if tp_dist > 0.0 && pnl >= tp_dist * 0.5 && !pos_guard.sl_moved_to_be {
-                    info!(
-                        symbol = %tick.symbol,
-                        ticket,
-                        pnl,
-                        "🛡️ BREAK-EVEN TRIGGERED — Moving SL to entry price"
-                    );
-                    return Ok(TradeSignal::ModifySL {
-                        mt5_ticket: ticket,
-                        new_sl:     pos_guard.entry_price,
-                        reason:     "BREAK_EVEN".to_string(),
-                    });
-                }
-            }
-            // ── 7.5 [SMC Pro Max] Opposing Zone Bailout ─────────────────────────
-            if let Some(opp_zone) = &strategy.opposing_zone {
-                let current_price = match pos_guard.direction {
-                    Direction::Buy  => tick.bid,
-                    Direction::Sell => tick.ask,
-                    Direction::NoTrade => tick.bid,
-                };
-                if opp_zone.contains(current_price) {
-                    let ticket = pos_guard.mt5_ticket.unwrap_or(0);
-                    if ticket > 0 {
-                        info!(
-                            symbol = %tick.symbol,
-                            ticket,
-                            current_price,
-                            "⚔️ OPPOSING ZONE ENTERED — Bailing out of position!"
-                        );
-                        return Ok(TradeSignal::ClosePosition {
-                            mt5_ticket: ticket,
-                            reason:     "OPPOSING_ZONE_BAILOUT".to_string(),
-                        });
-                    }
-                }
-            }
-
-            debug!(symbol = %tick.symbol, "Position already open — double-entry blocked");
-        }
-        return Ok(TradeSignal::NoAction);
+        return Err(Hold::NoTrade);
     }
 
-    // ── 8. Entry Price (ตาม Direction) ───────────────────────────────────────
+    // ── 7. Entry Price (ตาม Direction) ───────────────────────────────────────
     //   BUY  → จ่าย Ask (ราคาที่โบรกเกอร์ขายให้เรา)
     //   SELL → รับ Bid (ราคาที่โบรกเกอร์ซื้อจากเรา)
     let entry_price = match strategy.direction {
@@ -148,10 +224,29 @@ pub async fn evaluate_tick(
         Direction::NoTrade => unreachable!(),
     };
 
-    // ── 9. Zone Check ─────────────────────────────────────────────────────────
+    // ── 7b. Pending Entry — Order รออยู่ที่ขอบ Zone แทนการรอราคาเข้า Zone ──────
+    if strategy.entry_mode == EntryMode::Pending {
+        if state.orders.read().await.has_strategy(strategy.strategy_id) {
+            return Err(Hold::OrderPending);  // วางไว้แล้ว — รอ Fill
+        }
+        if let Some((kind, price)) = OrderKind::for_zone(strategy.direction, &strategy.entry_zone, entry_price) {
+            info!(
+                strategy_id = %strategy.strategy_id,
+                symbol      = %tick.symbol,
+                kind        = ?kind,
+                price,
+                "📌 Pending entry — placing order at zone edge"
+            );
+            let price = spec.normalize_price(price);
+            return Ok(TradeSignal::PlaceOrder { strategy: Box::new(strategy), kind, price });
+        }
+        // ราคาอยู่ใน Zone แล้ว → เข้าแบบ Market ผ่าน Confirmation ตามปกติ
+    }
+
+    // ── 8. Zone Check ─────────────────────────────────────────────────────────
     if !strategy.entry_zone.contains(entry_price) {
         debug!(entry_price, zone = ?strategy.entry_zone, "Outside zone");
-        return Ok(TradeSignal::NoAction);
+        return Err(Hold::OutsideZone);
     }
 
     // ─ ราคาอยู่ใน Zone แล้ว! → วิ่งไปหา Confirmation ──────────────────────────
@@ -165,30 +260,38 @@ pub async fn evaluate_tick(
         "📍 Price in entry zone — running confirmation checks..."
     );
 
-    // ── 10. [NEW] Confirmation Engine ────────────────────────────────────────
-    let tick_buffer = state.get_tick_buffer(&tick.symbol).await;
-    let candle      = state.get_latest_candle(&tick.symbol).await;
-    let config      = &*state.confirmation_config;
-
-    let confirmation = check_confirmation(
-        tick.bid,
-        tick.ask,
-        &strategy.entry_zone,
-        strategy.direction,
-        &tick_buffer,
-        candle.as_ref(),
-        tick.rsi_14,      // ← ส่ง RSI จาก TickData (ถ้า None → ข้าม RSI check)
-        config,
-    );
+    // ── 9. Confirmation Pipeline (ของ Strategy หรือ CONFIRM_PIPELINE) ──────────
+    let tick_buffer  = state.get_tick_buffer(&tick.symbol).await;
+    let pipeline     = ConfirmationPipeline::for_strategy(&strategy, &state.confirmation_config);
+    let trace = {
+        let candles    = state.candles.read().await;
+        let indicators = state.indicators.read().await;
+        pipeline.evaluate(&ConfirmationContext {
+            tick,
+            spec,
+            zone:       &strategy.entry_zone,
+            direction:  strategy.direction,
+            buffer:     &tick_buffer,
+            candles:    &candles,
+            indicators: &indicators,
+        })
+    };
+    let confirmation = trace.result();
+    if let Some(kept) = state.confirmation_traces.write().await.record(strategy.strategy_id, trace) {
+        state.broadcast(&WsEvent::ConfirmationTrace {
+            strategy_id: strategy.strategy_id,
+            trace:       Box::new(kept.clone()),
+        });
+    }
 
     match confirmation {
-        ConfirmationResult::Rejected { reason } => {
+        ConfirmationResult::Rejected { layer, reason } => {
             debug!(
-                reason,
+                layer,
                 entry_price,
                 "⏳ In zone but waiting for confirmation: {reason}"
             );
-            return Ok(TradeSignal::NoAction);
+            Err(Hold::Unconfirmed(layer))
         }
 
         ConfirmationResult::Confirmed => {
@@ -197,7 +300,7 @@ pub async fn evaluate_tick(
                 symbol      = %tick.symbol,
                 direction   = ?strategy.direction,
                 entry_price,
-                spread      = tick.ask - tick.bid,
+                spread_pips = spec.pips(tick.ask - tick.bid),
                 "🎯 CONFIRMED — firing trade!"
             );
 
@@ -0,0 +1,206 @@
+//! # engine::sizing — Position Sizing
+//!
+//! คำนวณ Volume (Lot) จากความเสี่ยงต่อ Trade แทนการเชื่อ `lot_size` จาก AI
+//!
+//! ```text
+//! risk_amount  = equity × SIZING_RISK_PERCENT / 100     (mode = percent)
+//!              = SIZING_RISK_AMOUNT                     (mode = fixed)
+//! loss_per_lot = |entry − stop_loss| / tick_size × tick_value
+//! volume       = risk_amount / loss_per_lot  → ปัดลงตาม volume_step → clamp [min, max]
+//! ```
+//!
+//! Equity มาจาก EA (`POST /api/mt5/account`) — ถ้า EA ยังไม่รายงานใช้ `ACCOUNT_EQUITY`
+//! tick_size / tick_value / min-max-step มาจาก [`SymbolSpec`]
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::ActiveStrategy;
+use crate::symbols::SymbolSpec;
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+/// วิธีคำนวณ Volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SizingMode {
+    /// เสี่ยง % ของ Equity ต่อ Trade
+    RiskPercent,
+    /// เสี่ยงเป็นจำนวนเงินคงที่ต่อ Trade
+    FixedAmount,
+    /// ใช้ `lot_size` จาก Strategy (ยัง Clamp ตาม Spec)
+    Strategy,
+}
+
+#[derive(Debug, Clone)]
+pub struct SizingConfig {
+    pub mode:           SizingMode,
+    /// % ของ Equity ที่ยอมเสียต่อ Trade (mode = percent)
+    pub risk_percent:   f64,
+    /// จำนวนเงินที่ยอมเสียต่อ Trade (mode = fixed)
+    pub risk_amount:    f64,
+    /// Equity สำรองเมื่อ EA ยังไม่รายงาน (`ACCOUNT_EQUITY`, 0 = ไม่มี)
+    pub account_equity: f64,
+}
+
+impl SizingConfig {
+    pub fn from_env() -> Self {
+        let mode = match std::env::var("SIZING_MODE").as_deref() {
+            Ok("fixed")    => SizingMode::FixedAmount,
+            Ok("strategy") => SizingMode::Strategy,
+            _              => SizingMode::RiskPercent,
+        };
+
+        Self {
+            mode,
+            risk_percent:   env_f64("SIZING_RISK_PERCENT", 1.0),
+            risk_amount:    env_f64("SIZING_RISK_AMOUNT", 0.0),
+            account_equity: env_f64("ACCOUNT_EQUITY", 0.0),
+        }
+    }
+}
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+// ─── Decision ─────────────────────────────────────────────────────────────────
+
+/// ผลการคำนวณ Volume — เก็บไว้ใน `TradeRecord.sizing` เพื่อตรวจย้อนหลัง
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingDecision {
+    pub mode:             SizingMode,
+    /// Equity ที่ใช้คำนวณ (None = ไม่ได้ใช้ เช่น mode = fixed)
+    pub equity:           Option<f64>,
+    /// เงินที่ตั้งใจเสี่ยง (สกุลเงินบัญชี)
+    pub risk_amount:      f64,
+    /// |entry − stop_loss|
+    pub stop_distance:    f64,
+    pub loss_per_lot:     f64,
+    /// `lot_size` ที่ Strategy ขอมา (0 = ไม่ได้ระบุ)
+    pub requested_volume: f64,
+    /// Volume ก่อนปัด/Clamp
+    pub raw_volume:       f64,
+    /// Volume ที่ส่งไป MT5 จริง
+    pub volume:           f64,
+    /// เงินที่เสี่ยงจริงหลังปัด Volume
+    pub actual_risk:      f64,
+    /// หมายเหตุ เช่น ถูก Clamp ที่ `volume_max`
+    #[serde(default)]
+    pub note:             Option<String>,
+}
+
+// ─── Sizing ───────────────────────────────────────────────────────────────────
+
+/// คำนวณ Volume ของ Strategy ที่ราคาเข้า `entry_price`
+///
+/// `equity` = Equity ล่าสุดจาก EA (None → ใช้ `ACCOUNT_EQUITY`)
+/// คืน `Err(reason)` ถ้าคำนวณไม่ได้หรือ Volume ต่ำกว่าขั้นต่ำของ Broker
+pub fn size_position(
+    config:      &SizingConfig,
+    spec:        &SymbolSpec,
+    equity:      Option<f64>,
+    strategy:    &ActiveStrategy,
+    entry_price: f64,
+) -> Result<SizingDecision, String> {
+    let stop_distance = (entry_price - strategy.stop_loss).abs();
+    if stop_distance <= 0.0 {
+        return Err(format!("Stop loss {} equals entry price", strategy.stop_loss));
+    }
+    let loss_per_lot = spec.loss_per_lot(stop_distance);
+
+    let equity = equity.or((config.account_equity > 0.0).then_some(config.account_equity));
+
+    let (equity_used, raw_volume) = match config.mode {
+        SizingMode::RiskPercent => {
+            let equity = equity
+                .filter(|e| *e > 0.0)
+                .ok_or("No account equity — EA has not reported and ACCOUNT_EQUITY is unset")?;
+            (Some(equity), equity * config.risk_percent / 100.0 / loss_per_lot)
+        }
+        SizingMode::FixedAmount => (None, config.risk_amount / loss_per_lot),
+        SizingMode::Strategy    => (None, strategy.lot_size),
+    };
+
+    let volume = spec.normalize_volume(raw_volume).ok_or_else(|| {
+        format!(
+            "Volume {raw_volume:.4} is below broker minimum {} (stop distance {stop_distance})",
+            spec.volume_min
+        )
+    })?;
+
+    let note = (raw_volume > spec.volume_max)
+        .then(|| format!("Clamped from {raw_volume:.4} to volume_max {}", spec.volume_max));
+
+    Ok(SizingDecision {
+        mode:             config.mode,
+        equity:           equity_used,
+        risk_amount:      raw_volume * loss_per_lot,
+        stop_distance,
+        loss_per_lot,
+        requested_volume: strategy.lot_size,
+        raw_volume,
+        volume,
+        actual_risk:      volume * loss_per_lot,
+        note,
+    })
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::strategy::test_strategy;
+    use crate::symbols::test_spec;
+
+    fn config(mode: SizingMode) -> SizingConfig {
+        SizingConfig {
+            mode,
+            risk_percent:   1.0,
+            risk_amount:    50.0,
+            account_equity: 0.0,
+        }
+    }
+
+    fn strategy(stop_loss: f64, lot_size: f64) -> ActiveStrategy {
+        ActiveStrategy { stop_loss, lot_size, ..test_strategy("XAUUSD") }
+    }
+
+    /// XAUUSD เข้าที่ 2000.0
+    fn size(config: &SizingConfig, equity: Option<f64>, strategy: &ActiveStrategy) -> Result<SizingDecision, String> {
+        size_position(config, &test_spec("XAUUSD"), equity, strategy, 2000.0)
+    }
+
+    #[test]
+    fn test_risk_percent_of_equity() {
+        // 1% ของ 10,000 = 100 USD / (SL 5$ × 100 oz = 500 USD ต่อ Lot) = 0.2 Lot
+        let d = size(&config(SizingMode::RiskPercent), Some(10_000.0), &strategy(1995.0, 5.0)).unwrap();
+        assert_eq!(d.volume, 0.2);
+        assert_eq!(d.requested_volume, 5.0);
+        assert!((d.actual_risk - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fixed_amount_rounds_down_to_step() {
+        // 50 USD / (SL 3$ → 300 USD ต่อ Lot) = 0.1666 → 0.16
+        let d = size(&config(SizingMode::FixedAmount), None, &strategy(1997.0, 0.0)).unwrap();
+        assert_eq!(d.volume, 0.16);
+        assert!(d.actual_risk <= 50.0);
+    }
+
+    #[test]
+    fn test_clamps_and_rejects() {
+        // ใหญ่เกิน → Clamp ที่ volume_max
+        let d = size(&config(SizingMode::Strategy), None, &strategy(1995.0, 80.0)).unwrap();
+        assert_eq!(d.volume, 50.0);
+        assert!(d.note.is_some());
+
+        // ต่ำกว่า volume_min → ไม่ยิง
+        let mut tiny = config(SizingMode::FixedAmount);
+        tiny.risk_amount = 1.0;
+        assert!(size(&tiny, None, &strategy(1995.0, 0.0)).is_err());
+
+        // ไม่มี Equity → ไม่ยิง
+        assert!(size(&config(SizingMode::RiskPercent), None, &strategy(1995.0, 0.0)).is_err());
+    }
+}
@@ -28,6 +28,11 @@ pub enum AppError {
     #[error("Trade execution error: {0}")]
     ExecutionError(String),
 
+    /// MT5 may or may not have executed the command (timeout / response lost
+    /// after the request was sent) — must not be treated as a rejection.
+    #[error("Trade execution outcome unknown: {0}")]
+    ExecutionUnknown(String),
+
     /// Catch-all for unexpected failures.
     #[error("Internal error: {0}")]
     Internal(#[from] anyhow::Error),
@@ -39,6 +44,7 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             AppError::ExecutionError(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
+            AppError::ExecutionUnknown(msg) => (StatusCode::GATEWAY_TIMEOUT, msg.clone()),
             AppError::Internal(err) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Internal error: {err}"),
@@ -8,7 +8,9 @@
 
 use serde::Serialize;
 
-use crate::models::ActiveStrategy;
+use crate::broker::PendingFill;
+use crate::engine::{candle_builder::Candle, confirmation::ConfirmationTrace};
+use crate::models::{command::EaCommand, ActiveStrategy, PendingOrder};
 use crate::models::position::{OpenPosition, TradeRecord};
 
 /// Event ทุกรูปแบบที่ SvelteKit Dashboard จะได้รับแบบ Real-time
@@ -21,7 +23,11 @@ pub enum WsEvent {
     },
 
     /// Strategy ถูกล้างออก — Reflex Loop Disarmed
-    StrategyCleared,
+    /// `symbol` = None และ `strategy_id` = None → ล้างทุก Symbol
+    StrategyCleared {
+        symbol:      Option<String>,
+        strategy_id: Option<uuid::Uuid>,
+    },
 
     /// Reflex Loop จับ Entry Zone ได้ → กำลังยิง Order
     TradeFiring {
@@ -33,21 +39,98 @@ pub enum WsEvent {
         position: Box<OpenPosition>,
     },
 
+    /// Pending Order (Limit / Stop) วางที่ขอบ Entry Zone แล้ว
+    OrderPlaced {
+        order: Box<PendingOrder>,
+    },
+
+    /// Pending Order ถูก Fill (บางส่วนหรือทั้งหมด) — `order` = สถานะหลัง Fill นี้
+    OrderFilled {
+        order: Box<PendingOrder>,
+        fill:  PendingFill,
+    },
+
+    /// Pending Order ถูกยกเลิก / หมดอายุก่อน Fill ครบ
+    OrderCancelled {
+        order:  Box<PendingOrder>,
+        /// "STRATEGY_CLEARED" | "STRATEGY_REPLACED" | "EXPIRED" | "KILL_SWITCH" | "DOUBLE_ENTRY"
+        reason: String,
+    },
+
     /// MT5 ปฏิเสธหรือส่งไม่ถึง
     TradeFailed {
         record: Box<TradeRecord>,
     },
 
+    /// ส่งแล้วแต่ไม่รู้ผล (Timeout) — รอ Reconciler ยืนยัน
+    TradeUnknown {
+        record: Box<TradeRecord>,
+    },
+
     /// MT5 ปิด Position แล้ว (TP / SL / Manual)
     PositionClosed {
         position_id:  uuid::Uuid,
+        mt5_ticket:   Option<u64>,
         symbol:       String,
         direction:    String,
         close_price:  f64,
         profit_pips:  f64,
+        /// กำไร/ขาดทุนในสกุลเงินบัญชี (ถ้า EA ส่งมา)
+        profit:       Option<f64>,
         close_reason: String,   // "TP" | "SL" | "MANUAL"
     },
 
+    /// ปิดบางส่วนแล้ว (Scale-out) — `position` = ส่วนที่ยังเปิดอยู่
+    PositionPartiallyClosed {
+        position:     Box<OpenPosition>,
+        /// Lot ที่ปิดไป
+        volume:       f64,
+        close_price:  f64,
+        profit_pips:  f64,
+        profit:       Option<f64>,
+        close_reason: String,
+    },
+
+    /// คำสั่งจัดการ Position เข้าคิวของ EA แล้ว (รอ EA รับ + Ack)
+    CommandQueued {
+        command: Box<EaCommand>,
+    },
+
+    /// EA รายงานผลคำสั่งแล้ว — `ok` = MT5 ทำสำเร็จ (Book อัปเดตแล้ว)
+    CommandAcked {
+        command: Box<EaCommand>,
+        ok:      bool,
+        message: String,
+    },
+
+    /// ส่งคำสั่งครบทุกครั้งแล้วไม่มี Ack — Book ไม่เปลี่ยน
+    CommandTimedOut {
+        command: Box<EaCommand>,
+    },
+
+    /// แท่งเทียนปิดแล้ว (ทุก Timeframe — ขอบแท่งตามเวลาของ Tick, `tick_count = 0` = แท่งที่เติมช่วงไม่มี Tick)
+    CandleClosed {
+        candle: Box<Candle>,
+    },
+
+    /// ผลประเมิน Confirmation ตอนราคาอยู่ใน Zone (ทุกชั้น + ค่าที่วัดได้) — Throttle ตาม `CONFIRM_TRACE_INTERVAL_MS`
+    ConfirmationTrace {
+        strategy_id: uuid::Uuid,
+        trace:       Box<ConfirmationTrace>,
+    },
+
+    /// Reconciler พบว่า Position Book ไม่ตรงกับ MT5 และแก้ไขแล้ว
+    ReconciliationDiff {
+        /// Position ที่ MT5 มีแต่ Book ไม่มี → เพิ่มเข้า Book
+        adopted:  Vec<OpenPosition>,
+        /// Ticket ที่ MT5 ปิดไปแล้ว → ลบออกจาก Book
+        closed:   Vec<u64>,
+        /// Ticket ที่ SL/TP ถูกอัปเดตตาม MT5
+        updated:  Vec<u64>,
+        /// TradeRecord "UNKNOWN" (Timeout) ที่ถามผลได้แล้ว
+        resolved: Vec<uuid::Uuid>,
+    },
+
     /// Risk Kill Switch ถูกเปิด (ไม่ว่าจาก Auto-Kill หรือ Manual)
     RiskKilled {
         reason: String,
@@ -55,10 +138,12 @@ pub enum WsEvent {
 
     /// สถิติ Server (ส่งทุก N tick เพื่อให้ Dashboard ยัง alive)
     ServerStats {
-        tick_count:   u64,
-        trade_count:  u64,
-        has_position: bool,
-        has_strategy: bool,
+        tick_count:        u64,
+        trade_count:       u64,
+        has_position:      bool,
+        has_strategy:      bool,
+        open_positions:    usize,
+        active_strategies: usize,
     },
 }
 
@@ -3,22 +3,24 @@
 //! ```text
 //!  ┌─────────────┐  POST /api/brain/strategy  ┌─────────────────────────────┐
 //!  │  OpenClaw   │ ─────────────────────────▶ │ AppState                    │
-//!  │  (AI Agent) │                             │ ├─ active_strategy          │
-//!  └─────────────┘                             │ ├─ open_position            │
+//!  │  (AI Agent) │                             │ ├─ strategies (per symbol)  │
+//!  └─────────────┘                             │ ├─ positions  (per ticket)  │
 //!                                              │ ├─ trade_history            │
 //!  ┌─────────────┐  POST /api/mt5/tick         │ ├─ risk_manager  🛡️         │
 //!  │  MT5 EA     │ ─────────────────────────▶ │ ├─ tick_buffer              │
+//!  │             │  /ws/mt5 · TCP stream      │ ├─ streams (lag metrics)    │
 //!  └─────────────┘  ← POST /order/send         │ └─ broadcast_tx ──────────┐ │
 //!                                              └────────────────────────────┘ │
 //!  ┌─────────────┐  ws://host/ws/monitor  ◀────────────────────────────────── ┘
-//!  │  Dashboard  │  GET  /api/monitor/*
-//!  └─────────────┘  POST /api/backtest   📊
+//!  │  Dashboard  │  GET  /api/monitor/* · /api/market/* · ws://host/ws/market 🕯️
+//!  └─────────────┘  POST /api/backtest   📊  ← POST /api/history/import (CSV / MT5 → tickstore)
 //!                   POST /api/risk/kill  ⛔
 //! ```
 
 use std::net::SocketAddr;
 
 use axum::{
+    extract::DefaultBodyLimit,
     Router,
     routing::{delete, get, post},
 };
@@ -30,6 +32,10 @@ use tracing::info;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 mod auth;
+mod book;
+mod broker;
+mod clock;
+mod db;
 mod engine;
 mod error;
 mod events;
@@ -37,16 +43,27 @@ mod models;
 mod risk;
 mod routes;
 mod state;
+mod symbols;
+mod tickstore;
 
 use auth::require_api_key;
 use routes::{
     backtest::run_backtest,
-    brain::{clear_strategy, get_strategy, set_strategy},
-    monitor::{get_history, get_position, get_stats, ws_monitor},
-    mt5::{handle_position_close, handle_tick, health_check},
+    brain::{clear_strategy, clear_strategy_by_id, get_strategy, get_strategy_trace, set_strategy},
+    history::{get_datasets, import_body_limit, import_history},
+    market::{get_candles, get_snapshot, get_ticks, ws_market},
+    monitor::{
+        get_commands, get_history, get_indicators, get_orders, get_position, get_stats, get_streams,
+        ws_monitor,
+    },
+    mt5::{
+        get_account, handle_command_ack, handle_order_fill, handle_poll_commands, handle_position_close,
+        handle_tick, health_check, list_symbols, reconcile_positions, report_account, report_symbol_spec,
+    },
+    mt5_stream::{spawn_tcp_listener, ws_mt5},
     risk::{get_risk_status, kill_switch_off, kill_switch_on},
 };
-use state::build_state;
+use state::AppState;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -63,6 +80,12 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
+    // ── 2b. Subcommand: `antigravity migrate [status]` ────────────────────────
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return db::run_migrate_command(&args[1..]).await;
+    }
+
     info!(r#"
 
   ╔═══════════════════════════════════════════════════════╗
@@ -71,7 +94,43 @@ async fn main() -> anyhow::Result<()> {
   ╚═══════════════════════════════════════════════════════╝"#);
 
     // ── 3. Shared state ───────────────────────────────────────────────────────
-    let state = build_state();
+    let mut app_state = AppState::new();
+
+    // ── 3b. PostgreSQL (optional) — Persist + Rehydrate สถานะที่ยัง Live ─────
+    #[cfg(feature = "postgres")]
+    match std::env::var("DATABASE_URL") {
+        Ok(url) => {
+            let pool = db::pg::init_pool(&url).await?;
+            db::pg::rehydrate(&pool, &app_state).await?;
+            app_state.db = db::Persistence::spawn(pool);
+        }
+        Err(_) => tracing::warn!("DATABASE_URL not set — running in-memory only"),
+    }
+
+    // ── 3c. Tick Store — Warm-up Candle / Indicator + บันทึก Tick Live ────────
+    let recorder = tickstore::recorder::RecorderConfig::from_env();
+    if recorder.warmup_hours > 0 {
+        if let Err(e) = tickstore::recorder::warm_up(&app_state, recorder.warmup_hours).await {
+            tracing::warn!(error = %e, "Tick store warm-up failed — starting with empty candles");
+        }
+    }
+    if recorder.enabled {
+        app_state.tick_recorder =
+            tickstore::recorder::TickRecorder::spawn((*app_state.tick_store).clone(), &recorder)?;
+    }
+
+    let state = std::sync::Arc::new(app_state);
+
+    // ── 3d. Position Reconciliation — Boot + ทุก RECONCILE_INTERVAL_SECS ──────
+    engine::reconcile::spawn_reconciler(
+        state.clone(),
+        engine::reconcile::ReconcileConfig::from_env(),
+    );
+
+    // ── 3e. MT5 TCP Stream (MQL5 ไม่มี WebSocket client) ─────────────────────
+    if let Some(addr) = std::env::var("MT5_STREAM_ADDR").ok().filter(|a| !a.trim().is_empty()) {
+        spawn_tcp_listener(state.clone(), addr.trim().parse()?);
+    }
 
     // ── 4. CORS ───────────────────────────────────────────────────────────────
     let cors = CorsLayer::new()
@@ -82,16 +141,36 @@ async fn main() -> anyhow::Result<()> {
     // ── 5. Router ─────────────────────────────────────────────────────────────
     let app = Router::new()
         // ── Reflex Loop ───────────────────────────────────────────────────────
+        .route("/ws/mt5",                 get(ws_mt5))
         .route("/api/mt5/tick",           post(handle_tick))
         .route("/api/mt5/health",         get(health_check))
         .route("/api/mt5/position-close", post(handle_position_close))
+        .route("/api/mt5/order-fill",     post(handle_order_fill))
+        .route("/api/mt5/commands",       get(handle_poll_commands))
+        .route("/api/mt5/commands/ack",   post(handle_command_ack))
+        .route("/api/mt5/reconcile",      post(reconcile_positions))
+        .route("/api/mt5/account",        post(report_account))
+        .route("/api/mt5/account",        get(get_account))
+        .route("/api/mt5/symbol-spec",    post(report_symbol_spec))
+        .route("/api/mt5/symbols",        get(list_symbols))
         // ── Brain Loop ────────────────────────────────────────────────────────
         .route("/api/brain/strategy",     post(set_strategy))
         .route("/api/brain/strategy",     get(get_strategy))
         .route("/api/brain/strategy",     delete(clear_strategy))
+        .route("/api/brain/strategy/:id", delete(clear_strategy_by_id))
+        .route("/api/brain/strategy/:id/trace", get(get_strategy_trace))
+        // ── Market Data ───────────────────────────────────────────────────────
+        .route("/ws/market",              get(ws_market))
+        .route("/api/market/candles",     get(get_candles))
+        .route("/api/market/ticks",       get(get_ticks))
+        .route("/api/market/snapshot",    get(get_snapshot))
         // ── Monitor Loop ──────────────────────────────────────────────────────
         .route("/ws/monitor",             get(ws_monitor))
         .route("/api/monitor/position",   get(get_position))
+        .route("/api/monitor/orders",     get(get_orders))
+        .route("/api/monitor/commands",   get(get_commands))
+        .route("/api/monitor/streams",    get(get_streams))
+        .route("/api/monitor/indicators", get(get_indicators))
         .route("/api/monitor/history",    get(get_history))
         .route("/api/monitor/stats",      get(get_stats))
         // ── Risk Management ───────────────────────────────────────────────────
@@ -100,6 +179,8 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/risk/status",        get(get_risk_status))
         // ── Backtesting ───────────────────────────────────────────────────────
         .route("/api/backtest",           post(run_backtest))
+        .route("/api/history/import",     post(import_history).layer(DefaultBodyLimit::max(import_body_limit())))
+        .route("/api/history/datasets",   get(get_datasets))
         // ── Middleware ────────────────────────────────────────────────────────
         .layer(axum::middleware::from_fn(require_api_key))
         .layer(TraceLayer::new_for_http())
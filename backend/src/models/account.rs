@@ -0,0 +1,22 @@
+//! # models::account
+//!
+//! สถานะบัญชี MT5 ที่ EA รายงานผ่าน `POST /api/mt5/account`
+//! ใช้เป็น Equity สำหรับ Position Sizing (ดู `engine::sizing`)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Snapshot ของบัญชี ณ เวลาที่ EA รายงาน (`AccountInfo*` ใน MQL5)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub balance:     f64,
+    pub equity:      f64,
+    #[serde(default)]
+    pub margin_free: Option<f64>,
+    /// สกุลเงินบัญชี เช่น `"USD"`
+    #[serde(default)]
+    pub currency:    Option<String>,
+    /// เวลาที่ Backend ได้รับ (ไม่เชื่อนาฬิกาของ MT5 Server)
+    #[serde(default = "Utc::now")]
+    pub updated_at:  DateTime<Utc>,
+}
@@ -0,0 +1,93 @@
+//! # models::command
+//!
+//! **EA Command** — คำสั่งจัดการ Position ที่ Backend ส่งถึง EA ผ่านคิว (`engine::commands`)
+//! แทนการแนบไปกับ Response ของ Tick ถัดไป
+//!
+//! ```text
+//! QUEUED ──(EA poll)──▶ DELIVERED ──(EA ack)──▶ ออกจากคิว → ok: Book เปลี่ยน / ไม่ ok: Book เดิม
+//!                          │
+//!                          └─(ไม่ ack ภายใน Timeout)──▶ QUEUED อีกครั้ง (command_id เดิม)
+//!                                                    ครบ max_attempts → TIMED_OUT
+//! ```
+//!
+//! EA ต้องจำ `command_id` ที่ทำไปแล้ว — ได้ซ้ำ (Retry หลัง Ack หาย) ตอบผลเดิมโดยไม่ทำซ้ำ
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// สิ่งที่ EA ต้องทำ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CommandKind {
+    /// ตั้ง SL / TP ใหม่ (ส่งทั้งคู่ — 0 = ไม่มี)
+    ModifyPosition { ticket: u64, sl: f64, tp: f64 },
+    /// ปิดที่ราคาตลาด — `volume` None = ทั้งหมด / Some = บางส่วน (Scale-out)
+    ClosePosition { ticket: u64, volume: Option<f64> },
+}
+
+impl CommandKind {
+    pub fn ticket(&self) -> u64 {
+        match *self {
+            CommandKind::ModifyPosition { ticket, .. } | CommandKind::ClosePosition { ticket, .. } => ticket,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CommandStatus {
+    /// รอ EA มารับ
+    Queued,
+    /// EA รับไปแล้ว รอ Ack
+    Delivered,
+    /// ส่งครบ `max_attempts` แล้วยังไม่มี Ack
+    TimedOut,
+}
+
+/// คำสั่งหนึ่งรายการในคิว
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EaCommand {
+    pub command_id:   Uuid,
+    /// EA ของ Symbol นี้เป็นผู้รับ
+    pub symbol:       String,
+    #[serde(flatten)]
+    pub kind:         CommandKind,
+    /// เหตุผลจาก Engine เช่น "BREAK_EVEN", "SCALE_OUT"
+    pub reason:       String,
+    pub status:       CommandStatus,
+    /// ส่งให้ EA ไปแล้วกี่ครั้ง
+    pub attempts:     u32,
+    /// EA connection (`ea_id`) ที่รับไปล่าสุด
+    pub delivered_to: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at:   DateTime<Utc>,
+}
+
+impl EaCommand {
+    pub fn new(symbol: &str, kind: CommandKind, reason: &str) -> Self {
+        Self {
+            command_id:   Uuid::new_v4(),
+            symbol:       symbol.to_string(),
+            kind,
+            reason:       reason.to_string(),
+            status:       CommandStatus::Queued,
+            attempts:     0,
+            delivered_to: None,
+            delivered_at: None,
+            created_at:   Utc::now(),
+        }
+    }
+}
+
+/// ผลที่ EA รายงานกลับ (`POST /api/mt5/commands/ack`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandAck {
+    pub command_id: Uuid,
+    /// MT5 ทำสำเร็จ (`TRADE_RETCODE_DONE`)
+    pub ok:         bool,
+    #[serde(default)]
+    pub retcode:    Option<u32>,
+    #[serde(default)]
+    pub message:    String,
+}
@@ -0,0 +1,152 @@
+//! # models::confirmation
+//!
+//! **Confirmation Pipeline** ต่อ Strategy (`ActiveStrategy.confirmation`) — ชั้นตรวจเรียงตามลำดับที่ส่งมา
+//! ไม่ส่งมา = `CONFIRM_PIPELINE` ของระบบ (ค่าเริ่มต้น: 5 ชั้นเดิมตาม `CONFIRM_*`)
+//!
+//! ```json
+//! [
+//!   { "type": "spread" },
+//!   { "type": "zone_dwell", "min_ticks": 3 },
+//!   { "type": "wick_rejection", "timeframe": "M5", "min_ratio": 0.5 },
+//!   { "type": "trend", "timeframe": "H1", "source": "ma", "strictness": "strict" }
+//! ]
+//! ```
+//!
+//! ค่าที่ไม่ใส่ในแต่ละชั้นใช้ค่าของ `ConfirmationConfig` — ตัวตรวจอยู่ใน `engine::confirmation`
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Timeframe;
+
+/// หนึ่งชั้นของ Confirmation Pipeline (`type` = ชื่อชั้น)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConfirmationLayerSpec {
+    /// Zone กว้างเกิน = แผนไม่ชัด (0 = ไม่จำกัด)
+    ZoneWidth {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_pips: Option<f64>,
+    },
+    /// Spread กว้างเกิน = ช่วงข่าว / Volatility สูง
+    Spread {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_pips: Option<f64>,
+    },
+    /// ราคาเคยออกนอก Zone (ฝั่ง SL) ใน `lookback` Tick ล่าสุด
+    ZoneProbe {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lookback: Option<usize>,
+    },
+    /// อยู่ใน Zone ต่อเนื่อง ≥ `min_ticks`
+    ZoneDwell {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_ticks: Option<usize>,
+    },
+    /// BUY ห้าม Overbought / SELL ห้าม Oversold — ไม่มี RSI = ข้าม
+    Rsi {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        overbought: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        oversold:   Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeframe:  Option<Timeframe>,
+    },
+    /// แท่งที่กำลังสร้างของ `timeframe` มีไส้ตบกลับ ≥ `min_ratio` ของทั้งแท่ง
+    WickRejection {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_ratio: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeframe: Option<Timeframe>,
+    },
+    /// BUY เฉพาะขาขึ้น / SELL เฉพาะขาลง บน `timeframe` (MA เร็ว vs ช้า และ/หรือ Swing Structure)
+    Trend {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeframe:      Option<Timeframe>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source:         Option<TrendSource>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        strictness:     Option<TrendStrictness>,
+        /// MA เร็วต้องห่าง MA ช้าอย่างน้อยกี่ Pips ถึงนับว่าเป็นเทรนด์
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_gap_pips:   Option<f64>,
+        /// จำนวนแท่งที่ปิดแล้วที่ใช้หา Swing High / Low
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        swing_lookback: Option<usize>,
+    },
+}
+
+/// แหล่งที่ใช้ตัดสินเทรนด์
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendSource {
+    /// EMA เร็ว vs EMA ช้าของ Indicator Engine (ยังไม่พอ Period → `ma_20` / `ma_50` ของ EA)
+    Ma,
+    /// Swing High / Low สองจุดล่าสุด — HH + HL = ขาขึ้น / LH + LL = ขาลง
+    Structure,
+    /// ทั้งสองแหล่ง (รวมผลตาม `TrendStrictness`)
+    Both,
+}
+
+/// ความเข้มของ Trend Filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendStrictness {
+    /// ทุกแหล่งต้องเป็นเทรนด์เดียวกับ Direction
+    Strict,
+    /// อย่างน้อยหนึ่งแหล่งเป็นเทรนด์เดียวกับ Direction
+    Normal,
+    /// แค่ไม่มีแหล่งไหนสวนทาง — ไม่มีเทรนด์ / ข้อมูลยังไม่พอ = ผ่าน
+    Lenient,
+}
+
+impl std::str::FromStr for TrendSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ma"        => Ok(TrendSource::Ma),
+            "structure" => Ok(TrendSource::Structure),
+            "both"      => Ok(TrendSource::Both),
+            other       => Err(format!("Unknown trend source '{other}' (ma, structure, both)")),
+        }
+    }
+}
+
+impl std::str::FromStr for TrendStrictness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict"  => Ok(TrendStrictness::Strict),
+            "normal"  => Ok(TrendStrictness::Normal),
+            "lenient" => Ok(TrendStrictness::Lenient),
+            other     => Err(format!("Unknown trend strictness '{other}' (strict, normal, lenient)")),
+        }
+    }
+}
+
+impl std::str::FromStr for ConfirmationLayerSpec {
+    type Err = String;
+
+    /// ชื่อชั้นเปล่าๆ (ค่าทั้งหมดจาก Config) — ใช้กับ `CONFIRM_PIPELINE=spread,zone_dwell,…`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "zone_width"     => Ok(ConfirmationLayerSpec::ZoneWidth { max_pips: None }),
+            "spread"         => Ok(ConfirmationLayerSpec::Spread { max_pips: None }),
+            "zone_probe"     => Ok(ConfirmationLayerSpec::ZoneProbe { lookback: None }),
+            "zone_dwell"     => Ok(ConfirmationLayerSpec::ZoneDwell { min_ticks: None }),
+            "rsi"            => Ok(ConfirmationLayerSpec::Rsi { overbought: None, oversold: None, timeframe: None }),
+            "wick_rejection" => Ok(ConfirmationLayerSpec::WickRejection { min_ratio: None, timeframe: None }),
+            "trend"          => Ok(ConfirmationLayerSpec::Trend {
+                timeframe:      None,
+                source:         None,
+                strictness:     None,
+                min_gap_pips:   None,
+                swing_lookback: None,
+            }),
+            other => Err(format!(
+                "Unknown confirmation layer '{other}' (zone_width, spread, zone_probe, zone_dwell, rsi, wick_rejection, trend)"
+            )),
+        }
+    }
+}
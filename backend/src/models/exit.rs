@@ -0,0 +1,142 @@
+//! # models::exit
+//!
+//! **Exit Policy** — กติกาการออกจาก Position ต่อ Strategy (`ActiveStrategy.exit_policy`)
+//! ไม่ส่งมา = `EXIT_POLICY` ของระบบ (ค่าเริ่มต้น: Break-Even ที่ 50% ของระยะ TP เหมือนเดิม)
+//!
+//! ```json
+//! {
+//!   "break_even": { "trigger": { "r": 1.0 }, "offset_pips": 2.0 },
+//!   "trailing":   { "distance": { "atr": { "period": 14, "multiplier": 2.0, "timeframe": "M5" } }, "activate_at": { "r": 1.0 } },
+//!   "scale_out":  [ { "at": { "r": 1.0 }, "fraction": 0.5 } ],
+//!   "max_hold_minutes": 240,
+//!   "flatten_before_close_minutes": 5
+//! }
+//! ```
+//!
+//! ระดับราคา ([`ExitLevel`]) วัดเป็นกำไรจากราคาเข้า: `r` = เท่าของระยะ SL ตอนเปิด,
+//! `tp_fraction` = สัดส่วนของระยะ TP, `pips` = Pips ตาม SymbolSpec
+//!
+//! Policy เป็นแค่ข้อมูล — ตัวประเมินอยู่ใน `engine::exits` (ใช้ทั้ง Reflex Loop และ Backtest)
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Timeframe;
+
+// ─── Levels ───────────────────────────────────────────────────────────────────
+
+/// ระยะกำไรจากราคาเข้าที่ทำให้กติกาทำงาน
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitLevel {
+    /// เท่าของความเสี่ยงเริ่มต้น (|entry − SL ตอนเปิด|)
+    R(f64),
+    /// สัดส่วนของระยะ TP (0.5 = ครึ่งทาง)
+    TpFraction(f64),
+    Pips(f64),
+}
+
+impl ExitLevel {
+    /// ระยะเป็นหน่วยราคา — None ถ้าฐานของระยะเป็น 0 (ไม่มี SL / TP) หรือค่าไม่บวก
+    pub fn distance(&self, risk: f64, tp_distance: f64, pip_size: f64) -> Option<f64> {
+        let distance = match *self {
+            ExitLevel::R(r)          => r * risk,
+            ExitLevel::TpFraction(f) => f * tp_distance,
+            ExitLevel::Pips(pips)    => pips * pip_size,
+        };
+        (distance > 0.0).then_some(distance)
+    }
+}
+
+/// ระยะที่ Trailing Stop ตามหลังราคา
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailDistance {
+    Pips(f64),
+    /// `multiplier` × ATR ของแท่ง `timeframe` (ค่าเริ่มต้น M1) `period` แท่งล่าสุด
+    /// — Timeframe ต้องอยู่ใน `INDICATOR_TIMEFRAMES` ไม่งั้นไม่มี ATR (ไม่ Trail)
+    Atr {
+        period:     usize,
+        multiplier: f64,
+        #[serde(default)]
+        timeframe:  Timeframe,
+    },
+}
+
+// ─── Rules ────────────────────────────────────────────────────────────────────
+
+/// ย้าย SL ไปที่ทุน (+ offset ฝั่งกำไร) ครั้งเดียวเมื่อกำไรถึง `trigger`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BreakEven {
+    pub trigger:     ExitLevel,
+    /// SL ใหม่ = entry ± offset (Pips) — ล็อกกำไรค่า Spread / Commission
+    #[serde(default)]
+    pub offset_pips: f64,
+}
+
+/// SL วิ่งตามราคาทีละขั้น (ไม่ถอยหลัง)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrailingStop {
+    pub distance:    TrailDistance,
+    /// เริ่ม Trail เมื่อกำไรถึงระดับนี้ (None = ตั้งแต่เปิด)
+    #[serde(default)]
+    pub activate_at: Option<ExitLevel>,
+    /// ขยับ SL เมื่อดีขึ้นอย่างน้อยกี่ Pips (กันส่งคำสั่งแก้ทุก Tick)
+    #[serde(default)]
+    pub step_pips:   f64,
+}
+
+/// ปิดบางส่วนเมื่อกำไรถึง `at`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScaleOut {
+    pub at:       ExitLevel,
+    /// สัดส่วนของ Volume ตอนเปิด (0.5 = ครึ่งหนึ่ง)
+    pub fraction: f64,
+}
+
+// ─── ExitPolicy ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExitPolicy {
+    #[serde(default)]
+    pub break_even:                   Option<BreakEven>,
+    #[serde(default)]
+    pub trailing:                     Option<TrailingStop>,
+    /// เรียงตามลำดับที่ต้องทำ — ทำทีละขั้น
+    #[serde(default)]
+    pub scale_out:                    Vec<ScaleOut>,
+    /// ถือนานสุดกี่นาที แล้วปิดที่ราคาตลาด
+    #[serde(default)]
+    pub max_hold_minutes:             Option<i64>,
+    /// ปิดทุก Position กี่นาทีก่อน Trading Session ของ Symbol ปิด (ไม่ถือข้าม Gap)
+    #[serde(default)]
+    pub flatten_before_close_minutes: Option<i64>,
+}
+
+impl ExitPolicy {
+    /// กติกาเดิมก่อนมี Exit Policy: Break-Even ที่ 50% ของระยะ TP
+    pub fn legacy() -> Self {
+        Self {
+            break_even: Some(BreakEven { trigger: ExitLevel::TpFraction(0.5), offset_pips: 0.0 }),
+            ..Self::default()
+        }
+    }
+
+    /// Policy ของระบบจาก `EXIT_POLICY` (JSON) — ไม่ตั้ง / อ่านไม่ได้ → [`ExitPolicy::legacy`]
+    pub fn from_env() -> Self {
+        match std::env::var("EXIT_POLICY") {
+            Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "Invalid EXIT_POLICY — using legacy break-even");
+                Self::legacy()
+            }),
+            _ => Self::legacy(),
+        }
+    }
+
+    /// Timeframe และ Period ของ ATR ที่ Policy นี้ต้องใช้ (ถ้ามี)
+    pub fn atr_params(&self) -> Option<(Timeframe, usize)> {
+        match self.trailing?.distance {
+            TrailDistance::Atr { timeframe, period, .. } => Some((timeframe, period)),
+            TrailDistance::Pips(_)                       => None,
+        }
+    }
+}
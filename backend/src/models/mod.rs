@@ -1,10 +1,21 @@
 //! Domain models shared across the entire Antigravity system.
 
+pub mod account;
+pub mod command;
+pub mod confirmation;
+pub mod exit;
+pub mod order;
 pub mod position;
 pub mod strategy;
+pub mod stream;
 pub mod tick;
+pub mod timeframe;
 
+pub use account::AccountInfo;
+pub use exit::ExitPolicy;
+pub use order::{OrderKind, PendingOrder};
 #[allow(unused_imports)]
 pub use position::{OpenPosition, TradeRecord, TradeStatus};
 pub use strategy::{ActiveStrategy, Direction};
 pub use tick::TickData;
+pub use timeframe::Timeframe;
@@ -0,0 +1,96 @@
+//! # models::order
+//!
+//! Pending Order (Limit / Stop) ที่วางไว้ที่ขอบ Entry Zone ตั้งแต่ Strategy ถูก Arm
+//! — ไม่ต้องรอราคาเข้า Zone แล้วค่อยยิง Market Order (ลด Latency + Spread ตอนตลาดเร็ว)
+//!
+//! ```text
+//! BUY  ราคาอยู่เหนือ Zone → BUY LIMIT  ที่ zone.high   (รอราคาย่อลงมา)
+//! BUY  ราคาอยู่ใต้ Zone   → BUY STOP   ที่ zone.low    (รอราคาขึ้นมาถึง)
+//! SELL ราคาอยู่ใต้ Zone   → SELL LIMIT ที่ zone.low    (รอราคาเด้งขึ้นมา)
+//! SELL ราคาอยู่เหนือ Zone → SELL STOP  ที่ zone.high   (รอราคาลงมาถึง)
+//! ราคาอยู่ใน Zone แล้ว    → Market Order ผ่าน Confirmation ตามปกติ
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{strategy::EntryZone, ActiveStrategy, Direction};
+
+// ─── OrderKind ────────────────────────────────────────────────────────────────
+
+/// ประเภท Order ที่ส่งถึงโบรกเกอร์
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderKind {
+    /// Fill ทันทีที่ราคาตลาด
+    #[default]
+    Market,
+    /// Fill ที่ราคานี้หรือดีกว่า (BUY ต่ำกว่า / SELL สูงกว่าราคาตลาด)
+    Limit,
+    /// Fill เมื่อราคาวิ่งทะลุมาถึง (BUY สูงกว่า / SELL ต่ำกว่าราคาตลาด)
+    Stop,
+}
+
+impl OrderKind {
+    /// Pending Order ที่ขอบ Zone สำหรับราคาตลาดปัจจุบัน (ask สำหรับ BUY / bid สำหรับ SELL)
+    ///
+    /// None = ราคาอยู่ใน Zone แล้ว (หรือ NoTrade) → ไม่ต้องวาง Pending Order
+    pub fn for_zone(direction: Direction, zone: &EntryZone, market: f64) -> Option<(OrderKind, f64)> {
+        match direction {
+            Direction::Buy  if market > zone.high => Some((OrderKind::Limit, zone.high)),
+            Direction::Buy  if market < zone.low  => Some((OrderKind::Stop,  zone.low)),
+            Direction::Sell if market < zone.low  => Some((OrderKind::Limit, zone.low)),
+            Direction::Sell if market > zone.high => Some((OrderKind::Stop,  zone.high)),
+            _ => None,
+        }
+    }
+}
+
+// ─── PendingOrder ─────────────────────────────────────────────────────────────
+
+/// Pending Order ที่วางอยู่ที่โบรกเกอร์ — ถูกลบออกเมื่อ Fill ครบ / ยกเลิก / หมดอายุ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingOrder {
+    /// Ticket ของ Order (ไม่ใช่ Position)
+    pub order_ticket:    u64,
+    /// TradeRecord ของ Order นี้
+    pub trade_id:        Uuid,
+    pub kind:            OrderKind,
+    pub price:           f64,
+    /// Volume ที่ขอ
+    pub volume:          f64,
+    /// Volume ที่ Fill แล้ว (Partial fill สะสม)
+    pub filled_volume:   f64,
+    /// Position ที่เกิดจาก Fill แรก — None = ยังไม่ Fill เลย
+    pub position_ticket: Option<u64>,
+    pub placed_at:       DateTime<Utc>,
+    /// Strategy ที่วาง Order นี้ — ใช้สร้าง OpenPosition ตอน Fill และหมดอายุตาม `expires_at`
+    pub strategy:        ActiveStrategy,
+}
+
+impl PendingOrder {
+    /// Strategy หมดอายุแล้ว → Order ต้องถูกยกเลิก
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.strategy.expires_at.is_some_and(|expiry| now >= expiry)
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_kind_for_zone() {
+        let zone = EntryZone { low: 1990.0, high: 2000.0 };
+
+        assert_eq!(OrderKind::for_zone(Direction::Buy, &zone, 2005.0), Some((OrderKind::Limit, 2000.0)));
+        assert_eq!(OrderKind::for_zone(Direction::Buy, &zone, 1985.0), Some((OrderKind::Stop, 1990.0)));
+        assert_eq!(OrderKind::for_zone(Direction::Sell, &zone, 1985.0), Some((OrderKind::Limit, 1990.0)));
+        assert_eq!(OrderKind::for_zone(Direction::Sell, &zone, 2005.0), Some((OrderKind::Stop, 2000.0)));
+        assert_eq!(OrderKind::for_zone(Direction::Buy, &zone, 1995.0), None);
+        assert_eq!(OrderKind::for_zone(Direction::NoTrade, &zone, 2005.0), None);
+    }
+}
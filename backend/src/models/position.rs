@@ -11,7 +11,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::models::{ActiveStrategy, Direction};
+use crate::engine::sizing::SizingDecision;
+use crate::models::{strategy::EntryZone, ActiveStrategy, Direction, ExitPolicy, OrderKind};
 
 // ─── TradeStatus ──────────────────────────────────────────────────────────────
 
@@ -25,8 +26,19 @@ pub enum TradeStatus {
     Confirmed,
     /// MT5 ปฏิเสธ Order (retcode ไม่ใช่ 10009)
     Rejected,
-    /// ส่งไม่ถึง MT5 เลย (network error / timeout)
+    /// ส่งไม่ถึง MT5 เลย หรือ Venue ปฏิเสธ (network error / retcode ไม่ใช่ 10009)
     Failed,
+    /// ส่งแล้วแต่ไม่รู้ผล (Timeout ทุกรอบ) — MT5 อาจ Fill แล้ว ไม่นับเป็น Failure
+    /// Reconciler Adopt Position ที่ comment = client_id แล้วยืนยัน Trade นี้ให้
+    Unknown,
+    /// Pending Order (Limit / Stop) วางที่โบรกเกอร์แล้ว รอราคามาถึง
+    Placed,
+    /// Pending Order Fill แล้วบางส่วน — ที่เหลือยังรออยู่
+    PartiallyFilled,
+    /// Pending Order ถูกยกเลิกก่อน Fill (Strategy ถูกล้าง / แทนที่ / Kill Switch)
+    Cancelled,
+    /// Pending Order หมดอายุพร้อม Strategy (`expires_at`) ก่อน Fill
+    Expired,
 }
 
 // ─── OpenPosition ─────────────────────────────────────────────────────────────
@@ -52,6 +64,25 @@ pub struct OpenPosition {
     pub opened_at: DateTime<Utc>,
     /// สถานะเลื่อน SL วิ่งตามไปบังทุน (Break-Even) ทำไปแล้วหรือยัง?
     pub sl_moved_to_be: bool,
+    /// Opposing Zone ของ Strategy ที่เปิด Position นี้ (สำหรับ Bailout)
+    /// เก็บไว้กับ Position เพราะ Strategy ถูกลบออกจาก Book ทันทีที่ยิง Order
+    #[serde(default)]
+    pub opposing_zone: Option<EntryZone>,
+    /// SL ตอนเปิด — ระยะ 1R ของ Exit Policy (0 = ไม่รู้ → ใช้ `stop_loss`)
+    #[serde(default)]
+    pub initial_stop_loss: f64,
+    /// Volume ตอนเปิด (รวม Fill ทุกครั้ง) — Scale-out คิดสัดส่วนจากค่านี้ (0 = ใช้ `lot_size`)
+    #[serde(default)]
+    pub initial_lot_size: f64,
+    /// Exit Policy ของ Strategy ต้นทาง — None = `EXIT_POLICY` ของระบบ
+    #[serde(default)]
+    pub exit_policy: Option<ExitPolicy>,
+    /// Scale-out ที่สั่งไปแล้วกี่ขั้น
+    #[serde(default)]
+    pub scale_outs_done: usize,
+    /// EA Ack คำสั่งปิดทั้งหมดแล้ว — รอผลการปิด (`/api/mt5/position-close`) ห้ามสั่งอะไรเพิ่ม
+    #[serde(default)]
+    pub closing: bool,
 }
 
 impl OpenPosition {
@@ -68,12 +99,28 @@ impl OpenPosition {
             mt5_ticket: None,
             opened_at: Utc::now(),
             sl_moved_to_be: false,
+            opposing_zone: strategy.opposing_zone,
+            initial_stop_loss: strategy.stop_loss,
+            initial_lot_size: strategy.lot_size,
+            exit_policy: strategy.exit_policy.clone(),
+            scale_outs_done: 0,
+            closing: false,
         }
     }
 
-    /// คาดเดา Unrealised PnL จากราคาปัจจุบัน (ใช้โดย Dashboard)
-    #[allow(dead_code)]
-    pub fn unrealised_pips(&self, current_price: f64) -> f64 {
+    /// ความเสี่ยงเริ่มต้น (1R) เป็นหน่วยราคา
+    pub fn initial_risk(&self) -> f64 {
+        let stop = if self.initial_stop_loss > 0.0 { self.initial_stop_loss } else { self.stop_loss };
+        if stop > 0.0 { (self.entry_price - stop).abs() } else { 0.0 }
+    }
+
+    /// Volume ตอนเปิด
+    pub fn initial_volume(&self) -> f64 {
+        if self.initial_lot_size > 0.0 { self.initial_lot_size } else { self.lot_size }
+    }
+
+    /// ระยะราคาที่ได้เปรียบ (บวก = กำไร) ณ ราคาปัจจุบัน — หน่วยราคา ไม่ใช่ Pips
+    pub fn favourable_move(&self, current_price: f64) -> f64 {
         match self.direction {
             Direction::Buy  => current_price - self.entry_price,
             Direction::Sell => self.entry_price - current_price,
@@ -104,8 +151,23 @@ pub struct TradeRecord {
     // ── ข้อมูลตอนปิด Position (เพิ่มเมื่อ MT5 แจ้ง close) ────────────────────
     pub close_price:    Option<f64>,
     pub profit_pips:    Option<f64>,
+    /// กำไร/ขาดทุนจริงในสกุลเงินบัญชี (รวม Swap + Commission)
+    #[serde(default)]
+    pub profit:         Option<f64>,
     pub close_reason:   Option<String>,  // "TP" | "SL" | "MANUAL"
     pub closed_at:      Option<DateTime<Utc>>,
+    /// Volume คำนวณมาอย่างไร (Equity, ความเสี่ยง, Clamp) — None = Record เก่าก่อนมี Sizing
+    #[serde(default)]
+    pub sizing:         Option<SizingDecision>,
+    // ── Pending Order (Limit / Stop) ──────────────────────────────────────────
+    #[serde(default)]
+    pub order_kind:     OrderKind,
+    /// Ticket ของ Pending Order (Market Order = None — มีแต่ Position ticket)
+    #[serde(default)]
+    pub order_ticket:   Option<u64>,
+    /// Volume ที่ Fill แล้ว — None = Market Order (Fill ครบตาม `lot_size` ทันที)
+    #[serde(default)]
+    pub filled_volume:  Option<f64>,
 }
 
 impl TradeRecord {
@@ -126,8 +188,13 @@ impl TradeRecord {
             fired_at:       Utc::now(),
             close_price:    None,
             profit_pips:    None,
+            profit:         None,
             close_reason:   None,
             closed_at:      None,
+            sizing:         None,
+            order_kind:     OrderKind::Market,
+            order_ticket:   None,
+            filled_volume:  None,
         }
     }
 }
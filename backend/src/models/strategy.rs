@@ -11,6 +11,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::{confirmation::ConfirmationLayerSpec, ExitPolicy};
+
 // ─── Direction ────────────────────────────────────────────────────────────────
 
 /// The AI's directional bias for the next trade.
@@ -27,9 +29,9 @@ pub enum Direction {
 
 /// A price range in which the strategy authorises entry.
 ///
-/// For a **Buy** the Reflex Loop triggers when price *drops into* the zone
-/// (limit order semantics).  For a **Sell** it triggers when price *rises into*
-/// the zone.
+/// For a **Buy** the Reflex Loop triggers when price *drops into* the zone.
+/// For a **Sell** it triggers when price *rises into* the zone.  With
+/// [`EntryMode::Pending`] a real Limit / Stop order rests at the zone edge instead.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct EntryZone {
     /// Lower bound of the acceptable entry range.
@@ -46,17 +48,30 @@ impl EntryZone {
     }
 }
 
+// ─── EntryMode ────────────────────────────────────────────────────────────────
+
+/// How the Reflex Loop enters once the strategy is armed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntryMode {
+    /// รอราคาเข้า Zone + ผ่าน Confirmation แล้วยิง Market Order
+    #[default]
+    Market,
+    /// วาง Limit / Stop ที่ขอบ Zone ทันทีที่ Arm (ดู `models::order`)
+    Pending,
+}
+
 // ─── ActiveStrategy ───────────────────────────────────────────────────────────
 
 /// The complete trade plan written by OpenClaw and held in shared state.
 ///
 /// This is intentionally **flat** — no nested heap allocations beyond
-/// `String` — so a `RwLock` clone is fast.
+/// `String` and the optional exit policy — so a `RwLock` clone is fast.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ActiveStrategy {
     /// Unique identifier for this strategy "session".
-    /// Useful for idempotency: MT5 can reject a duplicate order if the same
-    /// `strategy_id` arrives twice (network retry scenario).
+    /// Order idempotency is keyed on the TradeRecord instead — every retry of
+    /// a trade carries the same client order ID (`executor::client_order_id`).
     pub strategy_id: Uuid,
 
     /// The symbol this strategy applies to, e.g. `"BTCUSD"`.
@@ -77,9 +92,25 @@ pub struct ActiveStrategy {
     /// โซนตรงข้าม (Supply/Demand ดักหน้า) ที่ใช้สำหรับระบบ Bailout (เผ่นก่อนชน)
     pub opposing_zone: Option<EntryZone>,
 
-    /// Lot size / position size, e.g. `0.10` for 0.10 lots.
+    /// Lot size ที่ AI เสนอ — ใช้จริงเฉพาะ `SIZING_MODE=strategy`
+    /// ปกติ Backend คำนวณเองจากความเสี่ยงต่อ Trade (ดู `engine::sizing`), 0 = ไม่ได้ระบุ
+    #[serde(default)]
     pub lot_size: f64,
 
+    /// Market (default) หรือ Pending Order ที่ขอบ Zone
+    #[serde(default)]
+    pub entry_mode: EntryMode,
+
+    /// Break-Even / Trailing / Scale-out / Time exit ของ Position ที่เปิดจาก Strategy นี้
+    /// None = `EXIT_POLICY` ของระบบ (ดู `models::exit`)
+    #[serde(default)]
+    pub exit_policy: Option<ExitPolicy>,
+
+    /// ชั้น Confirmation เรียงตามลำดับ เช่น Wick Rejection M5 + RSI ของ Setup นี้
+    /// None = `CONFIRM_PIPELINE` ของระบบ (ดู `models::confirmation`)
+    #[serde(default)]
+    pub confirmation: Option<Vec<ConfirmationLayerSpec>>,
+
     /// Human-readable rationale from OpenClaw (for logging / UI display).
     pub rationale: String,
 
@@ -92,11 +123,33 @@ pub struct ActiveStrategy {
 }
 
 impl ActiveStrategy {
-    /// Returns `true` if the strategy has not expired yet (or has no expiry).
-    pub fn is_valid(&self) -> bool {
+    /// Returns `true` if the strategy has not expired at `now` (or has no expiry).
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
         match self.expires_at {
-            Some(expiry) => Utc::now() < expiry,
+            Some(expiry) => now < expiry,
             None => true,
         }
     }
 }
+
+/// Strategy สำหรับ Test — BUY โซน 1995–2000, TP 2030, SL 1990, 0.1 Lot
+/// แต่ละ Test เปลี่ยนเฉพาะ Field ที่ต้องการด้วย `..test_strategy(symbol)`
+#[cfg(test)]
+pub fn test_strategy(symbol: &str) -> ActiveStrategy {
+    ActiveStrategy {
+        strategy_id:   Uuid::new_v4(),
+        symbol:        symbol.to_string(),
+        direction:     Direction::Buy,
+        entry_zone:    EntryZone { low: 1995.0, high: 2000.0 },
+        take_profit:   2030.0,
+        stop_loss:     1990.0,
+        opposing_zone: None,
+        lot_size:      0.10,
+        entry_mode:    Default::default(),
+        exit_policy:   None,
+        confirmation:  None,
+        rationale:     "test".to_string(),
+        created_at:    Utc::now(),
+        expires_at:    None,
+    }
+}
@@ -0,0 +1,184 @@
+//! # models::stream
+//!
+//! **Tick Stream** — ข้อความบนช่องทาง Stream ของ EA (`/ws/mt5` หรือ TCP `MT5_STREAM_ADDR`)
+//! แทน HTTP POST ทีละ Tick
+//!
+//! หนึ่ง Frame (WebSocket text) / หนึ่งบรรทัด (TCP) มีได้หลายบรรทัด — แต่ละบรรทัดเป็นอย่างใดอย่างหนึ่ง:
+//!
+//! ```text
+//! T,XAUUSD,2345.10,2345.30,1,1740726000123              ← Tick แบบย่อ (time = Unix ms)
+//! T,XAUUSD,2345.10,2345.30,1,1740726000123,55.3,,2340.5 ← + rsi_14, ma_20, ma_50 (ว่าง = ไม่มี)
+//! {"type":"TICK","symbol":"XAUUSD","bid":…}              ← TickData เต็ม
+//! {"type":"TICKS","ticks":[{…},{…}]}                     ← Batch
+//! {"type":"ACK","command_id":"…","ok":true,"retcode":10009}
+//! {"type":"HELLO","ea_id":"XAUUSD-1234","symbol":"XAUUSD","api_key":"…"}
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{command::CommandAck, TickData};
+
+// ─── Inbound ──────────────────────────────────────────────────────────────────
+
+/// ข้อความจาก EA
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StreamInbound {
+    /// แนะนำตัว — TCP ต้องส่งเป็นบรรทัดแรก (พร้อม `api_key` ถ้าตั้ง `API_KEY`)
+    Hello {
+        ea_id:   String,
+        symbol:  String,
+        #[serde(default)]
+        api_key: String,
+    },
+    Tick(TickData),
+    Ticks { ticks: Vec<TickData> },
+    /// ผลคำสั่งจัดการ Position (แทน `POST /api/mt5/commands/ack`)
+    Ack(CommandAck),
+}
+
+/// แยก Frame เป็นข้อความ — บรรทัดที่อ่านไม่ได้คืนเป็น `Err` (บรรทัดอื่นยังใช้ได้)
+pub fn parse_frame(frame: &str) -> Vec<Result<StreamInbound, String>> {
+    frame
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("T,") {
+            Some(fields) => parse_compact_tick(fields).map(StreamInbound::Tick),
+            None         => serde_json::from_str(line).map_err(|e| format!("{e}: {line}")),
+        })
+        .collect()
+}
+
+/// `symbol,bid,ask,volume,time_ms[,rsi_14,ma_20,ma_50]`
+fn parse_compact_tick(fields: &str) -> Result<TickData, String> {
+    let parts: Vec<&str> = fields.split(',').map(str::trim).collect();
+    if parts.len() < 5 || parts[0].is_empty() {
+        return Err(format!("compact tick needs symbol,bid,ask,volume,time_ms: T,{fields}"));
+    }
+
+    let number = |i: usize| -> Result<f64, String> {
+        parts[i].parse().map_err(|_| format!("invalid number '{}' in T,{fields}", parts[i]))
+    };
+    let optional = |i: usize| -> Result<Option<f64>, String> {
+        match parts.get(i) {
+            None | Some(&"") => Ok(None),
+            Some(_)          => number(i).map(Some),
+        }
+    };
+
+    let time_ms: i64 = parts[4].parse().map_err(|_| format!("invalid time_ms in T,{fields}"))?;
+    let time = DateTime::from_timestamp_millis(time_ms).ok_or_else(|| format!("time_ms out of range in T,{fields}"))?;
+
+    Ok(TickData {
+        symbol: parts[0].to_string(),
+        bid:    number(1)?,
+        ask:    number(2)?,
+        mid:    None,
+        volume: number(3)?,
+        spread: None,
+        time,
+        rsi_14: optional(5)?,
+        ma_20:  optional(6)?,
+        ma_50:  optional(7)?,
+    })
+}
+
+// ─── Connection Metrics ───────────────────────────────────────────────────────
+
+/// น้ำหนักของค่าล่าสุดในค่าเฉลี่ย (EWMA)
+const EWMA_ALPHA: f64 = 0.05;
+
+/// สถิติต่อ Stream connection (`GET /api/monitor/streams`)
+///
+/// `lag_ms` = เวลารับ − `tick.time` — รวมความต่างของนาฬิกา MT5 Server กับ Backend ด้วย
+/// ดูแนวโน้ม (avg / max) มากกว่าค่าสัมบูรณ์ · `process_us` = เวลาที่ Backend ใช้ต่อ Tick
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamStats {
+    pub conn_id:        Uuid,
+    /// `"ws"` / `"tcp"`
+    pub transport:      &'static str,
+    pub ea_id:          Option<String>,
+    pub symbol:         Option<String>,
+    pub connected_at:   DateTime<Utc>,
+    pub frames:         u64,
+    pub ticks:          u64,
+    /// บรรทัดที่อ่านไม่ได้ / ประมวลผลไม่สำเร็จ
+    pub errors:         u64,
+    /// Tick ที่ทิ้งเพราะคิวของ Symbol เต็ม (Engine ของ Symbol นั้นตามไม่ทัน)
+    pub dropped:        u64,
+    pub commands_sent:  u64,
+    pub acks:           u64,
+    pub last_tick_at:   Option<DateTime<Utc>>,
+    pub lag_ms_last:    f64,
+    pub lag_ms_avg:     f64,
+    pub lag_ms_max:     f64,
+    pub process_us_avg: f64,
+    pub process_us_max: f64,
+}
+
+impl StreamStats {
+    pub fn new(transport: &'static str) -> Self {
+        Self {
+            conn_id:        Uuid::new_v4(),
+            transport,
+            ea_id:          None,
+            symbol:         None,
+            connected_at:   Utc::now(),
+            frames:         0,
+            ticks:          0,
+            errors:         0,
+            dropped:        0,
+            commands_sent:  0,
+            acks:           0,
+            last_tick_at:   None,
+            lag_ms_last:    0.0,
+            lag_ms_avg:     0.0,
+            lag_ms_max:     0.0,
+            process_us_avg: 0.0,
+            process_us_max: 0.0,
+        }
+    }
+
+    pub fn record_tick(&mut self, tick_time: DateTime<Utc>, received_at: DateTime<Utc>, process_us: f64) {
+        let lag_ms = (received_at - tick_time).num_milliseconds() as f64;
+        let ewma   = |avg: f64, value: f64| if self.ticks == 0 { value } else { avg + EWMA_ALPHA * (value - avg) };
+
+        self.lag_ms_avg     = ewma(self.lag_ms_avg, lag_ms);
+        self.process_us_avg = ewma(self.process_us_avg, process_us);
+        self.lag_ms_last    = lag_ms;
+        self.lag_ms_max     = self.lag_ms_max.max(lag_ms);
+        self.process_us_max = self.process_us_max.max(process_us);
+        self.last_tick_at   = Some(received_at);
+        self.ticks         += 1;
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame_mixes_compact_json_and_batches() {
+        let frame = "T,XAUUSD,2345.10,2345.30,1,1740726000123,55.3,,2340.5\n\
+                     {\"type\":\"TICKS\",\"ticks\":[{\"symbol\":\"XAUUSD\",\"bid\":2345.2,\"ask\":2345.4,\"volume\":0,\"time\":\"2025-02-28T07:00:00Z\"}]}\n\
+                     \n\
+                     T,XAUUSD,abc,2345.30,1,1740726000123\n\
+                     {\"type\":\"ACK\",\"command_id\":\"6f1c2c1e-4f7a-4b8e-9a57-2a4a1f0e7d10\",\"ok\":true}";
+        let parsed = parse_frame(frame);
+        assert_eq!(parsed.len(), 4, "blank lines are skipped");
+
+        let Ok(StreamInbound::Tick(tick)) = &parsed[0] else { panic!("compact tick: {:?}", parsed[0]) };
+        assert_eq!((tick.symbol.as_str(), tick.bid, tick.ask), ("XAUUSD", 2345.10, 2345.30));
+        assert_eq!(tick.time.timestamp_millis(), 1_740_726_000_123);
+        assert_eq!((tick.rsi_14, tick.ma_20, tick.ma_50), (Some(55.3), None, Some(2340.5)));
+
+        assert!(matches!(&parsed[1], Ok(StreamInbound::Ticks { ticks }) if ticks.len() == 1));
+        assert!(parsed[2].is_err(), "bad number rejects only its own line");
+        assert!(matches!(&parsed[3], Ok(StreamInbound::Ack(ack)) if ack.ok));
+    }
+}
@@ -62,3 +62,20 @@ impl TickData {
         self.mid.unwrap_or_else(|| (self.bid + self.ask) / 2.0)
     }
 }
+
+/// Tick สำหรับ Test — Spread คงที่ 0.3
+#[cfg(test)]
+pub fn test_tick(symbol: &str, time: DateTime<Utc>, bid: f64) -> TickData {
+    TickData {
+        symbol: symbol.to_string(),
+        bid,
+        ask: bid + 0.3,
+        mid: None,
+        volume: 1.0,
+        spread: None,
+        time,
+        rsi_14: None,
+        ma_20: None,
+        ma_50: None,
+    }
+}
@@ -0,0 +1,75 @@
+//! # models::timeframe
+//!
+//! **Timeframe** ของแท่งเทียน — ขอบแท่งคำนวณจากเวลาของ Tick (UTC) ไม่ใช่นาฬิกาเครื่อง
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Timeframe {
+    #[default]
+    M1,
+    M5,
+    M15,
+    H1,
+    H4,
+    D1,
+}
+
+impl Timeframe {
+    pub const ALL: [Timeframe; 6] = [
+        Timeframe::M1,
+        Timeframe::M5,
+        Timeframe::M15,
+        Timeframe::H1,
+        Timeframe::H4,
+        Timeframe::D1,
+    ];
+
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Timeframe::M1  => 60,
+            Timeframe::M5  => 5 * 60,
+            Timeframe::M15 => 15 * 60,
+            Timeframe::H1  => 60 * 60,
+            Timeframe::H4  => 4 * 60 * 60,
+            Timeframe::D1  => 24 * 60 * 60,
+        }
+    }
+
+    /// เวลาเริ่มของแท่งที่ `time` ตกอยู่
+    pub fn bucket_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = time.timestamp().div_euclid(self.seconds()) * self.seconds();
+        DateTime::from_timestamp(secs, 0).unwrap_or(time)
+    }
+
+    /// รายการคั่นด้วย `,` เช่น `"M1,M5,H1"` — ตัวที่อ่านไม่ได้ถูกข้าม
+    pub fn parse_list(raw: &str) -> Vec<Timeframe> {
+        let mut list: Vec<Timeframe> = raw.split(',').filter_map(|s| s.trim().parse().ok()).collect();
+        list.sort_unstable();
+        list.dedup();
+        list
+    }
+}
+
+impl std::str::FromStr for Timeframe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "M1"  => Ok(Timeframe::M1),
+            "M5"  => Ok(Timeframe::M5),
+            "M15" => Ok(Timeframe::M15),
+            "H1"  => Ok(Timeframe::H1),
+            "H4"  => Ok(Timeframe::H4),
+            "D1"  => Ok(Timeframe::D1),
+            other => Err(format!("Unknown timeframe '{other}' (M1, M5, M15, H1, H4, D1)")),
+        }
+    }
+}
+
+impl std::fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
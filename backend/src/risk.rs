@@ -7,13 +7,22 @@
 //! 2. **Max Trades/Day**    — จำกัดจำนวน Trade ต่อวัน
 //! 3. **Auto-Kill**         — หยุดอัตโนมัติเมื่อ Fail ติดต่อกัน N ครั้ง
 //! 4. **Cooldown**          — พักหลัง Fail ก่อน Trade ใหม่
+//! 5. **Daily Loss Limit**  — ขาดทุนจริง (Realized) ต่อวันเกินกำหนด → Kill
+//! 6. **Weekly Loss Limit** — ขาดทุนจริงต่อสัปดาห์ (จันทร์–อาทิตย์ UTC) เกินกำหนด → Kill
+//! 7. **Max Drawdown**      — Equity ของบัญชีลดลงจากจุดสูงสุดเกินกำหนด (รวมขาดทุนที่ยังไม่ปิด) → Kill
+//!
+//! P&L มาจาก `/api/mt5/position-close` (`profit` = สกุลเงินบัญชี) ผ่าน [`RiskManager::record_close`]
+//! Equity มาจาก `/api/mt5/account` ผ่าน [`RiskManager::record_equity`] — ยังไม่เคยได้รับ Equity
+//! (Broker จำลอง / หลัง Restart ก่อน EA รายงาน) → Drawdown คิดจาก Realized P&L สะสมแทน
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn};
 
+use crate::clock::Clock;
+
 // ─── Config ───────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone)]
@@ -24,6 +33,12 @@ pub struct RiskConfig {
     pub max_consecutive_failures: u32,
     /// พักหลังจาก Fail กี่วินาที
     pub cooldown_secs_after_failure: u64,
+    /// ขาดทุนสูงสุดต่อวัน (สกุลเงินบัญชี, 0 = ไม่จำกัด)
+    pub max_daily_loss: f64,
+    /// ขาดทุนสูงสุดต่อสัปดาห์ (สกุลเงินบัญชี, 0 = ไม่จำกัด)
+    pub max_weekly_loss: f64,
+    /// Drawdown สูงสุดจากจุดสูงสุดของ Equity (สกุลเงินบัญชี, 0 = ไม่จำกัด)
+    pub max_drawdown: f64,
 }
 
 impl RiskConfig {
@@ -32,6 +47,9 @@ impl RiskConfig {
             max_trades_per_day:         env_u32("RISK_MAX_TRADES_PER_DAY", 10),
             max_consecutive_failures:   env_u32("RISK_MAX_CONSECUTIVE_FAILS", 3),
             cooldown_secs_after_failure: env_u64("RISK_COOLDOWN_SECS", 300),
+            max_daily_loss:             env_f64("RISK_MAX_DAILY_LOSS", 0.0),
+            max_weekly_loss:            env_f64("RISK_MAX_WEEKLY_LOSS", 0.0),
+            max_drawdown:               env_f64("RISK_MAX_DRAWDOWN", 0.0),
         }
     }
 }
@@ -42,6 +60,14 @@ fn env_u32(key: &str, default: u32) -> u32 {
 fn env_u64(key: &str, default: u64) -> u64 {
     std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
 }
+fn env_f64(key: &str, default: f64) -> f64 {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// วันจันทร์ของสัปดาห์ (UTC) — ใช้เป็น Key ของ Weekly Loss
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
 
 // ─── Internal State ───────────────────────────────────────────────────────────
 
@@ -54,6 +80,50 @@ struct RiskInner {
     last_failure_at:      Option<DateTime<Utc>>,
     last_trade_at:        Option<DateTime<Utc>>,
     daily_reset_date:     NaiveDate,
+    // ── Realized P&L ──────────────────────────────────────────────────────────
+    pnl_today:            f64,
+    pnl_week:             f64,
+    week_start:           NaiveDate,
+    /// Realized P&L สะสม (Equity curve เทียบกับจุดเริ่มต้น)
+    equity:               f64,
+    /// จุดสูงสุดของ `equity` (Trailing)
+    equity_peak:          f64,
+    // ── Account Equity (EA รายงาน) ────────────────────────────────────────────
+    /// Equity ล่าสุดของบัญชี — None = ยังไม่เคยได้รับ (ใช้ Realized แทน)
+    account_equity:       Option<f64>,
+    /// จุดสูงสุดของ `account_equity` (Trailing)
+    account_peak:         f64,
+}
+
+impl RiskInner {
+    /// รีเซ็ตตัวนับรายวัน/รายสัปดาห์เมื่อข้ามวัน/สัปดาห์
+    fn roll_periods(&mut self, today: NaiveDate) {
+        if today > self.daily_reset_date {
+            self.trades_today     = 0;
+            self.pnl_today        = 0.0;
+            self.daily_reset_date = today;
+            info!("📅 Risk: daily counters reset");
+        }
+        let this_week = week_start(today);
+        if this_week > self.week_start {
+            self.pnl_week   = 0.0;
+            self.week_start = this_week;
+            info!("📅 Risk: weekly P&L reset");
+        }
+    }
+
+    /// (Peak, Equity) ที่ใช้คิด Drawdown — Equity ของบัญชีถ้ามี ไม่งั้น Realized
+    fn drawdown_basis(&self) -> (f64, f64) {
+        match self.account_equity {
+            Some(equity) => (self.account_peak, equity),
+            None         => (self.equity_peak, self.equity),
+        }
+    }
+
+    fn drawdown(&self) -> f64 {
+        let (peak, equity) = self.drawdown_basis();
+        peak - equity
+    }
 }
 
 // ─── Status (for Dashboard / API) ────────────────────────────────────────────
@@ -67,6 +137,13 @@ pub struct RiskStatus {
     pub last_trade_at:        Option<DateTime<Utc>>,
     pub in_cooldown:          bool,
     pub cooldown_ends_at:     Option<DateTime<Utc>>,
+    pub realized_pnl_today:   f64,
+    pub realized_pnl_week:    f64,
+    /// Equity ล่าสุดที่ EA รายงาน (None = Drawdown ยังคิดจาก Realized P&L)
+    pub account_equity:       Option<f64>,
+    /// จุดสูงสุดของฐานที่ใช้คิด Drawdown (Account Equity หรือ Realized)
+    pub equity_peak:          f64,
+    pub drawdown:             f64,
     pub config: RiskConfigSnapshot,
 }
 
@@ -75,6 +152,9 @@ pub struct RiskConfigSnapshot {
     pub max_trades_per_day:         u32,
     pub max_consecutive_failures:   u32,
     pub cooldown_secs_after_failure: u64,
+    pub max_daily_loss:             f64,
+    pub max_weekly_loss:            f64,
+    pub max_drawdown:               f64,
 }
 
 // ─── Decision ─────────────────────────────────────────────────────────────────
@@ -83,6 +163,8 @@ pub struct RiskConfigSnapshot {
 pub enum RiskDecision {
     Approved,
     Blocked(String),
+    /// Check นี้เพิ่งสั่ง Auto-Kill — ผู้เรียกต้องบันทึก Event `AUTO_KILL`
+    Killed(String),
 }
 
 // ─── Risk Manager ─────────────────────────────────────────────────────────────
@@ -90,10 +172,13 @@ pub enum RiskDecision {
 pub struct RiskManager {
     inner:  Arc<RwLock<RiskInner>>,
     config: Arc<RiskConfig>,
+    /// วัน / Cooldown นับตามนาฬิกานี้ (Backtest = เวลาของ Tick)
+    clock:  Arc<Clock>,
 }
 
 impl RiskManager {
-    pub fn new(config: RiskConfig) -> Self {
+    pub fn new(config: RiskConfig, clock: Arc<Clock>) -> Self {
+        let today = clock.now().date_naive();
         Self {
             inner: Arc::new(RwLock::new(RiskInner {
                 is_killed:            false,
@@ -102,24 +187,29 @@ impl RiskManager {
                 consecutive_failures: 0,
                 last_failure_at:      None,
                 last_trade_at:        None,
-                daily_reset_date:     Utc::now().date_naive(),
+                daily_reset_date:     today,
+                pnl_today:            0.0,
+                pnl_week:             0.0,
+                week_start:           week_start(today),
+                equity:               0.0,
+                equity_peak:          0.0,
+                account_equity:       None,
+                account_peak:         0.0,
             })),
             config: Arc::new(config),
+            clock,
         }
     }
 
     // ─── Pre-Trade Check (เรียกก่อนยิง Order ทุกครั้ง) ──────────────────────
 
+    /// ตรวจอย่างเดียว — ไม่นับ Trade (เรียก [`commit_trade`](Self::commit_trade) ตอนจะส่ง Order จริง)
+    /// Tick ที่ถูกปฏิเสธทีหลัง (Sizing / Double-Entry / Unknown) จึงไม่กิน Slot ของวัน
     pub async fn pre_trade_check(&self) -> RiskDecision {
         let mut inner = self.inner.write().await;
 
-        // Daily reset
-        let today = Utc::now().date_naive();
-        if today > inner.daily_reset_date {
-            inner.trades_today    = 0;
-            inner.daily_reset_date = today;
-            info!("📅 Risk: daily counters reset");
-        }
+        // Daily / weekly reset
+        inner.roll_periods(self.clock.now().date_naive());
 
         // [1] Kill switch
         if inner.is_killed {
@@ -131,7 +221,7 @@ impl RiskManager {
 
         // [2] Cooldown หลัง Fail
         if let Some(fail_time) = inner.last_failure_at {
-            let elapsed  = Utc::now().signed_duration_since(fail_time);
+            let elapsed  = self.clock.now().signed_duration_since(fail_time);
             let cooldown = chrono::Duration::seconds(self.config.cooldown_secs_after_failure as i64);
             if elapsed < cooldown {
                 let remaining = (cooldown - elapsed).num_seconds();
@@ -162,19 +252,23 @@ impl RiskManager {
             inner.is_killed   = true;
             inner.kill_reason = Some(reason.clone());
             warn!("⛔ Risk auto-kill activated: {reason}");
-            return RiskDecision::Blocked(reason);
+            return RiskDecision::Killed(reason);
         }
 
-        // Approved — บันทึก
+        RiskDecision::Approved
+    }
+
+    /// นับ Trade ที่ผ่าน [`pre_trade_check`](Self::pre_trade_check) — เรียกก่อนส่ง Order ไป Broker เท่านั้น
+    pub async fn commit_trade(&self) {
+        let mut inner = self.inner.write().await;
+        inner.roll_periods(self.clock.now().date_naive());
         inner.trades_today += 1;
-        inner.last_trade_at = Some(Utc::now());
+        inner.last_trade_at = Some(self.clock.now());
         info!(
             trades_today = inner.trades_today,
             max          = self.config.max_trades_per_day,
             "✅ Risk approved"
         );
-
-        RiskDecision::Approved
     }
 
     // ─── Trade Result Recording ───────────────────────────────────────────────
@@ -193,7 +287,7 @@ impl RiskManager {
     pub async fn record_failure(&self) {
         let mut inner = self.inner.write().await;
         inner.consecutive_failures += 1;
-        inner.last_failure_at = Some(Utc::now());
+        inner.last_failure_at = Some(self.clock.now());
         warn!(
             consecutive = inner.consecutive_failures,
             max         = self.config.max_consecutive_failures,
@@ -201,6 +295,107 @@ impl RiskManager {
         );
     }
 
+    /// เรียกเมื่อ Position ถูกปิด พร้อมกำไร/ขาดทุนจริง (สกุลเงินบัญชี)
+    ///
+    /// คืน `Some(reason)` ถ้าการปิดครั้งนี้ทำให้ชน Loss Limit และสั่ง Kill แล้ว
+    /// (ผู้เรียกต้องบันทึก `AUTO_KILL` และ Broadcast `RISK_KILLED`)
+    pub async fn record_close(&self, profit: f64) -> Option<String> {
+        let mut inner = self.inner.write().await;
+        self.apply_pnl(&mut inner, profit, self.clock.now());
+
+        info!(
+            profit,
+            pnl_today = inner.pnl_today,
+            pnl_week  = inner.pnl_week,
+            drawdown  = inner.drawdown(),
+            "💰 Risk: realized P&L recorded"
+        );
+
+        if inner.is_killed {
+            return None;
+        }
+
+        let reason = self.loss_limit_breach(&inner)?;
+        inner.is_killed   = true;
+        inner.kill_reason = Some(reason.clone());
+        warn!("⛔ Risk auto-kill activated: {reason}");
+        Some(reason)
+    }
+
+    /// เรียกเมื่อ EA รายงาน Equity ของบัญชี — Drawdown นับขาดทุนของ Position ที่ยังเปิดด้วย
+    ///
+    /// คืน `Some(reason)` ถ้า Equity นี้ชน Max Drawdown และสั่ง Kill แล้ว (ผู้เรียกจัดการเหมือน `record_close`)
+    pub async fn record_equity(&self, equity: f64) -> Option<String> {
+        let mut inner = self.inner.write().await;
+        inner.account_peak   = match inner.account_equity {
+            Some(_) => inner.account_peak.max(equity),
+            None    => equity,
+        };
+        inner.account_equity = Some(equity);
+
+        if inner.is_killed {
+            return None;
+        }
+
+        let reason = self.loss_limit_breach(&inner)?;
+        inner.is_killed   = true;
+        inner.kill_reason = Some(reason.clone());
+        warn!(equity, peak = inner.account_peak, "⛔ Risk auto-kill activated: {reason}");
+        Some(reason)
+    }
+
+    /// โหลด P&L ย้อนหลังตอน Startup (ไม่สั่ง Kill — สถานะ Kill มาจาก risk_events)
+    #[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+    pub async fn restore_close(&self, profit: f64, closed_at: DateTime<Utc>) {
+        let mut inner = self.inner.write().await;
+        self.apply_pnl(&mut inner, profit, closed_at);
+    }
+
+    /// Re-arm ที่เกิดก่อน Restart (ระหว่าง [`restore_close`](Self::restore_close)) — Peak = Equity ณ จุดนั้น
+    #[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+    pub async fn restore_rearm(&self) {
+        let mut inner = self.inner.write().await;
+        inner.equity_peak = inner.equity;
+    }
+
+    fn apply_pnl(&self, inner: &mut RiskInner, profit: f64, closed_at: DateTime<Utc>) {
+        inner.roll_periods(self.clock.now().date_naive());
+
+        let date = closed_at.date_naive();
+        if date == inner.daily_reset_date {
+            inner.pnl_today += profit;
+        }
+        if week_start(date) == inner.week_start {
+            inner.pnl_week += profit;
+        }
+        inner.equity      += profit;
+        inner.equity_peak  = inner.equity_peak.max(inner.equity);
+    }
+
+    fn loss_limit_breach(&self, inner: &RiskInner) -> Option<String> {
+        let cfg = &self.config;
+
+        if cfg.max_daily_loss > 0.0 && -inner.pnl_today >= cfg.max_daily_loss {
+            return Some(format!(
+                "Daily loss limit hit: {:.2} (limit {:.2})",
+                inner.pnl_today, cfg.max_daily_loss
+            ));
+        }
+        if cfg.max_weekly_loss > 0.0 && -inner.pnl_week >= cfg.max_weekly_loss {
+            return Some(format!(
+                "Weekly loss limit hit: {:.2} (limit {:.2})",
+                inner.pnl_week, cfg.max_weekly_loss
+            ));
+        }
+        if cfg.max_drawdown > 0.0 && inner.drawdown() >= cfg.max_drawdown {
+            return Some(format!(
+                "Max drawdown hit: {:.2} from peak (limit {:.2})",
+                inner.drawdown(), cfg.max_drawdown
+            ));
+        }
+        None
+    }
+
     // ─── Manual Controls ─────────────────────────────────────────────────────
 
     /// ปิดระบบฉุกเฉิน
@@ -212,23 +407,29 @@ impl RiskManager {
     }
 
     /// เปิดระบบอีกครั้ง (หลังแก้ไขปัญหาแล้ว)
+    ///
+    /// Drawdown ปัจจุบันถูกยอมรับเป็นฐานใหม่ (Peak = Equity ตอนนี้)
+    /// ไม่งั้นขาดทุนครั้งถัดไปจะ Kill ทันทีตลอดไป — Daily/Weekly รีเซ็ตเองตามรอบ
     pub async fn rearm(&self) {
         let mut inner = self.inner.write().await;
         inner.is_killed            = false;
         inner.kill_reason          = None;
         inner.consecutive_failures = 0;
         inner.last_failure_at      = None;
+        inner.equity_peak          = inner.equity;
+        inner.account_peak         = inner.account_equity.unwrap_or_default();
         info!("✅ KILL SWITCH DEACTIVATED — system re-armed");
     }
 
     // ─── Status ───────────────────────────────────────────────────────────────
 
     pub async fn status(&self) -> RiskStatus {
-        let inner = self.inner.read().await;
+        let mut inner = self.inner.write().await;
+        inner.roll_periods(self.clock.now().date_naive());
         let cooldown_ends = inner.last_failure_at.map(|t| {
             t + chrono::Duration::seconds(self.config.cooldown_secs_after_failure as i64)
         });
-        let in_cooldown = cooldown_ends.map(|end| Utc::now() < end).unwrap_or(false);
+        let in_cooldown = cooldown_ends.map(|end| self.clock.now() < end).unwrap_or(false);
 
         RiskStatus {
             is_killed:            inner.is_killed,
@@ -238,11 +439,129 @@ impl RiskManager {
             last_trade_at:        inner.last_trade_at,
             in_cooldown,
             cooldown_ends_at:     if in_cooldown { cooldown_ends } else { None },
+            realized_pnl_today:   inner.pnl_today,
+            realized_pnl_week:    inner.pnl_week,
+            account_equity:       inner.account_equity,
+            equity_peak:          inner.drawdown_basis().0,
+            drawdown:             inner.drawdown(),
             config: RiskConfigSnapshot {
                 max_trades_per_day:          self.config.max_trades_per_day,
                 max_consecutive_failures:    self.config.max_consecutive_failures,
                 cooldown_secs_after_failure: self.config.cooldown_secs_after_failure,
+                max_daily_loss:              self.config.max_daily_loss,
+                max_weekly_loss:             self.config.max_weekly_loss,
+                max_drawdown:                self.config.max_drawdown,
             },
         }
     }
 }
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(daily: f64, weekly: f64, drawdown: f64) -> RiskConfig {
+        RiskConfig {
+            max_trades_per_day:          0,
+            max_consecutive_failures:    0,
+            cooldown_secs_after_failure: 0,
+            max_daily_loss:              daily,
+            max_weekly_loss:             weekly,
+            max_drawdown:                drawdown,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_limit_kills() {
+        let risk = RiskManager::new(config(500.0, 0.0, 0.0), Arc::default());
+
+        assert!(risk.record_close(-300.0).await.is_none());
+        let reason = risk.record_close(-250.0).await.expect("should trip");
+        assert!(reason.starts_with("Daily loss limit"), "{reason}");
+
+        let status = risk.status().await;
+        assert!(status.is_killed);
+        assert_eq!(status.realized_pnl_today, -550.0);
+        assert!(matches!(risk.pre_trade_check().await, RiskDecision::Blocked(_)));
+    }
+
+    #[tokio::test]
+    async fn test_drawdown_is_trailing_from_peak() {
+        let risk = RiskManager::new(config(0.0, 0.0, 400.0), Arc::default());
+
+        assert!(risk.record_close(1000.0).await.is_none());
+        assert!(risk.record_close(-300.0).await.is_none());
+        // ยังกำไรสุทธิ +400 แต่ลงจาก Peak 1000 แล้ว 600
+        let reason = risk.record_close(-300.0).await.expect("should trip");
+        assert!(reason.starts_with("Max drawdown"), "{reason}");
+
+        // Rearm = ยอมรับ Drawdown ปัจจุบันเป็นฐานใหม่
+        risk.rearm().await;
+        assert!(risk.record_close(-100.0).await.is_none());
+        assert_eq!(risk.status().await.drawdown, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_only_committed_trades_count_toward_daily_limit() {
+        let risk = RiskManager::new(RiskConfig { max_trades_per_day: 1, ..config(0.0, 0.0, 0.0) }, Arc::default());
+
+        // Tick ที่ผ่าน Check แต่ถูกปฏิเสธทีหลัง (Sizing / Unknown) ไม่กิน Slot
+        for _ in 0..5 {
+            assert!(matches!(risk.pre_trade_check().await, RiskDecision::Approved));
+        }
+        risk.commit_trade().await;
+        assert_eq!(risk.status().await.trades_today, 1);
+        assert!(matches!(risk.pre_trade_check().await, RiskDecision::Blocked(_)));
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_rearm_baseline_across_restart() {
+        let risk = RiskManager::new(config(0.0, 0.0, 400.0), Arc::default());
+        let day  = Utc::now() - chrono::Duration::days(30);
+
+        // ก่อน Restart: Peak 1000 → Drawdown 600 (Kill) → Re-arm → ขาดทุนอีก 100
+        risk.restore_close(1000.0, day).await;
+        risk.restore_close(-600.0, day).await;
+        risk.restore_rearm().await;
+        risk.restore_close(-100.0, day).await;
+
+        assert_eq!(risk.status().await.drawdown, 100.0);
+        assert!(risk.record_close(-100.0).await.is_none(), "must not re-kill right after restart");
+    }
+
+    #[tokio::test]
+    async fn test_restore_does_not_kill_and_skips_old_weeks() {
+        let risk = RiskManager::new(config(100.0, 100.0, 0.0), Arc::default());
+
+        risk.restore_close(-5000.0, Utc::now() - chrono::Duration::days(30)).await;
+        risk.restore_close(-150.0, Utc::now()).await;
+
+        let status = risk.status().await;
+        assert!(!status.is_killed);
+        assert_eq!(status.realized_pnl_today, -150.0);
+        assert_eq!(status.realized_pnl_week, -150.0);
+    }
+
+    #[tokio::test]
+    async fn test_unrealized_loss_in_reported_equity_trips_drawdown() {
+        let risk = RiskManager::new(config(0.0, 0.0, 400.0), Arc::default());
+
+        // Peak 10,500 → Position ที่ยังเปิดติดลบ 450 — ยังไม่มีการปิดสักครั้ง
+        assert!(risk.record_equity(10_000.0).await.is_none());
+        assert!(risk.record_equity(10_500.0).await.is_none());
+        let reason = risk.record_equity(10_050.0).await.expect("should trip");
+        assert!(reason.starts_with("Max drawdown"), "{reason}");
+
+        let status = risk.status().await;
+        assert!(status.is_killed);
+        assert_eq!(status.realized_pnl_today, 0.0);
+        assert_eq!((status.equity_peak, status.drawdown), (10_500.0, 450.0));
+
+        // Rearm = Equity ตอนนี้เป็น Peak ใหม่
+        risk.rearm().await;
+        assert!(risk.record_equity(9_900.0).await.is_none());
+        assert_eq!(risk.status().await.drawdown, 150.0);
+    }
+}
@@ -3,20 +3,60 @@
 //! **Backtesting Engine** — ทดสอบ Strategy กับข้อมูลย้อนหลัง
 //!
 //! ## How it works
-//! รับ Array ของ TickData + ActiveStrategy แล้วจำลอง Reflex + Confirmation Engine
-//! คืน Statistics: Win Rate, PnL, Max Drawdown, Trade List
+//! Replay Tick ของ Request ผ่าน `routes::mt5::process_tick` ตัวเดียวกับ EA — Reflex / Confirmation /
+//! Exit Policy / Risk / Sizing / Pending Order ชุดเดียวกับ Live ไม่มี Logic จำลองแยก
+//!
+//! ```text
+//! AppState::simulated  → Book / Buffer / Candle / Indicator ว่าง, Config ชุดเดียวกับ Live
+//!                        นาฬิกาจำลองเดินตาม tick.time (Expiry / Max Hold / Cooldown / วันของ Risk)
+//! PaperBroker          → Fill / SL / TP / Scale-out / Bailout ที่ราคาของ Tick (PAPER_SLIPPAGE_PIPS, ไม่มี Latency)
+//!                        + Commission / Slippage / Swap ตาม Cost Model (`PAPER_COSTS` หรือ `costs` ใน Request)
+//! Response ของแต่ละ Tick (NO_ACTION + reason / RISK_BLOCKED / …) → rejection_log
+//! Trade History ของ State จำลอง (+ Tick ที่ Fill) → trades
+//! ```
+//!
+//! Tick มาจาก `ticks` ใน Request หรือ `dataset` (Symbol + ช่วงเวลา) ใน Tick Store ที่ Import ไว้
+//! (`POST /api/history/import`) หรือบันทึกจาก Live — Dataset ถูกอ่านทีละชั่วโมงระหว่าง Replay ไม่โหลดทั้งชุด
+//!
+//! Strategy ถูกใช้ครั้งเดียวเหมือน Live — ยิงแล้วถอดออกจาก Book (ไม่เข้าซ้ำหลังปิด)
+//! Pips / Points / Profit คำนวณผ่าน [`SymbolSpec`] — จาก Registry หรือ `symbol_spec` ใน Request
+//! `pips` / `points` = ระยะราคาที่ได้ (ก่อนต้นทุน) / `profit` = สกุลเงินบัญชีหลัง Commission + Swap
+//!
+//! ## Strategy Timeline
+//! แผนมาจากหนึ่งในสามแหล่ง (ใส่ได้อย่างเดียว):
+//!
+//! ```text
+//! strategy      → แผนเดียว ติดตั้งก่อน Tick แรก (เดิม)
+//! strategies    → หลายแผนพร้อมเวลา — ติดตั้งเมื่อนาฬิกาจำลองถึง created_at
+//! strategy_log  → โหลดแผนที่ Active ช่วงของ Tick จากตาราง strategy_log (ต้องมี PostgreSQL)
+//! ```
+//!
+//! การติดตั้งใช้ `routes::brain::install_strategy` ตัวเดียวกับ OpenClaw — แผนใหม่แทนที่แผนเดิมของ Symbol
+//! และยกเลิก Pending Order ของแผนเดิม · `strategies` ในผลลัพธ์บอกต่อแผนว่ายิงไหม ได้เท่าไร และทำไมไม่ยิง
 //!
 //! ## Endpoint
 //! POST /api/backtest
 
-use axum::{response::IntoResponse, Json};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{atomic::Ordering, Arc};
+
+use axum::{extract::State, response::IntoResponse, Json};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::VecDeque;
+use tokio::sync::{mpsc, RwLock};
+use tracing::debug;
+use uuid::Uuid;
 
 use crate::{
-    engine::confirmation::{check_confirmation, ConfirmationConfig, RecentTick},
-    models::{ActiveStrategy, Direction, TickData},
+    broker::{CostModel, PaperConfig, TradeCosts},
+    engine::{indicators::IndicatorEngine, sizing::SizingMode},
+    error::AppError,
+    models::{ActiveStrategy, Direction, ExitPolicy, OpenPosition, TickData},
+    routes::{brain::install_strategy, history::store_error, mt5::process_tick},
+    state::{AppState, SharedState},
+    symbols::SymbolSpec,
 };
 
 // ─── Request ──────────────────────────────────────────────────────────────────
@@ -24,16 +64,62 @@ use crate::{
 #[derive(Deserialize)]
 pub struct BacktestRequest {
     /// ชุดข้อมูล Tick ย้อนหลัง (เรียงตามเวลา เก่า → ใหม่)
+    #[serde(default)]
     pub ticks:    Vec<TickData>,
-    /// Strategy ที่ต้องการทดสอบ
-    pub strategy: ActiveStrategy,
-    /// Override Confirmation Config (ถ้าไม่ใส่ใช้ค่า default)
+    /// Tick จาก Tick Store แทน `ticks`
+    #[serde(default)]
+    pub dataset:  Option<DatasetRef>,
+    /// Strategy เดียวสำหรับทั้งชุด Tick (ติดตั้งก่อน Tick แรก)
+    #[serde(default)]
+    pub strategy: Option<ActiveStrategy>,
+    /// Timeline ของ Strategy — แต่ละแผนติดตั้งเมื่อถึง `created_at` (แทนที่แผนเดิมของ Symbol เหมือน Live)
+    #[serde(default)]
+    pub strategies:   Vec<ActiveStrategy>,
+    /// โหลด Timeline จากตาราง `strategy_log`
+    #[serde(default)]
+    pub strategy_log: Option<StrategyLogQuery>,
+    /// Override Confirmation Config (ถ้าไม่ใส่ใช้ `CONFIRM_*` ของระบบ)
     pub confirmation: Option<ConfirmationOverride>,
+    /// SymbolSpec ของ Symbol (ถ้าไม่ใส่ หรือ Symbol ไม่ตรง ใช้จาก Registry)
+    #[serde(default)]
+    pub symbol_spec:  Option<SymbolSpec>,
+    /// Volume คงที่ต่อ Trade (ไม่ใส่ = Position Sizing ของระบบ `SIZING_*` กับ Equity ของ Paper account)
+    #[serde(default)]
+    pub volume:       Option<f64>,
+    /// Balance เริ่มต้นของ Paper account (ไม่ใส่ = `PAPER_BALANCE`)
+    #[serde(default)]
+    pub balance:      Option<f64>,
+    /// Override Exit Policy ของทุกแผน (ไม่ใส่ = `exit_policy` ของแต่ละแผน หรือ `EXIT_POLICY` ของระบบ)
+    #[serde(default)]
+    pub exit_policy:  Option<ExitPolicy>,
+    /// Cost Model ต่อ Symbol (ไม่ใส่ = `PAPER_COSTS`) — ดู `broker::costs`
+    #[serde(default)]
+    pub costs:        Option<CostModel>,
+    /// Seed ของ Slippage แบบสุ่ม (ไม่ใส่ = `PAPER_SEED`)
+    #[serde(default)]
+    pub seed:         Option<u64>,
+}
+
+/// Dataset ใน Tick Store — ไม่ใส่ `from` / `to` = ทั้งชุดของ Symbol
+#[derive(Debug, Deserialize)]
+pub struct DatasetRef {
+    pub symbol: String,
+    pub from:   Option<DateTime<Utc>>,
+    pub to:     Option<DateTime<Utc>>,
+}
+
+/// ช่วงของ `strategy_log` ที่จะ Replay — ไม่ใส่ = ช่วงเวลาของ Tick / Symbol ที่มีใน Tick
+#[derive(Debug, Default, Deserialize)]
+pub struct StrategyLogQuery {
+    pub symbol: Option<String>,
+    pub from:   Option<DateTime<Utc>>,
+    pub to:     Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
 pub struct ConfirmationOverride {
-    pub max_spread:         Option<f64>,
+    pub max_spread_pips:    Option<f64>,
+    pub max_zone_pips:      Option<f64>,
     pub require_zone_probe: Option<bool>,
     pub min_zone_ticks:     Option<usize>,
     pub probe_lookback:     Option<usize>,
@@ -44,191 +130,807 @@ pub struct ConfirmationOverride {
 #[derive(Debug, Serialize)]
 pub struct BacktestResult {
     /// จำนวน Tick ที่ผ่าน
-    pub total_ticks:    usize,
+    pub total_ticks:         usize,
     /// จำนวน Trade ที่ถูก Trigger
-    pub total_trades:   usize,
-    /// กำไร/ขาดทุนรวม (pips) — สมมุติว่าทุก TP ถูก Hit
-    pub total_pips:     f64,
-    /// Win Rate % (TP ถูก Hit / Total Trades)
-    pub win_rate_pct:   f64,
-    /// Max Drawdown (pips) — ติดลบมากที่สุดในช่วง Simulation
-    pub max_drawdown:   f64,
+    pub total_trades:        usize,
+    /// กำไร/ขาดทุนรวม (Pips ตาม SymbolSpec — ก่อนต้นทุน)
+    pub total_pips:          f64,
+    /// กำไร/ขาดทุนรวม (Points — ก่อนต้นทุน)
+    pub total_points:        f64,
+    /// กำไร/ขาดทุนรวม (สกุลเงินบัญชี) ตาม Volume ของแต่ละ Trade — หลัง Commission + Swap
+    pub total_profit:        f64,
+    /// กำไร/ขาดทุนของราคาก่อน Commission + Swap (สกุลเงินบัญชี)
+    pub gross_profit:        f64,
+    /// ต้นทุนรวมทุก Trade
+    pub costs:               TradeCosts,
+    /// Balance ของ Paper account ตอนจบ (รวมเฉพาะ Trade ที่ปิดแล้ว)
+    pub final_balance:       f64,
+    /// Win Rate % (Trade ที่ปิดด้วยกำไร / Total Trades)
+    pub win_rate_pct:        f64,
+    /// Max Drawdown (Pips) — ติดลบมากที่สุดของ P&L สะสมทีละ Trade
+    pub max_drawdown:        f64,
+    /// Max Drawdown (สกุลเงินบัญชี หลังต้นทุน)
+    pub max_drawdown_profit: f64,
     /// รายการ Trade แต่ละ Entry
-    pub trades:         Vec<BacktestTrade>,
+    pub trades:              Vec<BacktestTrade>,
     /// เหตุผลที่ไม่ Trigger (breakdown)
-    pub rejection_log:  RejectionBreakdown,
+    pub rejection_log:       RejectionBreakdown,
+    /// ผลของแต่ละ Strategy ใน Timeline (เรียงตามเวลาติดตั้ง)
+    pub strategies:          Vec<StrategyAttribution>,
+    /// Exit Policy ที่ใช้จริง (แผนเดียว = ของแผนนั้น / Timeline = Override หรือของระบบ)
+    pub exit_policy:         ExitPolicy,
+}
+
+/// ผลของ Strategy หนึ่งแผนใน Timeline
+#[derive(Debug, Serialize)]
+pub struct StrategyAttribution {
+    pub strategy_id:     Uuid,
+    pub symbol:          String,
+    pub direction:       Direction,
+    /// เวลาที่ติดตั้งเข้า Book จำลอง (None = Tick หมดก่อนถึง `created_at`)
+    pub installed_at:    Option<DateTime<Utc>>,
+    pub expires_at:      Option<DateTime<Utc>>,
+    /// เวลาที่ถูกแผนใหม่แทนที่
+    pub replaced_at:     Option<DateTime<Utc>>,
+    pub outcome:         StrategyOutcome,
+    pub trades:          usize,
+    pub pips:            f64,
+    pub points:          f64,
+    pub profit:          f64,
+    /// จำนวน Tick ที่ Reflex ประเมินแผนนี้
+    pub ticks_evaluated: usize,
+    /// ทำไมไม่ยิง — เหตุผลของแต่ละ Tick ที่ประเมินแผนนี้
+    pub rejection_log:   RejectionBreakdown,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StrategyOutcome {
+    Triggered,        // เปิด Position อย่างน้อยหนึ่งครั้ง
+    SizingRejected,   // ผ่าน Confirmation แต่ Position Sizing ไม่ยอม
+    ExecutionFailed,  // Broker ปฏิเสธ / ไม่รู้ผล
+    Expired,          // หมดอายุก่อนยิง
+    Replaced,         // ถูกแผนใหม่แทนที่ก่อนยิง
+    Active,           // ยังรออยู่ใน Book ตอนจบ
+    NotInstalled,     // `created_at` หลัง Tick สุดท้าย
 }
 
 #[derive(Debug, Serialize)]
 pub struct BacktestTrade {
-    pub entry_price: f64,
-    pub direction:   String,
-    pub outcome:     TradeOutcome,
-    pub pips:        f64,
-    pub tick_index:  usize,
-    pub time:        chrono::DateTime<chrono::Utc>,
+    pub entry_price:  f64,
+    pub direction:    String,
+    /// Volume ตอนเปิด (จาก Position Sizing หรือ `volume` ใน Request)
+    pub volume:       f64,
+    pub outcome:      TradeOutcome,
+    /// Pips ถ่วงตาม Volume ของทุกขาที่ปิด (Scale-out + ส่วนที่เหลือ) — ก่อนต้นทุน
+    pub pips:         f64,
+    /// เหมือน `pips` เป็น Points
+    pub points:       f64,
+    /// สกุลเงินบัญชี หลัง Commission + Swap
+    pub profit:       f64,
+    /// `profit` ก่อน Commission + Swap
+    pub gross_profit: f64,
+    /// Commission / Swap / Slippage ของ Trade นี้
+    pub costs:        TradeCosts,
+    /// Tick ที่ Position เปิด (Market Fill หรือ Pending Order ถูก Fill)
+    pub tick_index:   usize,
+    pub time:         DateTime<Utc>,
+    /// Strategy ที่เปิด Trade นี้
+    pub strategy_id:  Uuid,
+    /// เหตุผลที่ปิดตาม Exit Policy (`outcome = PolicyExit`) เช่น "MAX_HOLD_TIME"
+    pub exit_reason:  Option<String>,
+    /// จำนวนขา Scale-out ที่ปิดไปก่อนจบ Trade
+    pub scale_outs:   usize,
+    /// SL สุดท้าย (หลัง Break-Even / Trailing)
+    pub final_sl:     f64,
 }
 
 #[derive(Debug, Serialize, PartialEq)]
 pub enum TradeOutcome {
-    TpHit,   // TP ถูก Hit (Win)
-    SlHit,   // SL ถูก Hit (Loss)
-    Open,    // ยังเปิดอยู่ตอนจบ Simulation
+    TpHit,       // TP ถูก Hit (Win)
+    SlHit,       // SL ถูก Hit (Loss — หรือกำไรถ้า SL ถูกย้ายไปแล้ว)
+    PolicyExit,  // ปิดตาม Exit Policy (Bailout / Max Hold / Session Flatten / Scale-out ขาสุดท้าย)
+    Open,        // ยังเปิดอยู่ตอนจบ Simulation
 }
 
+/// จำนวน Tick ต่อเหตุผลที่ไม่เข้า Trade — จาก Tick response ของ `process_tick`
 #[derive(Debug, Serialize, Default)]
 pub struct RejectionBreakdown {
+    /// ไม่มี Strategy ใน Book (ยังไม่เริ่ม / ยิงไปแล้ว) หรือ Direction = NO_TRADE
     pub no_strategy:         usize,
+    pub strategy_expired:    usize,
+    pub outside_session:     usize,
     pub outside_zone:        usize,
+    pub zone_too_wide:       usize,
     pub spread_too_wide:     usize,
     pub no_zone_probe:       usize,
     pub insufficient_dwell:  usize,
+    pub rsi_out_of_range:    usize,
+    pub no_wick_rejection:   usize,
+    pub trend_misaligned:    usize,
     pub position_open:       usize,
+    /// Pending Order วางแล้ว รอ Fill
+    pub order_pending:       usize,
+    pub risk_blocked:        usize,
+    pub sizing_rejected:     usize,
+    /// Broker ปฏิเสธ / ไม่รู้ผล
+    pub execution_failed:    usize,
+}
+
+impl RejectionBreakdown {
+    /// นับ Tick response หนึ่งตัว — Action ที่ทำงาน (TRADE_TRIGGERED / ORDER_PLACED / …) ไม่นับ
+    fn record(&mut self, body: &serde_json::Value) {
+        let reason = body["reason"].as_str().unwrap_or_default();
+        let counter = match body["action"].as_str().unwrap_or_default() {
+            "NO_ACTION" => match reason {
+                "NO_STRATEGY" | "NO_TRADE" => &mut self.no_strategy,
+                "STRATEGY_EXPIRED"         => &mut self.strategy_expired,
+                "OUTSIDE_SESSION"          => &mut self.outside_session,
+                "OUTSIDE_ZONE"             => &mut self.outside_zone,
+                "POSITION_OPEN"            => &mut self.position_open,
+                "ORDER_PENDING"            => &mut self.order_pending,
+                "UNCONFIRMED"              => match body["layer"].as_str().unwrap_or_default() {
+                    "zone_width"     => &mut self.zone_too_wide,
+                    "spread"         => &mut self.spread_too_wide,
+                    "zone_probe"     => &mut self.no_zone_probe,
+                    "zone_dwell"     => &mut self.insufficient_dwell,
+                    "rsi"            => &mut self.rsi_out_of_range,
+                    "wick_rejection" => &mut self.no_wick_rejection,
+                    "trend"          => &mut self.trend_misaligned,
+                    _                => return,
+                },
+                _ => return,
+            },
+            "POSITION_OPEN"   => &mut self.position_open,
+            "ORDER_PENDING"   => &mut self.order_pending,
+            "RISK_BLOCKED"    => &mut self.risk_blocked,
+            "SIZING_REJECTED" => &mut self.sizing_rejected,
+            "TRADE_UNKNOWN"   => &mut self.execution_failed,
+            _                 => return,
+        };
+        *counter += 1;
+    }
 }
 
 // ─── Backtest Handler ─────────────────────────────────────────────────────────
 
 /// POST /api/backtest
 pub async fn run_backtest(
+    State(state): State<SharedState>,
     Json(req): Json<BacktestRequest>,
-) -> impl IntoResponse {
-    let result = simulate(req);
-    Json(json!({ "ok": true, "result": result }))
+) -> Result<impl IntoResponse, AppError> {
+    let result = backtest(&state, req).await?;
+    Ok(Json(json!({ "ok": true, "result": result })))
 }
 
-// ─── Simulation Engine ────────────────────────────────────────────────────────
+/// Resolve Tick + Timeline + SymbolSpec แล้ว Replay
+async fn backtest(live: &AppState, mut req: BacktestRequest) -> Result<BacktestResult, AppError> {
+    let replay   = replay_ticks(live, &mut req).await?;
+    let timeline = timeline(live, &req, &replay).await?;
 
-fn simulate(req: BacktestRequest) -> BacktestResult {
-    let strategy = &req.strategy;
-
-    // Build confirmation config
-    let mut config = ConfirmationConfig::default();
-    if let Some(ov) = &req.confirmation {
-        if let Some(v) = ov.max_spread         { config.max_spread = v; }
-        if let Some(v) = ov.require_zone_probe  { config.require_zone_probe = v; }
-        if let Some(v) = ov.min_zone_ticks      { config.min_zone_ticks = v; }
-        if let Some(v) = ov.probe_lookback      { config.probe_lookback = v; }
-    }
-
-    let mut tick_buffer: VecDeque<RecentTick> = VecDeque::with_capacity(30);
-    let mut trades:      Vec<BacktestTrade>    = Vec::new();
-    let mut rejections   = RejectionBreakdown::default();
-    let mut open_pos:    Option<OpenSimPos>    = None;
-    let mut running_pnl  = 0.0_f64;
-    let mut max_drawdown = 0.0_f64;
-    let mut peak_pnl     = 0.0_f64;
-
-    for (i, tick) in req.ticks.iter().enumerate() {
-        // Feed buffer
-        if tick_buffer.len() >= 30 { tick_buffer.pop_front(); }
-        tick_buffer.push_back(RecentTick::new(tick.bid, tick.ask));
-
-        let entry_price = match strategy.direction {
-            Direction::Buy  => tick.ask,
-            Direction::Sell => tick.bid,
-            Direction::NoTrade => { rejections.no_strategy += 1; continue; }
+    let mut specs = HashMap::new();
+    for (_, strategy) in &timeline {
+        if specs.contains_key(&strategy.symbol) {
+            continue;
+        }
+        let spec = match &req.symbol_spec {
+            Some(spec) if spec.symbol == strategy.symbol => spec.clone(),
+            _ => live.symbol_spec(&strategy.symbol).await.ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "No SymbolSpec for {} — pass `symbol_spec` in the request",
+                    strategy.symbol
+                ))
+            })?,
         };
+        specs.insert(strategy.symbol.clone(), spec);
+    }
+
+    simulate(live, req, replay, timeline, specs).await
+}
+
+// ─── Tick Source ──────────────────────────────────────────────────────────────
+
+/// Tick ของ Dataset ที่อ่านล่วงหน้าได้ (ระหว่างรอ Replay)
+const REPLAY_BUFFER_TICKS: usize = 4_096;
+
+/// Tick ที่จะ Replay + ช่วงเวลา / Symbol ของมัน (สำหรับ Timeline)
+struct Replay {
+    ticks:   TickSource,
+    from:    DateTime<Utc>,
+    to:      DateTime<Utc>,
+    symbols: HashSet<String>,
+}
+
+enum TickSource {
+    Request(std::vec::IntoIter<TickData>),
+    /// Tick Store — อ่าน / Decompress บน Blocking thread แล้วส่งผ่าน Channel ที่มีขอบเขต
+    /// (ไม่ถือ Lock ของคลังบน Runtime ของ Tick)
+    Store(mpsc::Receiver<io::Result<TickData>>),
+}
+
+impl TickSource {
+    async fn next(&mut self) -> Option<io::Result<TickData>> {
+        match self {
+            TickSource::Request(ticks) => ticks.next().map(Ok),
+            TickSource::Store(rx)      => rx.recv().await,
+        }
+    }
+}
 
-        // Close open position if TP/SL hit
-        if let Some(pos) = open_pos.take() {
-            let outcome = check_exit(tick, &pos);
-            let pips = match &outcome {
-                TradeOutcome::TpHit => strategy.take_profit - pos.entry_price,
-                TradeOutcome::SlHit => strategy.stop_loss   - pos.entry_price,
-                TradeOutcome::Open  => { open_pos = Some(pos); continue; }
-            };
-            let pips = if pos.direction == "BUY" { pips } else { -pips };
-            running_pnl += pips;
-            let drawdown = peak_pnl - running_pnl;
-            if drawdown > max_drawdown { max_drawdown = drawdown; }
-            if running_pnl > peak_pnl { peak_pnl = running_pnl; }
-
-            if let Some(last) = trades.last_mut() {
-                last.outcome = outcome;
-                last.pips    = pips;
+/// `ticks` ใน Request หรือ `dataset` จาก Tick Store (ใส่ได้อย่างเดียว)
+async fn replay_ticks(live: &AppState, req: &mut BacktestRequest) -> Result<Replay, AppError> {
+    match (&req.dataset, req.ticks.is_empty()) {
+        (Some(_), false) => Err(AppError::BadRequest("Pass either `ticks` or `dataset`, not both".into())),
+        (None, true)     => Err(AppError::BadRequest("`ticks` is empty".into())),
+        (None, false) => {
+            let ticks   = std::mem::take(&mut req.ticks);
+            let symbols = ticks.iter().map(|t| t.symbol.clone()).collect();
+            let (from, to) = (ticks[0].time, ticks[ticks.len() - 1].time);
+            Ok(Replay { ticks: TickSource::Request(ticks.into_iter()), from, to, symbols })
+        }
+        (Some(dataset), true) => {
+            let (store, symbol) = (live.tick_store.clone(), dataset.symbol.clone());
+            let (lo, hi)        = (dataset.from, dataset.to);
+            let (from, to) = tokio::task::spawn_blocking(move || store.span(&symbol, lo, hi))
+                .await
+                .map_err(|e| AppError::Internal(e.into()))?
+                .map_err(store_error)?
+                .ok_or_else(|| {
+                    AppError::NotFound(format!(
+                        "No stored ticks for {} in this period — import them via POST /api/history/import",
+                        dataset.symbol
+                    ))
+                })?;
+
+            let (store, symbol) = (live.tick_store.clone(), dataset.symbol.clone());
+            let (tx, rx)        = mpsc::channel(REPLAY_BUFFER_TICKS);
+            tokio::task::spawn_blocking(move || {
+                let ticks = match store.read(&symbol, Some(from), Some(to)) {
+                    Ok(ticks) => ticks,
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(e));
+                        return;
+                    }
+                };
+                for tick in ticks {
+                    // Backtest จบ / ล้มก่อน → เลิกอ่าน
+                    if tx.blocking_send(tick).is_err() {
+                        return;
+                    }
+                }
+            });
+            Ok(Replay { ticks: TickSource::Store(rx), from, to, symbols: HashSet::from([dataset.symbol.clone()]) })
+        }
+    }
+}
+
+// ─── Timeline ─────────────────────────────────────────────────────────────────
+
+/// (เวลาติดตั้ง, Strategy) เรียงตามเวลา — Override ของ Request (`volume` / `exit_policy`) ใส่แล้ว
+async fn timeline(
+    live:   &AppState,
+    req:    &BacktestRequest,
+    replay: &Replay,
+) -> Result<Vec<(DateTime<Utc>, ActiveStrategy)>, AppError> {
+    let mut timeline: Vec<(DateTime<Utc>, ActiveStrategy)> =
+        match (&req.strategy, req.strategies.is_empty(), &req.strategy_log) {
+            (Some(strategy), true, None) => vec![(replay.from, strategy.clone())],
+            (None, false, None) => req.strategies.iter().map(|s| (s.created_at, s.clone())).collect(),
+            (None, true, Some(query)) => {
+                let from = query.from.unwrap_or(replay.from);
+                let to   = query.to.unwrap_or(replay.to);
+                let loaded = live
+                    .db
+                    .strategy_log(query.symbol.as_deref(), from, to)
+                    .await
+                    .map_err(AppError::Internal)?
+                    .ok_or_else(|| {
+                        AppError::BadRequest(
+                            "`strategy_log` needs PostgreSQL (DATABASE_URL + --features postgres)".into(),
+                        )
+                    })?;
+                // ไม่ระบุ Symbol → เฉพาะ Symbol ที่มี Tick
+                loaded
+                    .into_iter()
+                    .filter(|s| query.symbol.is_some() || replay.symbols.contains(&s.symbol))
+                    .map(|s| (s.created_at, s))
+                    .collect()
             }
-            continue;
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Pass exactly one of `strategy`, `strategies` or `strategy_log`".into(),
+                ))
+            }
+        };
+
+    if timeline.is_empty() {
+        return Err(AppError::BadRequest("No strategy to replay in this period".into()));
+    }
+    timeline.sort_by_key(|(at, _)| *at);
+
+    for (_, strategy) in &mut timeline {
+        if req.exit_policy.is_some() {
+            strategy.exit_policy = req.exit_policy.clone();
         }
+        if let Some(volume) = req.volume {
+            strategy.lot_size = volume;
+        }
+    }
+    Ok(timeline)
+}
 
-        // Symbol check
-        if strategy.symbol != tick.symbol { continue; }
-        if !strategy.is_valid()           { continue; }
-        if strategy.direction == Direction::NoTrade { rejections.no_strategy += 1; continue; }
+// ─── Simulation ───────────────────────────────────────────────────────────────
 
-        // Zone check
-        if !strategy.entry_zone.contains(entry_price) {
-            rejections.outside_zone += 1;
-            continue;
+/// Replay Tick ผ่าน `process_tick` บน State จำลองที่ใช้ Config ชุดเดียวกับ `live`
+/// ติดตั้งแต่ละแผนของ `timeline` ก่อน Tick แรกที่ถึงเวลาของมัน
+async fn simulate(
+    live:     &AppState,
+    req:      BacktestRequest,
+    replay:   Replay,
+    timeline: Vec<(DateTime<Utc>, ActiveStrategy)>,
+    specs:    HashMap<String, SymbolSpec>,
+) -> Result<BacktestResult, AppError> {
+    let sim = replay_state(live, &req, replay.from, &specs).await;
+
+    let mut attribution: Vec<StrategyAttribution> = timeline
+        .iter()
+        .map(|(_, s)| StrategyAttribution {
+            strategy_id:     s.strategy_id,
+            symbol:          s.symbol.clone(),
+            direction:       s.direction,
+            installed_at:    None,
+            expires_at:      s.expires_at,
+            replaced_at:     None,
+            outcome:         StrategyOutcome::Active,
+            trades:          0,
+            pips:            0.0,
+            points:          0.0,
+            profit:          0.0,
+            ticks_evaluated: 0,
+            rejection_log:   RejectionBreakdown::default(),
+        })
+        .collect();
+    let index: HashMap<Uuid, usize> = attribution.iter().enumerate().map(|(i, a)| (a.strategy_id, i)).collect();
+    let mut pending = timeline.into_iter().peekable();
+
+    let mut rejections = RejectionBreakdown::default();
+    // Ticket → (Tick ที่ Fill, เวลา) — อ่านจาก Trade History ทุกครั้งที่มี Trade ใหม่ ไม่ใช่จาก Book:
+    // Pending ที่ Fill แล้วโดน SL ใน Tick เดียวกันไม่เคยอยู่ใน Book ตอนจบ Tick
+    let mut opened: HashMap<u64, (usize, DateTime<Utc>)> = HashMap::new();
+    let mut trade_count = sim.trade_count.load(Ordering::Relaxed);
+    // Position ที่เห็นล่าสุดต่อ Ticket — SL / Scale-out สุดท้ายก่อนปิด
+    let mut seen: HashMap<u64, OpenPosition> = HashMap::new();
+    let mut total_ticks = 0;
+    let mut ticks       = replay.ticks;
+
+    while let Some(tick) = ticks.next().await {
+        let tick = tick.map_err(|e| AppError::Internal(e.into()))?;
+        let i    = total_ticks;
+        total_ticks += 1;
+
+        while let Some((_, strategy)) = pending.next_if(|(at, _)| *at <= tick.time) {
+            let id = strategy.strategy_id;
+            let (replaced, _) = install_strategy(&sim, strategy, false).await;
+            attribution[index[&id]].installed_at = Some(tick.time);
+            for old in replaced {
+                if let Some(&j) = index.get(&old) {
+                    attribution[j].replaced_at = Some(tick.time);
+                }
+            }
         }
 
-        // Confirmation check
-        use crate::engine::confirmation::ConfirmationResult;
-        match check_confirmation(tick.bid, tick.ask, &strategy.entry_zone, strategy.direction, &tick_buffer, None, tick.rsi_14, &config) {
-            ConfirmationResult::Rejected { reason } => {
-                match reason {
-                    "spread too wide"        => rejections.spread_too_wide += 1,
-                    "no zone probe detected" => rejections.no_zone_probe += 1,
-                    "insufficient zone dwell"=> rejections.insufficient_dwell += 1,
-                    _                        => {}
+        // แผนที่ Reflex ประเมินใน Tick นี้ (ยิงแล้วจะถูกถอดออก — อ่านก่อน)
+        let evaluated = sim.strategies_for(&tick.symbol).await.first().map(|s| s.strategy_id);
+        let target = |body: &serde_json::Value| {
+            body["strategy_id"]
+                .as_str()
+                .and_then(|id| id.parse::<Uuid>().ok())
+                .or(evaluated)
+                .and_then(|id| index.get(&id).copied())
+        };
+
+        let time = tick.time;
+        match process_tick(&sim, tick).await {
+            Ok((_, Json(body))) => {
+                rejections.record(&body);
+                if let Some(j) = target(&body) {
+                    attribution[j].ticks_evaluated += 1;
+                    attribution[j].rejection_log.record(&body);
                 }
-                continue;
             }
-            ConfirmationResult::Confirmed => {
-                let dir_str = format!("{:?}", strategy.direction).to_uppercase();
-                open_pos = Some(OpenSimPos {
-                    entry_price,
-                    direction:   dir_str.clone(),
-                    take_profit: strategy.take_profit,
-                    stop_loss:   strategy.stop_loss,
-                });
-                trades.push(BacktestTrade {
-                    entry_price,
-                    direction: dir_str,
-                    outcome:   TradeOutcome::Open,
-                    pips:      0.0,
-                    tick_index: i,
-                    time:      tick.time,
-                });
+            Err(e) => {
+                debug!(tick_index = i, error = %e, "Backtest: execution failed");
+                rejections.execution_failed += 1;
+                if let Some(j) = evaluated.and_then(|id| index.get(&id).copied()) {
+                    attribution[j].ticks_evaluated += 1;
+                    attribution[j].rejection_log.execution_failed += 1;
+                }
+            }
+        }
+        let count = sim.trade_count.load(Ordering::Relaxed);
+        if count != trade_count {
+            trade_count = count;
+            for record in sim.trade_history.read().await.iter() {
+                if let Some(ticket) = record.mt5_ticket {
+                    opened.entry(ticket).or_insert((i, time));
+                }
+            }
+        }
+        for pos in sim.positions.read().await.all() {
+            if let Some(ticket) = pos.mt5_ticket {
+                seen.insert(ticket, pos);
             }
         }
     }
 
-    // Close any remaining open position as "Open"
-    // (already pushed as Open above)
+    // ── Trade History ของ State จำลอง → Trade ที่เปิดจริง ───────────────────────
+    let history = sim.trade_history.read().await.clone();
+    let mut trades: Vec<BacktestTrade> = Vec::new();
+    for record in &history {
+        let Some(ticket) = record.mt5_ticket else { continue };
+        let Some(&(tick_index, time)) = opened.get(&ticket) else { continue };
+        let Some(spec) = specs.get(&record.symbol) else { continue };
+        // Book ไม่เคยเห็น (เปิดแล้วปิดใน Tick เดียว) → Volume / SL จาก Record, ไม่มี Scale-out
+        let pos    = seen.get(&ticket);
+        let volume = pos.map_or(record.filled_volume.unwrap_or(record.lot_size), OpenPosition::initial_volume);
+
+        // Profit ของ Broker = หลังต้นทุน (เหมือน EA) → ถอด Commission + Swap ออกเป็นกำไรของราคา
+        let costs     = sim.broker.trade_costs(ticket).await.unwrap_or_default();
+        let profit    = record.profit.unwrap_or_default();
+        let gross     = if record.close_reason.is_some() { profit - costs.total() } else { 0.0 };
+        // ระยะราคาถ่วงตาม Volume ของทุกขา = กำไรของราคา ÷ มูลค่าของราคา 1.0 ที่ Volume ตอนเปิด
+        let per_unit  = spec.profit(1.0, volume);
+        let delta     = if per_unit > 0.0 { gross / per_unit } else { 0.0 };
+        let outcome   = match record.close_reason.as_deref() {
+            None       => TradeOutcome::Open,
+            Some("TP") => TradeOutcome::TpHit,
+            Some("SL") => TradeOutcome::SlHit,
+            Some(_)    => TradeOutcome::PolicyExit,
+        };
+        trades.push(BacktestTrade {
+            entry_price:  record.entry_price,
+            direction:    format!("{:?}", record.direction).to_uppercase(),
+            volume,
+            exit_reason:  record.close_reason.clone().filter(|_| outcome == TradeOutcome::PolicyExit),
+            outcome,
+            pips:         spec.pips(delta),
+            points:       spec.points(delta),
+            profit,
+            gross_profit: gross,
+            costs,
+            tick_index,
+            time,
+            strategy_id:  record.strategy_id,
+            scale_outs:   pos.map_or(0, |p| p.scale_outs_done),
+            final_sl:     pos.map_or(record.stop_loss, |p| p.stop_loss),
+        });
+    }
+    trades.sort_by_key(|t| t.tick_index);
+
+    let mut pnl        = PnlTracker::default();
+    let mut pnl_profit = PnlTracker::default();
+    let mut costs      = TradeCosts::default();
+    for trade in &trades {
+        pnl.add(trade.pips);
+        pnl_profit.add(trade.profit);
+        costs.add(trade.costs);
+        if let Some(&j) = index.get(&trade.strategy_id) {
+            attribution[j].trades += 1;
+            attribution[j].pips   += trade.pips;
+            attribution[j].points += trade.points;
+            attribution[j].profit += trade.profit;
+        }
+    }
+
+    for a in &mut attribution {
+        a.outcome = a.resolve_outcome(replay.to);
+    }
 
     let total_trades = trades.len();
-    let wins         = trades.iter().filter(|t| t.outcome == TradeOutcome::TpHit).count();
+    let wins         = trades
+        .iter()
+        .filter(|t| t.outcome != TradeOutcome::Open && t.profit > 0.0)
+        .count();
     let total_pips   = trades.iter().map(|t| t.pips).sum();
+    let total_points = trades.iter().map(|t| t.points).sum();
+    let total_profit = trades.iter().map(|t| t.profit).sum();
+    let gross_profit = trades.iter().map(|t| t.gross_profit).sum();
     let win_rate_pct = if total_trades > 0 {
         (wins as f64 / total_trades as f64) * 100.0
     } else { 0.0 };
+    let final_balance = sim.broker.account_info().await.map(|a| a.balance).unwrap_or_default();
+
+    let exit_policy = req
+        .exit_policy
+        .clone()
+        .or_else(|| req.strategy.as_ref().and_then(|s| s.exit_policy.clone()))
+        .unwrap_or_else(|| (*live.exit_policy).clone());
 
-    BacktestResult {
-        total_ticks:  req.ticks.len(),
+    Ok(BacktestResult {
+        total_ticks,
         total_trades,
         total_pips,
+        total_points,
+        total_profit,
+        gross_profit,
+        costs,
+        final_balance,
         win_rate_pct,
-        max_drawdown,
+        max_drawdown: pnl.max_drawdown,
+        max_drawdown_profit: pnl_profit.max_drawdown,
         trades,
         rejection_log: rejections,
+        strategies:   attribution,
+        exit_policy,
+    })
+}
+
+impl StrategyAttribution {
+    /// ผลของแผนตอนจบ Replay — ยิงแล้วมาก่อน ตามด้วยเหตุที่ยิงไม่สำเร็จ แล้วจึงเหตุที่แผนจบ
+    fn resolve_outcome(&self, end: DateTime<Utc>) -> StrategyOutcome {
+        let ended = self.replaced_at.unwrap_or(end);
+        if self.installed_at.is_none() {
+            StrategyOutcome::NotInstalled
+        } else if self.trades > 0 {
+            StrategyOutcome::Triggered
+        } else if self.rejection_log.sizing_rejected > 0 {
+            StrategyOutcome::SizingRejected
+        } else if self.rejection_log.execution_failed > 0 {
+            StrategyOutcome::ExecutionFailed
+        } else if self.expires_at.is_some_and(|at| at <= ended) {
+            StrategyOutcome::Expired
+        } else if self.replaced_at.is_some() {
+            StrategyOutcome::Replaced
+        } else {
+            StrategyOutcome::Active
+        }
     }
 }
 
-struct OpenSimPos {
-    entry_price: f64,
-    direction:   String,
-    take_profit: f64,
-    stop_loss:   f64,
+/// State จำลองของการทดสอบหนึ่งครั้ง — Config จาก `live` + Override ของ Request, Book ว่าง
+async fn replay_state(
+    live:  &AppState,
+    req:   &BacktestRequest,
+    start: DateTime<Utc>,
+    specs: &HashMap<String, SymbolSpec>,
+) -> SharedState {
+    // Latency ของ Paper เป็นเวลาจริง — Replay ไม่รอ (Fill ที่ราคาของ Tick ที่ตัดสินใจ)
+    let mut paper = PaperConfig { latency_ms: 0, ..PaperConfig::from_env() };
+    if let Some(balance) = req.balance {
+        paper.balance = balance;
+    }
+    if let Some(costs) = &req.costs {
+        paper.costs = costs.clone();
+    }
+    if let Some(seed) = req.seed {
+        paper.seed = seed;
+    }
+    let mut sim = AppState::simulated(paper, start);
+
+    let mut confirmation = (*live.confirmation_config).clone();
+    if let Some(ov) = &req.confirmation {
+        if let Some(v) = ov.max_spread_pips    { confirmation.max_spread_pips = v; }
+        if let Some(v) = ov.max_zone_pips      { confirmation.max_zone_pips = v; }
+        if let Some(v) = ov.require_zone_probe { confirmation.require_zone_probe = v; }
+        if let Some(v) = ov.min_zone_ticks     { confirmation.min_zone_ticks = v; }
+        if let Some(v) = ov.probe_lookback     { confirmation.probe_lookback = v; }
+    }
+
+    // `volume` ใส่ไว้ใน lot_size ของทุกแผนแล้ว (ดู `timeline`)
+    let mut sizing = (*live.sizing_config).clone();
+    if req.volume.is_some() {
+        sizing.mode = SizingMode::Strategy;
+    }
+
+    sim.confirmation_config = Arc::new(confirmation);
+    sim.exit_policy         = live.exit_policy.clone();
+    sim.sizing_config       = Arc::new(sizing);
+    sim.execution_config    = live.execution_config.clone();
+    sim.indicators          = Arc::new(RwLock::new(IndicatorEngine::new(live.indicators.read().await.config().clone())));
+    {
+        let mut registry = sim.symbols.write().await;
+        for spec in specs.values() {
+            registry.upsert(spec.clone());
+        }
+    }
+    Arc::new(sim)
+}
+
+/// Running P&L (Pips / สกุลเงิน) สำหรับ Max Drawdown
+#[derive(Default)]
+struct PnlTracker {
+    running:      f64,
+    peak:         f64,
+    max_drawdown: f64,
 }
 
-/// ตรวจว่า Tick ปัจจุบัน Hit TP หรือ SL หรือยัง
-fn check_exit(tick: &TickData, pos: &OpenSimPos) -> TradeOutcome {
-    if pos.direction == "BUY" {
-        if tick.bid >= pos.take_profit { return TradeOutcome::TpHit; }
-        if tick.bid <= pos.stop_loss   { return TradeOutcome::SlHit; }
-    } else {
-        if tick.ask <= pos.take_profit { return TradeOutcome::TpHit; }
-        if tick.ask >= pos.stop_loss   { return TradeOutcome::SlHit; }
+impl PnlTracker {
+    fn add(&mut self, pnl: f64) {
+        self.running += pnl;
+        self.max_drawdown = self.max_drawdown.max(self.peak - self.running);
+        self.peak = self.peak.max(self.running);
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{confirmation::ConfirmationLayerSpec, strategy::{test_strategy, EntryMode, EntryZone}, tick::test_tick};
+    use crate::symbols::test_spec;
+
+    /// ข้อมูลย้อนหลังเริ่ม 2026-01-07 10:00:00 UTC — Strategy หมดอายุไปแล้วตามนาฬิกาจริง
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-07T10:00:00Z").unwrap().to_utc() + chrono::Duration::seconds(secs)
+    }
+
+    /// Tick XAUUSD ลำดับที่ `i` (ห่างกัน 1 วินาที)
+    fn tick(i: i64, bid: f64) -> TickData {
+        test_tick("XAUUSD", at(i), bid)
+    }
+
+    /// BUY โซน 1999–2001 สร้างตอนเริ่มข้อมูล — Confirmation เหลือแค่ Spread
+    fn strategy() -> ActiveStrategy {
+        ActiveStrategy {
+            entry_zone:   EntryZone { low: 1999.0, high: 2001.0 },
+            confirmation: Some(vec![ConfirmationLayerSpec::Spread { max_pips: None }]),
+            created_at:   at(0),
+            ..test_strategy("XAUUSD")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_moves_sl_to_break_even_on_simulated_clock() {
+        // หมดอายุตามนาฬิกาจริงแล้ว แต่ยังไม่หมดตามเวลาของ Tick
+        let req: BacktestRequest = serde_json::from_value(json!({
+            "strategy": ActiveStrategy { expires_at: Some(at(3600)), ..strategy() },
+            "ticks": [tick(0, 2010.0), tick(1, 2000.0), tick(2, 2011.0), tick(3, 2000.0), tick(4, 2000.5)],
+            "confirmation": null,
+            "symbol_spec": test_spec("XAUUSD"),
+            "volume": 0.1,
+            "balance": 10_000.0,
+            "exit_policy": { "break_even": { "trigger": { "r": 1.0 } } },
+        }))
+        .unwrap();
+
+        let result = backtest(&AppState::new(), req).await.unwrap();
+
+        // เข้าที่ ask 2000.3 → 1R (10.3) ถึงที่ bid 2011.0 → SL ย้ายไปทุน → bid 2000.0 แตะ SL
+        assert_eq!(result.total_trades, 1);
+        let trade = &result.trades[0];
+        assert_eq!((trade.tick_index, trade.time), (1, at(1)));
+        assert_eq!(trade.outcome, TradeOutcome::SlHit);
+        assert!((trade.final_sl - 2000.3).abs() < 1e-9);
+        // (2000.0 − 2000.3) × 100 × 0.1 = −3 USD = −3 Pips
+        assert!((trade.profit + 3.0).abs() < 1e-6);
+        assert!((trade.pips + 3.0).abs() < 1e-6);
+        assert!((result.final_balance - 9_997.0).abs() < 1e-6);
+
+        // Strategy ถูกใช้ครั้งเดียวเหมือน Live
+        assert_eq!(result.rejection_log.outside_zone, 1);
+        assert_eq!(result.rejection_log.no_strategy, 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_fill_and_sl_on_same_tick_is_reported() {
+        let req: BacktestRequest = serde_json::from_value(json!({
+            "strategy": ActiveStrategy { entry_mode: EntryMode::Pending, ..strategy() },
+            "ticks": [tick(0, 2010.0), tick(1, 1985.0), tick(2, 1986.0)],
+            "symbol_spec": test_spec("XAUUSD"),
+            "volume": 0.1,
+            "balance": 10_000.0,
+        }))
+        .unwrap();
+
+        let result = backtest(&AppState::new(), req).await.unwrap();
+
+        // Buy Limit 2001 → Gap ลง: Fill ที่ ask 1985.3 แล้วแตะ SL ที่ bid 1985.0 ใน Tick เดียวกัน
+        assert_eq!(result.total_trades, 1);
+        let trade = &result.trades[0];
+        assert_eq!((trade.tick_index, &trade.outcome), (1, &TradeOutcome::SlHit));
+        assert_eq!((trade.volume, trade.final_sl), (0.1, 1990.0));
+        assert!((trade.profit + 3.0).abs() < 1e-6);
+        assert!((result.total_profit - (result.final_balance - 10_000.0)).abs() < 1e-6);
+        assert_eq!(result.strategies[0].trades, 1);
+    }
+
+    #[tokio::test]
+    async fn test_costs_reported_in_currency_and_points() {
+        let req: BacktestRequest = serde_json::from_value(json!({
+            "strategy": ActiveStrategy { take_profit: 2010.0, ..strategy() },
+            "ticks": [tick(0, 2010.0), tick(1, 2000.0), tick(2, 2010.5)],
+            "confirmation": null,
+            "symbol_spec": test_spec("XAUUSD"),
+            "volume": 0.1,
+            "balance": 10_000.0,
+            "costs": { "*": { "commission_per_lot": 5.0 } },
+        }))
+        .unwrap();
+
+        let result = backtest(&AppState::new(), req).await.unwrap();
+
+        // เข้าที่ ask 2000.3 → TP ที่ bid 2010.5: (10.2) × 100 × 0.1 = 102 − Commission 2 ขา × 0.5
+        let trade = &result.trades[0];
+        assert_eq!(trade.outcome, TradeOutcome::TpHit);
+        assert!((trade.gross_profit - 102.0).abs() < 1e-6);
+        assert!((trade.costs.commission + 1.0).abs() < 1e-9);
+        assert!((trade.profit - 101.0).abs() < 1e-6);
+        // ระยะราคาก่อนต้นทุน: 10.2 = 102 Pips = 1,020 Points
+        assert!((trade.pips - 102.0).abs() < 1e-6);
+        assert!((result.total_points - 1_020.0).abs() < 1e-6);
+        assert!((result.final_balance - 10_101.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_replaces_plan_and_attributes_trades() {
+        let plan = |strategy_id: Uuid, low: f64, created: i64| ActiveStrategy {
+            strategy_id,
+            entry_zone: EntryZone { low, high: low + 2.0 },
+            stop_loss:  1980.0,
+            created_at: at(created),
+            expires_at: Some(at(3600)),
+            ..strategy()
+        };
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // แผนแรก (1990–1992) ไม่ถึงโซน → แผนที่สองมาแทนที่ตอน 10:00:02 แล้วยิงทันที
+        let req: BacktestRequest = serde_json::from_value(json!({
+            "strategies": [plan(second, 1999.0, 2), plan(first, 1990.0, 0)],
+            "ticks": [tick(0, 2010.0), tick(1, 2005.0), tick(2, 2000.0), tick(3, 2010.0)],
+            "symbol_spec": test_spec("XAUUSD"),
+            "volume": 0.1,
+        }))
+        .unwrap();
+
+        let result = backtest(&AppState::new(), req).await.unwrap();
+
+        assert_eq!(result.total_trades, 1);
+        assert_eq!(result.trades[0].strategy_id, second);
+
+        let [replaced, triggered] = &result.strategies[..] else { panic!("{:?}", result.strategies) };
+        assert_eq!((replaced.strategy_id, &replaced.outcome), (first, &StrategyOutcome::Replaced));
+        assert_eq!(replaced.replaced_at, Some(at(2)));
+        assert_eq!((replaced.ticks_evaluated, replaced.rejection_log.outside_zone), (2, 2));
+
+        assert_eq!((triggered.strategy_id, &triggered.outcome), (second, &StrategyOutcome::Triggered));
+        assert_eq!((triggered.installed_at, triggered.trades), (Some(at(2)), 1));
+        // ยังเปิดอยู่ตอนจบ Replay
+        assert_eq!(result.trades[0].outcome, TradeOutcome::Open);
+    }
+
+    #[tokio::test]
+    async fn test_dataset_reference_replays_stored_ticks_in_range() {
+        let dir   = std::env::temp_dir().join(format!("agv_bt_{}", Uuid::new_v4().simple()));
+        let store = crate::tickstore::TickStore::new(&dir);
+        let ticks: Vec<_> = [2010.0, 2000.0, 2031.0, 2000.0]
+            .iter()
+            .enumerate()
+            .map(|(i, bid)| crate::tickstore::StoredTick {
+                time_ms: at(i as i64).timestamp_millis(),
+                bid:     *bid,
+                ask:     bid + 0.3,
+                volume:  1.0,
+                ..Default::default()
+            })
+            .collect();
+        store.merge("XAUUSD", &ticks).unwrap();
+
+        let mut live = AppState::new();
+        live.tick_store = Arc::new(store);
+        let req: BacktestRequest = serde_json::from_value(json!({
+            "strategy": strategy(),
+            // Tick สุดท้าย (กลับมาที่ 2000) อยู่นอกช่วง
+            "dataset": { "symbol": "XAUUSD", "to": at(2) },
+            "symbol_spec": test_spec("XAUUSD"),
+            "volume": 0.1,
+        }))
+        .unwrap();
+
+        let result = backtest(&live, req).await.unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!((result.total_ticks, result.total_trades), (3, 1));
+        assert_eq!(result.trades[0].outcome, TradeOutcome::TpHit);
     }
-    TradeOutcome::Open
 }
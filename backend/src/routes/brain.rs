@@ -1,89 +1,255 @@
 //! # routes::brain
 //!
 //! Axum route handlers สำหรับ Brain Loop interface (OpenClaw → Axum)
+//!
+//! | Method | Path                          | Description                                  |
+//! |--------|-------------------------------|----------------------------------------------|
+//! | POST   | `/api/brain/strategy`         | ติดตั้ง Strategy (แทนที่แผนเดิมของ Symbol)    |
+//! | POST   | `/api/brain/strategy?append=true` | เพิ่ม Strategy โดยไม่แทนที่แผนเดิม        |
+//! | GET    | `/api/brain/strategy?symbol=` | Strategy ทั้งหมด (หรือเฉพาะ Symbol)           |
+//! | DELETE | `/api/brain/strategy?symbol=` | ล้างทั้งหมด (หรือเฉพาะ Symbol)                |
+//! | DELETE | `/api/brain/strategy/:id`     | ล้าง Strategy ตาม ID                          |
+//! | GET    | `/api/brain/strategy/:id/trace?limit=` | Trace ของ Confirmation ล่าสุด (ทำไมไม่เข้า) |
 
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
+use serde::Deserialize;
 use serde_json::json;
+use uuid::Uuid;
 
 use crate::{
+    engine::orders::{cancel_for_strategies, cancel_reason},
     error::AppError,
     events::WsEvent,
     models::ActiveStrategy,
     state::SharedState,
 };
 
+// ─── Query Params ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SetStrategyParams {
+    /// true = เพิ่มเข้า Book คู่กับแผนเดิมของ Symbol เดียวกัน
+    /// false (default) = แทนที่ทุกแผนของ Symbol นั้น (พฤติกรรมของ OpenClaw)
+    #[serde(default)]
+    pub append: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SymbolFilter {
+    pub symbol: Option<String>,
+}
+
 // ─── POST /api/brain/strategy ─────────────────────────────────────────────────
 
-/// OpenClaw ส่งแผนใหม่มา — ติดตั้งใน State + Broadcast แจ้ง Dashboard
+/// OpenClaw ส่งแผนใหม่มา — ติดตั้งใน Strategy Book + Broadcast แจ้ง Dashboard
+///
+/// แผนใหม่จะแทนที่เฉพาะแผนของ Symbol เดียวกันเท่านั้น
+/// Strategy ของ Symbol อื่นยัง Armed อยู่ตามเดิม
 pub async fn set_strategy(
     State(state): State<SharedState>,
+    Query(params): Query<SetStrategyParams>,
     Json(strategy): Json<ActiveStrategy>,
 ) -> Result<impl IntoResponse, AppError> {
-    let id = strategy.strategy_id;
+    let id     = strategy.strategy_id;
+    let symbol = strategy.symbol.clone();
 
-    // Broadcast ก่อน write เพื่อให้ Dashboard เห็นทันที
-    state.broadcast(&WsEvent::StrategyUpdated {
-        strategy: Box::new(strategy.clone()),
-    });
-
-    {
-        let mut guard = state.active_strategy.write().await;
-        *guard = Some(strategy);
-    }
+    let (replaced_ids, cancelled) = install_strategy(&state, strategy, params.append).await;
 
-    tracing::info!(strategy_id = %id, "🧠 [BRAIN] New strategy installed");
+    tracing::info!(
+        strategy_id = %id,
+        symbol      = %symbol,
+        replaced    = replaced_ids.len(),
+        cancelled,
+        "🧠 [BRAIN] New strategy installed"
+    );
 
     Ok((
         StatusCode::CREATED,
         Json(json!({
-            "ok":          true,
-            "strategy_id": id,
-            "message":     "Strategy activated — Reflex Loop is now armed.",
+            "ok":               true,
+            "strategy_id":      id,
+            "symbol":           symbol,
+            "replaced":         replaced_ids,
+            "cancelled_orders": cancelled,
+            "message":          "Strategy activated — Reflex Loop is now armed.",
         })),
     ))
 }
 
+/// ติดตั้ง Strategy เข้า Book — แทนที่แผนเดิมของ Symbol (หรือ `append`) + ยกเลิก Pending Order ของแผนที่ถูกแทนที่
+/// คืน (ID ของแผนที่ถูกแทนที่, จำนวน Order ที่ยกเลิก) — Backtest ใช้ตัวเดียวกันตอน Replay Timeline
+pub(crate) async fn install_strategy(
+    state:    &SharedState,
+    strategy: ActiveStrategy,
+    append:   bool,
+) -> (Vec<Uuid>, usize) {
+    state.db.strategy(&strategy);
+
+    // Broadcast ก่อน write เพื่อให้ Dashboard เห็นทันที
+    state.broadcast(&WsEvent::StrategyUpdated {
+        strategy: Box::new(strategy.clone()),
+    });
+
+    let replaced = {
+        let mut book = state.strategies.write().await;
+        if append {
+            book.insert(strategy);
+            Vec::new()
+        } else {
+            book.replace_symbol(strategy)
+        }
+    };
+
+    for old in &replaced {
+        state.broadcast(&WsEvent::StrategyCleared {
+            symbol:      Some(old.symbol.clone()),
+            strategy_id: Some(old.strategy_id),
+        });
+    }
+    // Pending Order ของแผนเดิมต้องไม่ Fill หลังแผนถูกแทนที่
+    let replaced_ids: Vec<Uuid> = replaced.iter().map(|s| s.strategy_id).collect();
+    let cancelled = cancel_for_strategies(state, &replaced_ids, cancel_reason::STRATEGY_REPLACED).await;
+
+    (replaced_ids, cancelled)
+}
+
 // ─── GET /api/brain/strategy ──────────────────────────────────────────────────
 
-/// อ่าน Strategy ปัจจุบัน (SvelteKit ใช้ Poll นี้)
+/// อ่าน Strategy ทั้งหมดใน Book (SvelteKit ใช้ Poll นี้)
+/// `?symbol=BTCUSD` → เฉพาะ Symbol นั้น
 pub async fn get_strategy(
     State(state): State<SharedState>,
+    Query(filter): Query<SymbolFilter>,
 ) -> Result<impl IntoResponse, AppError> {
-    let guard = state.active_strategy.read().await;
-
-    match guard.as_ref() {
-        Some(strategy) => Ok((
-            StatusCode::OK,
-            Json(json!({ "ok": true, "strategy": strategy })),
-        )),
-        None => Err(AppError::NotFound(
+    let strategies = {
+        let book = state.strategies.read().await;
+        match &filter.symbol {
+            Some(symbol) => book.for_symbol(symbol),
+            None         => book.all(),
+        }
+    };
+
+    if strategies.is_empty() {
+        return Err(AppError::NotFound(
             "No active strategy. Brain Loop has not published a plan yet.".into(),
-        )),
+        ));
     }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "ok":         true,
+            "count":      strategies.len(),
+            "strategies": strategies,
+        })),
+    ))
 }
 
 // ─── DELETE /api/brain/strategy ───────────────────────────────────────────────
 
 /// ล้าง Strategy — Disarm Reflex Loop ชั่วคราว
+/// `?symbol=BTCUSD` → ล้างเฉพาะ Symbol นั้น, ไม่ระบุ → ล้างทุก Symbol
 pub async fn clear_strategy(
     State(state): State<SharedState>,
+    Query(filter): Query<SymbolFilter>,
 ) -> impl IntoResponse {
-    {
-        let mut guard = state.active_strategy.write().await;
-        *guard = None;
-    }
+    let cleared = {
+        let mut book = state.strategies.write().await;
+        match &filter.symbol {
+            Some(symbol) => book.clear_symbol(symbol),
+            None         => book.clear_all(),
+        }
+    };
+
+    state.broadcast(&WsEvent::StrategyCleared {
+        symbol:      filter.symbol.clone(),
+        strategy_id: None,
+    });
 
-    state.broadcast(&WsEvent::StrategyCleared);
+    let cleared_ids: Vec<Uuid> = cleared.iter().map(|s| s.strategy_id).collect();
+    let cancelled = cancel_for_strategies(&state, &cleared_ids, cancel_reason::STRATEGY_CLEARED).await;
 
-    tracing::info!("🧠 [BRAIN] Strategy cleared — Reflex Loop disarmed");
+    tracing::info!(
+        symbol  = ?filter.symbol,
+        cleared = cleared.len(),
+        cancelled,
+        "🧠 [BRAIN] Strategy cleared — Reflex Loop disarmed"
+    );
 
     Json(json!({
-        "ok":      true,
-        "message": "Strategy cleared. Reflex Loop is now disarmed.",
+        "ok":               true,
+        "cleared":          cleared.len(),
+        "cancelled_orders": cancelled,
+        "message":          "Strategy cleared. Reflex Loop is now disarmed.",
     }))
 }
+
+// ─── DELETE /api/brain/strategy/:id ───────────────────────────────────────────
+
+/// ล้าง Strategy ตัวเดียวตาม ID
+pub async fn clear_strategy_by_id(
+    State(state): State<SharedState>,
+    Path(strategy_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let removed = state.strategies.write().await.remove(strategy_id);
+
+    let Some(strategy) = removed else {
+        return Err(AppError::NotFound(format!("Strategy {strategy_id} not found")));
+    };
+
+    state.broadcast(&WsEvent::StrategyCleared {
+        symbol:      Some(strategy.symbol.clone()),
+        strategy_id: Some(strategy_id),
+    });
+
+    let cancelled = cancel_for_strategies(&state, &[strategy_id], cancel_reason::STRATEGY_CLEARED).await;
+
+    tracing::info!(
+        strategy_id = %strategy_id,
+        symbol      = %strategy.symbol,
+        cancelled,
+        "🧠 [BRAIN] Strategy cleared"
+    );
+
+    Ok(Json(json!({
+        "ok":               true,
+        "strategy_id":      strategy_id,
+        "symbol":           strategy.symbol,
+        "cancelled_orders": cancelled,
+        "message":          "Strategy cleared.",
+    })))
+}
+
+// ─── GET /api/brain/strategy/:id/trace ────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TraceQuery {
+    /// จำนวน Trace ล่าสุด (ไม่ใส่ = ทั้ง Buffer)
+    pub limit: Option<usize>,
+}
+
+/// Trace การประเมิน Confirmation ของ Strategy (เก่า → ใหม่) — ยังอ่านได้หลัง Strategy ถูกล้าง / แทนที่
+pub async fn get_strategy_trace(
+    State(state): State<SharedState>,
+    Path(strategy_id): Path<Uuid>,
+    Query(query): Query<TraceQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let traces = state
+        .confirmation_traces
+        .read()
+        .await
+        .for_strategy(strategy_id, query.limit.unwrap_or(usize::MAX))
+        .ok_or_else(|| AppError::NotFound(format!("No confirmation trace for strategy {strategy_id}")))?;
+
+    Ok(Json(json!({
+        "ok":          true,
+        "strategy_id": strategy_id,
+        "count":       traces.len(),
+        "traces":      traces,
+    })))
+}
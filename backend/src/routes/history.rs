@@ -0,0 +1,158 @@
+//! # routes::history
+//!
+//! **Historical Data** — Import Tick / แท่งย้อนหลังเข้า Tick Store ให้ Backtest อ้างเป็น `dataset`
+//! แทนการส่ง Tick ทั้งชุดมาใน JSON
+//!
+//! | Method | Path                                                                    | Description                          |
+//! |--------|-------------------------------------------------------------------------|--------------------------------------|
+//! | POST   | `/api/history/import?symbol=&format=&utc_offset_minutes=&timeframe=`    | Body = ไฟล์ CSV (ดู `tickstore::import`) |
+//! | GET    | `/api/history/datasets`                                                 | Symbol / ช่วงเวลา / จำนวนวัน / ขนาด   |
+
+use std::io;
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    error::AppError,
+    models::Timeframe,
+    state::SharedState,
+    tickstore::import::{self, ImportFormat, ImportOptions},
+};
+
+/// ขนาดไฟล์ Import สูงสุด (`HISTORY_IMPORT_MAX_MB`, default 512)
+pub fn import_body_limit() -> usize {
+    let mb: usize = std::env::var("HISTORY_IMPORT_MAX_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(512);
+    mb.max(1) * 1024 * 1024
+}
+
+// ─── Query / Response ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub symbol:             String,
+    /// `mt5_ticks` / `mt5_bars` / `generic` — ไม่ใส่ = ดูจาก Header
+    #[serde(default)]
+    pub format:             Option<ImportFormat>,
+    /// เวลา Server ของไฟล์ − UTC (นาที) เช่น GMT+2 = 120
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Timeframe ของแท่ง (`mt5_bars`) — ไม่ใส่ = อนุมานจากระยะห่างของแท่ง
+    #[serde(default)]
+    pub timeframe:          Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub symbol:     String,
+    pub format:     Option<ImportFormat>,
+    pub timeframe:  Option<Timeframe>,
+    /// แถวข้อมูลในไฟล์
+    pub rows:       usize,
+    /// Tick ที่อ่านได้ (แท่ง = 4 Tick)
+    pub ticks:      usize,
+    /// Tick ใหม่ที่เข้าคลัง
+    pub inserted:   usize,
+    /// Tick ที่มีในคลังแล้ว / ซ้ำกันในไฟล์
+    pub duplicates: usize,
+    /// แถวที่ข้ามเพราะข้อมูลผิด (ตัวอย่างใน `errors`)
+    pub invalid:    usize,
+    /// แถวที่ไม่มี Bid / Ask ใหม่
+    pub skipped:    usize,
+    pub from:       Option<DateTime<Utc>>,
+    pub to:         Option<DateTime<Utc>>,
+    pub errors:     Vec<String>,
+}
+
+// ─── POST /api/history/import ─────────────────────────────────────────────────
+
+/// อ่านไฟล์ → ตรวจ → Merge เข้าคลัง (ตัดตัวซ้ำ) — Import ไฟล์เดิมซ้ำได้ไม่เพิ่มข้อมูล
+pub async fn import_history(
+    State(state): State<SharedState>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let timeframe = query
+        .timeframe
+        .as_deref()
+        .map(str::parse::<Timeframe>)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    let options = ImportOptions {
+        format:             query.format,
+        utc_offset_minutes: query.utc_offset_minutes,
+        timeframe,
+        point:              state.symbol_spec(&query.symbol).await.map(|s| s.point),
+    };
+
+    // Parse + เขียนไฟล์เป็นงาน CPU / Disk ก้อนใหญ่ — ไม่บล็อก Runtime ของ Tick
+    let store  = state.tick_store.clone();
+    let symbol = query.symbol;
+    let report = tokio::task::spawn_blocking(move || -> Result<ImportReport, AppError> {
+        let parsed = import::parse(&body, &options).map_err(AppError::BadRequest)?;
+        if parsed.ticks.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "No valid ticks in {} rows: {}",
+                parsed.rows,
+                parsed.errors.join("; ")
+            )));
+        }
+
+        let stats = store.merge(&symbol, &parsed.ticks).map_err(store_error)?;
+        Ok(ImportReport {
+            from:       parsed.ticks.iter().map(|t| t.time_ms).min().and_then(DateTime::from_timestamp_millis),
+            to:         parsed.ticks.iter().map(|t| t.time_ms).max().and_then(DateTime::from_timestamp_millis),
+            symbol,
+            format:     parsed.format,
+            timeframe:  parsed.timeframe,
+            rows:       parsed.rows,
+            ticks:      parsed.ticks.len(),
+            inserted:   stats.inserted,
+            duplicates: stats.duplicates,
+            invalid:    parsed.invalid,
+            skipped:    parsed.skipped,
+            errors:     parsed.errors,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.into()))??;
+
+    info!(
+        symbol     = %report.symbol,
+        rows       = report.rows,
+        inserted   = report.inserted,
+        duplicates = report.duplicates,
+        invalid    = report.invalid,
+        "📥 [HISTORY] Ticks imported"
+    );
+
+    Ok(Json(json!({ "ok": true, "report": report })))
+}
+
+// ─── GET /api/history/datasets ────────────────────────────────────────────────
+
+pub async fn get_datasets(State(state): State<SharedState>) -> Result<impl IntoResponse, AppError> {
+    let store    = state.tick_store.clone();
+    let datasets = tokio::task::spawn_blocking(move || store.datasets())
+        .await
+        .map_err(|e| AppError::Internal(e.into()))?
+        .map_err(store_error)?;
+
+    Ok(Json(json!({ "ok": true, "datasets": datasets })))
+}
+
+/// Symbol ที่ใช้เป็นชื่อโฟลเดอร์ไม่ได้ = Request ผิด / อื่นๆ = Disk
+pub fn store_error(e: io::Error) -> AppError {
+    match e.kind() {
+        io::ErrorKind::InvalidInput => AppError::BadRequest(e.to_string()),
+        _                           => AppError::Internal(e.into()),
+    }
+}
@@ -0,0 +1,346 @@
+//! # routes::market
+//!
+//! **Market Data** — แท่งเทียน / Tick ล่าสุดจาก Candle Aggregator ของ Backend
+//! ให้ Dashboard (Chart จุดเข้า / ออก) และ OpenClaw (โครงสร้างตลาด) อ่านได้โดยตรง
+//! ใส่ `from` (และ `to`) = ย้อนดูช่วงเวลาใดก็ได้จาก Tick Store แทนหน่วยความจำ
+//!
+//! | Method | Path                                               | Description                                   |
+//! |--------|----------------------------------------------------|-----------------------------------------------|
+//! | GET    | `/api/market/candles?symbol=&tf=&limit=&from=&to=` | แท่งที่ปิดแล้ว (เก่า → ใหม่) + แท่งที่กำลังสร้าง |
+//! | GET    | `/api/market/ticks?symbol=&limit=&from=&to=`       | Tick ดิบล่าสุด (เก่า → ใหม่)                    |
+//! | GET    | `/api/market/snapshot?symbol=&tf=`                 | ราคา + เปลี่ยนแปลง 1H / 24H + Indicator (OpenClaw) |
+//! | WS     | `/ws/market?symbol=&tf=M1,M5&interval_ms=`         | `CANDLES` → `CANDLE_UPDATE` / `CANDLE_CLOSED`  |
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info};
+
+use crate::{
+    engine::candle_builder::{Candle, CandleAggregator, CandleConfig},
+    error::AppError,
+    models::{TickData, Timeframe},
+    routes::history::store_error,
+    state::SharedState,
+};
+
+/// จำนวนแท่ง / Tick ที่คืนเมื่อไม่ส่ง `limit`
+const DEFAULT_LIMIT: usize = 200;
+
+// ─── Query Params ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct MarketQuery {
+    pub symbol:      String,
+    /// Timeframe เดียว (`/candles`, `/snapshot`) หรือคั่นด้วย `,` (`/ws/market`) — ค่าเริ่มต้น M1
+    #[serde(default)]
+    pub tf:          Option<String>,
+    #[serde(default)]
+    pub limit:       Option<usize>,
+    /// `/ws/market`: ส่ง `CANDLE_UPDATE` ของแท่งที่กำลังสร้างถี่สุดทุกกี่ ms
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// `/candles`, `/ticks`: อ่านจาก Tick Store ตั้งแต่เวลานี้ (`limit` ตัวแรก) แทนหน่วยความจำ
+    #[serde(default)]
+    pub from:        Option<DateTime<Utc>>,
+    /// ใช้คู่กับ `from` — ไม่ใส่ = ถึงปัจจุบัน
+    #[serde(default)]
+    pub to:          Option<DateTime<Utc>>,
+}
+
+impl MarketQuery {
+    fn timeframe(&self) -> Result<Timeframe, AppError> {
+        match &self.tf {
+            Some(tf) => tf.parse().map_err(AppError::BadRequest),
+            None     => Ok(Timeframe::M1),
+        }
+    }
+
+    fn timeframes(&self) -> Result<Vec<Timeframe>, AppError> {
+        let Some(raw) = &self.tf else {
+            return Ok(vec![Timeframe::M1]);
+        };
+        // ตรวจทีละตัวเพื่อบอกว่าตัวไหนผิด (parse_list ข้ามตัวที่อ่านไม่ได้)
+        for tf in raw.split(',') {
+            tf.parse::<Timeframe>().map_err(AppError::BadRequest)?;
+        }
+        Ok(Timeframe::parse_list(raw))
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).max(1)
+    }
+}
+
+// ─── REST ─────────────────────────────────────────────────────────────────────
+
+/// GET /api/market/candles — แท่งที่ปิดแล้ว `limit` แท่งล่าสุด + แท่งที่กำลังสร้าง
+/// (`from` = `limit` แท่งแรกของช่วง + แท่งสุดท้ายที่ยังไม่ครบ)
+pub async fn get_candles(
+    State(state): State<SharedState>,
+    Query(query): Query<MarketQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let timeframe          = query.timeframe()?;
+    let (candles, forming) = if query.from.is_some() {
+        stored_candles(&state, &query, timeframe).await?
+    } else {
+        let agg = state.candles.read().await;
+        (agg.closed(&query.symbol, timeframe, query.limit()), agg.forming(&query.symbol, timeframe).cloned())
+    };
+    Ok(Json(json!({
+        "ok":        true,
+        "symbol":    query.symbol,
+        "timeframe": timeframe,
+        "count":     candles.len(),
+        "candles":   candles,
+        "forming":   forming,
+    })))
+}
+
+/// GET /api/market/ticks — Tick ดิบ `limit` ตัวล่าสุด (`from` = `limit` ตัวแรกของช่วง)
+pub async fn get_ticks(
+    State(state): State<SharedState>,
+    Query(query): Query<MarketQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let ticks: Vec<_> = if query.from.is_some() {
+        stored_ticks(&state, &query).await?
+    } else {
+        let buffer = state.market_ticks.read().await;
+        buffer
+            .get(&query.symbol)
+            .map(|ticks| ticks.iter().skip(ticks.len().saturating_sub(query.limit())).cloned().collect())
+            .unwrap_or_default()
+    };
+    Ok(Json(json!({
+        "ok":     true,
+        "symbol": query.symbol,
+        "count":  ticks.len(),
+        "ticks":  ticks,
+    })))
+}
+
+// ─── Tick Store (from / to) ───────────────────────────────────────────────────
+
+/// สร้างแท่งใหม่จาก Tick ที่บันทึกไว้ — เริ่มที่ต้นแท่งของ `from` ไม่ให้แท่งแรกขาด
+async fn stored_candles(
+    state:     &SharedState,
+    query:     &MarketQuery,
+    timeframe: Timeframe,
+) -> Result<(Vec<Candle>, Option<Candle>), AppError> {
+    let store  = (*state.tick_store).clone();
+    let config = CandleConfig { history_size: 1, ..state.candles.read().await.config().clone() };
+    let symbol = query.symbol.clone();
+    let from   = query.from.map(|t| timeframe.bucket_start(t));
+    let (to, limit) = (query.to, query.limit());
+
+    tokio::task::spawn_blocking(move || {
+        let mut agg    = CandleAggregator::new(config);
+        let mut closed = Vec::new();
+        for tick in store.read(&symbol, from, to).map_err(store_error)? {
+            let tick = tick.map_err(store_error)?;
+            closed.extend(agg.on_tick(&tick).into_iter().filter(|c| c.timeframe == timeframe));
+            if closed.len() >= limit {
+                closed.truncate(limit);
+                return Ok((closed, None));
+            }
+        }
+        Ok((closed, agg.forming(&symbol, timeframe).cloned()))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.into()))?
+}
+
+async fn stored_ticks(state: &SharedState, query: &MarketQuery) -> Result<Vec<TickData>, AppError> {
+    let store = (*state.tick_store).clone();
+    let (symbol, from, to, limit) = (query.symbol.clone(), query.from, query.to, query.limit());
+
+    tokio::task::spawn_blocking(move || {
+        store
+            .read(&symbol, from, to)
+            .map_err(store_error)?
+            .take(limit)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(store_error)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.into()))?
+}
+
+/// GET /api/market/snapshot — สรุปตลาดของ Symbol สำหรับ OpenClaw
+///
+/// `rsi_14` / `ma_20` / `ma_50` = RSI / SMA เร็ว / SMA ช้าของ Indicator Engine ที่ `tf`
+/// (ชื่อเดิมของ EA — Period จริงตาม `INDICATOR_*`, ค่าเต็มอยู่ใน `indicators`)
+pub async fn get_snapshot(
+    State(state): State<SharedState>,
+    Query(query): Query<MarketQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let timeframe = query.timeframe()?;
+    let tick = state
+        .market_ticks
+        .read()
+        .await
+        .get(&query.symbol)
+        .and_then(|ticks| ticks.back().cloned())
+        .ok_or_else(|| AppError::NotFound(format!("No ticks received for {}", query.symbol)))?;
+    let price = tick.effective_mid();
+
+    let (last_hour, last_day) = {
+        let agg = state.candles.read().await;
+        (
+            agg.since(&query.symbol, Timeframe::M1, tick.time - Duration::hours(1)),
+            agg.since(&query.symbol, Timeframe::H1, tick.time - Duration::hours(24)),
+        )
+    };
+    let change_pct = |candles: &[crate::engine::candle_builder::Candle]| {
+        candles.first().filter(|c| c.open != 0.0).map(|c| (price - c.open) / c.open * 100.0)
+    };
+    let high_24h = last_day.iter().map(|c| c.high).reduce(f64::max);
+    let low_24h  = last_day.iter().map(|c| c.low).reduce(f64::min);
+    let volume   = last_day.iter().map(|c| c.volume).sum::<f64>();
+
+    let indicators = state
+        .indicators
+        .read()
+        .await
+        .series(&query.symbol, timeframe)
+        .map(|s| s.snapshot().clone());
+    let (rsi, sma_fast, sma_slow) = indicators
+        .as_ref()
+        .map_or((None, None, None), |s| (s.rsi, s.sma_fast, s.sma_slow));
+
+    Ok(Json(json!({
+        "ok":         true,
+        "symbol":     query.symbol,
+        "time":       tick.time,
+        "bid":        tick.bid,
+        "ask":        tick.ask,
+        "change_1h":  change_pct(&last_hour),
+        "change_24h": change_pct(&last_day),
+        "high_24h":   high_24h,
+        "low_24h":    low_24h,
+        "volume":     volume,
+        "rsi_14":     rsi,
+        "ma_20":      sma_fast,
+        "ma_50":      sma_slow,
+        "timeframe":  timeframe,
+        "indicators": indicators,
+    })))
+}
+
+// ─── WebSocket ────────────────────────────────────────────────────────────────
+
+/// GET /ws/market — แท่งเทียนสดของ Symbol × Timeframe ที่ขอ
+///
+/// ```text
+/// ต่อแล้ว        → CANDLES {symbol, timeframe, candles, forming}   (ทีละ Timeframe)
+/// ทุก interval  → CANDLE_UPDATE {candle}   เฉพาะแท่งที่กำลังสร้างที่เปลี่ยน
+/// แท่งปิด       → CANDLE_CLOSED {candle}
+/// ```
+pub async fn ws_market(
+    ws: WebSocketUpgrade,
+    State(state): State<SharedState>,
+    Query(query): Query<MarketQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let timeframes = query.timeframes()?;
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, query, timeframes)))
+}
+
+async fn handle_socket(socket: WebSocket, state: SharedState, query: MarketQuery, timeframes: Vec<Timeframe>) {
+    let mut candle_rx = state.candle_tx.subscribe();
+    let (mut sender, mut receiver) = socket.split();
+    let symbol = query.symbol.clone();
+
+    info!(%symbol, ?timeframes, "🕯️ Market WebSocket client connected");
+
+    // ── History ก่อน แล้วค่อยเป็นแท่งสด ──────────────────────────────────────
+    let history: Vec<String> = {
+        let agg = state.candles.read().await;
+        timeframes
+            .iter()
+            .map(|&tf| {
+                json!({
+                    "event":     "CANDLES",
+                    "symbol":    symbol,
+                    "timeframe": tf,
+                    "candles":   agg.closed(&symbol, tf, query.limit()),
+                    "forming":   agg.forming(&symbol, tf),
+                })
+                .to_string()
+            })
+            .collect()
+    };
+    for frame in history {
+        if sender.send(Message::Text(frame)).await.is_err() {
+            return;
+        }
+    }
+
+    let interval_ms = query.interval_ms.unwrap_or(500).max(100);
+    let mut updates = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+    // (start_time, tick_count) ของแท่งที่ส่งไปล่าสุดต่อ Timeframe — ไม่เปลี่ยน = ไม่ส่งซ้ำ
+    let mut last_sent = vec![None; timeframes.len()];
+
+    'conn: loop {
+        tokio::select! {
+            result = candle_rx.recv() => {
+                match result {
+                    Ok(candle) if candle.symbol == symbol && timeframes.contains(&candle.timeframe) => {
+                        let frame = json!({ "event": "CANDLE_CLOSED", "candle": candle }).to_string();
+                        if sender.send(Message::Text(frame)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(n)) => debug!("Market WS client lagged, skipped {n} candles"),
+                    Err(RecvError::Closed)    => break,
+                }
+            }
+
+            _ = updates.tick() => {
+                let frames: Vec<String> = {
+                    let agg = state.candles.read().await;
+                    timeframes
+                        .iter()
+                        .zip(last_sent.iter_mut())
+                        .filter_map(|(&tf, last)| {
+                            let candle = agg.forming(&symbol, tf)?;
+                            let key    = Some((candle.start_time, candle.tick_count));
+                            if *last == key {
+                                return None;
+                            }
+                            *last = key;
+                            Some(json!({ "event": "CANDLE_UPDATE", "candle": candle }).to_string())
+                        })
+                        .collect()
+                };
+                for frame in frames {
+                    if sender.send(Message::Text(frame)).await.is_err() {
+                        break 'conn;
+                    }
+                }
+            }
+
+            result = receiver.next() => {
+                match result {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(Message::Ping(data))) => {
+                        let _ = sender.send(Message::Pong(data)).await;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    info!(%symbol, "🕯️ Market WebSocket client disconnected");
+}
@@ -2,6 +2,9 @@
 
 pub mod backtest;
 pub mod brain;
+pub mod history;
+pub mod market;
 pub mod monitor;
 pub mod mt5;
+pub mod mt5_stream;
 pub mod risk;
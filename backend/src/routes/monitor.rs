@@ -6,15 +6,18 @@
 //!
 //! | Method    | Path                    | Description                              |
 //! |-----------|-------------------------|------------------------------------------|
-//! | GET (WS)  | `/ws/monitor`           | WebSocket real-time event stream         |
-//! | GET       | `/api/monitor/position` | Open position ปัจจุบัน                    |
+//! | GET (WS)  | `/ws/monitor?symbol=`   | WebSocket real-time event stream         |
+//! | GET       | `/api/monitor/position?symbol=` | Open positions ปัจจุบัน          |
+//! | GET       | `/api/monitor/commands` | คำสั่งถึง EA ที่รอรับ / รอ Ack             |
+//! | GET       | `/api/monitor/streams`  | Stream ของ EA ที่ต่ออยู่ + Lag metrics      |
+//! | GET       | `/api/monitor/indicators?symbol=` | Indicator ล่าสุดต่อ Symbol × Timeframe |
 //! | GET       | `/api/monitor/history`  | Trade history ทั้งหมด                     |
-//! | GET       | `/api/monitor/stats`    | tick_count, trade_count, uptime          |
+//! | GET       | `/api/monitor/stats`    | tick_count, trade_count, candle stats    |
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
     response::IntoResponse,
     Json,
@@ -24,7 +27,7 @@ use serde_json::json;
 use std::sync::atomic::Ordering;
 use tracing::{debug, info};
 
-use crate::{events::WsEvent, state::SharedState};
+use crate::{events::WsEvent, routes::brain::SymbolFilter, state::SharedState};
 
 // ─── WebSocket Handler ────────────────────────────────────────────────────────
 
@@ -32,14 +35,16 @@ use crate::{events::WsEvent, state::SharedState};
 ///
 /// SvelteKit ต่อที่ `ws://localhost:3000/ws/monitor`
 /// ทุก WsEvent จะถูกส่งมาเป็น JSON text frame
+/// `?symbol=BTCUSD` → Snapshot แรกจะมีเฉพาะ Strategy/Position ของ Symbol นั้น
 pub async fn ws_monitor(
     ws: WebSocketUpgrade,
     State(state): State<SharedState>,
+    Query(filter): Query<SymbolFilter>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    ws.on_upgrade(|socket| handle_socket(socket, state, filter.symbol))
 }
 
-async fn handle_socket(socket: WebSocket, state: SharedState) {
+async fn handle_socket(socket: WebSocket, state: SharedState, symbol: Option<String>) {
     let mut rx = state.broadcast_tx.subscribe();
     let (mut sender, mut receiver) = socket.split();
 
@@ -47,22 +52,35 @@ async fn handle_socket(socket: WebSocket, state: SharedState) {
 
     // ── ส่ง Snapshot ปัจจุบันทันทีที่ต่อ ─────────────────────────────────────
     let snapshot = {
-        let strategy  = state.active_strategy.read().await.clone();
-        let position  = state.open_position.read().await.clone();
+        let strategies = {
+            let book = state.strategies.read().await;
+            match &symbol {
+                Some(s) => book.for_symbol(s),
+                None    => book.all(),
+            }
+        };
+        let positions = {
+            let book = state.positions.read().await;
+            match &symbol {
+                Some(s) => book.for_symbol(s),
+                None    => book.all(),
+            }
+        };
         let ticks     = state.tick_count.load(Ordering::Relaxed);
         let trades    = state.trade_count.load(Ordering::Relaxed);
 
         json!({
             "event":        "SNAPSHOT",
-            "strategy":     strategy,
-            "position":     position,
+            "symbol":       symbol,
+            "strategies":   strategies,
+            "positions":    positions,
             "tick_count":   ticks,
             "trade_count":  trades,
         })
         .to_string()
     };
 
-    if sender.send(Message::Text(snapshot.into())).await.is_err() {
+    if sender.send(Message::Text(snapshot)).await.is_err() {
         return; // Client ปิดก่อน snapshot ส่งได้
     }
 
@@ -73,7 +91,7 @@ async fn handle_socket(socket: WebSocket, state: SharedState) {
             result = rx.recv() => {
                 match result {
                     Ok(json_str) => {
-                        if sender.send(Message::Text(json_str.into())).await.is_err() {
+                        if sender.send(Message::Text(json_str)).await.is_err() {
                             break; // Client disconnect
                         }
                     }
@@ -103,14 +121,70 @@ async fn handle_socket(socket: WebSocket, state: SharedState) {
 
 // ─── REST Monitoring Endpoints ────────────────────────────────────────────────
 
-/// GET /api/monitor/position — ดู Position ที่เปิดอยู่
+/// GET /api/monitor/position — ดู Position ที่เปิดอยู่ (`?symbol=` เพื่อกรอง)
 pub async fn get_position(
     State(state): State<SharedState>,
+    Query(filter): Query<SymbolFilter>,
+) -> impl IntoResponse {
+    let positions = {
+        let book = state.positions.read().await;
+        match &filter.symbol {
+            Some(symbol) => book.for_symbol(symbol),
+            None         => book.all(),
+        }
+    };
+    Json(json!({
+        "ok":        true,
+        "count":     positions.len(),
+        "positions": positions,
+    }))
+}
+
+/// GET /api/monitor/orders — Pending Order (Limit / Stop) ที่รอ Fill อยู่
+pub async fn get_orders(
+    State(state): State<SharedState>,
+) -> impl IntoResponse {
+    let orders = state.orders.read().await.all();
+    Json(json!({
+        "ok":     true,
+        "count":  orders.len(),
+        "orders": orders,
+    }))
+}
+
+/// GET /api/monitor/commands — คำสั่งจัดการ Position ที่ยังไม่ได้ Ack จาก EA
+pub async fn get_commands(
+    State(state): State<SharedState>,
 ) -> impl IntoResponse {
-    let position = state.open_position.read().await;
+    let commands = state.commands.read().await.all();
     Json(json!({
         "ok":       true,
-        "position": *position,
+        "count":    commands.len(),
+        "commands": commands,
+    }))
+}
+
+/// GET /api/monitor/streams — Stream connection ของ EA (`/ws/mt5`, TCP) พร้อม Lag / Throughput
+pub async fn get_streams(
+    State(state): State<SharedState>,
+) -> impl IntoResponse {
+    let streams: Vec<_> = state.streams.read().await.values().cloned().collect();
+    Json(json!({
+        "ok":      true,
+        "count":   streams.len(),
+        "streams": streams,
+    }))
+}
+
+/// GET /api/monitor/indicators — ค่า Indicator ของแท่งที่ปิดล่าสุดต่อ Timeframe (`?symbol=` เพื่อกรอง)
+pub async fn get_indicators(
+    State(state): State<SharedState>,
+    Query(filter): Query<SymbolFilter>,
+) -> impl IntoResponse {
+    let indicators = state.indicators.read().await.snapshots(filter.symbol.as_deref());
+    Json(json!({
+        "ok":         true,
+        "indicators": indicators,
     }))
 }
 
@@ -132,8 +206,15 @@ pub async fn get_stats(
 ) -> impl IntoResponse {
     let tick_count   = state.tick_count.load(Ordering::Relaxed);
     let trade_count  = state.trade_count.load(Ordering::Relaxed);
-    let has_strategy = state.active_strategy.read().await.is_some();
-    let has_position = state.open_position.read().await.is_some();
+    let (active_strategies, has_strategy) = {
+        let book = state.strategies.read().await;
+        (book.len(), !book.is_empty())
+    };
+    let (open_positions, has_position) = {
+        let book = state.positions.read().await;
+        (book.len(), !book.is_empty())
+    };
+    let candles = state.candles.read().await.stats();
 
     // Broadcast stats event ไปด้วยทุกครั้งที่มีคน poll
     state.broadcast(&WsEvent::ServerStats {
@@ -141,13 +222,19 @@ pub async fn get_stats(
         trade_count,
         has_position,
         has_strategy,
+        open_positions,
+        active_strategies,
     });
 
     Json(json!({
-        "ok":           true,
-        "tick_count":   tick_count,
-        "trade_count":  trade_count,
-        "has_strategy": has_strategy,
-        "has_position": has_position,
+        "ok":                true,
+        "tick_count":        tick_count,
+        "trade_count":       trade_count,
+        "has_strategy":      has_strategy,
+        "has_position":      has_position,
+        "active_strategies": active_strategies,
+        "open_positions":    open_positions,
+        "candles":           candles,
+        "tick_recorder":     state.tick_recorder.stats(),
     }))
 }
@@ -3,80 +3,110 @@
 //! Axum route handlers สำหรับ MetaTrader 5 interface (Reflex Loop)
 
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::atomic::Ordering;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::{
+    broker::{BrokerEvent, BrokerKind, OrderRequest, PendingFill, PositionClose},
     engine::{
-        executor::{build_order, fire_trade},
+        commands::{apply_ack, dispatch, expire_commands, Dispatch},
+        executor::{build_order, fire_trade, place_pending},
+        orders::{
+            apply_order_fill, cancel_all, cancel_for_strategies, cancel_for_symbol, cancel_reason,
+            expire_orders,
+        },
+        reconcile::{reconcile_once, RECONCILED},
         reflex::{evaluate_tick, TradeSignal},
+        sizing::size_position,
     },
+    db::risk_event,
     error::AppError,
     events::WsEvent,
     models::{
+        command::{CommandAck, CommandKind},
         position::{OpenPosition, TradeRecord, TradeStatus},
-        Direction, TickData,
+        AccountInfo, ActiveStrategy, Direction, OrderKind, PendingOrder, TickData,
     },
     risk::RiskDecision,
     state::SharedState,
+    symbols::SymbolSpec,
 };
 
 // ─── POST /api/mt5/tick ───────────────────────────────────────────────────────
 
 /// **Reflex Loop entry point** — รับ Tick จาก MT5, ประเมิน, ยิง Trade (ถ้าถึงเวลา)
+///
+/// HTTP ทีละ Tick — ช่องทางหลักคือ Stream (`routes::mt5_stream`) ส่วนนี้เป็น Fallback
 pub async fn handle_tick(
     State(state): State<SharedState>,
     Json(tick): Json<TickData>,
 ) -> Result<impl IntoResponse, AppError> {
+    process_tick(&state, tick).await
+}
+
+/// ประมวลผล Tick หนึ่งตัว (ใช้ทั้ง HTTP และ Stream) — Response = สิ่งที่ EA ต้องทำต่อ
+pub(crate) async fn process_tick(
+    state: &SharedState,
+    tick:  TickData,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    // นาฬิกาจำลอง (Backtest) เดินตามเวลาของ Tick — นาฬิกาจริงไม่มีผล
+    state.clock.advance_to(tick.time);
+    // Tick Store: บันทึกทุก Tick ตามลำดับที่ Engine เห็น (Backtest = no-op)
+    state.tick_recorder.record(&tick);
+
+    // ── 0. Broker จำลอง: Tick นี้อาจ Fill Pending Order / แตะ SL / TP ─────────
+    //    → ผ่าน Fill / Close path เดียวกับ Callback ของ EA
+    for event in state.broker.on_tick(&tick).await {
+        match event {
+            BrokerEvent::Filled(fill)  => { apply_order_fill(state, &fill).await; }
+            BrokerEvent::Closed(close) => { apply_position_close(state, &close).await; }
+        }
+    }
+    // Pending Order ที่ Strategy หมดอายุแล้ว / คำสั่งถึง EA ที่ไม่มี Ack
+    expire_orders(state, &tick.symbol).await;
+    expire_commands(state).await;
+    // EA ต้องไม่ทำตามคำสั่งเทรดของ Broker จำลองบนบัญชีจริง
+    let simulated = state.broker.kind().is_simulated();
+
     // ── 1. Reflex Engine ──────────────────────────────────────────────────────
-    let signal = evaluate_tick(&tick, &state).await?;
+    let signal = evaluate_tick(&tick, state).await?;
 
     match signal {
-        // ── Modify SL (Break-Even) ────────────────────────────────────────────
+        // ── Modify SL (Break-Even / Trailing Stop) ────────────────────────────
         TradeSignal::ModifySL { mt5_ticket, new_sl, reason } => {
-            // อัปเดต state
-            if let Some(pos) = state.open_position.write().await.as_mut() {
-                if pos.mt5_ticket == Some(mt5_ticket) {
-                    pos.sl_moved_to_be = true;
-                    pos.stop_loss = new_sl;
-                }
-            }
-
-            // ส่งคำสั่งกลับไป MT5 (MT5 EA จะอ่านค่าจาก response นี้)
-            Ok((
-                StatusCode::OK,
-                Json(json!({
-                    "ok":         true,
-                    "action":     "MODIFY_POSITION",
-                    "mt5_ticket": mt5_ticket,
-                    "new_sl":     new_sl,
-                    "reason":     reason,
-                })),
-            ))
+            // ส่ง TP เดิมไปด้วย — MT5 ตั้ง SL / TP พร้อมกันเสมอ
+            let take_profit = state
+                .positions
+                .read()
+                .await
+                .get(mt5_ticket)
+                .map(|p| p.take_profit)
+                .unwrap_or_default();
+            let kind = CommandKind::ModifyPosition { ticket: mt5_ticket, sl: new_sl, tp: take_profit };
+            Ok(position_command(state, &tick.symbol, "MODIFY_POSITION", kind, &reason, json!({ "new_sl": new_sl })).await)
         }
 
         // ── Close Position ──────────────────────────────────────────────────────
         TradeSignal::ClosePosition { mt5_ticket, reason } => {
-            // เราบอก MT5 EA ผ่าน HTTP response นี้ ให้มันไปจัดการ OrderClose
-            Ok((
-                StatusCode::OK,
-                Json(json!({
-                    "ok":         true,
-                    "action":     "CLOSE_POSITION",
-                    "mt5_ticket": mt5_ticket,
-                    "reason":     reason,
-                })),
-            ))
+            let kind = CommandKind::ClosePosition { ticket: mt5_ticket, volume: None };
+            Ok(position_command(state, &tick.symbol, "CLOSE_POSITION", kind, &reason, json!({})).await)
+        }
+
+        // ── Close Partial (Scale-out) ─────────────────────────────────────────
+        TradeSignal::ClosePartial { mt5_ticket, volume, reason } => {
+            let kind = CommandKind::ClosePosition { ticket: mt5_ticket, volume: Some(volume) };
+            Ok(position_command(state, &tick.symbol, "CLOSE_PARTIAL", kind, &reason, json!({ "volume": volume })).await)
         }
 
         // ── No Action — Fast path (ส่วนใหญ่จะผ่านทางนี้) ─────────────────────
-        TradeSignal::NoAction => Ok((
+        TradeSignal::NoAction(hold) => Ok((
             StatusCode::OK,
             Json(json!({
                 "ok":     true,
@@ -84,24 +114,51 @@ pub async fn handle_tick(
                 "symbol": tick.symbol,
                 "bid":    tick.bid,
                 "ask":    tick.ask,
+                "reason": hold.reason(),
+                "layer":  hold.layer(),
             })),
         )),
 
+        // ── Pending Order ที่ขอบ Zone ─────────────────────────────────────────
+        TradeSignal::PlaceOrder { strategy, kind, price } => {
+            if let Some(unresolved) = unknown_trade_open(state, &strategy.symbol).await {
+                return Ok(unresolved);
+            }
+            place_pending_order(state, &strategy, kind, price, simulated).await
+        }
+
         // ── Trade Triggered ───────────────────────────────────────────────────
         TradeSignal::Trigger(strategy) => {
-            // ── 2. Risk Check ────────────────────────────────────────────────────────────
-            match state.risk.pre_trade_check().await {
-                RiskDecision::Blocked(reason) => {
-                    return Ok((
-                        StatusCode::OK,
-                        Json(json!({
-                            "ok":     false,
-                            "action": "RISK_BLOCKED",
-                            "reason": reason,
-                        })),
-                    ));
-                }
-                RiskDecision::Approved => {}
+            // ── 2. Claim — ถอด Strategy ออกจาก Book ก่อน await / I/O ใดๆ ──────────
+            //    Tick ที่เข้ามาพร้อมกัน (HTTP Fallback + Stream) ได้ Some แค่ตัวเดียว
+            //    (Strategy ของ Symbol อื่นยัง Armed อยู่ตามเดิม)
+            let Some(strategy) = state.strategies.write().await.remove(strategy.strategy_id) else {
+                return Ok((
+                    StatusCode::OK,
+                    Json(json!({
+                        "ok":          false,
+                        "action":      "STRATEGY_CLAIMED",
+                        "strategy_id": strategy.strategy_id,
+                    })),
+                ));
+            };
+
+            // ── 2a. Trade ที่ยังไม่รู้ผล (Timeout) ของ Symbol นี้ ────────────────────────
+            if let Some(unresolved) = unknown_trade_open(state, &strategy.symbol).await {
+                return Ok(release_claim(state, strategy, unresolved).await);
+            }
+
+            // ── 2b. Double-Entry — Strategy อื่นของ Symbol เดียวกันอาจยิงไปแล้ว ──────
+            if state.has_open_position_for(&strategy.symbol).await {
+                let open = (
+                    StatusCode::OK,
+                    Json(json!({
+                        "ok":     false,
+                        "action": "POSITION_OPEN",
+                        "symbol": strategy.symbol,
+                    })),
+                );
+                return Ok(release_claim(state, strategy, open).await);
             }
 
             // ── 3. Entry price ────────────────────────────────────────────────────────────
@@ -111,52 +168,79 @@ pub async fn handle_tick(
                 Direction::NoTrade => tick.effective_mid(),
             };
 
-            // ── 3. Build MT5 order ────────────────────────────────────────────
-            let order = build_order(
+            // ── 3b. Position Sizing — Volume จากความเสี่ยงต่อ Trade ไม่ใช่จาก AI ───
+            //     ปฏิเสธ = Disarm (Strategy ถูก Claim ออกไปแล้ว ไม่ต้องคืน)
+            let Some(spec) = state.symbol_spec(&strategy.symbol).await else {
+                let reason = format!("No SymbolSpec for {}", strategy.symbol);
+                return Ok(sizing_rejected(state, &strategy, reason).await);
+            };
+            let equity = state.sizing_equity().await;
+            let sizing = match size_position(&state.sizing_config, &spec, equity, &strategy, entry_price) {
+                Ok(decision) => decision,
+                Err(reason)  => return Ok(sizing_rejected(state, &strategy, reason).await),
+            };
+            let volume = sizing.volume;
+
+            // ── 3c. Risk Check — หลัง Guard ที่ปฏิเสธได้เอง (Race / Sizing ไม่ถูกนับ) ──
+            if let Some(blocked) = risk_blocked(state).await {
+                return Ok(release_claim(state, strategy, blocked).await);
+            }
+
+            // ── 3d. สร้าง TradeRecord (สถานะ Pending) — trade_id คือ Client order ID ──
+            let mut record = TradeRecord::from_strategy(&strategy, entry_price);
+            record.lot_size = volume;
+            record.sizing   = Some(sizing);
+            record.fired_at = state.now();
+
+            // ── 4. Build MT5 order ────────────────────────────────────────────
+            //     SL / TP จาก AI อาจละเอียดเกินทศนิยมของ Symbol → MT5 ปฏิเสธ
+            let order = match build_order(
                 &strategy.symbol,
                 strategy.direction,
                 entry_price,
-                strategy.stop_loss,
-                strategy.take_profit,
-                strategy.lot_size,
-                strategy.strategy_id,
-            )?;
-
-            // ── 4. สร้าง TradeRecord (สถานะ Pending) ──────────────────────────
-            let mut record = TradeRecord::from_strategy(&strategy, entry_price);
+                spec.normalize_price(strategy.stop_loss),
+                spec.normalize_price(strategy.take_profit),
+                volume,
+                record.trade_id,
+            ) {
+                Ok(order) => order,
+                Err(e) => {
+                    state.strategies.write().await.insert(strategy);
+                    return Err(e);
+                }
+            };
 
-            // ── 5. Broadcast "กำลังยิง Trade" ─────────────────────────────────
+            // ── 5. Persist + Broadcast "กำลังยิง Trade" ───────────────────────
+            //    บันทึกสถานะ Pending ก่อน I/O — ถ้า Process ตายระหว่างรอ MT5 ยังตามรอยได้
+            state.db.trade(&record);
             state.broadcast(&WsEvent::TradeFiring {
                 record: Box::new(record.clone()),
             });
 
-            // ── 6. ล้าง ActiveStrategy ก่อน I/O ──────────────────────────────
-            //    ป้องกัน Tick ที่เข้ามาระหว่างรอ MT5ตอบ trigger ซ้ำ
-            {
-                let mut guard = state.active_strategy.write().await;
-                *guard = None;
-            }
-
-            // ── 7. ยิง Order จริงไป MT5 ───────────────────────────────────────
-            let mt5_url = std::env::var("MT5_BASE_URL")
-                .unwrap_or_else(|_| "http://localhost:8081".to_string());
-
-            match fire_trade(&order, &state.http_client, &mt5_url).await {
-                Ok(mt5_resp) => {
-                    // ── 7a. SUCCESS ───────────────────────────────────────────
-                    let ticket = mt5_resp.order;
+            // ── 6. ยิง Order จริงผ่าน Broker — นับ Trade ของวันตรงนี้เท่านั้น ───────
+            state.risk.commit_trade().await;
+            match fire_trade(&order, state.broker.as_ref(), &state.execution_config).await {
+                Ok(fill) => {
+                    // ── 6a. SUCCESS ───────────────────────────────────────────
+                    //     ราคาที่ได้จริงอาจต่างจาก Tick (Slippage / Latency)
+                    let ticket = fill.ticket;
+                    record.entry_price    = fill.price;
                     record.status         = TradeStatus::Confirmed;
-                    record.mt5_ticket     = ticket;
-                    record.status_message = mt5_resp.comment
-                        .unwrap_or_else(|| "Request completed".to_string());
+                    record.mt5_ticket     = Some(ticket);
+                    record.status_message = fill.comment;
 
                     // เปิด Position ใน State
-                    let mut position = OpenPosition::from_strategy(&strategy, entry_price);
-                    position.mt5_ticket = ticket;
+                    let mut position = OpenPosition::from_strategy(&strategy, fill.price);
+                    position.mt5_ticket       = Some(ticket);
+                    position.lot_size         = volume;
+                    position.initial_lot_size = volume;
+                    position.opened_at        = state.now();
 
-                    state.set_open_position(Some(position.clone())).await;
+                    state.open_position(ticket, position.clone()).await;
                     state.push_trade_record(record.clone()).await;
                     state.risk.record_success().await;  // ✅ Reset consecutive failures
+                    // Pending Order ของ Strategy อื่นใน Symbol นี้ห้าม Fill ซ้อน
+                    cancel_for_symbol(state, &strategy.symbol, None, cancel_reason::DOUBLE_ENTRY).await;
 
                     // Broadcast
                     state.broadcast(&WsEvent::PositionOpened {
@@ -172,109 +256,703 @@ pub async fn handle_tick(
                             "trade_id":    record.trade_id,
                             "symbol":      strategy.symbol,
                             "direction":   strategy.direction,
-                            "entry_price": entry_price,
+                            "entry_price": fill.price,
                             "tp":          strategy.take_profit,
                             "sl":          strategy.stop_loss,
+                            "lot_size":    volume,
                             "mt5_ticket":  ticket,
+                            "simulated":   simulated,
                         })),
                     ))
                 }
 
                 Err(e) => {
-                    // ── 7b. FAILED ────────────────────────────────────────────
+                    // ── 6b. FAILED / UNKNOWN ──────────────────────────────────
                     error!(error = %e, "Trade execution failed");
+                    record_execution_error(state, record, &e).await;
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+/// วาง Limit / Stop ที่ขอบ Zone — Strategy ยัง Armed อยู่จนกว่า Order จะ Fill
+/// (ล้าง / แทนที่ Strategy = ยกเลิก Order, ดู `engine::orders`)
+async fn place_pending_order(
+    state:     &SharedState,
+    strategy:  &ActiveStrategy,
+    kind:      OrderKind,
+    price:     f64,
+    simulated: bool,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    // จองก่อน I/O — Tick ที่เข้ามาระหว่างรอ Broker ต้องไม่วางซ้ำ
+    if !state.orders.write().await.reserve(strategy.strategy_id) {
+        return Ok((
+            StatusCode::OK,
+            Json(json!({
+                "ok":          false,
+                "action":      "ORDER_PENDING",
+                "strategy_id": strategy.strategy_id,
+            })),
+        ));
+    }
 
-                    record.status         = TradeStatus::Failed;
-                    record.status_message = e.to_string();
+    // ── Sizing ที่ราคาของ Order (ไม่ใช่ราคาตลาดตอนนี้) ─────────────────────────
+    let Some(spec) = state.symbol_spec(&strategy.symbol).await else {
+        state.orders.write().await.release(strategy.strategy_id);
+        let reason = format!("No SymbolSpec for {}", strategy.symbol);
+        return Ok(sizing_rejected(state, strategy, reason).await);
+    };
+    let equity = state.sizing_equity().await;
+    let sizing = match size_position(&state.sizing_config, &spec, equity, strategy, price) {
+        Ok(decision) => decision,
+        Err(reason)  => {
+            state.orders.write().await.release(strategy.strategy_id);
+            return Ok(sizing_rejected(state, strategy, reason).await);
+        }
+    };
+    let volume = sizing.volume;
 
-                    state.push_trade_record(record.clone()).await;
-                    state.risk.record_failure().await;  // ❌ Increment consecutive failures
-                    state.broadcast(&WsEvent::TradeFailed {
-                        record: Box::new(record),
-                    });
+    // ── Risk Check หลัง Sizing — Strategy ที่ Sizing ปฏิเสธไม่ผ่าน Risk ─────────
+    if let Some(blocked) = risk_blocked(state).await {
+        state.orders.write().await.release(strategy.strategy_id);
+        return Ok(blocked);
+    }
 
-                    Err(e)
-                }
+    let mut record = TradeRecord::from_strategy(strategy, price);
+    record.lot_size      = volume;
+    record.sizing        = Some(sizing);
+    record.order_kind    = kind;
+    record.filled_volume = Some(0.0);
+    record.fired_at      = state.now();
+
+    let order = match build_order(
+        &strategy.symbol,
+        strategy.direction,
+        price,
+        spec.normalize_price(strategy.stop_loss),
+        spec.normalize_price(strategy.take_profit),
+        volume,
+        record.trade_id,
+    ) {
+        Ok(order) => OrderRequest { kind, expires_at: strategy.expires_at, ..order },
+        Err(e) => {
+            state.orders.write().await.release(strategy.strategy_id);
+            return Err(e);
+        }
+    };
+    state.db.trade(&record);
+    state.risk.commit_trade().await;
+
+    match place_pending(&order, state.broker.as_ref(), &state.execution_config).await {
+        Ok(order_ticket) => {
+            record.status         = TradeStatus::Placed;
+            record.order_ticket   = Some(order_ticket);
+            record.status_message = format!("{kind:?} order placed @ {price}");
+            state.push_trade_record(record.clone()).await;
+            state.risk.record_success().await;
+
+            let pending = PendingOrder {
+                order_ticket,
+                trade_id:        record.trade_id,
+                kind,
+                price,
+                volume,
+                filled_volume:   0.0,
+                position_ticket: None,
+                placed_at:       state.now(),
+                strategy:        strategy.clone(),
+            };
+            state.orders.write().await.insert(pending.clone());
+            state.broadcast(&WsEvent::OrderPlaced {
+                order: Box::new(pending),
+            });
+
+            // Strategy ถูกล้าง / แทนที่ระหว่างรอ Broker → ไม่มีใครยกเลิก Order นี้ให้แล้ว
+            let armed = state
+                .strategies_for(&strategy.symbol)
+                .await
+                .iter()
+                .any(|s| s.strategy_id == strategy.strategy_id);
+            if !armed {
+                cancel_for_strategies(state, &[strategy.strategy_id], cancel_reason::STRATEGY_CLEARED).await;
             }
+
+            Ok((
+                StatusCode::OK,
+                Json(json!({
+                    "ok":           true,
+                    "action":       "ORDER_PLACED",
+                    "strategy_id":  strategy.strategy_id,
+                    "trade_id":     record.trade_id,
+                    "symbol":       strategy.symbol,
+                    "direction":    strategy.direction,
+                    "order_kind":   kind,
+                    "price":        price,
+                    "tp":           strategy.take_profit,
+                    "sl":           strategy.stop_loss,
+                    "lot_size":     volume,
+                    "order_ticket": order_ticket,
+                    "expires_at":   strategy.expires_at,
+                    "simulated":    simulated,
+                })),
+            ))
+        }
+
+        Err(e) => {
+            // วางไม่ได้ → Disarm เหมือน Market Order ที่ยิงไม่ผ่าน (ไม่วนวางใหม่ทุก Tick)
+            state.orders.write().await.release(strategy.strategy_id);
+            state.strategies.write().await.remove(strategy.strategy_id);
+            state.broadcast(&WsEvent::StrategyCleared {
+                symbol:      Some(strategy.symbol.clone()),
+                strategy_id: Some(strategy.strategy_id),
+            });
+
+            record_execution_error(state, record, &e).await;
+            Err(e)
         }
     }
 }
 
+/// บันทึก Trade ที่ยิงไม่สำเร็จ
+///
+/// Venue ปฏิเสธ / ไม่เคยได้รับ → `Failed` + นับ Consecutive failure (Cooldown)
+/// ไม่รู้ผล (Timeout) → `Unknown`: MT5 อาจ Fill แล้ว จึงไม่นับเป็น Failure —
+/// Reconciler ถามผลด้วย client_id แล้วปิดสถานะให้ (ดู `engine::reconcile`)
+async fn record_execution_error(state: &SharedState, mut record: TradeRecord, error: &AppError) {
+    record.status_message = error.to_string();
+
+    if matches!(error, AppError::ExecutionUnknown(_)) {
+        warn!(trade_id = %record.trade_id, symbol = %record.symbol, "⏳ Trade outcome unknown — awaiting reconciliation");
+        record.status = TradeStatus::Unknown;
+        state.push_trade_record(record.clone()).await;
+        state.broadcast(&WsEvent::TradeUnknown {
+            record: Box::new(record),
+        });
+        return;
+    }
+
+    record.status = TradeStatus::Failed;
+    state.push_trade_record(record.clone()).await;
+    state.risk.record_failure().await;  // ❌ Increment consecutive failures
+    state.db.risk_event(
+        risk_event::COOLDOWN,
+        format!("{} {}: {}", record.symbol, record.trade_id, record.status_message),
+    );
+    state.broadcast(&WsEvent::TradeFailed {
+        record: Box::new(record),
+    });
+}
+
+/// Risk Manager ไม่อนุญาตให้เข้า Trade ใหม่ — Auto-Kill ยกเลิก Pending Order ทั้งหมดด้วย
+/// (ไม่นับ Trade — `commit_trade` ก่อนส่ง Order)
+async fn risk_blocked(state: &SharedState) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    let reason = match state.risk.pre_trade_check().await {
+        RiskDecision::Approved => return None,
+        RiskDecision::Blocked(reason) => reason,
+        RiskDecision::Killed(reason) => {
+            state.risk_killed(risk_event::AUTO_KILL, reason.clone());
+            cancel_all(state, cancel_reason::KILL_SWITCH).await;
+            reason
+        }
+    };
+
+    Some((
+        StatusCode::OK,
+        Json(json!({
+            "ok":     false,
+            "action": "RISK_BLOCKED",
+            "reason": reason,
+        })),
+    ))
+}
+
+/// Entry ถูกปฏิเสธก่อนยิง Order — คืน Strategy ที่ Claim ไว้กลับเข้า Book (ยัง Armed อยู่)
+async fn release_claim(
+    state:    &SharedState,
+    strategy: ActiveStrategy,
+    response: (StatusCode, Json<serde_json::Value>),
+) -> (StatusCode, Json<serde_json::Value>) {
+    state.strategies.write().await.insert(strategy);
+    response
+}
+
+/// Trade ของ Symbol นี้ยังไม่รู้ผล (Timeout) — อาจมี Position ที่ Book ยังไม่เห็น
+/// → ห้ามเข้า Trade ใหม่จนกว่า Reconciler จะยืนยัน (Double-Entry Protection)
+async fn unknown_trade_open(state: &SharedState, symbol: &str) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    let trade_id = state.unknown_trade_for(symbol).await?;
+
+    Some((
+        StatusCode::OK,
+        Json(json!({
+            "ok":       false,
+            "action":   "TRADE_UNKNOWN",
+            "symbol":   symbol,
+            "trade_id": trade_id,
+        })),
+    ))
+}
+
+/// ส่งคำสั่งจัดการ Position ผ่าน `engine::commands` — Response เป็นข้อมูลให้ EA / Log เท่านั้น
+///
+/// `BROKER=mt5` คำสั่งเข้าคิว (`queued: true`) — EA รับทาง `GET /api/mt5/commands` ไม่ใช่จาก Response นี้
+async fn position_command(
+    state:  &SharedState,
+    symbol: &str,
+    action: &'static str,
+    kind:   CommandKind,
+    reason: &str,
+    extra:  serde_json::Value,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let ticket = kind.ticket();
+    let command_id = match dispatch(state, symbol, kind, reason).await {
+        Ok(Dispatch::Queued(command_id)) => Some(command_id),
+        // Broker จำลองปิดเสร็จแล้ว → ปิด Book ทันที
+        Ok(Dispatch::Applied(Some(close))) => {
+            apply_position_close(state, &close).await;
+            None
+        }
+        Ok(Dispatch::Applied(None)) => None,
+        // Broker ไม่รับ = Book ไม่เปลี่ยน, Tick ถัดไปจะลองใหม่
+        Err(e) => return broker_rejected(action, ticket, e),
+    };
+
+    let mut body = json!({
+        "ok":         true,
+        "action":     action,
+        "mt5_ticket": ticket,
+        "reason":     reason,
+        "command_id": command_id,
+        "queued":     command_id.is_some(),
+        "simulated":  state.broker.kind().is_simulated(),
+    });
+    if let (Some(body), Some(extra)) = (body.as_object_mut(), extra.as_object()) {
+        body.extend(extra.clone());
+    }
+    (StatusCode::OK, Json(body))
+}
+
+/// Broker ไม่รับคำสั่งจัดการ Position — Book ไม่เปลี่ยน
+fn broker_rejected(
+    action: &'static str,
+    ticket: u64,
+    error:  AppError,
+) -> (StatusCode, Json<serde_json::Value>) {
+    warn!(action, ticket, error = %error, "🏦 Broker rejected position command");
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "ok":         false,
+            "action":     "BROKER_REJECTED",
+            "command":    action,
+            "mt5_ticket": ticket,
+            "reason":     error.to_string(),
+        })),
+    )
+}
+
+/// Strategy นี้ยิงไม่ได้ด้วย SL / Spec ปัจจุบัน → ถอดออก ไม่ให้ Trigger ซ้ำทุก Tick
+async fn sizing_rejected(
+    state:    &SharedState,
+    strategy: &ActiveStrategy,
+    reason:   String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    warn!(symbol = %strategy.symbol, %reason, "📏 Sizing rejected — strategy disarmed");
+    state.strategies.write().await.remove(strategy.strategy_id);
+    state.broadcast(&WsEvent::StrategyCleared {
+        symbol:      Some(strategy.symbol.clone()),
+        strategy_id: Some(strategy.strategy_id),
+    });
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "ok":          false,
+            "action":      "SIZING_REJECTED",
+            "strategy_id": strategy.strategy_id,
+            "reason":      reason,
+        })),
+    )
+}
+
 // ─── POST /api/mt5/position-close ────────────────────────────────────────────
 //
 // MT5 EA เรียก endpoint นี้เมื่อ Position ถูกปิด (TP / SL / Manual)
-// ทำให้ open_position = None → Double-Entry Protection รีเซ็ต → พร้อม Trade ใหม่
-
-#[derive(serde::Deserialize)]
-pub struct PositionClosePayload {
-    pub mt5_ticket:   Option<u64>,
-    pub symbol:       String,
-    pub close_price:  f64,
-    pub profit_pips:  f64,
-    /// "TP" | "SL" | "MANUAL"
-    pub close_reason: String,
-}
+// ลบ Position ออกจาก Book → Double-Entry Protection ของ Symbol นั้นรีเซ็ต → พร้อม Trade ใหม่
+// ส่ง `volume` น้อยกว่าที่เปิด = ปิดบางส่วน (Scale-out) — Position ยังอยู่ด้วย Volume ที่เหลือ
 
 pub async fn handle_position_close(
     State(state): State<SharedState>,
-    Json(payload): Json<PositionClosePayload>,
+    Json(payload): Json<PositionClose>,
 ) -> impl IntoResponse {
-    // ดึง open_position ก่อน clear
-    let current_pos = {
-        let guard = state.open_position.read().await;
-        guard.clone()
+    match apply_position_close(&state, &payload).await {
+        Some((pos, profit_pips)) => Json(json!({
+            "ok":           true,
+            "message":      "Position closed",
+            "symbol":       pos.symbol,
+            "mt5_ticket":   pos.mt5_ticket,
+            "lot_size":     pos.lot_size,
+            "close_price":  payload.close_price,
+            "profit_pips":  profit_pips,
+            "profit":       payload.profit,
+            "close_reason": payload.close_reason,
+        })),
+        None => Json(json!({
+            "ok":      false,
+            "message": "No open position to close",
+        })),
+    }
+}
+
+/// ปิด Position ใน Book — ใช้ทั้ง Callback ของ EA และ Position ที่ Broker จำลองปิดเอง
+///
+/// คืน Position ที่ถูกปิดพร้อม Pips จริง หรือ None ถ้าไม่พบ Position ที่ตรงกัน
+pub(crate) async fn apply_position_close(
+    state:   &SharedState,
+    payload: &PositionClose,
+) -> Option<(OpenPosition, f64)> {
+    // ผลของ Scale-out ที่ Engine สั่ง → ขั้นนั้นทำแล้วตั้งแต่ตรงนี้ ไม่ต้องรอ Ack (Ack อาจหาย)
+    let settled = match (payload.mt5_ticket, payload.volume) {
+        (Some(ticket), Some(_)) => state.commands.write().await.settle_partial_close(ticket),
+        _                       => None,
+    };
+
+    // หา Position ตาม Ticket — ถ้า EA ไม่ส่ง Ticket มา ใช้ Position แรกของ Symbol
+    let (current_pos, partial) = {
+        let mut positions = state.positions.write().await;
+        let ticket = payload.mt5_ticket.or_else(|| {
+            positions
+                .for_symbol(&payload.symbol)
+                .first()
+                .and_then(|p| p.mt5_ticket)
+        });
+
+        // ปิดบางส่วน (Scale-out) → ลด Volume แต่ Position ยังอยู่ใน Book
+        let remaining = match (ticket.and_then(|t| positions.get_mut(t)), payload.volume) {
+            (Some(pos), Some(volume)) if volume < pos.lot_size - 1e-9 => {
+                pos.lot_size = ((pos.lot_size - volume) * 1e8).round() / 1e8;
+                if let Some(command) = &settled {
+                    pos.scale_outs_done += 1;
+                    tracing::info!(command_id = %command.command_id, ticket = ?pos.mt5_ticket, "📨 Scale-out settled by close result");
+                }
+                state.db.position(pos);
+                Some(pos.clone())
+            }
+            _ => None,
+        };
+        match remaining {
+            Some(pos) => (Some(pos), true),
+            None      => (ticket.and_then(|t| positions.close(t)), false),
+        }
+    };
+
+    let Some(pos) = current_pos else {
+        if !settle_reconciled_close(state, payload).await {
+            tracing::warn!(
+                symbol = %payload.symbol,
+                ticket = ?payload.mt5_ticket,
+                "position-close called but no matching open position found"
+            );
+        }
+        return None;
     };
 
-    if let Some(pos) = current_pos {
-        // 1. Clear open position → Reflex Loop พร้อม Trade ใหม่
-        state.set_open_position(None).await;
+    // 1. Position ถูกลบออกจาก Book แล้ว → Reflex Loop พร้อม Trade Symbol นี้ใหม่
+    //    (ปิดบางส่วน: Position ยังอยู่ — Volume ลดลง)
 
-        // 2. อัปเดต TradeRecord ใน History ด้วยข้อมูล Close
+    // Pips จริงตาม SymbolSpec (ไม่ใช่ส่วนต่างราคาดิบ)
+    let profit_pips = match state.symbol_spec(&pos.symbol).await {
+        Some(spec) => spec.pips(pos.favourable_move(payload.close_price)),
+        None       => payload.profit_pips.unwrap_or_default(),
+    };
+
+    // 2. อัปเดต TradeRecord ใน History ด้วยข้อมูล Close
+    {
+        let mut history = state.trade_history.write().await;
+        if let Some(record) = history.iter_mut()
+            .rev()
+            .find(|r| r.mt5_ticket == pos.mt5_ticket && r.closed_at.is_none())
         {
-            let mut history = state.trade_history.write().await;
-            if let Some(record) = history.iter_mut()
-                .find(|r| r.mt5_ticket == payload.mt5_ticket || r.symbol == payload.symbol)
-            {
+            // กำไรของทุกขาที่ปิดไปแล้ว (Scale-out) รวมเข้า Trade เดียว
+            record.profit = match (record.profit, payload.profit) {
+                (Some(realised), Some(profit)) => Some(realised + profit),
+                (realised, profit)             => profit.or(realised),
+            };
+            if !partial {
                 record.close_price  = Some(payload.close_price);
-                record.profit_pips  = Some(payload.profit_pips);
+                record.profit_pips  = Some(profit_pips);
                 record.close_reason = Some(payload.close_reason.clone());
-                record.closed_at    = Some(chrono::Utc::now());
+                record.closed_at    = Some(state.now());
             }
+            state.db.trade(record);
         }
+    }
 
-        // 3. Broadcast → Dashboard อัปเดต Real-time
+    // 3. Broadcast → Dashboard อัปเดต Real-time
+    if partial {
+        state.broadcast(&WsEvent::PositionPartiallyClosed {
+            position:     Box::new(pos.clone()),
+            volume:       payload.volume.unwrap_or_default(),
+            close_price:  payload.close_price,
+            profit_pips,
+            profit:       payload.profit,
+            close_reason: payload.close_reason.clone(),
+        });
+    } else {
+        if let Some(ticket) = pos.mt5_ticket {
+            state.db.position_closed(ticket);
+        }
         state.broadcast(&WsEvent::PositionClosed {
             position_id:  pos.position_id,
+            mt5_ticket:   pos.mt5_ticket,
             symbol:       pos.symbol.clone(),
             direction:    format!("{:?}", pos.direction).to_uppercase(),
             close_price:  payload.close_price,
-            profit_pips:  payload.profit_pips,
+            profit_pips,
+            profit:       payload.profit,
             close_reason: payload.close_reason.clone(),
         });
+    }
 
-        tracing::info!(
-            symbol       = %pos.symbol,
-            close_price  = payload.close_price,
-            profit_pips  = payload.profit_pips,
-            close_reason = %payload.close_reason,
-            "✅ Position closed — Reflex Loop re-armed"
-        );
-
-        Json(serde_json::json!({
-            "ok":          true,
-            "message":     "Position closed",
-            "symbol":      pos.symbol,
-            "close_price": payload.close_price,
-            "profit_pips": payload.profit_pips,
-            "close_reason": payload.close_reason,
-        }))
-    } else {
-        tracing::warn!("position-close called but no open position found");
-        Json(serde_json::json!({
-            "ok":     false,
-            "message": "No open position to close",
-        }))
+    // 4. Realized P&L → Risk Manager (อาจชน Daily/Weekly Loss หรือ Drawdown)
+    record_realized(state, payload.profit).await;
+
+    tracing::info!(
+        symbol       = %pos.symbol,
+        ticket       = ?pos.mt5_ticket,
+        close_price  = payload.close_price,
+        profit_pips,
+        profit       = ?payload.profit,
+        close_reason = %payload.close_reason,
+        partial,
+        "✅ Position closed"
+    );
+
+    Some((pos, profit_pips))
+}
+
+/// Reconciler ปิด Position นี้ไปก่อนโดยไม่รู้ผล (Deal history ไม่มี / Snapshot แข่งกับการปิดจริง)
+/// → Callback ที่มาช้ายังต้องปิด TradeRecord และนับ P&L เข้า Risk — คืน false ถ้าไม่ใช่กรณีนี้
+async fn settle_reconciled_close(state: &SharedState, payload: &PositionClose) -> bool {
+    let Some(ticket) = payload.mt5_ticket else {
+        return false;
+    };
+    {
+        let mut history = state.trade_history.write().await;
+        let Some(record) = history.iter_mut().rev().find(|r| {
+            r.mt5_ticket == Some(ticket)
+                && r.close_reason.as_deref() == Some(RECONCILED)
+                && r.close_price.is_none()
+        }) else {
+            return false;
+        };
+        record.profit = match (record.profit, payload.profit) {
+            (Some(realised), Some(profit)) => Some(realised + profit),
+            (realised, profit)             => profit.or(realised),
+        };
+        record.close_price = Some(payload.close_price);
+        state.db.trade(record);
+    }
+
+    warn!(ticket, profit = ?payload.profit, "🔄 Late position-close for reconciled ticket — P&L recorded");
+    record_realized(state, payload.profit).await;
+    true
+}
+
+/// Realized P&L → Risk Manager — ชน Limit = Kill Switch + ยกเลิกทุก Pending Order
+async fn record_realized(state: &SharedState, profit: Option<f64>) {
+    let Some(profit) = profit else {
+        return;
+    };
+    if let Some(reason) = state.risk.record_close(profit).await {
+        state.risk_killed(risk_event::AUTO_KILL, reason);
+        cancel_all(state, cancel_reason::KILL_SWITCH).await;
+    }
+}
+
+// ─── POST /api/mt5/order-fill ────────────────────────────────────────────────
+//
+// MT5 EA เรียก endpoint นี้เมื่อ Pending Order (Limit / Stop) ถูก Fill บางส่วนหรือทั้งหมด
+
+pub async fn handle_order_fill(
+    State(state): State<SharedState>,
+    Json(fill): Json<PendingFill>,
+) -> Result<impl IntoResponse, AppError> {
+    if !(fill.volume.is_finite() && fill.volume > 0.0 && fill.price.is_finite()) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid fill volume={} price={}", fill.volume, fill.price
+        )));
     }
+
+    Ok(Json(match apply_order_fill(&state, &fill).await {
+        Some(order) => json!({
+            "ok":              true,
+            "order_ticket":    order.order_ticket,
+            "position_ticket": order.position_ticket,
+            "filled_volume":   order.filled_volume,
+            "volume":          order.volume,
+            "complete":        fill.remaining_volume <= 0.0,
+        }),
+        None => json!({
+            "ok":      false,
+            "message": "No pending order with this ticket",
+        }),
+    }))
+}
+
+// ─── GET /api/mt5/commands ────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct CommandPoll {
+    /// ตัวระบุ EA connection (เช่น Chart ID) — บันทึกไว้ว่าใครรับคำสั่งไป
+    pub ea_id:  String,
+    /// Symbol ของ Chart ที่ EA ติดอยู่ — รับเฉพาะคำสั่งของ Symbol นี้
+    pub symbol: String,
+    /// รับสูงสุดกี่คำสั่งต่อครั้ง (ค่าเริ่มต้น 10)
+    #[serde(default)]
+    pub max:    Option<usize>,
+}
+
+/// EA Poll คำสั่งจัดการ Position — คำสั่งที่ได้ไปต้องตอบด้วย `POST /api/mt5/commands/ack`
+pub async fn handle_poll_commands(
+    State(state): State<SharedState>,
+    Query(poll): Query<CommandPoll>,
+) -> impl IntoResponse {
+    // คำสั่งที่ค้าง Ack เกิน Timeout กลับเข้าคิวก่อน — Poll นี้รับไปใหม่ได้
+    expire_commands(&state).await;
+    let commands = state.commands.write().await.lease(
+        &poll.ea_id,
+        &poll.symbol,
+        poll.max.unwrap_or(10),
+        chrono::Utc::now(),
+    );
+
+    Json(json!({
+        "ok":       true,
+        "commands": commands,
+    }))
+}
+
+// ─── POST /api/mt5/commands/ack ───────────────────────────────────────────────
+
+/// EA รายงานผลคำสั่ง — สำเร็จแล้ว Position Book จึงเปลี่ยน
+pub async fn handle_command_ack(
+    State(state): State<SharedState>,
+    Json(ack): Json<CommandAck>,
+) -> impl IntoResponse {
+    match apply_ack(&state, &ack).await {
+        Some(command) => Json(json!({
+            "ok":         true,
+            "command_id": command.command_id,
+            "applied":    ack.ok,
+        })),
+        None => Json(json!({
+            "ok":         false,
+            "command_id": ack.command_id,
+            "message":    "Unknown or timed-out command",
+        })),
+    }
+}
+
+// ─── POST /api/mt5/account ────────────────────────────────────────────────────
+
+/// EA รายงาน Balance / Equity เป็นระยะ — ใช้คำนวณ Volume ตามความเสี่ยงต่อ Trade
+pub async fn report_account(
+    State(state): State<SharedState>,
+    Json(mut account): Json<AccountInfo>,
+) -> Result<impl IntoResponse, AppError> {
+    if !account.equity.is_finite() || account.equity <= 0.0 {
+        return Err(AppError::BadRequest(format!("Invalid equity {}", account.equity)));
+    }
+
+    account.updated_at = chrono::Utc::now();
+    *state.account.write().await = Some(account.clone());
+
+    // Drawdown ตาม Equity จริง (รวม Floating) — Broker จำลองไม่ได้เทรดบัญชีนี้ จึงไม่นับ
+    if !state.broker.kind().is_simulated() {
+        if let Some(reason) = state.risk.record_equity(account.equity).await {
+            state.risk_killed(risk_event::AUTO_KILL, reason);
+            cancel_all(&state, cancel_reason::KILL_SWITCH).await;
+        }
+    }
+
+    Ok(Json(json!({
+        "ok":      true,
+        "account": account,
+    })))
+}
+
+// ─── GET /api/mt5/account ─────────────────────────────────────────────────────
+
+/// Balance / Equity ตาม Broker ที่ใช้อยู่ (Paper = บัญชีจำลอง)
+pub async fn get_account(State(state): State<SharedState>) -> Result<impl IntoResponse, AppError> {
+    let account = state.broker.account_info().await?;
+
+    Ok(Json(json!({
+        "ok":      true,
+        "broker":  state.broker.kind(),
+        "account": account,
+    })))
+}
+
+// ─── POST /api/mt5/symbol-spec ────────────────────────────────────────────────
+
+/// EA ส่ง SymbolSpec จริงของ Broker มาตอน Start (ทับค่า Default / `SYMBOL_SPECS`)
+pub async fn report_symbol_spec(
+    State(state): State<SharedState>,
+    Json(spec): Json<SymbolSpec>,
+) -> Result<impl IntoResponse, AppError> {
+    let valid = [spec.point, spec.tick_size, spec.tick_value, spec.volume_min, spec.volume_step]
+        .iter()
+        .all(|v| v.is_finite() && *v > 0.0)
+        && spec.volume_max >= spec.volume_min;
+    if !valid {
+        return Err(AppError::BadRequest(format!("Invalid SymbolSpec for {}", spec.symbol)));
+    }
+
+    let symbol = spec.symbol.clone();
+    state.symbols.write().await.upsert(spec);
+    let spec = state.symbol_spec(&symbol).await;
+
+    tracing::info!(%symbol, "📐 SymbolSpec updated from EA");
+
+    Ok(Json(json!({
+        "ok":   true,
+        "spec": spec,
+    })))
+}
+
+// ─── GET /api/mt5/symbols ─────────────────────────────────────────────────────
+
+/// SymbolSpec ทั้งหมดที่ระบบรู้จัก
+pub async fn list_symbols(State(state): State<SharedState>) -> impl IntoResponse {
+    let specs = state.symbols.read().await.all();
+    Json(json!({
+        "ok":      true,
+        "count":   specs.len(),
+        "symbols": specs,
+    }))
+}
+
+// ─── POST /api/mt5/reconcile ──────────────────────────────────────────────────
+
+/// สั่ง Reconcile Position Book กับ Broker ทันที (ปกติรันเองตาม RECONCILE_INTERVAL_SECS)
+pub async fn reconcile_positions(
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, AppError> {
+    if state.broker.kind() == BrokerKind::Paper {
+        return Err(AppError::BadRequest("Reconciliation is unavailable for the paper broker".into()));
+    }
+
+    let diff = reconcile_once(&state).await?;
+
+    Ok(Json(json!({
+        "ok":      true,
+        "in_sync": diff.is_empty(),
+        "diff":    diff,
+    })))
 }
 
 // ─── GET /api/mt5/health ──────────────────────────────────────────────────────
@@ -282,14 +960,71 @@ pub async fn handle_position_close(
 pub async fn health_check(State(state): State<SharedState>) -> impl IntoResponse {
     let tick_count   = state.tick_count.load(Ordering::Relaxed);
     let trade_count  = state.trade_count.load(Ordering::Relaxed);
-    let has_strategy = state.active_strategy.read().await.is_some();
-    let has_position = state.open_position.read().await.is_some();
+    let (active_strategies, has_strategy) = {
+        let book = state.strategies.read().await;
+        (book.len(), !book.is_empty())
+    };
+    let (open_positions, has_position) = {
+        let book = state.positions.read().await;
+        (book.len(), !book.is_empty())
+    };
+    let pending_orders = state.orders.read().await.len();
+    let account_equity = state.account_equity().await;
 
     Json(json!({
-        "ok":           true,
-        "tick_count":   tick_count,
-        "trade_count":  trade_count,
-        "has_strategy": has_strategy,
-        "has_position": has_position,
+        "ok":                true,
+        "tick_count":        tick_count,
+        "trade_count":       trade_count,
+        "has_strategy":      has_strategy,
+        "has_position":      has_position,
+        "active_strategies": active_strategies,
+        "open_positions":    open_positions,
+        "pending_orders":    pending_orders,
+        "account_equity":    account_equity,
+        "broker":            state.broker.kind(),
     }))
 }
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, time::Duration};
+
+    use chrono::Utc;
+
+    use crate::broker::{recording::BrokerCall, PaperBroker, PaperConfig, RecordingBroker};
+    use crate::models::{confirmation::ConfirmationLayerSpec, strategy::test_strategy, tick::test_tick};
+    use crate::state::AppState;
+
+    #[tokio::test]
+    async fn test_concurrent_ticks_trigger_strategy_once() {
+        let mut state = AppState::new();
+        let paper     = PaperBroker::new(PaperConfig { balance: 10_000.0, ..PaperConfig::default() }, state.symbols.clone());
+        let broker    = Arc::new(RecordingBroker::new(Arc::new(paper)));
+        // Broker ตอบช้า → Tick แรกยังค้างอยู่ระหว่าง Sizing / ส่ง Order ตอน Tick ที่สองเข้ามา
+        broker.delay_calls(Duration::from_millis(50));
+        state.broker  = broker.clone();
+        let state     = Arc::new(state);
+        // Confirmation เหลือแค่ Spread → Tick แรกในโซนยิงได้เลย
+        state.strategies.write().await.insert(ActiveStrategy {
+            confirmation: Some(vec![ConfirmationLayerSpec::Spread { max_pips: None }]),
+            ..test_strategy("XAUUSD")
+        });
+
+        // HTTP Fallback + Stream ส่ง Tick ในโซนพร้อมกัน
+        let tick = test_tick("XAUUSD", Utc::now(), 1999.0);
+        let (first, second) = tokio::join!(process_tick(&state, tick.clone()), process_tick(&state, tick));
+
+        let triggered = [first.unwrap(), second.unwrap()]
+            .into_iter()
+            .filter(|(_, Json(body))| body["action"] == "TRADE_TRIGGERED")
+            .count();
+        assert_eq!(triggered, 1);
+        let sends = broker.calls().into_iter().filter(|call| matches!(call, BrokerCall::SendOrder { .. })).count();
+        assert_eq!(sends, 1);
+        assert_eq!(state.positions.read().await.len(), 1);
+        assert!(state.strategies.read().await.is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+//! # routes::mt5_stream
+//!
+//! **Streaming Tick Ingestion** — EA ต่อค้างไว้แทน HTTP POST ทีละ Tick
+//!
+//! | Transport | ต่อที่                                   | Auth                                |
+//! |-----------|-----------------------------------------|-------------------------------------|
+//! | WebSocket | `ws://host:3000/ws/mt5?ea_id=&symbol=`  | `X-API-Key` header (เหมือน REST)     |
+//! | TCP       | `MT5_STREAM_ADDR` (ปิดไว้ถ้าไม่ตั้ง)       | บรรทัดแรกเป็น `HELLO` พร้อม `api_key` |
+//!
+//! ขาเข้าดู `models::stream` (Tick แบบย่อ / JSON / Batch / ACK) — ขาออกเป็น JSON ทีละบรรทัด:
+//!
+//! ```text
+//! {"type":"CONNECTED","conn_id":"…","simulated":false}
+//! {"type":"TICK_RESULT","action":"TRADE_TRIGGERED",…}   ← เฉพาะ Tick ที่ไม่ใช่ NO_ACTION
+//! {"type":"COMMAND","command":{EaCommand}}               ← คิวคำสั่งของ Symbol นี้ (ตอบด้วย ACK)
+//! {"type":"ACK_RESULT","command_id":"…","applied":true}
+//! {"type":"STATS",…StreamStats}                           ← ทุก 10 วินาที
+//! {"type":"ERROR","message":"…"}
+//! ```
+//!
+//! ทุก Tick ผ่าน `process_tick` ตัวเดียวกับ `POST /api/mt5/tick` (ยังใช้เป็น Fallback ได้)
+//! ใน Worker ของแต่ละ Symbol (คิวจำกัด [`TICK_QUEUE_PER_SYMBOL`]) — ตามลำดับภายใน Symbol เดียวกัน
+//! แต่ Read loop ไม่รอ: Order ที่ Broker ตอบช้าไม่ทำให้ ACK / Tick ของ Symbol อื่นค้าง
+
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{info, warn};
+
+use crate::{
+    engine::commands::{apply_ack, expire_commands},
+    models::{
+        stream::{parse_frame, StreamInbound, StreamStats},
+        TickData,
+    },
+    routes::mt5::process_tick,
+    state::SharedState,
+};
+
+/// ส่งคำสั่งที่รออยู่ให้ EA ถี่แค่ไหน (นอกเหนือจากหลังทุก Frame)
+const COMMAND_PUSH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// ส่ง `STATS` ให้ EA ทุกกี่วินาที
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Lease คำสั่งได้สูงสุดกี่คำสั่งต่อรอบ
+const MAX_COMMANDS_PER_PUSH: usize = 10;
+
+/// TCP: หนึ่งบรรทัดยาวได้สูงสุดเท่าไร (Batch ของ Tick) — เกิน = ปิด Connection
+const MAX_FRAME_BYTES: usize = 1024 * 1024;
+
+/// TCP: ต้องได้ `HELLO` ภายในเวลานี้ (เมื่อตั้ง API_KEY) ไม่งั้นปิด Connection
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tick ที่รอ Worker ของแต่ละ Symbol ได้สูงสุด — เต็ม = ทิ้ง (นับใน `dropped`) ไม่หยุดอ่าน
+const TICK_QUEUE_PER_SYMBOL: usize = 256;
+
+// ─── Tick Worker ──────────────────────────────────────────────────────────────
+
+/// Tick ที่อ่านจาก Connection แล้ว รอ Worker ของ Symbol
+struct QueuedTick {
+    tick:        TickData,
+    received_at: DateTime<Utc>,
+}
+
+/// ผลของ Tick หนึ่งตัวจาก Worker → ส่งกลับทาง Connection เดิม
+struct TickOutcome {
+    tick_time:   DateTime<Utc>,
+    received_at: DateTime<Utc>,
+    process_us:  f64,
+    result:      Result<serde_json::Value, String>,
+}
+
+/// Worker ของหนึ่ง Symbol — `process_tick` ทีละ Tick ตามลำดับ (รวม Retry / Backoff ของ Order)
+/// Connection ปิดแล้วยังทำ Tick ที่ค้างในคิวจนหมด (ผลไม่มีที่ส่ง — ทิ้ง)
+fn spawn_tick_worker(state: SharedState, results: mpsc::Sender<TickOutcome>) -> mpsc::Sender<QueuedTick> {
+    let (tx, mut rx) = mpsc::channel::<QueuedTick>(TICK_QUEUE_PER_SYMBOL);
+    tokio::spawn(async move {
+        while let Some(QueuedTick { tick, received_at }) = rx.recv().await {
+            let tick_time = tick.time;
+            let started   = Instant::now();
+            let result    = process_tick(&state, tick)
+                .await
+                .map(|(_, Json(body))| body)
+                .map_err(|e| e.to_string());
+            let _ = results
+                .send(TickOutcome {
+                    tick_time,
+                    received_at,
+                    process_us: started.elapsed().as_secs_f64() * 1e6,
+                    result,
+                })
+                .await;
+        }
+    });
+    tx
+}
+
+// ─── Session ──────────────────────────────────────────────────────────────────
+
+/// สถานะของ Connection หนึ่ง — Transport แค่ส่งข้อความเข้า / ออก
+struct StreamSession {
+    state:   SharedState,
+    stats:   StreamStats,
+    /// ผ่าน Auth แล้ว (WebSocket ผ่าน Middleware / TCP ต้อง HELLO)
+    authed:  bool,
+    /// Auth ไม่ผ่าน — ปิด Connection หลังส่ง ERROR
+    closed:  bool,
+    /// คิวของ Worker แต่ละ Symbol (สร้างเมื่อเจอ Tick แรกของ Symbol)
+    workers: HashMap<String, mpsc::Sender<QueuedTick>>,
+    /// Worker ส่งผลกลับทางนี้ — ฝั่งรับอยู่ใน Loop ของ Transport
+    results: mpsc::Sender<TickOutcome>,
+}
+
+impl StreamSession {
+    async fn open(
+        state:     SharedState,
+        transport: &'static str,
+        ea_id:     Option<String>,
+        symbol:    Option<String>,
+        authed:    bool,
+    ) -> (Self, mpsc::Receiver<TickOutcome>) {
+        let mut stats = StreamStats::new(transport);
+        stats.ea_id   = ea_id;
+        stats.symbol  = symbol;
+        info!(conn_id = %stats.conn_id, transport, ea_id = ?stats.ea_id, "📡 MT5 stream connected");
+        state.streams.write().await.insert(stats.conn_id, stats.clone());
+        let (results, outcomes) = mpsc::channel(TICK_QUEUE_PER_SYMBOL);
+        let session = Self { state, stats, authed, closed: false, workers: HashMap::new(), results };
+        (session, outcomes)
+    }
+
+    fn connected_line(&self) -> String {
+        line("CONNECTED", json!({
+            "conn_id":   self.stats.conn_id,
+            "simulated": self.state.broker.kind().is_simulated(),
+        }))
+    }
+
+    /// ประมวลผลหนึ่ง Frame — คืนข้อความที่ต้องส่งกลับ
+    async fn handle_frame(&mut self, frame: &str) -> Vec<String> {
+        let received_at = Utc::now();
+        let mut out     = Vec::new();
+        self.stats.frames += 1;
+
+        for message in parse_frame(frame) {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    self.stats.errors += 1;
+                    out.push(error_line(&e));
+                    continue;
+                }
+            };
+
+            match message {
+                StreamInbound::Hello { ea_id, symbol, api_key } => {
+                    let expected = std::env::var("API_KEY").unwrap_or_default();
+                    if !expected.is_empty() && api_key != expected {
+                        warn!(conn_id = %self.stats.conn_id, %ea_id, "❌ MT5 stream rejected — invalid api_key");
+                        out.push(error_line("Unauthorized: invalid api_key"));
+                        self.closed = true;
+                        break;
+                    }
+                    self.authed       = true;
+                    self.stats.ea_id  = Some(ea_id);
+                    self.stats.symbol = Some(symbol);
+                    out.push(self.connected_line());
+                }
+                _ if !self.authed => {
+                    out.push(error_line("HELLO with api_key required"));
+                    self.closed = true;
+                    break;
+                }
+                StreamInbound::Tick(tick)       => self.tick(tick, received_at),
+                StreamInbound::Ticks { ticks }  => {
+                    for tick in ticks {
+                        self.tick(tick, received_at);
+                    }
+                }
+                StreamInbound::Ack(ack) => {
+                    self.stats.acks += 1;
+                    let applied = apply_ack(&self.state, &ack).await.map(|_| ack.ok);
+                    out.push(line("ACK_RESULT", json!({
+                        "ok":         applied.is_some(),
+                        "command_id": ack.command_id,
+                        "applied":    applied.unwrap_or(false),
+                    })));
+                }
+            }
+        }
+
+        self.sync_stats().await;
+        out
+    }
+
+    /// ส่ง Tick เข้าคิวของ Symbol — ไม่รอผล (ผลมาทาง [`tick_done`](Self::tick_done))
+    fn tick(&mut self, tick: TickData, received_at: DateTime<Utc>) {
+        // EA ที่ไม่ได้บอก Symbol ตอนต่อ → รับคำสั่งของ Symbol ที่ส่ง Tick มา
+        if self.stats.symbol.is_none() {
+            self.stats.symbol = Some(tick.symbol.clone());
+        }
+        let worker = self
+            .workers
+            .entry(tick.symbol.clone())
+            .or_insert_with(|| spawn_tick_worker(self.state.clone(), self.results.clone()));
+
+        let Err(TrySendError::Full(queued) | TrySendError::Closed(queued)) = worker.try_send(QueuedTick { tick, received_at })
+        else {
+            return;
+        };
+        self.stats.dropped += 1;
+        if self.stats.dropped.is_power_of_two() {
+            warn!(
+                conn_id = %self.stats.conn_id,
+                symbol  = %queued.tick.symbol,
+                dropped = self.stats.dropped,
+                "📡 Tick queue full — ticks dropped"
+            );
+        }
+    }
+
+    /// Worker ประมวลผล Tick เสร็จ — คืนข้อความที่ต้องส่งกลับ (NO_ACTION ไม่ต้องส่ง)
+    async fn tick_done(&mut self, outcome: TickOutcome) -> Vec<String> {
+        self.stats.record_tick(outcome.tick_time, outcome.received_at, outcome.process_us);
+        let out = match outcome.result {
+            Ok(body) if body["action"] == "NO_ACTION" => Vec::new(),
+            Ok(body) => vec![line("TICK_RESULT", body)],
+            Err(e) => {
+                self.stats.errors += 1;
+                vec![error_line(&e)]
+            }
+        };
+        self.sync_stats().await;
+        out
+    }
+
+    /// คำสั่งในคิวของ Symbol นี้ → DELIVERED ให้ Connection นี้
+    async fn pending_commands(&mut self) -> Vec<String> {
+        let Some(symbol) = self.stats.symbol.clone().filter(|_| self.authed) else {
+            return Vec::new();
+        };
+        expire_commands(&self.state).await;
+
+        let ea_id    = self.stats.ea_id.clone().unwrap_or_else(|| self.stats.conn_id.to_string());
+        let commands = self.state.commands.write().await.lease(&ea_id, &symbol, MAX_COMMANDS_PER_PUSH, Utc::now());
+        if commands.is_empty() {
+            return Vec::new();
+        }
+        self.stats.commands_sent += commands.len() as u64;
+        self.sync_stats().await;
+        commands
+            .into_iter()
+            .map(|command| line("COMMAND", json!({ "command": command })))
+            .collect()
+    }
+
+    fn stats_line(&self) -> String {
+        line("STATS", serde_json::to_value(&self.stats).unwrap_or_default())
+    }
+
+    async fn sync_stats(&self) {
+        self.state.streams.write().await.insert(self.stats.conn_id, self.stats.clone());
+    }
+
+    async fn close(self) {
+        self.state.streams.write().await.remove(&self.stats.conn_id);
+        info!(
+            conn_id    = %self.stats.conn_id,
+            ea_id      = ?self.stats.ea_id,
+            ticks      = self.stats.ticks,
+            lag_ms_avg = self.stats.lag_ms_avg,
+            "📡 MT5 stream disconnected"
+        );
+    }
+}
+
+/// ข้อความขาออก — `{"type": kind, ...body}`
+fn line(kind: &str, body: serde_json::Value) -> String {
+    let mut message = json!({ "type": kind });
+    if let (Some(message), serde_json::Value::Object(body)) = (message.as_object_mut(), body) {
+        message.extend(body);
+    }
+    message.to_string()
+}
+
+fn error_line(message: &str) -> String {
+    line("ERROR", json!({ "message": message }))
+}
+
+// ─── WebSocket: GET /ws/mt5 ───────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct StreamParams {
+    #[serde(default)]
+    pub ea_id:  Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+pub async fn ws_mt5(
+    ws: WebSocketUpgrade,
+    State(state): State<SharedState>,
+    Query(params): Query<StreamParams>,
+) -> impl IntoResponse {
+    ws.on_upgrade(|socket| handle_ws(socket, state, params))
+}
+
+async fn handle_ws(socket: WebSocket, state: SharedState, params: StreamParams) {
+    let (mut session, mut outcomes) = StreamSession::open(state, "ws", params.ea_id, params.symbol, true).await;
+    let (mut sender, mut receiver) = socket.split();
+    let mut push   = tokio::time::interval(COMMAND_PUSH_INTERVAL);
+    let mut report = tokio::time::interval(STATS_INTERVAL);
+    report.reset();
+
+    let mut out = vec![session.connected_line()];
+    loop {
+        for text in out.drain(..) {
+            if sender.send(Message::Text(text)).await.is_err() {
+                session.close().await;
+                return;
+            }
+        }
+        if session.closed {
+            break;
+        }
+
+        tokio::select! {
+            result = receiver.next() => match result {
+                Some(Ok(Message::Text(frame))) => {
+                    out = session.handle_frame(&frame).await;
+                    out.extend(session.pending_commands().await);
+                }
+                Some(Ok(Message::Binary(frame))) => {
+                    out = session.handle_frame(&String::from_utf8_lossy(&frame)).await;
+                    out.extend(session.pending_commands().await);
+                }
+                Some(Ok(Message::Ping(data))) => {
+                    let _ = sender.send(Message::Pong(data)).await;
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(Message::Pong(_))) => {}
+            },
+            Some(outcome) = outcomes.recv() => {
+                out = session.tick_done(outcome).await;
+                out.extend(session.pending_commands().await);
+            }
+            _ = push.tick()   => out = session.pending_commands().await,
+            _ = report.tick() => out = vec![session.stats_line()],
+        }
+    }
+    session.close().await;
+}
+
+// ─── TCP: MT5_STREAM_ADDR ─────────────────────────────────────────────────────
+
+/// เปิด TCP listener สำหรับ EA (MQL5 Socket ไม่มี WebSocket) — หนึ่งบรรทัด = หนึ่ง Frame
+pub fn spawn_tcp_listener(state: SharedState, addr: std::net::SocketAddr) {
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!(%addr, error = %e, "📡 MT5 TCP stream disabled — bind failed");
+                return;
+            }
+        };
+        info!(%addr, "📡 MT5 TCP stream listening");
+
+        loop {
+            match listener.accept().await {
+                Ok((socket, peer)) => {
+                    let _ = socket.set_nodelay(true);
+                    tokio::spawn(handle_tcp(socket, peer, state.clone()));
+                }
+                Err(e) => warn!(error = %e, "📡 MT5 TCP accept failed"),
+            }
+        }
+    });
+}
+
+async fn handle_tcp(socket: tokio::net::TcpStream, peer: std::net::SocketAddr, state: SharedState) {
+    // ไม่ได้ตั้ง API_KEY = Dev mode เหมือน REST / WebSocket
+    let authed = std::env::var("API_KEY").unwrap_or_default().is_empty();
+    let (mut session, mut outcomes) = StreamSession::open(state, "tcp", None, None, authed).await;
+    let (read, mut write) = socket.into_split();
+    let mut reader = BufReader::new(read);
+    let mut buf    = Vec::new();
+    let mut push   = tokio::time::interval(COMMAND_PUSH_INTERVAL);
+    let mut report = tokio::time::interval(STATS_INTERVAL);
+    let hello      = tokio::time::sleep(HELLO_TIMEOUT);
+    tokio::pin!(hello);
+    report.reset();
+
+    // ยังไม่ Auth → รอ HELLO ก่อนตอบ CONNECTED
+    let mut out = if authed { vec![session.connected_line()] } else { Vec::new() };
+    loop {
+        for text in out.drain(..) {
+            if write.write_all(format!("{text}\n").as_bytes()).await.is_err() {
+                session.close().await;
+                return;
+            }
+        }
+        if session.closed {
+            break;
+        }
+
+        tokio::select! {
+            result = next_frame(&mut reader, &mut buf) => match result {
+                Ok(Some(frame)) => {
+                    out = session.handle_frame(&frame).await;
+                    out.extend(session.pending_commands().await);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(%peer, error = %e, "📡 MT5 TCP stream read failed");
+                    break;
+                }
+            },
+            Some(outcome) = outcomes.recv() => {
+                out = session.tick_done(outcome).await;
+                out.extend(session.pending_commands().await);
+            }
+            _ = push.tick()   => out = session.pending_commands().await,
+            _ = report.tick() => out = vec![session.stats_line()],
+            _ = &mut hello, if !session.authed => {
+                warn!(%peer, "📡 MT5 TCP stream closed — no HELLO within timeout");
+                out = vec![error_line("HELLO timeout")];
+                session.closed = true;
+            }
+        }
+    }
+    session.close().await;
+}
+
+/// อ่านหนึ่งบรรทัด (ไม่รวม `\n`) ยาวไม่เกิน [`MAX_FRAME_BYTES`] — `Ok(None)` = Peer ปิด Connection
+///
+/// Cancel-safe ใน `select!`: ส่วนที่อ่านแล้วค้างอยู่ใน `buf` จนครบบรรทัด
+async fn next_frame<R: AsyncBufRead + Unpin>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+    let limit = (MAX_FRAME_BYTES + 1).saturating_sub(buf.len()) as u64;
+    (&mut *reader).take(limit).read_until(b'\n', buf).await?;
+
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    } else if buf.len() > MAX_FRAME_BYTES {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame exceeds MAX_FRAME_BYTES"));
+    } else if buf.is_empty() {
+        return Ok(None);
+    }
+    String::from_utf8(std::mem::take(buf))
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::broker::{recording::BrokerCall, PaperBroker, PaperConfig, RecordingBroker};
+    use crate::models::{confirmation::ConfirmationLayerSpec, strategy::test_strategy, ActiveStrategy};
+    use crate::state::AppState;
+
+    #[tokio::test]
+    async fn test_slow_order_does_not_block_next_tick() {
+        const LATENCY: Duration = Duration::from_millis(300);
+
+        let mut state = AppState::new();
+        let paper     = PaperBroker::new(PaperConfig { balance: 10_000.0, ..PaperConfig::default() }, state.symbols.clone());
+        let broker    = Arc::new(RecordingBroker::new(Arc::new(paper)));
+        broker.delay_calls(LATENCY);
+        state.broker  = broker.clone();
+        let state     = Arc::new(state);
+        state.strategies.write().await.insert(ActiveStrategy {
+            confirmation: Some(vec![ConfirmationLayerSpec::Spread { max_pips: None }]),
+            ..test_strategy("XAUUSD")
+        });
+
+        let (mut session, mut outcomes) = StreamSession::open(state, "tcp", None, None, true).await;
+        let now     = Utc::now().timestamp_millis();
+        let started = Instant::now();
+
+        // XAUUSD เข้าโซน → ส่ง Order ไป Broker ที่ตอบช้า / Tick ถัดไปบน Connection เดียวกัน
+        assert!(session.handle_frame(&format!("T,XAUUSD,1999.0,1999.3,1,{now}")).await.is_empty());
+        assert!(session.handle_frame(&format!("T,EURUSD,1.0850,1.0851,1,{now}")).await.is_empty());
+        assert!(session.handle_frame(r#"{"type":"ACK","command_id":"00000000-0000-0000-0000-000000000000","ok":true}"#).await[0].contains("ACK_RESULT"));
+        assert!(started.elapsed() < LATENCY, "read loop waited for the broker");
+
+        // EURUSD ได้ผลก่อน Order ของ XAUUSD จะเสร็จ
+        let next = outcomes.recv().await.unwrap();
+        assert_eq!(next.result.as_ref().unwrap()["symbol"], "EURUSD");
+        assert!(started.elapsed() < LATENCY);
+        assert!(session.tick_done(next).await.is_empty());
+
+        let trade = outcomes.recv().await.unwrap();
+        assert_eq!(trade.result.as_ref().unwrap()["action"], "TRADE_TRIGGERED");
+        assert!(session.tick_done(trade).await[0].contains("TICK_RESULT"));
+        assert_eq!(session.stats.ticks, 2);
+        assert!(broker.calls().iter().any(|call| matches!(call, BrokerCall::SendOrder { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_next_frame_splits_lines_and_rejects_oversized_frame() {
+        let mut buf    = Vec::new();
+        let mut reader: &[u8] = b"T,XAUUSD,1,2,1,0\r\n{\"type\":\"ACK\"}";
+        assert_eq!(next_frame(&mut reader, &mut buf).await.unwrap().as_deref(), Some("T,XAUUSD,1,2,1,0"));
+        assert_eq!(next_frame(&mut reader, &mut buf).await.unwrap().as_deref(), Some("{\"type\":\"ACK\"}"));
+        assert_eq!(next_frame(&mut reader, &mut buf).await.unwrap(), None);
+
+        // Peer ที่ส่งบรรทัดไม่จบ — หยุดอ่านที่ MAX_FRAME_BYTES ไม่โตตามที่ส่งมา
+        let endless = vec![b'x'; MAX_FRAME_BYTES * 2];
+        let mut reader = BufReader::new(&endless[..]);
+        let err = next_frame(&mut reader, &mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(buf.len(), MAX_FRAME_BYTES + 1);
+    }
+}
@@ -17,7 +17,11 @@ use axum::{
 use serde::Deserialize;
 use serde_json::json;
 
-use crate::state::SharedState;
+use crate::{
+    db::risk_event,
+    engine::orders::{cancel_all, cancel_reason},
+    state::SharedState,
+};
 
 #[derive(Deserialize)]
 pub struct KillBody {
@@ -34,10 +38,14 @@ pub async fn kill_switch_on(
         .unwrap_or_else(|| "Manual kill via API".to_string());
 
     state.risk.kill(&reason).await;
+    state.risk_killed(risk_event::KILL_SWITCH_ON, reason.clone());
+    // Pending Order ที่วางไว้ยัง Fill ได้แม้ Kill Switch เปิด → ยกเลิกทั้งหมด
+    let cancelled = cancel_all(&state, cancel_reason::KILL_SWITCH).await;
 
     (StatusCode::OK, Json(json!({
-        "ok":      true,
-        "message": format!("Kill switch activated: {reason}"),
+        "ok":               true,
+        "message":          format!("Kill switch activated: {reason}"),
+        "cancelled_orders": cancelled,
     })))
 }
 
@@ -46,6 +54,7 @@ pub async fn kill_switch_off(
     State(state): State<SharedState>,
 ) -> impl IntoResponse {
     state.risk.rearm().await;
+    state.db.risk_event(risk_event::KILL_SWITCH_OFF, "Manual re-arm via API");
 
     Json(json!({
         "ok":      true,
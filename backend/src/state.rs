@@ -1,40 +1,62 @@
 //! # state
 //!
 //! AppState ที่ขยายแล้ว — รองรับ Position Management, Trade History,
-//! WebSocket Broadcast Channel และ shared HTTP Client
+//! WebSocket Broadcast Channel และ Broker (Execution venue)
 
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::{broadcast, RwLock};
+use uuid::Uuid;
 
-use crate::engine::confirmation::{ConfirmationConfig, RecentTick};
-use crate::engine::candle_builder::Candle;
-use crate::models::{ActiveStrategy, OpenPosition, TradeRecord};
+use crate::book::{CommandBook, OrderBook, PositionBook, StrategyBook};
+use crate::broker::{self, Broker, BrokerConfig, PaperBroker, PaperConfig};
+use crate::clock::Clock;
+use crate::db::Persistence;
+use crate::engine::commands::CommandConfig;
+use crate::engine::confirmation::{ConfirmationConfig, RecentTick, TraceBook};
+use crate::engine::candle_builder::{Candle, CandleAggregator, CandleConfig};
+use crate::engine::executor::ExecutionConfig;
+use crate::engine::indicators::{IndicatorConfig, IndicatorEngine};
+use crate::engine::sizing::SizingConfig;
+use crate::models::{
+    stream::StreamStats, AccountInfo, ActiveStrategy, ExitPolicy, OpenPosition, TickData, TradeRecord,
+    TradeStatus,
+};
 use crate::risk::{RiskConfig, RiskManager};
+use crate::symbols::{SymbolRegistry, SymbolSpec};
+use crate::tickstore::{recorder::TickRecorder, TickStore};
 
 /// จำนวน Tick ที่เก็บ History ต่อ Symbol
 const TICK_BUFFER_SIZE: usize = 30;
 
+/// จำนวน Tick ดิบที่เก็บต่อ Symbol สำหรับ `GET /api/market/ticks`
+const MARKET_TICK_HISTORY: usize = 1_000;
+
 // ─── AppState ─────────────────────────────────────────────────────────────────
 
 /// Top-level shared state injected into every Axum handler.
 #[derive(Clone)]
 pub struct AppState {
     // ── Brain Loop ────────────────────────────────────────────────────────────
-    /// แผนการเทรดปัจจุบันจาก OpenClaw
-    /// None = ยังไม่มีแผน หรือ แผนถูกล้างหลังจาก Trade fired
-    pub active_strategy: Arc<RwLock<Option<ActiveStrategy>>>,
+    /// แผนการเทรดทั้งหมดจาก OpenClaw แยกตาม Symbol และ strategy_id
+    /// Strategy ถูกลบออกเมื่อ Trade fired หรือถูกแทนที่ด้วยแผนใหม่ของ Symbol เดียวกัน
+    pub strategies: Arc<RwLock<StrategyBook>>,
 
     // ── Position Management ───────────────────────────────────────────────────
-    /// Position ที่เปิดอยู่ใน MT5 ณ ตอนนี้
-    /// None = ไม่มี Position เปิด → Reflex Loop พร้อม trade
-    /// Some = มี Position อยู่แล้ว → ห้าม Double Entry
-    pub open_position: Arc<RwLock<Option<OpenPosition>>>,
+    /// Position ที่เปิดอยู่ใน MT5 ณ ตอนนี้ แยกตาม MT5 ticket
+    /// ไม่มี Position ของ Symbol → Reflex Loop พร้อม trade Symbol นั้น
+    /// มี Position ของ Symbol อยู่แล้ว → ห้าม Double Entry (เฉพาะ Symbol นั้น)
+    pub positions: Arc<RwLock<PositionBook>>,
+    /// Pending Order (Limit / Stop) ที่วางไว้ที่ขอบ Entry Zone แยกตาม Order ticket
+    /// ถูกลบเมื่อ Fill ครบ / Strategy ถูกล้างหรือแทนที่ / หมดอายุ (ดู `engine::orders`)
+    pub orders: Arc<RwLock<OrderBook>>,
+    /// คำสั่งจัดการ Position ที่รอ EA รับ / Ack (ดู `engine::commands`)
+    pub commands: Arc<RwLock<CommandBook>>,
 
     // ── Trade History ─────────────────────────────────────────────────────────
     /// บันทึกทุก Order ที่เคยยิง (ไม่มีวันลบ — ใช้สำหรับ Dashboard)
-    /// ในอนาคตจะ persist ลง PostgreSQL
+    /// Persist ลง PostgreSQL ผ่าน `db` (ถ้าเปิดใช้)
     pub trade_history: Arc<RwLock<Vec<TradeRecord>>>,
 
     // ── Monitor / WebSocket ───────────────────────────────────────────────────
@@ -42,10 +64,9 @@ pub struct AppState {
     /// ใช้ String (pre-serialized JSON) เพื่อหลีกเลี่ยง Clone constraints
     pub broadcast_tx: broadcast::Sender<String>,
 
-    // ── HTTP Client ───────────────────────────────────────────────────────────
-    /// reqwest Client ที่ share กันทั้งระบบ (thread-safe, connection pooling)
-    /// สร้างครั้งเดียว ไม่ต้องสร้างใหม่ทุก Request
-    pub http_client: reqwest::Client,
+    // ── MT5 Streams ───────────────────────────────────────────────────────────
+    /// Stream connection ของ EA ที่ต่ออยู่ (`/ws/mt5`, TCP) — Lag / Throughput ต่อ Connection
+    pub streams: Arc<RwLock<HashMap<Uuid, StreamStats>>>,
 
     // ── Metrics ───────────────────────────────────────────────────────────────
     pub tick_count:  Arc<std::sync::atomic::AtomicU64>,
@@ -55,39 +76,128 @@ pub struct AppState {
     /// เก็บ Tick ย้อนหลังต่อ Symbol สำหรับ Zone Probe และ Dwell detection
     /// Key = symbol string, Value = ล่าสุดอยู่ท้าย VecDeque
     pub tick_buffer: Arc<RwLock<HashMap<String, VecDeque<RecentTick>>>>,
+    /// Tick ดิบล่าสุดต่อ Symbol (`MARKET_TICK_HISTORY`) — Market Data API
+    pub market_ticks: Arc<RwLock<HashMap<String, VecDeque<TickData>>>>,
 
     // ── Candle Builder (M1 Rejection Engine) ──────────────────────────────────
-    /// เก็บแท่งเทียนที่กำลังสร้างจาก Tick
-    pub latest_candle: Arc<RwLock<HashMap<String, Candle>>>,
+    /// แท่ง M1 … D1 ต่อ Symbol จากเวลาของ Tick — แท่งที่กำลังสร้าง + Ring buffer ของแท่งที่ปิดแล้ว
+    /// (ดู `engine::candle_builder`)
+    pub candles: Arc<RwLock<CandleAggregator>>,
+    /// แท่งที่เพิ่งปิด (Typed) สำหรับ `/ws/market` — แยกจาก `broadcast_tx` เพื่อไม่ต้อง Parse JSON กรอง
+    pub candle_tx: broadcast::Sender<Candle>,
+
+    // ── Indicator Engine ──────────────────────────────────────────────────────
+    /// RSI / EMA / SMA / ATR / VWAP / Bollinger ต่อ Symbol × Timeframe (ดู `engine::indicators`)
+    /// ป้อนทุก Tick ใน `evaluate_tick` — Confirmation และ Exit Policy อ่านจากที่นี่
+    pub indicators: Arc<RwLock<IndicatorEngine>>,
+
+    // ── Broker ────────────────────────────────────────────────────────────────
+    /// Execution venue ตาม `BROKER` — ทุกคำสั่งเทรดผ่านตัวนี้
+    /// (Mt5HttpBroker ถือ reqwest Client ของตัวเอง — connection pooling)
+    pub broker: Arc<dyn Broker>,
+    /// Retry / Backoff ของการส่ง Order (`ORDER_MAX_ATTEMPTS`, `ORDER_RETRY_BACKOFF_MS`)
+    pub execution_config: Arc<ExecutionConfig>,
+    /// Timeout / Retry ของคำสั่งถึง EA (`EA_COMMAND_ACK_TIMEOUT_MS`, `EA_COMMAND_MAX_ATTEMPTS`)
+    pub command_config: Arc<CommandConfig>,
 
     // ── Confirmation Config ───────────────────────────────────────────────────
     pub confirmation_config: Arc<ConfirmationConfig>,
+    /// Trace การประเมิน Confirmation ล่าสุดต่อ Strategy (`GET /api/brain/strategy/:id/trace`)
+    pub confirmation_traces: Arc<RwLock<TraceBook>>,
+
+    // ── Exit Management ───────────────────────────────────────────────────────
+    /// Exit Policy ของระบบ (`EXIT_POLICY`) — ใช้เมื่อ Strategy ไม่ได้กำหนดเอง
+    pub exit_policy: Arc<ExitPolicy>,
 
     // ── Risk Management ─────────────────────────────────────────────────
     pub risk: Arc<RiskManager>,
+
+    // ── Symbol Specs ──────────────────────────────────────────────────────────
+    /// Point / Pip / Contract size / Volume limits ต่อ Symbol (Config + EA push)
+    pub symbols: Arc<RwLock<SymbolRegistry>>,
+
+    // ── Position Sizing ───────────────────────────────────────────────────────
+    pub sizing_config: Arc<SizingConfig>,
+    /// สถานะบัญชีล่าสุดจาก EA — None = ยังไม่รายงาน (ใช้ `ACCOUNT_EQUITY` แทน)
+    pub account: Arc<RwLock<Option<AccountInfo>>>,
+
+    // ── Persistence ───────────────────────────────────────────────────────────
+    /// Handle สำหรับเขียนลง PostgreSQL — no-op ถ้าไม่ได้ตั้ง `DATABASE_URL`
+    pub db: Persistence,
+
+    // ── Tick Store ────────────────────────────────────────────────────────────
+    /// Tick บนดิสก์ (`TICK_STORE_DIR`) — Tick Live ที่บันทึกไว้ + Import จาก CSV / MT5 แล้ว Backtest อ้างเป็น Dataset
+    pub tick_store: Arc<TickStore>,
+    /// บันทึก Tick Live ลงคลัง (`TICK_RECORD`) — no-op จนกว่า main จะเริ่ม Recorder
+    pub tick_recorder: TickRecorder,
+
+    // ── Clock ─────────────────────────────────────────────────────────────────
+    /// เวลาของ Engine — Live = นาฬิกาจริง / Backtest = เวลาของ Tick (ดู `clock`)
+    /// Broker จำลองและ Risk Manager ถือตัวเดียวกัน
+    pub clock: Arc<Clock>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        Self::build(Arc::new(Clock::system()), None)
+    }
+
+    /// State แยกสำหรับ Replay (Backtest) — Config ชุดเดียวกับ Live แต่ Book / Buffer ว่าง,
+    /// Broker = PaperBroker, นาฬิกาเริ่มที่ `start` แล้วเดินตามเวลาของ Tick และไม่ Persist
+    pub fn simulated(paper: PaperConfig, start: chrono::DateTime<chrono::Utc>) -> Self {
+        Self::build(Arc::new(Clock::simulated(start)), Some(paper))
+    }
+
+    /// `paper` = Some → PaperBroker ที่ใช้ `clock` / None → Broker ตาม `BROKER`
+    fn build(clock: Arc<Clock>, paper: Option<PaperConfig>) -> Self {
         let (broadcast_tx, _) = broadcast::channel(256);
+        let (candle_tx, _)    = broadcast::channel(256);
+        let symbols           = Arc::new(RwLock::new(SymbolRegistry::from_env()));
+        let confirmation      = ConfirmationConfig::from_env();
+        let broker: Arc<dyn Broker> = match paper {
+            Some(paper) => Arc::new(PaperBroker::with_clock(paper, symbols.clone(), clock.clone())),
+            None        => broker::from_config(&BrokerConfig::from_env(), reqwest::Client::new(), symbols.clone()),
+        };
 
         Self {
-            active_strategy:     Arc::new(RwLock::new(None)),
-            open_position:       Arc::new(RwLock::new(None)),
+            strategies:          Arc::new(RwLock::new(StrategyBook::default())),
+            positions:           Arc::new(RwLock::new(PositionBook::default())),
+            orders:              Arc::new(RwLock::new(OrderBook::default())),
+            commands:            Arc::new(RwLock::new(CommandBook::default())),
             trade_history:       Arc::new(RwLock::new(Vec::new())),
             broadcast_tx,
-            http_client:         reqwest::Client::new(),
+            streams:             Arc::new(RwLock::new(HashMap::new())),
+            broker,
+            execution_config:    Arc::new(ExecutionConfig::from_env()),
+            command_config:      Arc::new(CommandConfig::from_env()),
             tick_count:          Arc::new(std::sync::atomic::AtomicU64::new(0)),
             trade_count:         Arc::new(std::sync::atomic::AtomicU64::new(0)),
             tick_buffer:         Arc::new(RwLock::new(HashMap::new())),
-            latest_candle:       Arc::new(RwLock::new(HashMap::new())),
-            confirmation_config: Arc::new(ConfirmationConfig::from_env()),
-            risk:                Arc::new(RiskManager::new(RiskConfig::from_env())),
+            market_ticks:        Arc::new(RwLock::new(HashMap::new())),
+            candles:             Arc::new(RwLock::new(CandleAggregator::new(CandleConfig::from_env()))),
+            candle_tx,
+            indicators:          Arc::new(RwLock::new(IndicatorEngine::new(IndicatorConfig::from_env()))),
+            confirmation_traces: Arc::new(RwLock::new(TraceBook::new(&confirmation))),
+            confirmation_config: Arc::new(confirmation),
+            exit_policy:         Arc::new(ExitPolicy::from_env()),
+            risk:                Arc::new(RiskManager::new(RiskConfig::from_env(), clock.clone())),
+            symbols,
+            sizing_config:       Arc::new(SizingConfig::from_env()),
+            account:             Arc::new(RwLock::new(None)),
+            db:                  Persistence::default(),
+            tick_store:          Arc::new(TickStore::from_env()),
+            tick_recorder:       TickRecorder::default(),
+            clock,
         }
     }
 
     // ── Helper Methods ────────────────────────────────────────────────────────
 
+    /// เวลาปัจจุบันของ Engine (`clock`)
+    pub fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.clock.now()
+    }
+
     /// Broadcast WsEvent ไปยัง WebSocket clients ทั้งหมด
     /// ไม่ panic ถ้าไม่มี listener (ปลอดภัยสำหรับ headless mode)
     pub fn broadcast(&self, event: &crate::events::WsEvent) {
@@ -95,52 +205,104 @@ impl AppState {
         let _ = self.broadcast_tx.send(event.to_json());
     }
 
-    /// เพิ่ม TradeRecord เข้า history
+    /// Kill Switch ถูกเปิด → บันทึก risk_events + Broadcast `RISK_KILLED`
+    pub fn risk_killed(&self, event_type: &'static str, reason: String) {
+        self.db.risk_event(event_type, reason.clone());
+        self.broadcast(&crate::events::WsEvent::RiskKilled { reason });
+    }
+
+    /// เพิ่ม TradeRecord เข้า history (และ persist)
     pub async fn push_trade_record(&self, record: TradeRecord) {
+        self.db.trade(&record);
         let mut history = self.trade_history.write().await;
         history.push(record);
     }
 
-    /// อัปเดต open_position (None = ปิด Position แล้ว)
-    pub async fn set_open_position(&self, position: Option<OpenPosition>) {
-        let mut guard = self.open_position.write().await;
-        *guard = position;
+    /// SymbolSpec ของ Symbol (clone ออกมาเพื่อปล่อย lock) — None = ไม่รู้จัก Symbol นี้
+    pub async fn symbol_spec(&self, symbol: &str) -> Option<SymbolSpec> {
+        self.symbols.read().await.get(symbol).cloned()
+    }
+
+    /// Equity ล่าสุดที่ EA รายงาน
+    pub async fn account_equity(&self) -> Option<f64> {
+        self.account.read().await.as_ref().map(|a| a.equity)
+    }
+
+    /// Equity สำหรับ Sizing
+    ///
+    /// Broker จริง: ค่าที่ EA รายงาน → ถ้ายังไม่มีถาม Broker
+    /// Broker จำลอง: Equity ของ Paper account เท่านั้น (บัญชีจริงของ EA ไม่เกี่ยว)
+    pub async fn sizing_equity(&self) -> Option<f64> {
+        if !self.broker.kind().is_simulated() {
+            if let Some(equity) = self.account_equity().await {
+                return Some(equity);
+            }
+        }
+        match self.broker.account_info().await {
+            Ok(account) => (account.equity > 0.0).then_some(account.equity),
+            Err(e) => {
+                tracing::debug!(error = %e, "Broker account info unavailable");
+                None
+            }
+        }
+    }
+
+    /// Strategy ทั้งหมดของ Symbol (clone ออกมาเพื่อปล่อย lock)
+    pub async fn strategies_for(&self, symbol: &str) -> Vec<ActiveStrategy> {
+        self.strategies.read().await.for_symbol(symbol)
+    }
+
+    /// บันทึก Position ที่ MT5 ยืนยันแล้วลง Position Book
+    pub async fn open_position(&self, ticket: u64, mut position: OpenPosition) {
+        position.mt5_ticket = Some(ticket);
+        self.db.position(&position);
+        self.positions.write().await.open(ticket, position);
+    }
+
+    /// Position ทั้งหมดของ Symbol (clone ออกมาเพื่อปล่อย lock)
+    pub async fn positions_for(&self, symbol: &str) -> Vec<OpenPosition> {
+        self.positions.read().await.for_symbol(symbol)
     }
 
     /// เช็คว่ามี open position สำหรับ symbol นี้ไหม
     pub async fn has_open_position_for(&self, symbol: &str) -> bool {
-        let guard = self.open_position.read().await;
-        guard.as_ref().map(|p| p.symbol == symbol).unwrap_or(false)
+        self.positions.read().await.has_symbol(symbol)
+    }
+
+    /// Trade ของ Symbol ที่ส่งไปแล้วแต่ยังไม่รู้ผล (`TradeStatus::Unknown`)
+    pub async fn unknown_trade_for(&self, symbol: &str) -> Option<Uuid> {
+        self.trade_history
+            .read()
+            .await
+            .iter()
+            .rev()
+            .find(|r| r.symbol == symbol && r.status == TradeStatus::Unknown)
+            .map(|r| r.trade_id)
     }
 
-    /// บันทึก Tick ลง Buffer สำหรับ Confirmation Engine
+    /// บันทึก Tick ลง Buffer สำหรับ Confirmation Engine (+ Tick ดิบสำหรับ Market Data API)
     /// เรียกทุก Tick ก่อน Reflex evaluation
-    pub async fn record_tick(&self, symbol: &str, bid: f64, ask: f64) {
-        let mut buffer = self.tick_buffer.write().await;
-        let entry = buffer
-            .entry(symbol.to_string())
-            .or_insert_with(|| VecDeque::with_capacity(TICK_BUFFER_SIZE + 1));
-
-        if entry.len() >= TICK_BUFFER_SIZE {
-            entry.pop_front();  // ลบ Tick เก่าสุด
+    pub async fn record_tick(&self, tick: &TickData) {
+        {
+            let mut buffer = self.tick_buffer.write().await;
+            let entry = buffer
+                .entry(tick.symbol.clone())
+                .or_insert_with(|| VecDeque::with_capacity(TICK_BUFFER_SIZE + 1));
+
+            if entry.len() >= TICK_BUFFER_SIZE {
+                entry.pop_front();  // ลบ Tick เก่าสุด
+            }
+            entry.push_back(RecentTick::new(tick.bid, tick.ask));
         }
-        entry.push_back(RecentTick::new(bid, ask));
-
-        // ── สร้างหรืออัปเดตแท่งเทียน (M1) ──────────────────────────────────────────
-        let mut candles = self.latest_candle.write().await;
-        let mid_price = (bid + ask) / 2.0;
-        let now = chrono::Utc::now();
-        
-        let candle = candles.entry(symbol.to_string()).or_insert_with(|| {
-            Candle::new(symbol, now, mid_price)
-        });
-
-        // ถ้าเข้าสู่นาทีใหม่ เริ่มแท่งใหม่
-        if now.timestamp() / 60 > candle.start_time.timestamp() / 60 {
-            *candle = Candle::new(symbol, now, mid_price);
-        } else {
-            candle.update(mid_price);
+
+        let mut ticks = self.market_ticks.write().await;
+        let entry = ticks
+            .entry(tick.symbol.clone())
+            .or_insert_with(|| VecDeque::with_capacity(MARKET_TICK_HISTORY + 1));
+        if entry.len() >= MARKET_TICK_HISTORY {
+            entry.pop_front();
         }
+        entry.push_back(tick.clone());
     }
 
     /// อ่าน Tick Buffer ของ symbol (clone ออกมาเพื่อปล่อย lock)
@@ -149,10 +311,18 @@ impl AppState {
         buffer.get(symbol).cloned().unwrap_or_default()
     }
 
-    /// อ่านแท่งเทียนล่าสุด
-    pub async fn get_latest_candle(&self, symbol: &str) -> Option<Candle> {
-        let candles = self.latest_candle.read().await;
-        candles.get(symbol).cloned()
+    /// ใส่ Tick เข้า Candle Aggregator → แท่งที่ปิดป้อน Indicator Engine + Broadcast `CANDLE_CLOSED`
+    pub async fn record_candles(&self, tick: &TickData) {
+        let closed = self.candles.write().await.on_tick(tick);
+        if closed.is_empty() {
+            return;
+        }
+        let mut indicators = self.indicators.write().await;
+        for candle in closed {
+            indicators.on_candle(&candle);
+            let _ = self.candle_tx.send(candle.clone());
+            self.broadcast(&crate::events::WsEvent::CandleClosed { candle: Box::new(candle) });
+        }
     }
 }
 
@@ -162,7 +332,3 @@ impl Default for AppState {
 
 /// Convenience type alias
 pub type SharedState = Arc<AppState>;
-
-pub fn build_state() -> SharedState {
-    Arc::new(AppState::new())
-}
@@ -0,0 +1,316 @@
+//! # symbols — Symbol Specification Registry
+//!
+//! ทุกการคำนวณที่ขึ้นกับ Symbol (Pips, Spread, Zone width, P&L, Volume) ต้องผ่าน
+//! [`SymbolSpec`] — ราคาต่างกัน 1.0 ของ BTCUSD กับ EURUSD ไม่ได้มีความหมายเท่ากัน
+//!
+//! ```text
+//! point      = 10^-digits                  (ราคาขยับเล็กที่สุดที่แสดง)
+//! pip        = หน่วยที่เทรดเดอร์นับ          FX 5 digits: 0.0001 | JPY: 0.01 | XAUUSD: 0.1 | BTCUSD: 1.0
+//! P&L        = Δprice / tick_size × tick_value × volume   (สกุลเงินบัญชี)
+//! ```
+//!
+//! แหล่งข้อมูล (ตามลำดับ — ตัวหลังทับตัวก่อน)
+//! 1. Default ในตัว (บัญชี USD) — [`default_specs`]
+//! 2. `SYMBOL_SPECS` (JSON Array ของ `SymbolSpec`)
+//! 3. EA ส่งมาเองตอน Start ผ่าน `POST /api/mt5/symbol-spec` (ค่าจริงของ Broker)
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+// ─── Trading Session ──────────────────────────────────────────────────────────
+
+/// ช่วงเวลาที่เปิดให้เทรดในวันหนึ่ง (เวลาเดียวกับ `TickData.time` — Server time ของ Broker)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TradingSession {
+    pub day:   Weekday,
+    /// `"HH:MM:SS"`
+    pub open:  NaiveTime,
+    /// `"HH:MM:SS"` (รวมวินาทีนี้ด้วย — ปิดเที่ยงคืนใช้ `"23:59:59"`)
+    pub close: NaiveTime,
+}
+
+impl TradingSession {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.time();
+        at.weekday() == self.day && time >= self.open && time <= self.close
+    }
+}
+
+// ─── Symbol Spec ──────────────────────────────────────────────────────────────
+
+/// สเปกของ Symbol หนึ่งตัว (ค่าเดียวกับ `SymbolInfo*` ใน MT5)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolSpec {
+    pub symbol:        String,
+    /// จำนวนทศนิยมของราคา (`SYMBOL_DIGITS`)
+    pub digits:        u32,
+    /// ราคาขยับเล็กที่สุดที่แสดง (`SYMBOL_POINT`)
+    pub point:         f64,
+    /// ขนาด 1 Pip ในหน่วยราคา — 0 = อนุมานจาก `digits` (ดู [`SymbolSpec::derived_pip_size`])
+    #[serde(default)]
+    pub pip_size:      f64,
+    /// ขนาดสัญญาต่อ 1 Lot (เช่น XAUUSD = 100 oz)
+    pub contract_size: f64,
+    /// ราคาขยับขั้นต่ำที่คิดเงิน (`SYMBOL_TRADE_TICK_SIZE`)
+    pub tick_size:     f64,
+    /// มูลค่าของ 1 Tick ต่อ 1 Lot ในสกุลเงินบัญชี (`SYMBOL_TRADE_TICK_VALUE`)
+    pub tick_value:    f64,
+    pub volume_min:    f64,
+    pub volume_max:    f64,
+    pub volume_step:   f64,
+    /// ช่วงเวลาเทรด — ว่าง = เทรดได้ตลอด (เช่น Crypto หรือยังไม่รู้)
+    #[serde(default)]
+    pub sessions:      Vec<TradingSession>,
+}
+
+impl SymbolSpec {
+    /// Pip ตามธรรมเนียม: ราคา 3/5 ทศนิยม (FX) = 10 points, อื่นๆ = 1 point
+    pub fn derived_pip_size(digits: u32, point: f64) -> f64 {
+        if digits == 3 || digits == 5 { point * 10.0 } else { point }
+    }
+
+    /// ระยะราคา → Pips
+    pub fn pips(&self, price_delta: f64) -> f64 {
+        price_delta / self.pip_size
+    }
+
+    /// ระยะราคา → Points
+    pub fn points(&self, price_delta: f64) -> f64 {
+        price_delta / self.point
+    }
+
+    /// กำไร/ขาดทุน (สกุลเงินบัญชี) ของ `volume` Lot เมื่อราคาวิ่ง `price_delta` ไปทางที่ได้กำไร
+    pub fn profit(&self, price_delta: f64, volume: f64) -> f64 {
+        price_delta / self.tick_size * self.tick_value * volume
+    }
+
+    /// ขาดทุนต่อ 1 Lot ถ้าราคาวิ่งสวน `distance`
+    pub fn loss_per_lot(&self, distance: f64) -> f64 {
+        self.profit(distance, 1.0)
+    }
+
+    /// ปัดราคาให้ตรงกับจำนวนทศนิยมของ Symbol (MT5 ปฏิเสธราคาที่ละเอียดเกิน)
+    pub fn normalize_price(&self, price: f64) -> f64 {
+        let factor = 10f64.powi(self.digits as i32);
+        (price * factor).round() / factor
+    }
+
+    /// ปัด Volume ลงตาม Step แล้ว Clamp ไม่เกิน `volume_max`
+    /// คืน `None` ถ้าต่ำกว่า `volume_min`
+    pub fn normalize_volume(&self, raw: f64) -> Option<f64> {
+        let capped = raw.min(self.volume_max);
+        // + epsilon กัน 0.3 / 0.1 = 2.9999999 ปัดลงเหลือ 0.2
+        let steps  = (capped / self.volume_step + 1e-9).floor();
+        let volume = round_to_step(steps * self.volume_step, self.volume_step);
+
+        (volume >= self.volume_min - 1e-9).then_some(volume)
+    }
+
+    /// เปิดให้เทรด ณ เวลา `at` ไหม
+    pub fn is_trading_at(&self, at: DateTime<Utc>) -> bool {
+        self.sessions.is_empty() || self.sessions.iter().any(|s| s.contains(at))
+    }
+
+    /// เวลาที่ตลาดจะปิดจริงครั้งถัดไป นับจาก Session ที่เปิดอยู่ ณ `at`
+    ///
+    /// Session ที่ต่อกัน (เช่น `23:59:59` → `00:00:00` วันถัดไป หรือพักไม่เกิน 1 นาที)
+    /// นับเป็นช่วงเดียว — None = ไม่มี Session (เปิดตลอด), ตลาดปิดอยู่ หรือไม่มีวันปิดเลย
+    pub fn session_end(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut session = *self.sessions.iter().find(|s| s.contains(at))?;
+        let mut date    = at.date_naive();
+
+        // 7 วัน × หลาย Session ต่อวัน — เกินนี้ = เปิดตลอดสัปดาห์
+        for _ in 0..self.sessions.len() * 7 {
+            let close = date.and_time(session.close).and_utc();
+            let next  = self.sessions.iter().find_map(|s| {
+                [date, date.succ_opt()?].into_iter().find_map(|d| {
+                    let open = d.and_time(s.open).and_utc();
+                    let gap  = open - close;
+                    (d.weekday() == s.day && gap > chrono::Duration::zero() && gap <= chrono::Duration::minutes(1))
+                        .then_some((*s, d))
+                })
+            });
+            match next {
+                Some((s, d)) => {
+                    session = s;
+                    date    = d;
+                }
+                None => return Some(close),
+            }
+        }
+        None
+    }
+}
+
+/// ตัดเศษทศนิยมที่เกินความละเอียดของ Step (0.30000000000000004 → 0.3)
+fn round_to_step(value: f64, step: f64) -> f64 {
+    let decimals = (-step.log10()).ceil().max(0.0) as i32;
+    let factor   = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+// ─── Defaults ─────────────────────────────────────────────────────────────────
+
+#[allow(clippy::too_many_arguments)]
+fn spec(
+    symbol: &str,
+    digits: u32,
+    pip_size: f64,
+    contract_size: f64,
+    tick_value: f64,
+    volume_min: f64,
+    volume_max: f64,
+    volume_step: f64,
+) -> SymbolSpec {
+    let point = 10f64.powi(-(digits as i32));
+    SymbolSpec {
+        symbol: symbol.to_string(),
+        digits,
+        point,
+        pip_size,
+        contract_size,
+        tick_size: point,
+        tick_value,
+        volume_min,
+        volume_max,
+        volume_step,
+        sessions: Vec::new(),
+    }
+}
+
+/// Spec เริ่มต้นของ Symbol ที่ใช้บ่อย (บัญชี USD, ค่าทั่วไปของ Broker ส่วนใหญ่)
+/// Broker จริงอาจต่างกัน — EA จะส่งค่าจริงมาทับตอน Start
+fn default_specs() -> Vec<SymbolSpec> {
+    vec![
+        //    symbol    digits pip      contract   tick_value min   max    step
+        spec("EURUSD", 5, 0.0001, 100_000.0, 1.0,   0.01, 100.0, 0.01),
+        spec("GBPUSD", 5, 0.0001, 100_000.0, 1.0,   0.01, 100.0, 0.01),
+        spec("AUDUSD", 5, 0.0001, 100_000.0, 1.0,   0.01, 100.0, 0.01),
+        // tick_value ของ JPY ขึ้นกับอัตรา USDJPY — ค่านี้ประมาณที่ 150
+        spec("USDJPY", 3, 0.01,   100_000.0, 0.667, 0.01, 100.0, 0.01),
+        spec("XAUUSD", 2, 0.1,    100.0,     1.0,   0.01, 50.0,  0.01),
+        spec("XAGUSD", 3, 0.01,   5_000.0,   5.0,   0.01, 50.0,  0.01),
+        spec("BTCUSD", 2, 1.0,    1.0,       0.01,  0.01, 10.0,  0.01),
+        spec("ETHUSD", 2, 0.1,    1.0,       0.01,  0.01, 100.0, 0.01),
+        spec("NAS100", 2, 1.0,    1.0,       0.01,  0.1,  100.0, 0.1),
+        spec("US30",   2, 1.0,    1.0,       0.01,  0.1,  100.0, 0.1),
+    ]
+}
+
+// ─── Registry ─────────────────────────────────────────────────────────────────
+
+/// SymbolSpec ทั้งหมดที่ระบบรู้จัก — Key = symbol
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    specs: HashMap<String, SymbolSpec>,
+}
+
+impl SymbolRegistry {
+    pub fn from_env() -> Self {
+        let mut registry = Self::default();
+        for spec in default_specs() {
+            registry.upsert(spec);
+        }
+
+        if let Ok(raw) = std::env::var("SYMBOL_SPECS") {
+            match serde_json::from_str::<Vec<SymbolSpec>>(&raw) {
+                Ok(specs) => specs.into_iter().for_each(|s| registry.upsert(s)),
+                Err(e) => warn!(error = %e, "SYMBOL_SPECS is not valid JSON — using defaults"),
+            }
+        }
+
+        registry
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&SymbolSpec> {
+        self.specs.get(symbol)
+    }
+
+    /// เพิ่ม/แทนที่ Spec — ถ้าไม่ระบุ `pip_size` ใช้ค่าเดิมของ Symbol (หรืออนุมานจาก `digits`)
+    pub fn upsert(&mut self, mut spec: SymbolSpec) {
+        if spec.pip_size <= 0.0 {
+            spec.pip_size = self
+                .specs
+                .get(&spec.symbol)
+                .map(|old| old.pip_size)
+                .unwrap_or_else(|| SymbolSpec::derived_pip_size(spec.digits, spec.point));
+        }
+        self.specs.insert(spec.symbol.clone(), spec);
+    }
+
+    /// Spec ทั้งหมด เรียงตามชื่อ Symbol
+    pub fn all(&self) -> Vec<SymbolSpec> {
+        let mut all: Vec<_> = self.specs.values().cloned().collect();
+        all.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        all
+    }
+}
+
+/// Spec สำหรับ Test — ใช้ค่า Default ในตัว
+#[cfg(test)]
+pub fn test_spec(symbol: &str) -> SymbolSpec {
+    default_specs()
+        .into_iter()
+        .find(|s| s.symbol == symbol)
+        .expect("default spec exists")
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pips_per_asset_class() {
+        assert!((test_spec("EURUSD").pips(0.0003) - 3.0).abs() < 1e-9);
+        assert!((test_spec("USDJPY").pips(0.25) - 25.0).abs() < 1e-9);
+        assert!((test_spec("XAUUSD").pips(1.5) - 15.0).abs() < 1e-9);
+        assert!((test_spec("BTCUSD").pips(50.0) - 50.0).abs() < 1e-9);
+
+        // 1 Lot EURUSD วิ่ง 10 pips = 100 USD | 1 Lot XAUUSD วิ่ง 1$ = 100 USD
+        assert!((test_spec("EURUSD").profit(0.0010, 1.0) - 100.0).abs() < 1e-6);
+        assert!((test_spec("XAUUSD").profit(1.0, 1.0) - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_upsert_keeps_pip_size_when_ea_omits_it() {
+        let mut registry = SymbolRegistry::default();
+        registry.upsert(test_spec("XAUUSD"));
+
+        let mut pushed = test_spec("XAUUSD");
+        pushed.pip_size   = 0.0;
+        pushed.volume_max = 20.0;
+        registry.upsert(pushed);
+        assert_eq!(registry.get("XAUUSD").unwrap().pip_size, 0.1);
+        assert_eq!(registry.get("XAUUSD").unwrap().volume_max, 20.0);
+
+        let mut unknown = test_spec("EURUSD");
+        unknown.symbol   = "EURGBP".into();
+        unknown.pip_size = 0.0;
+        registry.upsert(unknown);
+        assert!((registry.get("EURGBP").unwrap().pip_size - 0.0001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut spec = test_spec("EURUSD");
+        let monday_noon = "2026-03-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(spec.is_trading_at(monday_noon), "no sessions = always open");
+
+        spec.sessions = serde_json::from_str(
+            r#"[{ "day": "Mon", "open": "00:05:00", "close": "23:59:59" }]"#,
+        )
+        .unwrap();
+        assert!(spec.is_trading_at(monday_noon));
+        assert!(!spec.is_trading_at(monday_noon + chrono::Duration::days(1)));
+        assert_eq!(spec.session_end(monday_noon), Some("2026-03-02T23:59:59Z".parse().unwrap()));
+
+        // Session ที่ต่อข้ามเที่ยงคืนนับเป็นช่วงเดียว
+        spec.sessions.push(serde_json::from_str(r#"{ "day": "Tue", "open": "00:00:00", "close": "21:00:00" }"#).unwrap());
+        assert_eq!(spec.session_end(monday_noon), Some("2026-03-03T21:00:00Z".parse().unwrap()));
+    }
+}
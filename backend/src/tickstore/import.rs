@@ -0,0 +1,430 @@
+//! # tickstore::import — อ่านไฟล์ Tick / แท่งย้อนหลัง
+//!
+//! | `format`    | ไฟล์                                   | คอลัมน์ (Header)                                        |
+//! |-------------|----------------------------------------|---------------------------------------------------------|
+//! | `mt5_ticks` | MT5 Symbols → Ticks → Export           | `<DATE> <TIME> <BID> <ASK> <LAST> <VOLUME> <FLAGS>`      |
+//! | `mt5_bars`  | MT5 Symbols → Bars → Export            | `<DATE> [<TIME>] <OPEN> <HIGH> <LOW> <CLOSE> <TICKVOL> <VOL> <SPREAD>` |
+//! | `generic`   | CSV ทั่วไป                              | `time` (หรือ `date` + `time`), `bid`, `ask`, [`volume`] |
+//!
+//! - ตัวคั่น Tab / `,` / `;` ตาม Header · UTF-8 หรือ UTF-16LE (ไฟล์ Export ของ MT5) ที่มี BOM
+//! - เวลาที่ไม่มี Timezone คือเวลา Server ของโบรกเกอร์ → ลบ `utc_offset_minutes` ให้เป็น UTC
+//!   (Unix seconds / ms และ RFC 3339 ที่มี Offset ไม่ถูกเลื่อน)
+//! - `mt5_ticks`: ช่อง Bid / Ask ว่าง = ราคาเดิม (MT5 เขียนเฉพาะค่าที่เปลี่ยน) — แถวที่ไม่มี Bid / Ask
+//!   ใหม่เลย (เช่น Last-only) ถูกข้าม
+//! - `mt5_bars`: แต่ละแท่ง → 4 Tick แบบ OHLC ของ Strategy Tester (แท่งขึ้น O → L → H → C,
+//!   แท่งลง O → H → L → C) ห่างกัน ¼ แท่ง · Ask = Bid + `<SPREAD>` × Point
+//! - แถวที่อ่านไม่ได้ / ราคา ≤ 0 / Ask < Bid / High-Low ไม่ครอบ Open-Close ถูกข้ามและรายงาน
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::StoredTick;
+use crate::models::Timeframe;
+
+/// จำนวนข้อความ Error ที่รายงานกลับ (ที่เหลือนับใน `invalid` อย่างเดียว)
+const MAX_REPORTED_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Mt5Ticks,
+    Mt5Bars,
+    Generic,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// None = ดูจาก Header (มี OPEN = แท่ง / ชื่อแบบ `<BID>` = MT5 Ticks / อื่นๆ = Generic)
+    pub format:             Option<ImportFormat>,
+    /// เวลา Server ของไฟล์ − UTC (นาที) เช่น GMT+2 = 120
+    pub utc_offset_minutes: i32,
+    /// Timeframe ของแท่ง (`mt5_bars`) — None = ระยะห่างที่สั้นที่สุดระหว่างแท่ง
+    pub timeframe:          Option<Timeframe>,
+    /// `SYMBOL_POINT` สำหรับแปลง `<SPREAD>` (Points) เป็นราคา — None = ไม่มี SymbolSpec
+    pub point:              Option<f64>,
+}
+
+/// ผลการอ่านไฟล์ — `ticks` ยังไม่เรียง / ยังไม่ตัดตัวซ้ำ (คลังทำตอน Merge)
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub format:    Option<ImportFormat>,
+    pub ticks:     Vec<StoredTick>,
+    /// แถวข้อมูลทั้งหมด (ไม่รวม Header)
+    pub rows:      usize,
+    pub invalid:   usize,
+    /// แถวที่ไม่มี Bid / Ask ใหม่ (`mt5_ticks`)
+    pub skipped:   usize,
+    pub errors:    Vec<String>,
+    /// Timeframe ของแท่งที่ใช้ (`mt5_bars`)
+    pub timeframe: Option<Timeframe>,
+}
+
+impl Parsed {
+    fn reject(&mut self, line: usize, reason: impl std::fmt::Display) {
+        self.invalid += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(format!("line {line}: {reason}"));
+        }
+    }
+}
+
+/// อ่านไฟล์ทั้งก้อน — Err = อ่านไม่ได้ทั้งไฟล์ (Encoding / Header / หา Timeframe ไม่ได้)
+pub fn parse(bytes: &[u8], options: &ImportOptions) -> Result<Parsed, String> {
+    let text      = decode(bytes)?;
+    let header    = text.lines().next().ok_or("file is empty")?;
+    let delimiter = if header.contains('\t') {
+        b'\t'
+    } else if header.contains(';') && !header.contains(',') {
+        b';'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let columns = Columns::new(reader.headers().map_err(|e| e.to_string())?);
+
+    let format = options.format.unwrap_or(if columns.find(&["open"]).is_some() {
+        ImportFormat::Mt5Bars
+    } else if header.contains('<') {
+        ImportFormat::Mt5Ticks
+    } else {
+        ImportFormat::Generic
+    });
+
+    let mut parsed = match format {
+        ImportFormat::Mt5Bars => parse_bars(&mut reader, &columns, options)?,
+        _                     => parse_ticks(&mut reader, &columns, options)?,
+    };
+    parsed.format = Some(format);
+    Ok(parsed)
+}
+
+// ─── Ticks ────────────────────────────────────────────────────────────────────
+
+fn parse_ticks(
+    reader:  &mut csv::Reader<&[u8]>,
+    columns: &Columns,
+    options: &ImportOptions,
+) -> Result<Parsed, String> {
+    let time   = columns.time()?;
+    let bid    = columns.require(&["bid"])?;
+    let ask    = columns.require(&["ask"])?;
+    let volume = columns.find(&["volume", "vol", "tickvol"]);
+
+    let mut parsed = Parsed::default();
+    let mut quote: (Option<f64>, Option<f64>) = (None, None);
+
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        parsed.rows += 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(e)     => { parsed.reject(line, e); continue; }
+        };
+
+        let time = match time.parse(&record, options.utc_offset_minutes) {
+            Ok(time) => time,
+            Err(e)   => { parsed.reject(line, e); continue; }
+        };
+        let (new_bid, new_ask) = match (price(&record, bid), price(&record, ask)) {
+            (Ok(b), Ok(a)) => (b, a),
+            (Err(e), _) | (_, Err(e)) => { parsed.reject(line, e); continue; }
+        };
+        if new_bid.is_none() && new_ask.is_none() {
+            parsed.skipped += 1;
+            continue;
+        }
+        quote = (new_bid.or(quote.0), new_ask.or(quote.1));
+
+        let (Some(bid), Some(ask)) = quote else {
+            // ยังไม่รู้อีกฝั่ง (ต้นไฟล์) — รอแถวถัดไป
+            parsed.skipped += 1;
+            continue;
+        };
+        if ask < bid {
+            parsed.reject(line, format!("ask {ask} < bid {bid}"));
+            continue;
+        }
+        let volume = volume.and_then(|c| number(&record, c).ok().flatten()).unwrap_or(0.0).max(0.0);
+
+        parsed.ticks.push(StoredTick { time_ms: time.timestamp_millis(), bid, ask, volume, ..Default::default() });
+    }
+    Ok(parsed)
+}
+
+// ─── Bars ─────────────────────────────────────────────────────────────────────
+
+struct Bar {
+    time:   DateTime<Utc>,
+    ohlc:   [f64; 4],
+    volume: f64,
+    spread: f64,
+}
+
+fn parse_bars(
+    reader:  &mut csv::Reader<&[u8]>,
+    columns: &Columns,
+    options: &ImportOptions,
+) -> Result<Parsed, String> {
+    let time   = columns.time()?;
+    let ohlc   = [
+        columns.require(&["open"])?,
+        columns.require(&["high"])?,
+        columns.require(&["low"])?,
+        columns.require(&["close"])?,
+    ];
+    let volume = columns.find(&["tickvol", "volume", "vol"]);
+    let spread = columns.find(&["spread"]);
+    let point  = match (spread, options.point) {
+        (Some(_), None)  => return Err("<SPREAD> is in points — register the SymbolSpec first".into()),
+        (_, point)      => point.unwrap_or_default(),
+    };
+
+    let mut parsed = Parsed::default();
+    let mut bars   = Vec::new();
+
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        parsed.rows += 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(e)     => { parsed.reject(line, e); continue; }
+        };
+
+        let time = match time.parse(&record, options.utc_offset_minutes) {
+            Ok(time) => time,
+            Err(e)   => { parsed.reject(line, e); continue; }
+        };
+        let mut prices = [0.0; 4];
+        let mut error  = None;
+        for (slot, column) in prices.iter_mut().zip(ohlc) {
+            match price(&record, column) {
+                Ok(Some(p)) => *slot = p,
+                Ok(None)    => error = Some("missing OHLC value".to_string()),
+                Err(e)      => error = Some(e),
+            }
+        }
+        if let Some(e) = error {
+            parsed.reject(line, e);
+            continue;
+        }
+        let [open, high, low, close] = prices;
+        if high < open.max(close) || low > open.min(close) {
+            parsed.reject(line, format!("high {high} / low {low} do not contain open {open} / close {close}"));
+            continue;
+        }
+
+        let field  = |c: Option<usize>| c.and_then(|c| number(&record, c).ok().flatten()).unwrap_or(0.0).max(0.0);
+        let spread = field(spread) * point;
+        bars.push(Bar { time, ohlc: prices, volume: field(volume), spread });
+    }
+
+    bars.sort_by_key(|b| b.time);
+    let timeframe = match options.timeframe {
+        Some(tf) => tf,
+        None if bars.len() < 2 => Timeframe::M1,
+        None => {
+            let gap = bars.windows(2).map(|w| (w[1].time - w[0].time).num_seconds()).filter(|g| *g > 0).min();
+            Timeframe::ALL
+                .into_iter()
+                .find(|tf| Some(tf.seconds()) == gap)
+                .ok_or("cannot infer the bar timeframe — pass `timeframe`")?
+        }
+    };
+    parsed.timeframe = Some(timeframe);
+
+    let quarter = Duration::seconds(timeframe.seconds()) / 4;
+    for bar in bars {
+        let [open, high, low, close] = bar.ohlc;
+        let path = if close >= open { [open, low, high, close] } else { [open, high, low, close] };
+        for (k, bid) in path.into_iter().enumerate() {
+            parsed.ticks.push(StoredTick {
+                time_ms: (bar.time + quarter * k as i32).timestamp_millis(),
+                bid,
+                ask:     bid + bar.spread,
+                volume:  bar.volume / 4.0,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(parsed)
+}
+
+// ─── Columns ──────────────────────────────────────────────────────────────────
+
+/// ตำแหน่งคอลัมน์ตามชื่อใน Header (`<BID>` / `Bid` / `bid` เหมือนกัน)
+struct Columns {
+    names: Vec<String>,
+}
+
+/// คอลัมน์เวลา — ช่องเดียว หรือ Date + Time แยกกัน (แท่ง D1 ของ MT5 ไม่มี `<TIME>`)
+enum TimeColumns {
+    Single(usize),
+    Split { date: usize, time: Option<usize> },
+}
+
+impl Columns {
+    fn new(headers: &csv::StringRecord) -> Self {
+        let names = headers
+            .iter()
+            .map(|h| h.trim().trim_start_matches('<').trim_end_matches('>').to_ascii_lowercase())
+            .collect();
+        Self { names }
+    }
+
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        self.names.iter().position(|n| names.contains(&n.as_str()))
+    }
+
+    fn require(&self, names: &[&str]) -> Result<usize, String> {
+        self.find(names).ok_or_else(|| format!("missing column '{}' (header: {})", names[0], self.names.join(", ")))
+    }
+
+    fn time(&self) -> Result<TimeColumns, String> {
+        if let Some(date) = self.find(&["date"]) {
+            return Ok(TimeColumns::Split { date, time: self.find(&["time"]) });
+        }
+        self.require(&["time", "timestamp", "datetime", "date_time"]).map(TimeColumns::Single)
+    }
+}
+
+impl TimeColumns {
+    fn parse(&self, record: &csv::StringRecord, offset_minutes: i32) -> Result<DateTime<Utc>, String> {
+        let field = |i: usize| record.get(i).unwrap_or_default();
+        match self {
+            TimeColumns::Single(i) => parse_time(field(*i), offset_minutes),
+            TimeColumns::Split { date, time } => {
+                let raw = match time {
+                    Some(time) => format!("{} {}", field(*date), field(*time)),
+                    None       => field(*date).to_string(),
+                };
+                parse_time(raw.trim(), offset_minutes)
+            }
+        }
+    }
+}
+
+/// ช่องตัวเลข — ว่าง = None
+fn number(record: &csv::StringRecord, column: usize) -> Result<Option<f64>, String> {
+    let raw = record.get(column).unwrap_or_default();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    raw.parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map(Some)
+        .ok_or_else(|| format!("invalid number '{raw}'"))
+}
+
+/// ช่องราคา — ว่าง = None / ต้อง > 0
+fn price(record: &csv::StringRecord, column: usize) -> Result<Option<f64>, String> {
+    match number(record, column)? {
+        Some(value) if value <= 0.0 => Err(format!("non-positive price {value}")),
+        other                       => Ok(other),
+    }
+}
+
+/// Unix seconds / ms / µs, RFC 3339 หรือเวลา Server (`2026.01.07 10:00:00.123`, `2026-01-07 10:00:00`, …)
+pub fn parse_time(raw: &str, offset_minutes: i32) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("invalid time '{raw}'");
+
+    if !raw.is_empty() && raw.bytes().all(|b| b.is_ascii_digit()) {
+        let n: i64 = raw.parse().map_err(|_| invalid())?;
+        let time = match n {
+            n if n >= 100_000_000_000_000 => DateTime::from_timestamp_micros(n),
+            n if n >= 100_000_000_000     => DateTime::from_timestamp_millis(n),
+            n                             => DateTime::from_timestamp(n, 0),
+        };
+        return time.ok_or_else(invalid);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(time.to_utc());
+    }
+
+    const DATETIME: [&str; 4] = ["%Y.%m.%d %H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y.%m.%d %H:%M"];
+    const DATE:     [&str; 2] = ["%Y.%m.%d", "%Y-%m-%d"];
+
+    let naive = DATETIME
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(raw, f).ok())
+        .or_else(|| {
+            DATE.iter()
+                .find_map(|f| NaiveDate::parse_from_str(raw, f).ok())
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(invalid)?;
+
+    Ok(naive.and_utc() - Duration::minutes(offset_minutes as i64))
+}
+
+/// UTF-8 (มีหรือไม่มี BOM) หรือ UTF-16LE ที่มี BOM (ไฟล์ Export ของ MT5)
+fn decode(bytes: &[u8]) -> Result<String, String> {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        return String::from_utf16(&units).map_err(|_| "invalid UTF-16 text".to_string());
+    }
+    let rest = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8(rest.to_vec()).map_err(|_| "file is not UTF-8 or UTF-16LE text".to_string())
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    #[test]
+    fn test_mt5_ticks_carry_forward_and_server_offset() {
+        let file = "<DATE>\t<TIME>\t<BID>\t<ASK>\t<LAST>\t<VOLUME>\t<FLAGS>\n\
+                    2026.01.07\t12:00:00.100\t2000.10\t\t\t\t2\n\
+                    2026.01.07\t12:00:00.250\t\t2000.40\t\t\t4\n\
+                    2026.01.07\t12:00:01\t2000.20\t\t\t\t2\n\
+                    2026.01.07\t12:00:01.500\t\t\t2000.30\t1\t8\n\
+                    2026.01.07\t12:00:02\t2000.50\t2000.45\t\t\t6\n\
+                    2026.01.07\tbad\t2000.10\t2000.40\t\t\t6\n";
+        let options = ImportOptions { utc_offset_minutes: 120, ..Default::default() };
+
+        let parsed = parse(&utf16le(file), &options).unwrap();
+
+        assert_eq!(parsed.format, Some(ImportFormat::Mt5Ticks));
+        // แถวแรกยังไม่รู้ Ask / แถว Last-only ข้าม / Ask < Bid และเวลาอ่านไม่ได้ = invalid
+        assert_eq!((parsed.rows, parsed.skipped, parsed.invalid), (6, 2, 2));
+        let ticks: Vec<(String, f64, f64)> = parsed
+            .ticks
+            .iter()
+            .map(|t| (t.time().format("%H:%M:%S%.3f").to_string(), t.bid, t.ask))
+            .collect();
+        assert_eq!(ticks, vec![
+            ("10:00:00.250".to_string(), 2000.10, 2000.40),
+            ("10:00:01.000".to_string(), 2000.20, 2000.40),
+        ]);
+    }
+
+    #[test]
+    fn test_mt5_bars_become_ohlc_ticks_with_spread() {
+        let file = "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>\n\
+                    2026.01.07\t10:00:00\t2000.0\t2003.0\t1999.0\t2002.0\t40\t0\t30\n\
+                    2026.01.07\t10:05:00\t2002.0\t2002.5\t1998.0\t1998.5\t20\t0\t30\n";
+        let options = ImportOptions { point: Some(0.01), ..Default::default() };
+
+        let parsed = parse(file.as_bytes(), &options).unwrap();
+
+        assert_eq!((parsed.format, parsed.timeframe), (Some(ImportFormat::Mt5Bars), Some(Timeframe::M5)));
+        let bids: Vec<f64> = parsed.ticks.iter().map(|t| t.bid).collect();
+        assert_eq!(bids, vec![2000.0, 1999.0, 2003.0, 2002.0, 2002.0, 2002.5, 1998.0, 1998.5]);
+        assert_eq!(parsed.ticks[1].time_ms - parsed.ticks[0].time_ms, 75_000);
+        assert!((parsed.ticks[0].ask - 2000.3).abs() < 1e-9);
+        assert!(parse(file.as_bytes(), &ImportOptions::default()).is_err(), "spread needs the symbol's point");
+    }
+}
@@ -0,0 +1,510 @@
+//! # tickstore — คลัง Tick บนดิสก์
+//!
+//! Tick ทุกตัวที่ Engine เห็น (Live — [`recorder`]) และ Tick ย้อนหลังที่ Import ([`import`]) อยู่ในคลังเดียวกัน
+//! — Backtest (`dataset`), Candle / Tick API (`from` / `to`) และ Warm-up ตอน Boot อ่านจากที่นี่
+//!
+//! ```text
+//! TICK_STORE_DIR/                 (default ./data/ticks)
+//! └─ XAUUSD/
+//!    └─ 2026-01-07/               หนึ่งโฟลเดอร์ต่อวัน (UTC)
+//!       ├─ 09.csv.gz              หนึ่งไฟล์ต่อชั่วโมง — gzip หลายก้อนต่อกัน
+//!       └─ 10.csv.gz              บรรทัดละ Tick: time_ms,bid,ask,volume,mid,spread,rsi_14,ma_20,ma_50
+//! ```
+//!
+//! - Append (Recorder): Tick หนึ่งชุด → gzip ก้อนใหม่ต่อท้ายไฟล์ของชั่วโมง — ลำดับในไฟล์ = ลำดับที่ Engine เห็น
+//! - Merge (Import): อ่านทั้งชั่วโมง + Tick ใหม่ → ตัดตัวซ้ำ (เวลา / Bid / Ask / Volume ตรงกันทุกค่า)
+//!   → เรียงตามเวลา (Tick ที่ ms เดียวกันคงลำดับเดิม) → เขียนไฟล์ชั่วคราวแล้ว Rename
+//! - อ่านช่วงเวลา ([`TickStore::read`]): เปิดเฉพาะชั่วโมงที่คาบเกี่ยว ทีละไฟล์ — ไม่โหลดทั้งชุดเข้า Memory
+//! - Append / Merge / อ่านไฟล์ ถือ Lock เดียวกัน — ไม่อ่านเจอ gzip ก้อนที่เขียนไม่จบ
+
+pub mod import;
+pub mod recorder;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::Serialize;
+
+use crate::models::TickData;
+
+const HOUR_MS: i64 = 3_600_000;
+
+// ─── Stored Tick ──────────────────────────────────────────────────────────────
+
+/// Tick หนึ่งตัวในคลัง (ไม่มี Symbol — อยู่ในชื่อโฟลเดอร์)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StoredTick {
+    /// Unix milliseconds (UTC)
+    pub time_ms: i64,
+    pub bid:     f64,
+    pub ask:     f64,
+    pub volume:  f64,
+    /// Field ที่ EA ส่งมาเอง (Tick จาก Import ไม่มี)
+    pub mid:     Option<f64>,
+    pub spread:  Option<f64>,
+    pub rsi_14:  Option<f64>,
+    pub ma_20:   Option<f64>,
+    pub ma_50:   Option<f64>,
+}
+
+impl StoredTick {
+    pub fn from_tick(tick: &TickData) -> Self {
+        Self {
+            time_ms: tick.time.timestamp_millis(),
+            bid:     tick.bid,
+            ask:     tick.ask,
+            volume:  tick.volume,
+            mid:     tick.mid,
+            spread:  tick.spread,
+            rsi_14:  tick.rsi_14,
+            ma_20:   tick.ma_20,
+            ma_50:   tick.ma_50,
+        }
+    }
+
+    pub fn time(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.time_ms).unwrap_or_default()
+    }
+
+    pub fn to_tick(self, symbol: &str) -> TickData {
+        TickData {
+            symbol: symbol.to_string(),
+            bid:    self.bid,
+            ask:    self.ask,
+            mid:    self.mid,
+            volume: self.volume,
+            spread: self.spread,
+            time:   self.time(),
+            rsi_14: self.rsi_14,
+            ma_20:  self.ma_20,
+            ma_50:  self.ma_50,
+        }
+    }
+
+    /// Key สำหรับตัดตัวซ้ำ — ทุกค่าตรงกัน (Tick ต่างราคาที่ ms เดียวกันไม่ซ้ำ)
+    fn key(&self) -> (i64, u64, u64, u64) {
+        (self.time_ms, self.bid.to_bits(), self.ask.to_bits(), self.volume.to_bits())
+    }
+
+    /// ชั่วโมง (นับจาก Epoch) ที่ Tick นี้อยู่
+    fn hour(&self) -> i64 {
+        self.time_ms.div_euclid(HOUR_MS)
+    }
+
+    fn write_line(&self, out: &mut impl Write) -> io::Result<()> {
+        let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            self.time_ms, self.bid, self.ask, self.volume,
+            opt(self.mid), opt(self.spread), opt(self.rsi_14), opt(self.ma_20), opt(self.ma_50),
+        )
+    }
+
+    fn parse_line(line: &str) -> Option<Self> {
+        let mut fields = line.split(',');
+        let mut next   = || fields.next();
+        let opt        = |v: Option<&str>| v.filter(|v| !v.is_empty()).and_then(|v| v.parse().ok());
+
+        Some(Self {
+            time_ms: next()?.parse().ok()?,
+            bid:     next()?.parse().ok()?,
+            ask:     next()?.parse().ok()?,
+            volume:  next()?.parse().ok()?,
+            mid:     opt(next()),
+            spread:  opt(next()),
+            rsi_14:  opt(next()),
+            ma_20:   opt(next()),
+            ma_50:   opt(next()),
+        })
+    }
+}
+
+// ─── Tick Store ───────────────────────────────────────────────────────────────
+
+/// ผลของการ Merge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MergeStats {
+    /// Tick ใหม่ที่เขียนลงคลัง
+    pub inserted:   usize,
+    /// Tick ที่มีอยู่แล้ว (ในคลัง หรือซ้ำกันเองในชุดที่ส่งมา)
+    pub duplicates: usize,
+}
+
+/// ชุดข้อมูลของ Symbol หนึ่งตัว (`GET /api/history/datasets`)
+#[derive(Debug, Clone, Serialize)]
+pub struct Dataset {
+    pub symbol: String,
+    pub from:   DateTime<Utc>,
+    pub to:     DateTime<Utc>,
+    /// จำนวนวันที่มีข้อมูล
+    pub days:   usize,
+    /// ขนาดบนดิสก์ (บีบอัดแล้ว)
+    pub bytes:  u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TickStore {
+    dir:  PathBuf,
+    /// Lock ของไฟล์ทั้งคลัง — Clone (Recorder / Import / Reader) ใช้ตัวเดียวกัน
+    lock: Arc<Mutex<()>>,
+}
+
+impl TickStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), lock: Arc::default() }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("TICK_STORE_DIR").unwrap_or_else(|_| "data/ticks".to_string()))
+    }
+
+    /// ต่อท้าย `ticks` (ลำดับตามที่มา) — gzip ก้อนใหม่ต่อชั่วโมงที่มี Tick
+    pub fn append(&self, symbol: &str, ticks: &[StoredTick]) -> io::Result<()> {
+        let dir = self.symbol_dir(symbol)?;
+        let _guard = self.lock();
+
+        for (hour, ticks) in by_hour(ticks) {
+            let path = partition_path(&dir, hour);
+            fs::create_dir_all(path.parent().unwrap_or(&dir))?;
+            let block = compress(&ticks)?;
+            fs::OpenOptions::new().create(true).append(true).open(&path)?.write_all(&block)?;
+        }
+        Ok(())
+    }
+
+    /// รวม `ticks` เข้าคลังของ `symbol` (ไม่ต้องเรียงมาก่อน) — ตัดตัวซ้ำแล้วเขียนชั่วโมงที่เปลี่ยนใหม่
+    pub fn merge(&self, symbol: &str, ticks: &[StoredTick]) -> io::Result<MergeStats> {
+        let dir = self.symbol_dir(symbol)?;
+        let _guard = self.lock();
+
+        let mut stats = MergeStats::default();
+        for (hour, incoming) in by_hour(ticks) {
+            let path     = partition_path(&dir, hour);
+            let mut all  = if path.exists() { read_partition(&path)? } else { Vec::new() };
+            let mut seen: HashSet<_> = all.iter().map(StoredTick::key).collect();
+
+            let before = all.len();
+            for tick in incoming {
+                if seen.insert(tick.key()) {
+                    all.push(tick);
+                } else {
+                    stats.duplicates += 1;
+                }
+            }
+            if all.len() == before {
+                continue;
+            }
+            stats.inserted += all.len() - before;
+
+            // Stable — Tick ที่ ms เดียวกันคงลำดับตามที่มา
+            all.sort_by_key(|t| t.time_ms);
+            fs::create_dir_all(path.parent().unwrap_or(&dir))?;
+            let tmp = path.with_extension("gz.tmp");
+            fs::write(&tmp, compress(&all)?)?;
+            fs::rename(tmp, path)?;
+        }
+        Ok(stats)
+    }
+
+    /// ชุดข้อมูลทั้งหมดในคลัง เรียงตาม Symbol
+    pub fn datasets(&self) -> io::Result<Vec<Dataset>> {
+        let mut datasets = Vec::new();
+        for entry in read_dir_or_empty(&self.dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let symbol     = entry.file_name().to_string_lossy().into_owned();
+            let partitions = list_partitions(&entry.path(), None, None)?;
+            let Some((from, to)) = self.span(&symbol, None, None)? else { continue };
+
+            let days: HashSet<i64> = partitions.iter().map(|(hour, _)| hour.div_euclid(24)).collect();
+            let mut bytes = 0;
+            for (_, path) in &partitions {
+                bytes += fs::metadata(path)?.len();
+            }
+            datasets.push(Dataset { symbol, from, to, days: days.len(), bytes });
+        }
+        datasets.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        Ok(datasets)
+    }
+
+    /// เวลาของ Tick แรก / สุดท้ายของ `symbol` ในช่วง `[from, to]` — None = ไม่มี Tick
+    pub fn span(
+        &self,
+        symbol: &str,
+        from:   Option<DateTime<Utc>>,
+        to:     Option<DateTime<Utc>>,
+    ) -> io::Result<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        let dir        = self.symbol_dir(symbol)?;
+        let partitions = list_partitions(&dir, from, to)?;
+        let (lo, hi)   = bounds(from, to);
+
+        // ในไฟล์เรียงตามลำดับที่มา (Tick มาช้าได้) — หา Min / Max ของชั่วโมงแรก / สุดท้ายที่มี Tick ในช่วง
+        let edge = |paths: &mut dyn Iterator<Item = &PathBuf>, pick: fn(i64, i64) -> i64| -> io::Result<Option<i64>> {
+            for path in paths {
+                let ticks = self.read_locked(path)?;
+                let found = ticks.iter().map(|t| t.time_ms).filter(|t| *t >= lo && *t <= hi).reduce(pick);
+                if found.is_some() {
+                    return Ok(found);
+                }
+            }
+            Ok(None)
+        };
+
+        let Some(first) = edge(&mut partitions.iter().map(|(_, p)| p), i64::min)? else {
+            return Ok(None);
+        };
+        let last = edge(&mut partitions.iter().rev().map(|(_, p)| p), i64::max)?.unwrap_or(first);
+        Ok(DateTime::from_timestamp_millis(first).zip(DateTime::from_timestamp_millis(last)))
+    }
+
+    /// อ่าน Tick ของ `symbol` ในช่วง `[from, to]` — ทีละชั่วโมง เรียงตามชั่วโมง / ลำดับในไฟล์
+    pub fn read(
+        &self,
+        symbol: &str,
+        from:   Option<DateTime<Utc>>,
+        to:     Option<DateTime<Utc>>,
+    ) -> io::Result<TickReader> {
+        let dir        = self.symbol_dir(symbol)?;
+        let partitions = list_partitions(&dir, from, to)?;
+        let (lo, hi)   = bounds(from, to);
+
+        Ok(TickReader {
+            store:      self.clone(),
+            symbol:     symbol.to_string(),
+            partitions: partitions.into_iter().map(|(_, path)| path).collect::<Vec<_>>().into_iter(),
+            current:    Vec::new().into_iter(),
+            lo,
+            hi,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // Thread ที่ถือ Lock แล้ว Panic ไม่ได้ทำให้ไฟล์เสีย (เขียนแบบ Append / Rename)
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read_locked(&self, path: &Path) -> io::Result<Vec<StoredTick>> {
+        let _guard = self.lock();
+        read_partition(path)
+    }
+
+    /// โฟลเดอร์ของ Symbol — ชื่อ Symbol มาจาก Request จึงห้ามมี Path separator
+    fn symbol_dir(&self, symbol: &str) -> io::Result<PathBuf> {
+        let valid = !symbol.is_empty()
+            && symbol != "."
+            && symbol != ".."
+            && symbol.chars().all(|c| c.is_ascii_alphanumeric() || "._-#".contains(c));
+        if !valid {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid symbol '{symbol}'")));
+        }
+        Ok(self.dir.join(symbol))
+    }
+}
+
+// ─── Reader ───────────────────────────────────────────────────────────────────
+
+/// Iterator ของ Tick จากคลัง — เปิดไฟล์ชั่วโมงถัดไปเมื่อชั่วโมงปัจจุบันหมด
+pub struct TickReader {
+    store:      TickStore,
+    symbol:     String,
+    partitions: std::vec::IntoIter<PathBuf>,
+    current:    std::vec::IntoIter<StoredTick>,
+    lo:         i64,
+    hi:         i64,
+}
+
+impl Iterator for TickReader {
+    type Item = io::Result<TickData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tick) = self.current.next() {
+                if tick.time_ms < self.lo || tick.time_ms > self.hi {
+                    continue;
+                }
+                return Some(Ok(tick.to_tick(&self.symbol)));
+            }
+
+            let path = self.partitions.next()?;
+            match self.store.read_locked(&path) {
+                Ok(ticks) => self.current = ticks.into_iter(),
+                Err(e) => {
+                    self.partitions = Vec::new().into_iter();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+// ─── Files ────────────────────────────────────────────────────────────────────
+
+fn bounds(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> (i64, i64) {
+    (
+        from.map_or(i64::MIN, |t| t.timestamp_millis()),
+        to.map_or(i64::MAX, |t| t.timestamp_millis()),
+    )
+}
+
+fn by_hour(ticks: &[StoredTick]) -> BTreeMap<i64, Vec<StoredTick>> {
+    let mut hours: BTreeMap<i64, Vec<StoredTick>> = BTreeMap::new();
+    for tick in ticks {
+        hours.entry(tick.hour()).or_default().push(*tick);
+    }
+    hours
+}
+
+fn partition_path(dir: &Path, hour: i64) -> PathBuf {
+    let start = DateTime::from_timestamp_millis(hour * HOUR_MS).unwrap_or_default();
+    dir.join(start.format("%Y-%m-%d").to_string()).join(start.format("%H.csv.gz").to_string())
+}
+
+fn read_dir_or_empty(dir: &Path) -> io::Result<Vec<io::Result<fs::DirEntry>>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => Ok(entries.collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// ไฟล์ชั่วโมงของ Symbol ที่คาบเกี่ยว `[from, to]` เรียงจากเก่า → ใหม่
+fn list_partitions(
+    dir:  &Path,
+    from: Option<DateTime<Utc>>,
+    to:   Option<DateTime<Utc>>,
+) -> io::Result<Vec<(i64, PathBuf)>> {
+    let (lo, hi) = bounds(from, to);
+    let (lo, hi) = (lo.div_euclid(HOUR_MS), hi.div_euclid(HOUR_MS));
+
+    let mut partitions = Vec::new();
+    for day in read_dir_or_empty(dir)? {
+        let day = day?;
+        let Some(date) = day.file_name().to_str().and_then(|n| NaiveDate::parse_from_str(n, "%Y-%m-%d").ok()) else {
+            continue;
+        };
+        let day_hour = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp_millis() / HOUR_MS;
+        if day_hour + 23 < lo || day_hour > hi {
+            continue;
+        }
+
+        for file in fs::read_dir(day.path())? {
+            let file = file?;
+            let hour = file
+                .file_name()
+                .to_str()
+                .and_then(|n| n.strip_suffix(".csv.gz"))
+                .and_then(|n| n.parse::<i64>().ok())
+                .filter(|h| (0..24).contains(h));
+            if let Some(hour) = hour.map(|h| day_hour + h).filter(|h| (lo..=hi).contains(h)) {
+                partitions.push((hour, file.path()));
+            }
+        }
+    }
+    partitions.sort_unstable_by_key(|(hour, _)| *hour);
+    Ok(partitions)
+}
+
+/// Tick ทั้งไฟล์ (ทุก gzip ก้อนต่อกัน)
+fn read_partition(path: &Path) -> io::Result<Vec<StoredTick>> {
+    let mut ticks = Vec::new();
+    for (i, line) in BufReader::new(MultiGzDecoder::new(fs::File::open(path)?)).lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let tick = StoredTick::parse_line(&line).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: malformed tick", path.display(), i + 1))
+        })?;
+        ticks.push(tick);
+    }
+    Ok(ticks)
+}
+
+/// gzip หนึ่งก้อน — ต่อท้ายไฟล์เดิมได้เลย
+fn compress(ticks: &[StoredTick]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for tick in ticks {
+        tick.write_line(&mut encoder)?;
+    }
+    encoder.finish()
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(time_ms: i64, bid: f64) -> StoredTick {
+        StoredTick { time_ms, bid, ask: bid + 0.3, volume: 1.0, ..Default::default() }
+    }
+
+    fn temp_store() -> (TickStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("agv_ticks_{}", uuid::Uuid::new_v4().simple()));
+        (TickStore::new(&dir), dir)
+    }
+
+    #[test]
+    fn test_merge_dedupes_sorts_and_reads_range_across_days() {
+        let (store, dir) = temp_store();
+        let day = 86_400_000;
+
+        // ชุดแรกไม่เรียง + ข้ามวัน / ชุดที่สองทับซ้อนบางส่วน + Tick ต่างราคาที่ ms เดียวกัน
+        let stats = store.merge("XAUUSD", &[tick(day + 5, 2001.0), tick(10, 2000.0), tick(day, 2000.5)]).unwrap();
+        assert_eq!(stats, MergeStats { inserted: 3, duplicates: 0 });
+        let stats = store.merge("XAUUSD", &[tick(10, 2000.0), tick(10, 2000.1), tick(10, 2000.1)]).unwrap();
+        assert_eq!(stats, MergeStats { inserted: 1, duplicates: 2 });
+
+        let all: Vec<(i64, f64)> = store
+            .read("XAUUSD", None, None)
+            .unwrap()
+            .map(|t| t.unwrap())
+            .map(|t| (t.time.timestamp_millis(), t.bid))
+            .collect();
+        assert_eq!(all, vec![(10, 2000.0), (10, 2000.1), (day, 2000.5), (day + 5, 2001.0)]);
+
+        let from = DateTime::from_timestamp_millis(11);
+        let to   = DateTime::from_timestamp_millis(day + 1);
+        assert_eq!(store.read("XAUUSD", from, to).unwrap().count(), 1);
+        assert_eq!(
+            store.span("XAUUSD", None, None).unwrap(),
+            Some((tick(10, 0.0).time(), tick(day + 5, 0.0).time()))
+        );
+        assert!(store.read("../etc", None, None).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_keeps_arrival_order_and_indicator_fields() {
+        let (store, dir) = temp_store();
+        let live = StoredTick { rsi_14: Some(55.5), spread: Some(30.0), ..tick(2_000, 2000.0) };
+
+        // สองก้อน (สอง Flush) ในชั่วโมงเดียวกัน — ก้อนที่สองมี Tick มาช้า
+        store.append("XAUUSD", &[tick(1_000, 1999.0), live]).unwrap();
+        store.append("XAUUSD", &[tick(3_000, 2001.0), tick(1_500, 1999.5), tick(HOUR_MS, 2002.0)]).unwrap();
+
+        let ticks: Vec<TickData> = store.read("XAUUSD", None, None).unwrap().map(|t| t.unwrap()).collect();
+        let bids: Vec<f64> = ticks.iter().map(|t| t.bid).collect();
+        assert_eq!(bids, vec![1999.0, 2000.0, 2001.0, 1999.5, 2002.0]);
+        assert_eq!((ticks[1].rsi_14, ticks[1].spread, ticks[0].rsi_14), (Some(55.5), Some(30.0), None));
+
+        // Span = Min / Max จริง ไม่ใช่ Tick แรก / สุดท้ายในไฟล์
+        let first_hour = DateTime::from_timestamp_millis(HOUR_MS - 1);
+        assert_eq!(
+            store.span("XAUUSD", None, first_hour).unwrap(),
+            Some((tick(1_000, 0.0).time(), tick(3_000, 0.0).time()))
+        );
+        assert_eq!(store.datasets().unwrap()[0].days, 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! # tickstore::recorder — บันทึก Tick Live ลงคลัง
+//!
+//! `process_tick` ส่ง Tick เข้า Channel (ไม่รอ Disk) → Thread ของ Recorder สะสมแล้ว Append
+//! เป็น gzip ก้อนละรอบ `TICK_RECORD_FLUSH_MS` — Process ตายก่อน Flush = เสีย Tick ไม่เกินหนึ่งรอบ
+//! Channel จำกัดที่ `TICK_RECORD_BUFFER` — Disk ช้า / ค้างจนเต็ม = ทิ้ง Tick (นับใน `dropped`) ไม่ใช่ RAM โตไม่จำกัด
+//!
+//! Replay วันไหนก็ได้ (`dataset` ของ Backtest) ด้วย Tick ชุดเดียวกับที่ Engine เห็น ตามลำดับที่เห็น
+//! และตอน Boot ป้อน Tick ล่าสุดกลับเข้า Candle / Indicator ([`warm_up`]) ให้ Snapshot ของ OpenClaw มีประวัติทันที
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::{StoredTick, TickStore};
+use crate::{
+    engine::{candle_builder::CandleAggregator, indicators::IndicatorEngine},
+    models::TickData,
+    state::AppState,
+};
+
+// ─── Config ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    /// `TICK_RECORD` (default true)
+    pub enabled:        bool,
+    /// `TICK_RECORD_FLUSH_MS` (default 1000)
+    pub flush_interval: Duration,
+    /// `TICK_RECORD_BUFFER` (default 100000) — Tick ที่รอเขียนได้สูงสุด
+    pub buffer:         usize,
+    /// `TICK_WARMUP_HOURS` (default 48, 0 = ไม่ Warm-up)
+    pub warmup_hours:   i64,
+}
+
+impl RecorderConfig {
+    pub fn from_env() -> Self {
+        let env = |key: &str| std::env::var(key).ok();
+        Self {
+            enabled:        env("TICK_RECORD").map(|v| v != "false" && v != "0").unwrap_or(true),
+            flush_interval: Duration::from_millis(
+                env("TICK_RECORD_FLUSH_MS").and_then(|v| v.parse().ok()).unwrap_or(1_000).max(10),
+            ),
+            buffer:         env("TICK_RECORD_BUFFER").and_then(|v| v.parse().ok()).unwrap_or(100_000).max(1),
+            warmup_hours:   env("TICK_WARMUP_HOURS").and_then(|v| v.parse().ok()).unwrap_or(48).max(0),
+        }
+    }
+}
+
+// ─── Recorder ─────────────────────────────────────────────────────────────────
+
+/// Handle ส่ง Tick ให้ Recorder — Default = no-op (State ของ Backtest ไม่บันทึก)
+#[derive(Debug, Clone, Default)]
+pub struct TickRecorder {
+    tx:      Option<mpsc::SyncSender<TickData>>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// สถานะของ Recorder สำหรับ `GET /api/monitor/stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecorderStats {
+    pub enabled: bool,
+    /// Tick ที่ไม่ได้บันทึก (Channel เต็ม / Thread หยุด)
+    pub dropped: u64,
+}
+
+impl TickRecorder {
+    /// เริ่ม Thread ของ Recorder — หยุดเองเมื่อ Handle ทุกตัวถูก Drop (Flush ก้อนสุดท้ายก่อน)
+    pub fn spawn(store: TickStore, config: &RecorderConfig) -> std::io::Result<Self> {
+        let (tx, rx) = mpsc::sync_channel(config.buffer);
+        let interval = config.flush_interval;
+        std::thread::Builder::new()
+            .name("tick-recorder".into())
+            .spawn(move || run(store, rx, interval))?;
+        Ok(Self { tx: Some(tx), dropped: Arc::default() })
+    }
+
+    /// ไม่รอ — Channel เต็ม (Disk ตามไม่ทัน) หรือ Thread หยุดไปแล้ว = ทิ้ง Tick นี้ ไม่ทำให้ Tick สะดุด
+    pub fn record(&self, tick: &TickData) {
+        let Some(tx) = &self.tx else { return };
+        if tx.try_send(tick.clone()).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped.is_power_of_two() {
+                warn!(dropped, "⚠️ [TICKSTORE] Recorder queue full — ticks dropped");
+            }
+        }
+    }
+
+    pub fn stats(&self) -> RecorderStats {
+        RecorderStats {
+            enabled: self.tx.is_some(),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn run(store: TickStore, rx: mpsc::Receiver<TickData>, interval: Duration) {
+    let mut pending: HashMap<String, Vec<StoredTick>> = HashMap::new();
+    let mut deadline = Instant::now() + interval;
+
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(tick) => pending.entry(tick.symbol.clone()).or_default().push(StoredTick::from_tick(&tick)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                flush(&store, &mut pending);
+                return;
+            }
+        }
+
+        if Instant::now() >= deadline {
+            flush(&store, &mut pending);
+            deadline = Instant::now() + interval;
+        }
+    }
+}
+
+fn flush(store: &TickStore, pending: &mut HashMap<String, Vec<StoredTick>>) {
+    for (symbol, ticks) in pending.iter_mut() {
+        if ticks.is_empty() {
+            continue;
+        }
+        if let Err(e) = store.append(symbol, ticks) {
+            warn!(symbol = %symbol, ticks = ticks.len(), error = %e, "⚠️ [TICKSTORE] Failed to record ticks");
+        }
+        ticks.clear();
+    }
+}
+
+// ─── Warm-up ──────────────────────────────────────────────────────────────────
+
+/// Boot: ป้อน Tick ที่บันทึกไว้ `hours` ชั่วโมงล่าสุดเข้า Candle Aggregator + Indicator Engine ใหม่
+/// แล้วสลับเข้า State — ราคาล่าสุด (`market_ticks`) ยังรอ Tick จริงจาก EA
+pub async fn warm_up(state: &AppState, hours: i64) -> anyhow::Result<usize> {
+    let store      = (*state.tick_store).clone();
+    let candle_cfg = state.candles.read().await.config().clone();
+    let ind_cfg    = state.indicators.read().await.config().clone();
+    let from       = Utc::now() - chrono::Duration::hours(hours);
+
+    let (candles, indicators, count) = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let mut candles    = CandleAggregator::new(candle_cfg);
+        let mut indicators = IndicatorEngine::new(ind_cfg);
+        let mut count      = 0;
+
+        for dataset in store.datasets()? {
+            if dataset.to < from {
+                continue;
+            }
+            for tick in store.read(&dataset.symbol, Some(from), None)? {
+                for candle in candles.on_tick(&tick?) {
+                    indicators.on_candle(&candle);
+                }
+                count += 1;
+            }
+        }
+        Ok((candles, indicators, count))
+    })
+    .await??;
+
+    *state.candles.write().await    = candles;
+    *state.indicators.write().await = indicators;
+    info!(hours, ticks = count, "🔥 [TICKSTORE] Candles / indicators warmed up from recorded ticks");
+    Ok(count)
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_queue_drops_instead_of_growing() {
+        // Thread ที่ค้าง (Disk ตามไม่ทัน) = ไม่มีใครรับจาก Channel
+        let (tx, _stalled) = mpsc::sync_channel(2);
+        let recorder = TickRecorder { tx: Some(tx), dropped: Arc::default() };
+        let tick: TickData = serde_json::from_value(serde_json::json!({
+            "symbol": "XAUUSD", "bid": 2000.0, "ask": 2000.3, "volume": 1.0, "time": Utc::now(),
+        }))
+        .unwrap();
+
+        for _ in 0..5 {
+            recorder.record(&tick);
+        }
+        assert_eq!(recorder.stats().dropped, 3);
+    }
+}
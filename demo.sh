@@ -7,8 +7,8 @@
 #   chmod +x demo.sh
 #   ./demo.sh
 #
-# ต้องรัน backend ก่อน:
-#   cd backend && cargo run
+# ต้องรัน backend ก่อน (Paper broker — ไม่ต้องมี MT5):
+#   cd backend && BROKER=paper cargo run
 
 set -e
 
@@ -32,7 +32,7 @@ HEALTH=$(curl -sf "${BASE_URL}/api/mt5/health" 2>/dev/null || echo "FAIL")
 if echo "$HEALTH" | grep -q '"ok":true'; then
     ok "Backend is running at ${BASE_URL}"
 else
-    error "Backend not reachable at ${BASE_URL}. Run: cd backend && cargo run"
+    error "Backend not reachable at ${BASE_URL}. Run: cd backend && BROKER=paper cargo run"
 fi
 
 # ── 1. ตรวจสอบ Risk Status ─────────────────────────────────────────────────────
@@ -169,12 +169,12 @@ header "Step 5: Current State"
 
 log "Strategy:"
 curl -sf "${BASE_URL}/api/brain/strategy" | \
-    python3 -c "import sys,json; d=json.load(sys.stdin); s=d.get('strategy'); print(f'  {s[\"direction\"] if s else \"None\"} {s[\"symbol\"] if s else \"\"}')" 2>/dev/null || \
+    python3 -c "import sys,json; d=json.load(sys.stdin); [print(f'  {s[\"direction\"]} {s[\"symbol\"]}') for s in d.get('strategies', [])] or print('  None')" 2>/dev/null || \
     curl -sf "${BASE_URL}/api/brain/strategy"
 
 log "Position:"
 curl -sf "${BASE_URL}/api/monitor/position" | \
-    python3 -c "import sys,json; d=json.load(sys.stdin); p=d.get('position'); print(f'  {p[\"direction\"] if p else \"FLAT\"} @ {p.get(\"entry_price\",\"-\") if p else \"\"}')" 2>/dev/null || \
+    python3 -c "import sys,json; d=json.load(sys.stdin); [print(f'  {p[\"direction\"]} {p[\"symbol\"]} @ {p[\"entry_price\"]}') for p in d.get('positions', [])] or print('  FLAT')" 2>/dev/null || \
     curl -sf "${BASE_URL}/api/monitor/position"
 
 log "Trade History:"
@@ -207,7 +207,7 @@ sleep 0.3
 # ── 7. ยืนยันว่า position ถูก clear ─────────────────────────────────────────
 header "Step 7: Verify Reset"
 POS_AFTER=$(curl -sf "${BASE_URL}/api/monitor/position")
-if echo "$POS_AFTER" | grep -q '"position":null'; then
+if echo "$POS_AFTER" | grep -q '"positions":\[\]'; then
     ok "Position cleared — Reflex Loop re-armed! Ready for next trade."
 else
     warn "Position may still be set: $POS_AFTER"
@@ -1,11 +1,42 @@
 // WebSocket connection manager + Svelte stores
 
-import { writable } from 'svelte/store';
+import { derived, writable } from 'svelte/store';
 
 // ── Types ──────────────────────────────────────────────────────────────────
 
 export interface EntryZone { low: number; high: number; }
 
+/** ระดับกำไรจากราคาเข้า — ใส่ key เดียว */
+export type ExitLevel = { r: number } | { tp_fraction: number } | { pips: number };
+
+export interface ExitPolicy {
+    break_even?: { trigger: ExitLevel; offset_pips?: number } | null;
+    trailing?: {
+        distance: { pips: number } | { atr: { period: number; multiplier: number } };
+        activate_at?: ExitLevel | null;
+        step_pips?: number;
+    } | null;
+    scale_out?: { at: ExitLevel; fraction: number }[];
+    max_hold_minutes?: number | null;
+    flatten_before_close_minutes?: number | null;
+}
+
+/** หนึ่งชั้นของ Confirmation Pipeline — ค่าที่ไม่ใส่ใช้ CONFIRM_* ของ Backend */
+export type ConfirmationLayer =
+    | { type: 'zone_width' | 'spread'; max_pips?: number }
+    | { type: 'zone_probe'; lookback?: number }
+    | { type: 'zone_dwell'; min_ticks?: number }
+    | { type: 'rsi'; overbought?: number; oversold?: number; timeframe?: string }
+    | { type: 'wick_rejection'; min_ratio?: number; timeframe?: string }
+    | {
+          type: 'trend';
+          timeframe?: string;
+          source?: 'ma' | 'structure' | 'both';
+          strictness?: 'strict' | 'normal' | 'lenient';
+          min_gap_pips?: number;
+          swing_lookback?: number;
+      };
+
 export interface ActiveStrategy {
     strategy_id: string;
     symbol: string;
@@ -14,6 +45,9 @@ export interface ActiveStrategy {
     take_profit: number;
     stop_loss: number;
     lot_size: number;
+    entry_mode: 'MARKET' | 'PENDING';
+    exit_policy?: ExitPolicy | null;
+    confirmation?: ConfirmationLayer[] | null;
     rationale: string;
     created_at: string;
     expires_at: string | null;
@@ -30,6 +64,8 @@ export interface OpenPosition {
     stop_loss: number;
     mt5_ticket: number | null;
     opened_at: string;
+    initial_lot_size: number;
+    scale_outs_done: number;
 }
 
 export interface TradeRecord {
@@ -42,13 +78,32 @@ export interface TradeRecord {
     take_profit: number;
     stop_loss: number;
     mt5_ticket: number | null;
-    status: 'PENDING' | 'CONFIRMED' | 'FAILED';
+    status: 'PENDING' | 'CONFIRMED' | 'REJECTED' | 'FAILED' | 'UNKNOWN'
+        | 'PLACED' | 'PARTIALLY_FILLED' | 'CANCELLED' | 'EXPIRED';
     status_message: string;
     fired_at: string;
     close_price: number | null;
     profit_pips: number | null;
+    profit: number | null;
     close_reason: string | null;
     closed_at: string | null;
+    sizing: SizingDecision | null;
+    order_kind: 'MARKET' | 'LIMIT' | 'STOP';
+    order_ticket: number | null;
+    filled_volume: number | null;
+}
+
+export interface SizingDecision {
+    mode: 'RISK_PERCENT' | 'FIXED_AMOUNT' | 'STRATEGY';
+    equity: number | null;
+    risk_amount: number;
+    stop_distance: number;
+    loss_per_lot: number;
+    requested_volume: number;
+    raw_volume: number;
+    volume: number;
+    actual_risk: number;
+    note: string | null;
 }
 
 export interface LogEntry {
@@ -67,23 +122,49 @@ export interface RiskStatus {
     last_trade_at: string | null;
     in_cooldown: boolean;
     cooldown_ends_at: string | null;
+    realized_pnl_today: number;
+    realized_pnl_week: number;
+    account_equity: number | null;
+    equity_peak: number;
+    drawdown: number;
     config: {
         max_trades_per_day: number;
         max_consecutive_failures: number;
         cooldown_secs_after_failure: number;
+        max_daily_loss: number;
+        max_weekly_loss: number;
+        max_drawdown: number;
     };
 }
 
+export interface Candle {
+    symbol: string;
+    timeframe: string;
+    start_time: string;
+    open: number;
+    high: number;
+    low: number;
+    close: number;
+    tick_count: number;
+    volume: number;
+}
+
 // ── Stores ─────────────────────────────────────────────────────────────────
 
 export const wsStatus = writable<'connecting' | 'connected' | 'disconnected'>('disconnected');
-export const strategy = writable<ActiveStrategy | null>(null);
-export const position = writable<OpenPosition | null>(null);
+// Strategy / Position Book (หลาย Symbol พร้อมกัน)
+export const strategies = writable<ActiveStrategy[]>([]);
+export const positions = writable<OpenPosition[]>([]);
+// ตัวแรกของ Book — ใช้โดย Card เดิมบน Dashboard
+export const strategy = derived(strategies, $s => $s[0] ?? null);
+export const position = derived(positions, $p => $p[0] ?? null);
 export const history = writable<TradeRecord[]>([]);
 export const tickCount = writable<number>(0);
 export const tradeCount = writable<number>(0);
 export const eventLog = writable<LogEntry[]>([]);
 export const riskStatus = writable<RiskStatus | null>(null);
+// แท่งของ Symbol × Timeframe ที่ Chart เปิดอยู่ (เก่า → ใหม่, ตัวสุดท้ายอาจเป็นแท่งที่กำลังสร้าง)
+export const candles = writable<Candle[]>([]);
 
 let logIdCounter = 0;
 
@@ -157,24 +238,56 @@ function handleEvent(data: Record<string, unknown>) {
 
     switch (event) {
         case 'SNAPSHOT':
-            strategy.set((data.strategy as ActiveStrategy) ?? null);
-            position.set((data.position as OpenPosition) ?? null);
+            strategies.set((data.strategies as ActiveStrategy[]) ?? []);
+            positions.set((data.positions as OpenPosition[]) ?? []);
             tickCount.set((data.tick_count as number) ?? 0);
             tradeCount.set((data.trade_count as number) ?? 0);
             addLog('SNAPSHOT', 'State snapshot loaded', 'default');
             break;
 
-        case 'STRATEGY_UPDATED':
-            strategy.set(data.strategy as ActiveStrategy);
+        case 'STRATEGY_UPDATED': {
+            const s = data.strategy as ActiveStrategy;
+            strategies.update(list => [...list.filter(x => x.strategy_id !== s.strategy_id), s]);
             addLog('STRATEGY_UPDATED',
                 `New: ${(data.strategy as ActiveStrategy).direction} ${(data.strategy as ActiveStrategy).symbol}`,
                 'strategy_updated');
             break;
+        }
 
-        case 'STRATEGY_CLEARED':
-            strategy.set(null);
-            addLog('STRATEGY_CLEARED', 'Strategy cleared — Reflex Loop disarmed', 'default');
+        case 'STRATEGY_CLEARED': {
+            const id = data.strategy_id as string | null;
+            const sym = data.symbol as string | null;
+            strategies.update(list => list.filter(x =>
+                id ? x.strategy_id !== id : sym ? x.symbol !== sym : false));
+            addLog('STRATEGY_CLEARED',
+                `Strategy cleared${sym ? ` (${sym})` : ''} — Reflex Loop disarmed`, 'default');
             break;
+        }
+
+        case 'ORDER_PLACED': {
+            const o = data.order as { kind: string; price: number; order_ticket: number; strategy: ActiveStrategy };
+            addLog('ORDER_PLACED',
+                `${o.strategy.direction} ${o.kind} ${o.strategy.symbol} @ ${o.price} | #${o.order_ticket}`,
+                'trade_firing');
+            fetchHistory();
+            break;
+        }
+
+        case 'ORDER_FILLED': {
+            const d = data as { order: { kind: string; filled_volume: number; volume: number }; fill: { price: number; symbol: string } };
+            addLog('ORDER_FILLED',
+                `${d.order.kind} filled ${d.fill.symbol} @ ${d.fill.price} | ${d.order.filled_volume}/${d.order.volume}`,
+                'position_opened');
+            fetchHistory();
+            break;
+        }
+
+        case 'ORDER_CANCELLED': {
+            const d = data as { order: { kind: string; order_ticket: number }; reason: string };
+            addLog('ORDER_CANCELLED', `${d.order.kind} #${d.order.order_ticket} cancelled | ${d.reason}`, 'default');
+            fetchHistory();
+            break;
+        }
 
         case 'TRADE_FIRING':
             addLog('TRADE_FIRING',
@@ -183,7 +296,7 @@ function handleEvent(data: Record<string, unknown>) {
             break;
 
         case 'POSITION_OPENED':
-            position.set(data.position as OpenPosition);
+            positions.update(list => [...list, data.position as OpenPosition]);
             tradeCount.update(n => n + 1);
             addLog('POSITION_OPENED',
                 `Opened: ${(data.position as OpenPosition).direction} @ ${(data.position as OpenPosition).entry_price} | #${(data.position as OpenPosition).mt5_ticket ?? '?'}`,
@@ -192,8 +305,8 @@ function handleEvent(data: Record<string, unknown>) {
             break;
 
         case 'POSITION_CLOSED': {
-            position.set(null);  // ← ล้าง position — Critical!
-            const d = data as { symbol: string; direction: string; close_price: number; profit_pips: number; close_reason: string };
+            const d = data as { position_id: string; symbol: string; direction: string; close_price: number; profit_pips: number; close_reason: string };
+            positions.update(list => list.filter(p => p.position_id !== d.position_id));  // ← ล้าง position — Critical!
             const pips = d.profit_pips ?? 0;
             addLog('POSITION_CLOSED',
                 `Closed ${d.direction} ${d.symbol} @ ${d.close_price} | ${pips >= 0 ? '+' : ''}${pips.toFixed(1)} pips | ${d.close_reason}`,
@@ -203,6 +316,58 @@ function handleEvent(data: Record<string, unknown>) {
             break;
         }
 
+        case 'POSITION_PARTIALLY_CLOSED': {
+            const d = data as { position: OpenPosition; volume: number; close_price: number; profit_pips: number; close_reason: string };
+            positions.update(list => list.map(p => p.position_id === d.position.position_id ? d.position : p));
+            addLog('POSITION_PARTIALLY_CLOSED',
+                `Scaled out ${d.volume} ${d.position.symbol} @ ${d.close_price} | ${d.profit_pips >= 0 ? '+' : ''}${d.profit_pips.toFixed(1)} pips | ${d.position.lot_size} left`,
+                'position_opened');
+            fetchHistory();
+            fetchRiskStatus();
+            break;
+        }
+
+        case 'COMMAND_QUEUED': {
+            const c = data.command as { type: string; ticket: number; reason: string };
+            addLog('COMMAND_QUEUED', `${c.type} #${c.ticket} queued for EA | ${c.reason}`, 'default');
+            break;
+        }
+
+        case 'COMMAND_ACKED': {
+            const d = data as { command: { type: string; ticket: number; attempts: number }; ok: boolean; message: string };
+            if (d.ok) fetchPositions();  // SL / Scale-out ใน Book เปลี่ยนแล้ว
+            addLog('COMMAND_ACKED',
+                `${d.command.type} #${d.command.ticket} ${d.ok ? 'done' : `failed: ${d.message}`}`,
+                d.ok ? 'position_opened' : 'trade_failed');
+            break;
+        }
+
+        case 'CONFIRMATION_TRACE': {
+            // Log เฉพาะ Near-miss (ขาดชั้นเดียว) — Trace เต็มอยู่ที่ /api/brain/strategy/:id/trace
+            const t = data.trace as { symbol: string; failed: number; rejected_by: string | null };
+            if (t.failed === 1) {
+                addLog('CONFIRMATION_TRACE', `${t.symbol} near-miss — blocked by ${t.rejected_by}`, 'default');
+            }
+            break;
+        }
+
+        case 'COMMAND_TIMED_OUT': {
+            const c = data.command as { type: string; ticket: number; attempts: number };
+            addLog('COMMAND_TIMED_OUT', `${c.type} #${c.ticket} not acknowledged after ${c.attempts} attempts`, 'trade_failed');
+            break;
+        }
+
+        case 'RECONCILIATION_DIFF': {
+            const d = data as { adopted: OpenPosition[]; closed: number[]; updated: number[]; resolved: string[] };
+            // Book ฝั่ง Backend ถูกแก้ตาม MT5 แล้ว → โหลดรายการ Position ใหม่ทั้งหมด
+            fetchPositions();
+            addLog('RECONCILIATION_DIFF',
+                `Reconciled with MT5: +${d.adopted.length} adopted | -${d.closed.length} closed | ${d.updated.length} updated | ${d.resolved.length} resolved`,
+                'trade_failed');
+            fetchHistory();
+            break;
+        }
+
         case 'TRADE_FAILED':
             addLog('TRADE_FAILED',
                 `Failed: ${(data.record as TradeRecord).status_message}`,
@@ -211,6 +376,13 @@ function handleEvent(data: Record<string, unknown>) {
             fetchRiskStatus();
             break;
 
+        case 'TRADE_UNKNOWN':
+            addLog('TRADE_UNKNOWN',
+                `Outcome unknown: ${(data.record as TradeRecord).status_message} — awaiting reconciliation`,
+                'trade_failed');
+            fetchHistory();
+            break;
+
         case 'RISK_KILLED':
             addLog('RISK_KILLED', `⛔ Kill switch: ${data.reason}`, 'trade_failed');
             fetchRiskStatus();
@@ -223,8 +395,64 @@ function handleEvent(data: Record<string, unknown>) {
     }
 }
 
+// ── Market WebSocket (Chart) ──────────────────────────────────────────────
+
+const MARKET_WS_URL = 'ws://localhost:3000/ws/market';
+
+let marketWs: WebSocket | null = null;
+
+// ต่อ /ws/market ของ Symbol × Timeframe เดียว — เปลี่ยน Symbol / Timeframe = ต่อใหม่
+export function connectMarketWs(symbol: string, tf = 'M1', limit = 200) {
+    disconnectMarketWs();
+    candles.set([]);
+    marketWs = new WebSocket(`${MARKET_WS_URL}?symbol=${encodeURIComponent(symbol)}&tf=${tf}&limit=${limit}`);
+
+    marketWs.onmessage = (e: MessageEvent) => {
+        try {
+            const data = JSON.parse(e.data);
+            switch (data.event) {
+                case 'CANDLES':
+                    candles.set(data.forming ? [...data.candles, data.forming] : data.candles);
+                    break;
+                case 'CANDLE_UPDATE':
+                case 'CANDLE_CLOSED':
+                    candles.update(list => upsertCandle(list, data.candle as Candle, limit));
+                    break;
+            }
+        } catch {
+            console.warn('Market WS parse error:', e.data);
+        }
+    };
+}
+
+export function disconnectMarketWs() {
+    marketWs?.close();
+    marketWs = null;
+}
+
+function upsertCandle(list: Candle[], candle: Candle, limit: number): Candle[] {
+    const last = list[list.length - 1];
+    if (last?.start_time === candle.start_time) {
+        return [...list.slice(0, -1), candle];
+    }
+    // CANDLE_CLOSED ของแท่งที่เก่ากว่าตัวสุดท้าย = อัปเดตแท่งเดิม
+    const idx = list.findIndex(c => c.start_time === candle.start_time);
+    if (idx >= 0) {
+        return list.map((c, i) => (i === idx ? candle : c));
+    }
+    return [...list, candle].slice(-(limit + 1));
+}
+
 // ── REST Helpers ──────────────────────────────────────────────────────────
 
+async function fetchPositions() {
+    try {
+        const resp = await fetch(`${API_URL}/api/monitor/position`);
+        const data = await resp.json();
+        positions.set(data.positions ?? []);
+    } catch { /* silent */ }
+}
+
 async function fetchHistory() {
     try {
         const resp = await fetch(`${API_URL}/api/monitor/history`);
@@ -34,8 +34,14 @@ pub struct Config {
     pub brain_interval:   Duration,
     /// Strategy มีอายุกี่นาที (หลังจากนี้ Reflex Loop จะไม่ใช้)
     pub strategy_ttl_min: u64,
-    /// URL ดึงข้อมูลตลาด (MT5 Bridge หรือ Exchange API)
-    pub market_url:       Option<String>,
+    /// URL ดึงข้อมูลตลาด — ค่าเริ่มต้น = `aitrade_url` (Market Data API ของ Backend)
+    pub market_url:       String,
+    /// true = ใช้ Mock data (dev ที่ไม่มี Backend / MT5)
+    pub market_mock:      bool,
+    /// Timeframe ของแท่งและ Indicator ที่ส่งให้ AI เช่น "H1"
+    pub market_timeframe: String,
+    /// จำนวนแท่งล่าสุดที่ใส่ใน Prompt
+    pub market_candles:   usize,
 }
 
 impl Config {
@@ -58,14 +64,19 @@ impl Config {
             .parse()
             .context("BRAIN_INTERVAL_SECS must be a number")?;
 
+        let aitrade_url = std::env::var("AITRADE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+
         Ok(Self {
             ai_provider,
             api_key,
             symbol:           std::env::var("SYMBOL").unwrap_or_else(|_| "BTCUSD".to_string()),
-            aitrade_url:      std::env::var("AITRADE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            market_url:       std::env::var("MARKET_URL").ok().filter(|v| !v.is_empty()).unwrap_or_else(|| aitrade_url.clone()),
+            aitrade_url,
             brain_interval:   Duration::from_secs(interval_secs),
             strategy_ttl_min: std::env::var("STRATEGY_TTL_MIN").unwrap_or_else(|_| "15".to_string()).parse().unwrap_or(15),
-            market_url:       std::env::var("MARKET_URL").ok(),
+            market_mock:      std::env::var("MARKET_MOCK").map(|v| v == "true" || v == "1").unwrap_or(false),
+            market_timeframe: std::env::var("MARKET_TIMEFRAME").unwrap_or_else(|_| "H1".to_string()),
+            market_candles:   std::env::var("MARKET_CANDLES").ok().and_then(|v| v.parse().ok()).unwrap_or(24),
         })
     }
 }
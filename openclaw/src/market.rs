@@ -3,10 +3,12 @@
 //! ดึงข้อมูลตลาดมาสรุปให้ AI วิเคราะห์
 //!
 //! ## Data Sources (เลือกได้)
-//! 1. MT5 Bridge API (mt5-bridge) — ถ้ามี MARKET_URL ใน .env
-//! 2. Mock/Placeholder — สำหรับ dev/test โดยไม่ต้องมี MT5
+//! 1. Market Data API ของ aitrade Backend (ค่าเริ่มต้น) — หรือ MARKET_URL ที่ตอบ Format เดียวกัน
+//!    `GET /api/market/snapshot` (ราคา + Indicator) และ `GET /api/market/candles` (โครงสร้างตลาด)
+//! 2. Mock/Placeholder — `MARKET_MOCK=true` สำหรับ dev/test โดยไม่ต้องมี Backend / MT5
 
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
@@ -31,11 +33,25 @@ pub struct MarketSnapshot {
     pub ma_20:          Option<f64>,
     /// Moving average 50 period
     pub ma_50:          Option<f64>,
+    /// Timeframe ของ `candles` และ Indicator
+    pub timeframe:      String,
+    /// แท่งที่ปิดแล้วล่าสุด (เก่า → ใหม่) — ว่าง = Backend ยังไม่มีแท่ง
+    pub candles:        Vec<CandleBar>,
 }
 
-/// Response format จาก mt5-bridge /api/market/snapshot
+/// แท่งเทียนจาก `GET /api/market/candles`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleBar {
+    pub start_time: DateTime<Utc>,
+    pub open:       f64,
+    pub high:       f64,
+    pub low:        f64,
+    pub close:      f64,
+}
+
+/// Response format จาก /api/market/snapshot
 #[derive(Debug, Deserialize)]
-struct BridgeMarketResponse {
+struct MarketSnapshotResponse {
     symbol:        String,
     bid:           f64,
     ask:           f64,
@@ -49,38 +65,56 @@ struct BridgeMarketResponse {
     ma_50:         Option<f64>,
 }
 
+/// Response format จาก /api/market/candles
+#[derive(Debug, Deserialize)]
+struct CandlesResponse {
+    candles: Vec<CandleBar>,
+}
+
 /// ดึง Market Snapshot
-/// ถ้ามี MARKET_URL → เรียก MT5 Bridge
-/// ถ้าไม่มี → ใช้ Mock data (สำหรับ dev)
+/// ปกติ → Market Data API (`MARKET_URL`, ค่าเริ่มต้น = Backend)
+/// `MARKET_MOCK=true` → ใช้ Mock data (สำหรับ dev)
 pub async fn fetch_market_snapshot(
     client: &reqwest::Client,
     config: &Config,
 ) -> anyhow::Result<MarketSnapshot> {
-    if let Some(market_url) = &config.market_url {
-        fetch_from_bridge(client, market_url, &config.symbol).await
-    } else {
-        tracing::warn!("MARKET_URL not set — using MOCK market data");
-        Ok(mock_snapshot(&config.symbol))
+    if config.market_mock {
+        tracing::warn!("MARKET_MOCK=true — using MOCK market data");
+        return Ok(mock_snapshot(&config.symbol, &config.market_timeframe));
     }
+    fetch_from_backend(client, config).await
 }
 
-async fn fetch_from_bridge(
+async fn fetch_from_backend(
     client: &reqwest::Client,
-    base_url: &str,
-    symbol: &str,
+    config: &Config,
 ) -> anyhow::Result<MarketSnapshot> {
-    let url = format!("{base_url}/api/market/snapshot?symbol={symbol}");
+    let base_url = &config.market_url;
+    let symbol   = &config.symbol;
+    let tf       = &config.market_timeframe;
+    let url      = format!("{base_url}/api/market/snapshot?symbol={symbol}&tf={tf}");
 
-    let resp: BridgeMarketResponse = client
+    let resp: MarketSnapshotResponse = client
         .get(&url)
         .timeout(std::time::Duration::from_secs(5))
         .send()
         .await
         .context("Market API unreachable")?
+        .error_for_status()
+        .context("Market API has no data for this symbol yet")?
         .json()
         .await
         .context("Failed to parse market response")?;
 
+    // แท่งไม่ใช่ข้อมูลบังคับ — ดึงไม่ได้ก็ยังวิเคราะห์จาก Snapshot ได้
+    let candles = match fetch_candles(client, config).await {
+        Ok(candles) => candles,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to fetch candles — prompting without market structure");
+            Vec::new()
+        }
+    };
+
     let mid = (resp.bid + resp.ask) / 2.0;
 
     Ok(MarketSnapshot {
@@ -96,11 +130,36 @@ async fn fetch_from_bridge(
         rsi_14:         resp.rsi_14,
         ma_20:          resp.ma_20,
         ma_50:          resp.ma_50,
+        timeframe:      tf.clone(),
+        candles,
     })
 }
 
+async fn fetch_candles(
+    client: &reqwest::Client,
+    config: &Config,
+) -> anyhow::Result<Vec<CandleBar>> {
+    let url = format!(
+        "{}/api/market/candles?symbol={}&tf={}&limit={}",
+        config.market_url, config.symbol, config.market_timeframe, config.market_candles
+    );
+
+    let resp: CandlesResponse = client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .context("Market API unreachable")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to parse candles response")?;
+
+    Ok(resp.candles)
+}
+
 /// Mock data สำหรับ development (ไม่ต้องมี MT5)
-fn mock_snapshot(symbol: &str) -> MarketSnapshot {
+fn mock_snapshot(symbol: &str, timeframe: &str) -> MarketSnapshot {
     MarketSnapshot {
         symbol:         symbol.to_string(),
         current_price:  67000.0,
@@ -114,5 +173,7 @@ fn mock_snapshot(symbol: &str) -> MarketSnapshot {
         rsi_14:         Some(52.4),
         ma_20:          Some(66200.0),
         ma_50:          Some(64800.0),
+        timeframe:      timeframe.to_string(),
+        candles:        Vec::new(),
     }
 }
@@ -17,6 +17,18 @@ pub fn build_prompt(snapshot: &MarketSnapshot, config: &Config) -> String {
         _ => "- MA: N/A".to_string(),
     };
 
+    // โครงสร้างตลาด: แท่งล่าสุดจาก Backend (ไม่มี = ละไว้)
+    let candles_section = if snapshot.candles.is_empty() {
+        String::new()
+    } else {
+        let rows: Vec<String> = snapshot.candles.iter().map(|c| {
+            format!("{} | {:.2} | {:.2} | {:.2} | {:.2}",
+                c.start_time.format("%Y-%m-%d %H:%M"), c.open, c.high, c.low, c.close)
+        }).collect();
+        format!("\n## Recent {} Candles (oldest → newest, UTC)\nTime | Open | High | Low | Close\n{}\n",
+            snapshot.timeframe, rows.join("\n"))
+    };
+
     let ttl = config.strategy_ttl_min;
     let symbol = &snapshot.symbol;
 
@@ -31,7 +43,7 @@ pub fn build_prompt(snapshot: &MarketSnapshot, config: &Config) -> String {
 - 24H High: {high:.2} | 24H Low: {low:.2}
 {rsi_line}
 {ma_line}
-
+{candles_section}
 ## Your Task
 Analyze the market conditions and provide a precise trading strategy.
 
@@ -47,7 +59,7 @@ Analyze the market conditions and provide a precise trading strategy.
   "stop_loss": <float>,
   "opposing_zone_low": <float or null>,
   "opposing_zone_high": <float or null>,
-  "lot_size": 0.10,
+  "confirmation": [<layer>, ...] or null,
   "rationale": "<brief explanation max 100 chars>"
 }}
 ```
@@ -61,6 +73,11 @@ Analyze the market conditions and provide a precise trading strategy.
 6. Risk/Reward ratio must be >= 1.5
 7. Strategy is valid for {ttl} minutes
 8. You may specify an `opposing_zone` (Supply/Demand ahead of target) where we should exit early if price struggles there (Bailout).
+9. `confirmation` (optional, null = system default) lists the checks required before entry, in order. Layers:
+   {{"type":"spread"}}, {{"type":"zone_width"}}, {{"type":"zone_probe"}}, {{"type":"zone_dwell","min_ticks":3}},
+   {{"type":"rsi","timeframe":"M15"}}, {{"type":"wick_rejection","timeframe":"M5","min_ratio":0.6}},
+   {{"type":"trend","timeframe":"H1","source":"ma"|"structure"|"both","strictness":"strict"|"normal"|"lenient"}}
+   Timeframes: M1, M5, M15, H1, H4, D1.
 
 Respond with JSON only:"#,
         price  = snapshot.current_price,
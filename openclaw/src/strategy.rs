@@ -19,7 +19,12 @@ struct AiStrategyJson {
     stop_loss:        f64,
     opposing_zone_low: Option<f64>,
     opposing_zone_high: Option<f64>,
-    lot_size:         f64,
+    /// ไม่ได้ขอใน Prompt แล้ว — Backend คำนวณ Volume เองจากความเสี่ยงต่อ Trade
+    #[serde(default)]
+    lot_size:         Option<f64>,
+    /// Confirmation Pipeline ของ Setup นี้ (ส่งต่อให้ aitrade ตามที่ AI ตอบ — Backend ตรวจ Format เอง)
+    #[serde(default)]
+    confirmation:     Option<serde_json::Value>,
     rationale:        String,
 }
 
@@ -34,7 +39,12 @@ pub struct ActiveStrategy {
     pub take_profit:  f64,
     pub stop_loss:    f64,
     pub opposing_zone: Option<EntryZone>,
-    pub lot_size:     f64,
+    /// ส่งเฉพาะเมื่อ AI ระบุมาเอง (ใช้จริงเฉพาะ `SIZING_MODE=strategy` ฝั่ง aitrade)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lot_size:     Option<f64>,
+    /// None = `CONFIRM_PIPELINE` ของ aitrade
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<serde_json::Value>,
     pub rationale:    String,
     pub created_at:   chrono::DateTime<Utc>,
     pub expires_at:   Option<chrono::DateTime<Utc>>,
@@ -91,6 +101,7 @@ pub fn parse_strategy_from_ai(
             _ => None,
         },
         lot_size:    parsed.lot_size,
+        confirmation: parsed.confirmation.filter(|v| !v.is_null()),
         rationale:   parsed.rationale,
         created_at:  now,
         expires_at,